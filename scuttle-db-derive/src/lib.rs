@@ -0,0 +1,91 @@
+//! Derive macros for `scuttle-db`, enabled by its `derive` feature.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{Data, DeriveInput, Fields, LitStr, parse_macro_input, spanned::Spanned};
+
+/// Derives `FromRow`, decoding each field from a column of a result row.
+///
+/// Named fields read the column of the same name, `#[scuttle(rename = "column")]`
+/// reads another one. Fields of tuple structs read the columns in order.
+///
+/// ```ignore
+/// #[derive(FromRow)]
+/// struct User {
+///     name: String,
+///     #[scuttle(rename = "years")]
+///     age: Option<i64>,
+/// }
+/// ```
+#[proc_macro_derive(FromRow, attributes(scuttle))]
+pub fn derive_from_row(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    from_row(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn from_row(input: &DeriveInput) -> syn::Result<TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.span(),
+            "FromRow can only be derived for structs",
+        ));
+    };
+
+    let construct = match &data.fields {
+        Fields::Named(fields) => {
+            let fields = fields
+                .named
+                .iter()
+                .map(|field| {
+                    let ident = field.ident.as_ref().expect("named fields have names");
+                    let column = column_name(field)?
+                        .unwrap_or_else(|| ident.to_string().trim_start_matches("r#").to_string());
+                    Ok(quote! { #ident: row.get(schema, #column)? })
+                })
+                .collect::<syn::Result<Vec<_>>>()?;
+            quote! { Self { #(#fields),* } }
+        }
+        Fields::Unnamed(fields) => {
+            let fields = (0..fields.unnamed.len()).map(|index| quote! { row.get(schema, #index)? });
+            quote! { Self(#(#fields),*) }
+        }
+        Fields::Unit => quote! { Self },
+    };
+
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::scuttle_db::FromRow for #name #type_generics #where_clause {
+            fn from_row(
+                row: &::scuttle_db::Row,
+                schema: &::scuttle_db::OutputSchema,
+            ) -> ::std::result::Result<Self, ::scuttle_db::DatabaseError> {
+                ::std::result::Result::Ok(#construct)
+            }
+        }
+    })
+}
+
+/// The column given by `#[scuttle(rename = "...")]`, if any.
+fn column_name(field: &syn::Field) -> syn::Result<Option<String>> {
+    let mut column = None;
+    for attribute in field
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("scuttle"))
+    {
+        attribute.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                column = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else {
+                Err(meta.error("unsupported scuttle attribute, expected `rename`"))
+            }
+        })?;
+    }
+    Ok(column)
+}
@@ -0,0 +1,61 @@
+use scuttle_db::{ColumnDef, DataType, Database, DatabaseError, FromRow, Row, Schema, Value};
+
+#[derive(Debug, PartialEq, FromRow)]
+struct User {
+    name: String,
+    #[scuttle(rename = "years")]
+    age: Option<i64>,
+}
+
+#[derive(Debug, PartialEq, FromRow)]
+struct Pair(String, Option<i64>);
+
+fn database(name: &str) -> Database {
+    let directory = std::env::temp_dir().join(name);
+    std::fs::remove_dir_all(&directory).ok();
+
+    let schema = Schema::new(vec![
+        ColumnDef::new("name", DataType::Text, false),
+        ColumnDef::new("age", DataType::Int64, true),
+    ]);
+    let mut db = Database::new(&directory);
+    db.create_table("users", schema).unwrap();
+    for (name, age) in [("Alice", Value::Int64(30)), ("Bob", Value::Null)] {
+        let row = Row::new(vec![Value::Text(name.to_string()), age]);
+        db.insert_row("users", row).unwrap();
+    }
+    db
+}
+
+#[test]
+fn test_derive_from_row() {
+    let mut db = database("scuttle_derive_from_row_tests");
+
+    let response = db
+        .execute_query("SELECT name, age AS years FROM users")
+        .unwrap();
+    let users = response.rows_as::<User>().unwrap();
+    assert_eq!(
+        users,
+        [
+            User {
+                name: "Alice".to_string(),
+                age: Some(30),
+            },
+            User {
+                name: "Bob".to_string(),
+                age: None,
+            },
+        ]
+    );
+
+    let pairs = response.rows_as::<Pair>().unwrap();
+    assert_eq!(pairs[0], Pair("Alice".to_string(), Some(30)));
+
+    // Without the alias the renamed column is missing
+    let response = db.execute_query("SELECT name, age FROM users").unwrap();
+    assert!(matches!(
+        response.rows_as::<User>(),
+        Err(DatabaseError::ColumnNotFound(column)) if column == "years"
+    ));
+}
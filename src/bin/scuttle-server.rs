@@ -0,0 +1,94 @@
+#[path = "scuttle/demo.rs"]
+mod demo;
+
+use std::{net::TcpListener, path::PathBuf};
+
+use miette::{IntoDiagnostic, Result, miette};
+use scuttle_db::{Database, Server, SharedDatabase};
+
+use crate::demo::create_demo_tables;
+
+const USAGE: &str =
+    "Usage: scuttle-server [--data-dir DIR] [--listen ADDRESS] [--socket-dir DIR] [--demo]
+
+Serves a database to PostgreSQL clients, e.g. `psql -h 127.0.0.1 -p 5432`.
+
+Options:
+  --data-dir DIR     Directory of the database files (default: ./db)
+  --listen ADDRESS   TCP address to listen on (default: 127.0.0.1:5432)
+  --socket-dir DIR   Also listen on the Unix socket DIR/.s.PGSQL.<port>
+  --demo             Create users and customers tables with sample rows
+  --help             Show this message";
+
+struct Options {
+    data_dir: PathBuf,
+    listen: String,
+    socket_dir: Option<PathBuf>,
+    demo: bool,
+}
+
+fn parse_options() -> Result<Options> {
+    let mut options = Options {
+        data_dir: PathBuf::from("./db"),
+        listen: "127.0.0.1:5432".to_string(),
+        socket_dir: None,
+        demo: false,
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| miette!("Missing value for {arg}\n\n{USAGE}"))
+        };
+        match arg.as_str() {
+            "--data-dir" => options.data_dir = PathBuf::from(value()?),
+            "--listen" => options.listen = value()?,
+            "--socket-dir" => options.socket_dir = Some(PathBuf::from(value()?)),
+            "--demo" => options.demo = true,
+            "--help" | "-h" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            _ => return Err(miette!("Unknown option {arg}\n\n{USAGE}")),
+        }
+    }
+
+    Ok(options)
+}
+
+fn main() -> Result<()> {
+    miette::set_panic_hook();
+    let options = parse_options()?;
+
+    let mut db = Database::open(&options.data_dir)?;
+    if options.demo {
+        create_demo_tables(&mut db)?;
+    }
+    let server = Server::new(SharedDatabase::new(db));
+
+    let listener = TcpListener::bind(&options.listen).into_diagnostic()?;
+    let address = listener.local_addr().into_diagnostic()?;
+    println!("Listening on {address}");
+
+    if let Some(socket_dir) = options.socket_dir {
+        #[cfg(unix)]
+        {
+            let path = socket_dir.join(format!(".s.PGSQL.{}", address.port()));
+            // A socket left by a previous run would make binding fail
+            std::fs::remove_file(&path).ok();
+            let unix_listener = std::os::unix::net::UnixListener::bind(&path).into_diagnostic()?;
+            println!("Listening on {}", path.display());
+
+            let server = server.clone();
+            std::thread::spawn(move || server.serve_unix(unix_listener));
+        }
+        #[cfg(not(unix))]
+        return Err(miette!(
+            "Unix sockets are not supported on this platform: {}",
+            socket_dir.display()
+        ));
+    }
+
+    server.serve_tcp(listener).into_diagnostic()
+}
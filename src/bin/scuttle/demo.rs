@@ -0,0 +1,39 @@
+use miette::Result;
+use scuttle_db::{ColumnDef, DataType, Database, Row, Schema, Value};
+
+/// Creates `users`, with a few rows, and an empty `customers` table to try queries on.
+///
+/// Tables that already exist are left as they are, so the demo data can be loaded
+/// again into a database that has it.
+pub fn create_demo_tables(db: &mut Database) -> Result<()> {
+    let schema = Schema::new(vec![
+        ColumnDef::new("id", DataType::Int64, false),
+        ColumnDef::new("name", DataType::VarChar(255), false),
+        ColumnDef::new("age", DataType::Int64, true),
+        ColumnDef::new("is_active", DataType::Bool, true),
+    ]);
+
+    if !db.tables.contains_key("customers") {
+        db.create_table("customers", schema.clone())?;
+    }
+    if db.tables.contains_key("users") {
+        return Ok(());
+    }
+    db.create_table("users", schema)?;
+
+    for (id, name, age, is_active) in [
+        (1, "Alice", Value::Int64(30), Value::Null),
+        (2, "Bob", Value::Null, Value::Bool(false)),
+        (3, "Charlie", Value::Int64(35), Value::Bool(true)),
+    ] {
+        let row = Row::new(vec![
+            Value::Int64(id),
+            Value::Text(name.to_string()),
+            age,
+            is_active,
+        ]);
+        db.insert_row("users", row)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,232 @@
+mod demo;
+mod meta_command;
+mod shell;
+
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use miette::{IntoDiagnostic, Result, miette};
+use rustyline::{DefaultEditor, error::ReadlineError};
+use scuttle_db::{Database, OutputFormat};
+
+use crate::{
+    demo::create_demo_tables,
+    shell::{Flow, Shell},
+};
+
+const HISTORY_FILE: &str = ".scuttle_history";
+
+const USAGE: &str = "Usage: scuttle [OPTIONS]
+
+Opens the database in the data directory, creating it if needed, and reads statements
+from the terminal. With -c or -f, runs those instead and exits, with status 1 if any
+statement failed.
+
+Options:
+  --data-dir DIR     Directory of the database files (default: ./db)
+  --init             Delete the tables in the data directory and start empty
+  --demo             Create users and customers tables with sample rows
+  -c, --command SQL  Run statements or a meta-command, can be repeated
+  -f, --file FILE    Run the statements of a file, can be repeated
+  --read-only        Refuse changes to the database
+  --format FORMAT    Print results as aligned (default), csv, json, jsonl or markdown
+  -h, --help         Show this message";
+
+/// Something to run instead of reading the terminal, in the order given.
+enum Script {
+    Command(String),
+    File(PathBuf),
+}
+
+struct Options {
+    data_dir: PathBuf,
+    init: bool,
+    demo: bool,
+    scripts: Vec<Script>,
+    read_only: bool,
+    format: OutputFormat,
+}
+
+fn main() -> Result<()> {
+    miette::set_hook(Box::new(|_| {
+        Box::new(
+            miette::MietteHandlerOpts::new()
+                .terminal_links(true)
+                .unicode(false)
+                .context_lines(3)
+                .tab_width(4)
+                .break_words(true)
+                .build(),
+        )
+    }))
+    .into_diagnostic()?;
+    miette::set_panic_hook();
+
+    let options = parse_options()?;
+    let db = open_database(&options)?;
+
+    let mut shell = Shell::new(db, options.format.clone());
+    if options.scripts.is_empty() {
+        return run_interactive(&mut shell, &options);
+    }
+
+    for script in &options.scripts {
+        let flow = match script {
+            Script::Command(command) => {
+                let flow = shell.handle_line(command)?;
+                shell.finish()?;
+                flow
+            }
+            Script::File(path) => shell.run_file(path)?,
+        };
+        if flow == Flow::Quit {
+            break;
+        }
+    }
+
+    // Errors were reported as they happened, scripts only need to signal them
+    if shell.failed() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn parse_options() -> Result<Options> {
+    let mut options = Options {
+        data_dir: PathBuf::from("./db"),
+        init: false,
+        demo: false,
+        scripts: Vec::new(),
+        read_only: false,
+        format: OutputFormat::default(),
+    };
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| miette!("Missing value for {arg}\n\n{USAGE}"))
+        };
+        match arg.as_str() {
+            "--data-dir" => options.data_dir = PathBuf::from(value()?),
+            "--init" => options.init = true,
+            "--demo" => options.demo = true,
+            "-c" | "--command" => options.scripts.push(Script::Command(value()?)),
+            "-f" | "--file" => options.scripts.push(Script::File(PathBuf::from(value()?))),
+            "--read-only" => options.read_only = true,
+            "--format" => {
+                let style = value()?;
+                options.format.style = style
+                    .parse()
+                    .map_err(|_| miette!("Unknown format {style}\n\n{USAGE}"))?;
+            }
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                std::process::exit(0);
+            }
+            _ => return Err(miette!("Unknown option {arg}\n\n{USAGE}")),
+        }
+    }
+
+    if options.read_only && (options.init || options.demo) {
+        return Err(miette!(
+            "--read-only cannot be combined with --init or --demo"
+        ));
+    }
+    Ok(options)
+}
+
+fn open_database(options: &Options) -> Result<Database> {
+    let data_dir = &options.data_dir;
+    if options.read_only && !data_dir.is_dir() {
+        return Err(miette!(
+            "There is no database in {} to open read-only",
+            data_dir.display()
+        ));
+    }
+    if options.init {
+        remove_relations(data_dir)?;
+    }
+
+    let mut db = Database::open(data_dir)?;
+    if options.demo {
+        create_demo_tables(&mut db)?;
+    }
+    db.set_read_only(options.read_only);
+    Ok(db)
+}
+
+/// Deletes the files of every relation in the data directory, catalog included, and
+/// leaves anything else alone.
+fn remove_relations(data_dir: &Path) -> Result<()> {
+    let entries = match std::fs::read_dir(data_dir) {
+        Ok(entries) => entries,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(error) => return Err(error).into_diagnostic(),
+    };
+
+    for entry in entries {
+        let path = entry.into_diagnostic()?.path();
+        let is_relation = path
+            .extension()
+            .is_some_and(|extension| extension == "table" || extension == "fsm");
+        if is_relation {
+            std::fs::remove_file(&path)
+                .map_err(|error| miette!("Could not delete {}: {error}", path.display()))?;
+        }
+    }
+    Ok(())
+}
+
+fn run_interactive(shell: &mut Shell, options: &Options) -> Result<()> {
+    let mode = if options.read_only { ", read-only" } else { "" };
+    println!(
+        "Scuttle shell on {}{mode}. Type \\? for help, \\q to quit.",
+        options.data_dir.display()
+    );
+
+    let mut editor = DefaultEditor::new().into_diagnostic()?;
+    let history = history_path();
+    if let Some(history) = &history {
+        // There is no history before the first session
+        editor.load_history(history).ok();
+    }
+
+    loop {
+        let prompt = if shell.in_statement() { "*  " } else { "DB: " };
+        let line = match editor.readline(prompt) {
+            Ok(line) => line,
+            // Ctrl-C drops the statement being typed, like psql
+            Err(ReadlineError::Interrupted) => {
+                shell.clear();
+                continue;
+            }
+            Err(ReadlineError::Eof) => {
+                shell.finish()?;
+                break;
+            }
+            Err(error) => return Err(miette!("Input reading failed: {error}")),
+        };
+
+        if !line.trim().is_empty() {
+            editor.add_history_entry(line.as_str()).into_diagnostic()?;
+        }
+        if shell.handle_line(&line)? == Flow::Quit {
+            break;
+        }
+    }
+
+    if let Some(history) = &history {
+        editor.save_history(history).into_diagnostic()?;
+    }
+    println!("Exiting Scuttle");
+
+    Ok(())
+}
+
+/// History is kept in the home directory across sessions.
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(HISTORY_FILE))
+}
@@ -0,0 +1,113 @@
+use std::path::PathBuf;
+
+use scuttle_db::OutputStyle;
+
+pub const HELP: &str = "Meta-commands:
+  \\q              quit
+  \\?              show this help
+  \\dt             list tables
+  \\d TABLE        describe the columns of a table
+  \\timing [on|off] show how long each statement takes
+  \\i FILE         run the statements of a file
+  \\o [FILE]       send results to a file, or back to the terminal
+  \\pset format F   print results as aligned, csv, json, jsonl or markdown
+  \\pset null TEXT  show NULL as TEXT
+  \\x [on|off]      show every row as a list of columns
+
+Statements end with ';' and can span several lines.";
+
+/// A command of the shell itself, a line starting with a backslash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MetaCommand {
+    Quit,
+    Help,
+    ListTables,
+    Describe(String),
+
+    /// Turns timing on or off, toggles it if `None`.
+    Timing(Option<bool>),
+
+    Include(PathBuf),
+
+    /// Sends results to a file, back to the terminal if `None`.
+    Output(Option<PathBuf>),
+
+    Format(OutputStyle),
+    NullDisplay(String),
+
+    /// Turns the expanded layout on or off, toggles it if `None`.
+    Expanded(Option<bool>),
+}
+
+impl MetaCommand {
+    /// Parses a line such as `\d users`, the error says what is wrong with it.
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let line = line.trim().trim_start_matches('\\');
+        let (command, argument) = match line.split_once(char::is_whitespace) {
+            Some((command, argument)) => (command, Some(argument.trim())),
+            None => (line, None),
+        };
+        let argument = argument.filter(|argument| !argument.is_empty());
+
+        let required = |usage: &str| argument.ok_or_else(|| format!("Usage: \\{command} {usage}"));
+
+        Ok(match command {
+            "q" | "quit" => MetaCommand::Quit,
+            "?" | "help" => MetaCommand::Help,
+            "dt" => MetaCommand::ListTables,
+            "d" => match argument {
+                Some(table) => MetaCommand::Describe(table.to_string()),
+                None => MetaCommand::ListTables,
+            },
+            "timing" => MetaCommand::Timing(parse_switch("\\timing", argument)?),
+            "i" => MetaCommand::Include(PathBuf::from(required("FILE")?)),
+            "o" => MetaCommand::Output(argument.map(PathBuf::from)),
+            "x" => MetaCommand::Expanded(parse_switch("\\x", argument)?),
+            "pset" => parse_pset(argument)?,
+            _ => return Err(format!("Invalid command \\{command}. Try \\? for help.")),
+        })
+    }
+}
+
+/// Parses the option and value of `\pset`, e.g. `format csv`.
+fn parse_pset(argument: Option<&str>) -> Result<MetaCommand, String> {
+    let argument = argument.ok_or("Usage: \\pset format|null|expanded [VALUE]")?;
+    let (option, value) = match argument.split_once(char::is_whitespace) {
+        Some((option, value)) => (option, Some(value.trim())),
+        None => (argument, None),
+    };
+
+    Ok(match option {
+        "format" => {
+            let style = value.ok_or("Usage: \\pset format aligned|csv|json|jsonl|markdown")?;
+            let style = style.parse().map_err(|_| {
+                format!(
+                    "\\pset: unknown format \"{style}\", \
+                     expected aligned, csv, json, jsonl or markdown"
+                )
+            })?;
+            MetaCommand::Format(style)
+        }
+        // Quotes keep surrounding spaces, as in '(null) '
+        "null" => MetaCommand::NullDisplay(
+            value
+                .map(|value| value.trim_matches('\''))
+                .unwrap_or_default()
+                .to_string(),
+        ),
+        "expanded" | "x" => MetaCommand::Expanded(parse_switch("\\pset expanded", value)?),
+        _ => return Err(format!("\\pset: unknown option: {option}")),
+    })
+}
+
+/// Parses the `on` or `off` of a command such as `\timing`, `None` toggles the setting.
+fn parse_switch(command: &str, argument: Option<&str>) -> Result<Option<bool>, String> {
+    match argument {
+        None => Ok(None),
+        Some("on") => Ok(Some(true)),
+        Some("off") => Ok(Some(false)),
+        Some(other) => Err(format!(
+            "Unrecognized value \"{other}\" for {command}: expected on or off"
+        )),
+    }
+}
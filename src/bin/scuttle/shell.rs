@@ -0,0 +1,353 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write, stdout},
+    path::Path,
+    time::Instant,
+};
+
+use miette::{IntoDiagnostic, Result, miette};
+use scuttle_db::{
+    DataType, Database, Field, OutputFormat, OutputSchema, QueryResponse, Row, Value,
+};
+
+use crate::meta_command::{HELP, MetaCommand};
+
+/// Whether the shell goes on reading input after a line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Flow {
+    Continue,
+    Quit,
+}
+
+/// Runs statements and meta-commands read line by line, from the terminal or a file.
+///
+/// Statements end with `;` and can span several lines, the text of an unfinished one
+/// is kept until it ends.
+pub struct Shell {
+    db: Database,
+    buffer: String,
+    timing: bool,
+    format: OutputFormat,
+    output: Box<dyn Write>,
+
+    /// Whether a statement or meta-command failed, scripts then exit with an error.
+    failed: bool,
+}
+
+impl Shell {
+    pub fn new(db: Database, format: OutputFormat) -> Self {
+        Self {
+            db,
+            buffer: String::new(),
+            timing: false,
+            format,
+            output: Box::new(stdout()),
+            failed: false,
+        }
+    }
+
+    /// Whether any statement or meta-command failed so far.
+    pub fn failed(&self) -> bool {
+        self.failed
+    }
+
+    /// Whether a statement was started and not ended yet.
+    pub fn in_statement(&self) -> bool {
+        !self.buffer.trim().is_empty()
+    }
+
+    /// Drops the statement being typed.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+
+    /// Handles a line of input, running the statements it ends.
+    pub fn handle_line(&mut self, line: &str) -> Result<Flow> {
+        let trimmed = line.trim();
+        if !self.in_statement() && matches!(trimmed, "exit" | "quit" | ":q") {
+            return Ok(Flow::Quit);
+        }
+        if trimmed.starts_with('\\') {
+            return self.run_meta_command(trimmed);
+        }
+
+        self.buffer.push_str(line);
+        self.buffer.push('\n');
+        for statement in take_statements(&mut self.buffer) {
+            self.run_statement(&statement)?;
+        }
+        Ok(Flow::Continue)
+    }
+
+    /// Runs the statement left unfinished, e.g. the last one of a file without `;`.
+    pub fn finish(&mut self) -> Result<()> {
+        let statement = strip_comments(&std::mem::take(&mut self.buffer));
+        if !statement.trim().is_empty() {
+            self.run_statement(statement.trim())?;
+        }
+        self.output.flush().into_diagnostic()
+    }
+
+    /// Runs every line of a file, as if it was typed.
+    pub fn run_file(&mut self, path: &Path) -> Result<Flow> {
+        let script = std::fs::read_to_string(path)
+            .map_err(|error| miette!("Could not read {}: {error}", path.display()))?;
+
+        // The statement being typed when the file was included goes on afterwards
+        let typed = std::mem::take(&mut self.buffer);
+        let mut flow = Flow::Continue;
+        for line in script.lines() {
+            flow = self.handle_line(line)?;
+            if flow == Flow::Quit {
+                break;
+            }
+        }
+        if flow == Flow::Continue {
+            self.finish()?;
+        }
+        self.buffer = typed;
+
+        Ok(flow)
+    }
+
+    fn run_statement(&mut self, statement: &str) -> Result<()> {
+        let start = Instant::now();
+        let result = self.db.execute_query(statement);
+        let elapsed = start.elapsed();
+
+        match result {
+            // Statements such as VACUUM have no result to show
+            Ok(response) if response.schema.fields.is_empty() => {}
+            Ok(response) => response.write_as(&self.format, &mut self.output)?,
+            // Errors go to the terminal even when results go to a file
+            Err(error) => {
+                eprintln!("{:?}", error.with_source_code(statement.to_string()));
+                self.failed = true;
+            }
+        }
+        if self.timing {
+            writeln!(
+                self.output,
+                "Time: {:.3} ms",
+                elapsed.as_secs_f64() * 1000.0
+            )
+            .into_diagnostic()?;
+        }
+        self.output.flush().into_diagnostic()
+    }
+
+    fn run_meta_command(&mut self, line: &str) -> Result<Flow> {
+        let command = match MetaCommand::parse(line) {
+            Ok(command) => command,
+            Err(message) => {
+                eprintln!("{message}");
+                self.failed = true;
+                return Ok(Flow::Continue);
+            }
+        };
+
+        match command {
+            MetaCommand::Quit => return Ok(Flow::Quit),
+            MetaCommand::Help => writeln!(self.output, "{HELP}").into_diagnostic()?,
+            MetaCommand::ListTables => {
+                let rows = self
+                    .db
+                    .tables
+                    .keys()
+                    .map(|name| Row::new(vec![Value::Text(name.clone())]))
+                    .collect();
+                let response = QueryResponse {
+                    schema: output_schema(&[("name", DataType::Text)]),
+                    rows,
+                };
+                response.write_as(&self.format, &mut self.output)?;
+            }
+            MetaCommand::Describe(table_name) => match self.db.table_schema(&table_name) {
+                Ok(schema) => {
+                    let rows = schema
+                        .columns
+                        .iter()
+                        .map(|column| {
+                            Row::new(vec![
+                                Value::Text(column.name.clone()),
+                                Value::Text(column.data_type.to_string()),
+                                Value::Bool(column.nullable),
+                            ])
+                        })
+                        .collect();
+                    let response = QueryResponse {
+                        schema: output_schema(&[
+                            ("column", DataType::Text),
+                            ("type", DataType::Text),
+                            ("nullable", DataType::Bool),
+                        ]),
+                        rows,
+                    };
+                    response.write_as(&self.format, &mut self.output)?;
+                }
+                Err(error) => {
+                    eprintln!("{error}");
+                    self.failed = true;
+                }
+            },
+            MetaCommand::Timing(timing) => {
+                self.timing = timing.unwrap_or(!self.timing);
+                let state = if self.timing { "on" } else { "off" };
+                writeln!(self.output, "Timing is {state}.").into_diagnostic()?;
+            }
+            MetaCommand::Format(style) => {
+                self.format.style = style;
+                writeln!(self.output, "Output format is {style}.").into_diagnostic()?;
+            }
+            MetaCommand::NullDisplay(null_display) => {
+                writeln!(self.output, "Null display is \"{null_display}\".").into_diagnostic()?;
+                self.format.null_display = null_display;
+            }
+            MetaCommand::Expanded(expanded) => {
+                self.format.expanded = expanded.unwrap_or(!self.format.expanded);
+                let state = if self.format.expanded { "on" } else { "off" };
+                writeln!(self.output, "Expanded display is {state}.").into_diagnostic()?;
+            }
+            MetaCommand::Include(path) => match self.run_file(&path) {
+                Ok(flow) => return Ok(flow),
+                Err(error) => {
+                    eprintln!("{error}");
+                    self.failed = true;
+                }
+            },
+            MetaCommand::Output(path) => {
+                self.output.flush().into_diagnostic()?;
+                self.output = match path {
+                    Some(path) => match File::create(&path) {
+                        Ok(file) => Box::new(BufWriter::new(file)),
+                        Err(error) => {
+                            eprintln!("Could not open {}: {error}", path.display());
+                            self.failed = true;
+                            return Ok(Flow::Continue);
+                        }
+                    },
+                    None => Box::new(stdout()),
+                };
+            }
+        }
+
+        self.output.flush().into_diagnostic()?;
+        Ok(Flow::Continue)
+    }
+}
+
+fn output_schema(columns: &[(&str, DataType)]) -> OutputSchema {
+    OutputSchema {
+        fields: columns
+            .iter()
+            .map(|(name, data_type)| Field {
+                name: name.to_string(),
+                alias: None,
+                relation: None,
+                data_type: *data_type,
+                is_nullable: false,
+            })
+            .collect(),
+    }
+}
+
+/// Removes the statements ended by a `;` from the start of `buffer` and returns them,
+/// without comments.
+fn take_statements(buffer: &mut String) -> Vec<String> {
+    let text = strip_comments(buffer);
+
+    let mut statements = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+    for (index, char) in text.char_indices() {
+        match (quote, char) {
+            (None, '\'' | '"') => quote = Some(char),
+            (Some(open), _) if open == char => quote = None,
+            (None, ';') => {
+                let statement = text[start..index].trim();
+                if !statement.is_empty() {
+                    statements.push(statement.to_string());
+                }
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+
+    *buffer = text[start..].to_string();
+    if buffer.trim().is_empty() {
+        buffer.clear();
+    }
+    statements
+}
+
+/// Removes `--` comments, up to the end of their line, outside of quotes.
+fn strip_comments(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut quote = None;
+    let mut chars = text.chars().peekable();
+
+    while let Some(char) = chars.next() {
+        match (quote, char) {
+            (None, '-') if chars.peek() == Some(&'-') => {
+                // Skip to the end of the line, keeping the newline
+                while chars.peek().is_some_and(|next| *next != '\n') {
+                    chars.next();
+                }
+                continue;
+            }
+            (None, '\'' | '"') => quote = Some(char),
+            (Some(open), _) if open == char => quote = None,
+            _ => {}
+        }
+        stripped.push(char);
+    }
+
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use scuttle_db::OutputStyle;
+
+    use super::*;
+
+    #[test]
+    fn test_statements_end_with_semicolons() {
+        let mut buffer = "SELECT 1; SELECT ';' -- a; comment\nFROM t;\nSELECT".to_string();
+        assert_eq!(
+            take_statements(&mut buffer),
+            vec!["SELECT 1", "SELECT ';' \nFROM t"]
+        );
+        assert_eq!(buffer, "\nSELECT");
+
+        let mut buffer = "SELECT 'a;\n".to_string();
+        assert!(take_statements(&mut buffer).is_empty());
+        assert_eq!(buffer, "SELECT 'a;\n");
+    }
+
+    #[test]
+    fn test_parse_meta_commands() {
+        assert_eq!(
+            MetaCommand::parse("\\d  users "),
+            Ok(MetaCommand::Describe("users".to_string()))
+        );
+        assert_eq!(
+            MetaCommand::parse("\\timing on"),
+            Ok(MetaCommand::Timing(Some(true)))
+        );
+        assert_eq!(MetaCommand::parse("\\o"), Ok(MetaCommand::Output(None)));
+        assert_eq!(
+            MetaCommand::parse("\\pset format jsonl"),
+            Ok(MetaCommand::Format(OutputStyle::JsonLines))
+        );
+        assert_eq!(
+            MetaCommand::parse("\\pset null '(null) '"),
+            Ok(MetaCommand::NullDisplay("(null) ".to_string()))
+        );
+        assert_eq!(MetaCommand::parse("\\x"), Ok(MetaCommand::Expanded(None)));
+        assert!(MetaCommand::parse("\\pset format yaml").is_err());
+        assert!(MetaCommand::parse("\\i").is_err());
+        assert!(MetaCommand::parse("\\nope").is_err());
+    }
+}
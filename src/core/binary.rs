@@ -0,0 +1,166 @@
+//! Text representations of binary values: hex strings for `BYTEA` and the
+//! canonical form of `UUID`s, plus random version 4 UUIDs.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::DatabaseError;
+
+/// Lowercase hex encoding of `bytes`.
+pub fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Decodes a hex string with an even number of digits, in either case.
+pub fn decode_hex(hex: &str) -> Result<Vec<u8>, DatabaseError> {
+    let invalid = || DatabaseError::TypeMismatch(format!("Invalid hexadecimal data: \"{hex}\""));
+
+    if !hex.len().is_multiple_of(2) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            hex.get(i..i + 2)
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(invalid)
+        })
+        .collect()
+}
+
+/// Parses the text form of a `BYTEA`: `\x` followed by hex digits, or otherwise the
+/// UTF-8 bytes of the text itself.
+pub fn parse_bytea(text: &str) -> Result<Vec<u8>, DatabaseError> {
+    match text.strip_prefix("\\x") {
+        Some(hex) => decode_hex(hex),
+        None => Ok(text.as_bytes().to_vec()),
+    }
+}
+
+/// Formats a `BYTEA` in hex output format, e.g. `\xdeadbeef`.
+pub fn format_bytea(bytes: &[u8]) -> String {
+    format!("\\x{}", encode_hex(bytes))
+}
+
+/// Parses a UUID, accepting the canonical hyphenated form as well as plain 32 hex
+/// digits, optionally in braces.
+pub fn parse_uuid(text: &str) -> Result<u128, DatabaseError> {
+    let invalid =
+        || DatabaseError::TypeMismatch(format!("Invalid input syntax for type Uuid: \"{text}\""));
+
+    let trimmed = text.trim();
+    let unbraced = trimmed
+        .strip_prefix('{')
+        .and_then(|t| t.strip_suffix('}'))
+        .unwrap_or(trimmed);
+
+    let hyphenated = unbraced.len() == 36
+        && [8, 13, 18, 23]
+            .iter()
+            .all(|&i| unbraced.as_bytes()[i] == b'-');
+    let digits: String = if hyphenated {
+        unbraced.chars().filter(|&c| c != '-').collect()
+    } else {
+        unbraced.to_string()
+    };
+
+    if digits.len() != 32 || !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+    u128::from_str_radix(&digits, 16).map_err(|_| invalid())
+}
+
+/// Formats a UUID in its canonical form, e.g. `a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11`.
+pub fn format_uuid(uuid: u128) -> String {
+    let hex = format!("{uuid:032x}");
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[0..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..32]
+    )
+}
+
+/// Generates a random version 4 UUID.
+///
+/// Randomness comes from the standard library's randomly keyed hasher mixed with the
+/// clock and a counter. It is unique enough for keys but not cryptographically secure.
+pub fn random_uuid() -> u128 {
+    let random = ((random_u64() as u128) << 64) | random_u64() as u128;
+
+    // Set the version (4) and the RFC 4122 variant bits
+    (random & !(0xF << 76) & !(0x3 << 62)) | (0x4 << 76) | (0x2 << 62)
+}
+
+fn random_u64() -> u64 {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos());
+
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+    hasher.write_u128(nanos);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_round_trip() {
+        assert_eq!(encode_hex(&[0xDE, 0xAD, 0xBE, 0xEF]), "deadbeef");
+        assert_eq!(
+            decode_hex("DEADbeef").unwrap(),
+            vec![0xDE, 0xAD, 0xBE, 0xEF]
+        );
+        assert!(decode_hex("abc").is_err());
+        assert!(decode_hex("zz").is_err());
+        assert!(decode_hex("+f").is_err());
+    }
+
+    #[test]
+    fn test_bytea_text_forms() {
+        assert_eq!(parse_bytea("\\x0102").unwrap(), vec![1, 2]);
+        assert_eq!(parse_bytea("ab").unwrap(), b"ab".to_vec());
+        assert_eq!(format_bytea(&[0, 255]), "\\x00ff");
+    }
+
+    #[test]
+    fn test_uuid_parsing() {
+        let canonical = "a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11";
+        let uuid = parse_uuid(canonical).unwrap();
+        assert_eq!(format_uuid(uuid), canonical);
+
+        assert_eq!(
+            parse_uuid("A0EEBC999C0B4EF8BB6D6BB9BD380A11").unwrap(),
+            uuid
+        );
+        assert_eq!(
+            parse_uuid("{a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11}").unwrap(),
+            uuid
+        );
+        assert!(parse_uuid("a0eebc99-9c0b-4ef8-bb6d").is_err());
+        assert!(parse_uuid("g0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11").is_err());
+    }
+
+    #[test]
+    fn test_random_uuid_is_version_4() {
+        let a = random_uuid();
+        let b = random_uuid();
+        assert_ne!(a, b);
+
+        let formatted = format_uuid(a);
+        assert_eq!(&formatted[14..15], "4");
+        assert!(matches!(&formatted[19..20], "8" | "9" | "a" | "b"));
+    }
+}
@@ -0,0 +1,213 @@
+//! Conversions between [`Value`] and Rust types, for callers reading query results.
+
+use crate::{DatabaseError, Decimal, Interval, Json, Value};
+
+/// A type a [`Value`] can be decoded into, see [`Row::get`](crate::Row::get).
+///
+/// Implemented for the Rust types matching the SQL types. `Option<T>` decodes NULL
+/// as `None`, the other types fail on NULL.
+pub trait FromValue: Sized {
+    fn from_value(value: Value) -> Result<Self, DatabaseError>;
+}
+
+fn mismatch(value: &Value, target: &str) -> DatabaseError {
+    match value {
+        Value::Null => DatabaseError::TypeMismatch(format!(
+            "Cannot convert NULL to {target}, use Option<{target}> for nullable values"
+        )),
+        value => DatabaseError::TypeMismatch(format!("Cannot convert {value:?} to {target}")),
+    }
+}
+
+impl FromValue for Value {
+    fn from_value(value: Value) -> Result<Self, DatabaseError> {
+        Ok(value)
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: Value) -> Result<Self, DatabaseError> {
+        match value {
+            Value::Null => Ok(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+}
+
+/// Implements [`FromValue`] and `TryFrom<Value>` for a type through a match on the
+/// variants it can be read from.
+macro_rules! from_value {
+    ($target:ty, $name:literal, |$value:ident| $convert:expr) => {
+        impl FromValue for $target {
+            fn from_value($value: Value) -> Result<Self, DatabaseError> {
+                let converted: Option<$target> = $convert;
+                converted.ok_or_else(|| mismatch(&$value, $name))
+            }
+        }
+
+        impl TryFrom<Value> for $target {
+            type Error = DatabaseError;
+
+            fn try_from(value: Value) -> Result<Self, DatabaseError> {
+                Self::from_value(value)
+            }
+        }
+    };
+}
+
+// Integers convert to any integer type they fit into
+from_value!(i64, "i64", |value| match value {
+    Value::Int16(n) => Some(n.into()),
+    Value::Int32(n) => Some(n.into()),
+    Value::Int64(n) => Some(n),
+    _ => None,
+});
+from_value!(i32, "i32", |value| match value {
+    Value::Int16(n) => Some(n.into()),
+    Value::Int32(n) => Some(n),
+    Value::Int64(n) => n.try_into().ok(),
+    _ => None,
+});
+from_value!(i16, "i16", |value| match value {
+    Value::Int16(n) => Some(n),
+    Value::Int32(n) => n.try_into().ok(),
+    Value::Int64(n) => n.try_into().ok(),
+    _ => None,
+});
+from_value!(f64, "f64", |value| match value {
+    Value::Float64(n) => Some(n),
+    _ => None,
+});
+from_value!(bool, "bool", |value| match value {
+    Value::Bool(b) => Some(b),
+    _ => None,
+});
+from_value!(String, "String", |value| match &value {
+    Value::Text(text) => Some(text.clone()),
+    _ => None,
+});
+from_value!(Vec<u8>, "Vec<u8>", |value| match &value {
+    Value::Bytea(bytes) => Some(bytes.clone()),
+    _ => None,
+});
+from_value!(Decimal, "Decimal", |value| match &value {
+    Value::Decimal(decimal) => Some(*decimal),
+    _ => None,
+});
+from_value!(Interval, "Interval", |value| match value {
+    Value::Interval(interval) => Some(interval),
+    _ => None,
+});
+from_value!(Json, "Json", |value| match &value {
+    Value::Json(json) => Some(json.clone()),
+    _ => None,
+});
+
+impl From<i16> for Value {
+    fn from(value: i16) -> Self {
+        Value::Int16(value)
+    }
+}
+
+impl From<i32> for Value {
+    fn from(value: i32) -> Self {
+        Value::Int32(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int64(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float64(value)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::Text(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::Text(value.to_string())
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(value: Vec<u8>) -> Self {
+        Value::Bytea(value)
+    }
+}
+
+impl From<Decimal> for Value {
+    fn from(value: Decimal) -> Self {
+        Value::Decimal(value)
+    }
+}
+
+impl From<Interval> for Value {
+    fn from(value: Interval) -> Self {
+        Value::Interval(value)
+    }
+}
+
+impl From<Json> for Value {
+    fn from(value: Json) -> Self {
+        Value::Json(value)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Value::Null, Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_values_convert_to_rust_types() {
+        assert_eq!(i64::try_from(Value::Int32(7)).unwrap(), 7);
+        assert_eq!(i16::try_from(Value::Int64(-3)).unwrap(), -3);
+        assert!(i16::try_from(Value::Int64(70_000)).is_err());
+        assert_eq!(f64::try_from(Value::Float64(1.5)).unwrap(), 1.5);
+        assert!(bool::try_from(Value::Bool(true)).unwrap());
+        assert_eq!(
+            String::try_from(Value::Text("Alice".to_string())).unwrap(),
+            "Alice"
+        );
+
+        assert_eq!(Option::<i64>::from_value(Value::Null).unwrap(), None);
+        assert_eq!(
+            Option::<i64>::from_value(Value::Int64(30)).unwrap(),
+            Some(30)
+        );
+
+        let error = i64::try_from(Value::Null).unwrap_err();
+        assert!(error.to_string().contains("Option<i64>"), "{error}");
+        let error = i64::try_from(Value::Text("30".to_string())).unwrap_err();
+        assert!(matches!(error, DatabaseError::TypeMismatch(_)), "{error}");
+    }
+
+    #[test]
+    fn test_rust_types_convert_to_values() {
+        assert!(matches!(Value::from(30_i64), Value::Int64(30)));
+        assert!(matches!(Value::from("Bob"), Value::Text(text) if text == "Bob"));
+        assert!(matches!(Value::from(None::<bool>), Value::Null));
+        assert!(matches!(Value::from(Some(1.5)), Value::Float64(_)));
+    }
+}
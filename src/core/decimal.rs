@@ -0,0 +1,466 @@
+//! Exact fixed-point decimal numbers backing `NUMERIC`/`DECIMAL` columns.
+//!
+//! A [`Decimal`] is an `i128` mantissa with a decimal scale, `mantissa * 10^-scale`,
+//! which holds up to 38 significant digits. Rounding follows SQL: half away from zero.
+
+use std::{cmp::Ordering, fmt, hash::Hash, str::FromStr};
+
+use crate::DatabaseError;
+
+/// Maximum number of significant digits of a decimal.
+pub const MAX_PRECISION: u8 = 38;
+
+/// Minimum scale of a quotient, so `1 / 3` does not truncate to 0.
+const MIN_DIVISION_SCALE: u8 = 6;
+
+/// `10^38`, the smallest magnitude that does not fit in 38 digits.
+const MANTISSA_LIMIT: i128 = 10i128.pow(MAX_PRECISION as u32);
+
+/// An exact decimal number.
+///
+/// Equality, ordering and hashing compare the numeric value, so `1.0 == 1.00`.
+#[derive(Debug, Clone, Copy)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u8,
+}
+
+impl Decimal {
+    /// Creates `mantissa * 10^-scale`, `None` if it does not fit in 38 digits.
+    pub fn new(mantissa: i128, scale: u8) -> Option<Self> {
+        (mantissa.abs() < MANTISSA_LIMIT && scale <= MAX_PRECISION)
+            .then_some(Self { mantissa, scale })
+    }
+
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    pub fn scale(&self) -> u8 {
+        self.scale
+    }
+
+    /// Number of significant digits left of the decimal point.
+    pub fn integer_digits(&self) -> u8 {
+        let integer = self.mantissa.unsigned_abs() / 10u128.pow(self.scale as u32);
+        if integer == 0 {
+            0
+        } else {
+            integer.ilog10() as u8 + 1
+        }
+    }
+
+    /// Whether the value fits in `NUMERIC(precision, scale)` once rounded to `scale`.
+    pub fn fits(&self, precision: u8, scale: u8) -> bool {
+        self.rescale(scale)
+            .is_some_and(|d| d.integer_digits() <= precision.saturating_sub(scale))
+    }
+
+    /// Changes the scale, rounding half away from zero when digits are dropped.
+    pub fn rescale(&self, scale: u8) -> Option<Self> {
+        match scale.cmp(&self.scale) {
+            Ordering::Equal => Some(*self),
+            Ordering::Greater => {
+                let factor = 10i128.checked_pow((scale - self.scale) as u32)?;
+                Self::new(self.mantissa.checked_mul(factor)?, scale)
+            }
+            Ordering::Less => {
+                let divisor = 10i128.pow((self.scale - scale) as u32);
+                Self::new(div_round(self.mantissa, divisor), scale)
+            }
+        }
+    }
+
+    /// Rounds to `NUMERIC(precision, scale)`, failing if the value does not fit.
+    pub fn round_to(&self, precision: u8, scale: u8) -> Result<Self, DatabaseError> {
+        self.rescale(scale)
+            .filter(|d| d.integer_digits() <= precision.saturating_sub(scale))
+            .ok_or_else(|| {
+                DatabaseError::TypeMismatch(format!(
+                    "Numeric value {self} out of range for Numeric({precision}, {scale})"
+                ))
+            })
+    }
+
+    /// Brings both values to the larger scale of the two.
+    fn align(&self, other: &Self) -> Option<(i128, i128, u8)> {
+        let scale = self.scale.max(other.scale);
+        Some((
+            self.rescale(scale)?.mantissa,
+            other.rescale(scale)?.mantissa,
+            scale,
+        ))
+    }
+
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        let (a, b, scale) = self.align(other)?;
+        Self::new(a.checked_add(b)?, scale)
+    }
+
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        let (a, b, scale) = self.align(other)?;
+        Self::new(a.checked_sub(b)?, scale)
+    }
+
+    /// The exact product, rounded if its scale would exceed 38.
+    pub fn checked_mul(&self, other: &Self) -> Option<Self> {
+        let mantissa = self.mantissa.checked_mul(other.mantissa)?;
+        let scale = self.scale as u32 + other.scale as u32;
+
+        if scale > MAX_PRECISION as u32 {
+            let divisor = 10i128.checked_pow(scale - MAX_PRECISION as u32)?;
+            return Self::new(div_round(mantissa, divisor), MAX_PRECISION);
+        }
+        Self::new(mantissa, scale as u8)
+    }
+
+    /// The quotient rounded to [`Decimal::division_scale`], `None` on division by zero.
+    pub fn checked_div(&self, other: &Self) -> Option<Self> {
+        if other.mantissa == 0 {
+            return None;
+        }
+
+        let scale = Self::division_scale(self.scale, other.scale);
+        let exponent = scale as u32 + other.scale as u32 - self.scale as u32;
+        let numerator = self.mantissa.checked_mul(10i128.checked_pow(exponent)?)?;
+
+        Self::new(div_round(numerator, other.mantissa), scale)
+    }
+
+    /// Scale of the quotient of decimals with the given scales.
+    pub fn division_scale(left: u8, right: u8) -> u8 {
+        left.max(right).max(MIN_DIVISION_SCALE)
+    }
+
+    pub fn checked_neg(&self) -> Option<Self> {
+        Self::new(self.mantissa.checked_neg()?, self.scale)
+    }
+
+    pub fn abs(&self) -> Self {
+        Self {
+            mantissa: self.mantissa.abs(),
+            scale: self.scale,
+        }
+    }
+
+    /// Converts a float through its shortest round-trip representation, so `0.1` is
+    /// exactly `0.1`. `None` for NaN, infinities and values beyond 38 digits.
+    pub fn from_f64(value: f64) -> Option<Self> {
+        if !value.is_finite() {
+            return None;
+        }
+        value.to_string().parse().ok()
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        // Parsing the decimal representation is correctly rounded
+        self.to_string().parse().unwrap_or(f64::NAN)
+    }
+
+    /// Rounds to an integer, `None` if it does not fit in an `i64`.
+    pub fn to_i64(&self) -> Option<i64> {
+        i64::try_from(self.rescale(0)?.mantissa).ok()
+    }
+
+    /// Strips trailing fractional zeros, giving a canonical form for hashing.
+    fn normalize(&self) -> Self {
+        let mut normalized = *self;
+        while normalized.scale > 0 && normalized.mantissa % 10 == 0 {
+            normalized.mantissa /= 10;
+            normalized.scale -= 1;
+        }
+        normalized
+    }
+
+    /// Compact storage encoding: scale, byte length, then the minimal little-endian
+    /// two's complement mantissa.
+    pub fn encode(&self, bytes: &mut Vec<u8>) {
+        let mantissa = self.mantissa.to_le_bytes();
+        let sign_byte = if self.mantissa < 0 { 0xFF } else { 0x00 };
+
+        // Drop redundant sign extension bytes, keeping the sign bit intact
+        let mut length = mantissa.len();
+        while length > 1
+            && mantissa[length - 1] == sign_byte
+            && (mantissa[length - 2] & 0x80) == (sign_byte & 0x80)
+        {
+            length -= 1;
+        }
+
+        bytes.push(self.scale);
+        bytes.push(length as u8);
+        bytes.extend_from_slice(&mantissa[..length]);
+    }
+
+    /// Decodes a value written by [`Decimal::encode`], returning it and the bytes read.
+    pub fn decode(bytes: &[u8]) -> Option<(Self, usize)> {
+        let scale = *bytes.first()?;
+        let length = *bytes.get(1)? as usize;
+        let mantissa_bytes = bytes.get(2..2 + length)?;
+        if length == 0 || length > 16 {
+            return None;
+        }
+
+        let sign_byte = if mantissa_bytes[length - 1] & 0x80 != 0 {
+            0xFF
+        } else {
+            0x00
+        };
+        let mut mantissa = [sign_byte; 16];
+        mantissa[..length].copy_from_slice(mantissa_bytes);
+
+        let decimal = Self::new(i128::from_le_bytes(mantissa), scale)?;
+        Some((decimal, 2 + length))
+    }
+}
+
+/// Divides rounding half away from zero.
+fn div_round(numerator: i128, divisor: i128) -> i128 {
+    let quotient = numerator / divisor;
+    let remainder = numerator % divisor;
+
+    if remainder.unsigned_abs() * 2 >= divisor.unsigned_abs() {
+        if (numerator < 0) == (divisor < 0) {
+            quotient + 1
+        } else {
+            quotient - 1
+        }
+    } else {
+        quotient
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(value: i64) -> Self {
+        Self {
+            mantissa: value as i128,
+            scale: 0,
+        }
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = DatabaseError;
+
+    /// Parses `[+-]digits[.digits][e[+-]digits]`.
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            DatabaseError::TypeMismatch(format!(
+                "Invalid input syntax for type Numeric: \"{input}\""
+            ))
+        };
+        let out_of_range =
+            || DatabaseError::TypeMismatch(format!("Numeric value \"{input}\" is out of range"));
+
+        let trimmed = input.trim();
+        let (number, exponent) = match trimmed.find(['e', 'E']) {
+            Some(index) => (
+                &trimmed[..index],
+                trimmed[index + 1..].parse::<i32>().map_err(|_| invalid())?,
+            ),
+            None => (trimmed, 0),
+        };
+
+        let (negative, digits) = match number.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, number.strip_prefix('+').unwrap_or(number)),
+        };
+        let (integer, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+        if integer.is_empty() && fraction.is_empty()
+            || !integer
+                .chars()
+                .chain(fraction.chars())
+                .all(|c| c.is_ascii_digit())
+        {
+            return Err(invalid());
+        }
+
+        let mut mantissa: i128 = 0;
+        for digit in integer.chars().chain(fraction.chars()) {
+            mantissa = mantissa
+                .checked_mul(10)
+                .and_then(|m| m.checked_add(digit as i128 - '0' as i128))
+                .ok_or_else(out_of_range)?;
+        }
+        if negative {
+            mantissa = -mantissa;
+        }
+
+        let scale = fraction.len() as i32 - exponent;
+        let decimal = if scale < 0 {
+            let factor = 10i128
+                .checked_pow(scale.unsigned_abs())
+                .ok_or_else(out_of_range)?;
+            Self::new(mantissa.checked_mul(factor).ok_or_else(out_of_range)?, 0)
+        } else if scale > MAX_PRECISION as i32 {
+            // Too many fractional digits, round them away
+            let divisor = 10i128
+                .checked_pow((scale - MAX_PRECISION as i32) as u32)
+                .unwrap_or(i128::MAX);
+            Self::new(div_round(mantissa, divisor), MAX_PRECISION)
+        } else {
+            Self::new(mantissa, scale as u8)
+        };
+
+        decimal.ok_or_else(out_of_range)
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.mantissa.unsigned_abs().to_string();
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let scale = self.scale as usize;
+
+        if scale == 0 {
+            return write!(f, "{sign}{digits}");
+        }
+
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (integer, fraction) = digits.split_at(digits.len() - scale);
+        write!(f, "{sign}{integer}.{fraction}")
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.align(other) {
+            Some((a, b, _)) => a.cmp(&b),
+            // Only the side with the smaller scale can overflow when scaled up, in
+            // which case its magnitude exceeds anything the other side can hold
+            None if self.scale < other.scale => self.mantissa.cmp(&0),
+            None => 0.cmp(&other.mantissa),
+        }
+    }
+}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl Hash for Decimal {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        let normalized = self.normalize();
+        normalized.mantissa.hash(state);
+        normalized.scale.hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(s: &str) -> Decimal {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!(dec("19.99").to_string(), "19.99");
+        assert_eq!(dec("-0.05").to_string(), "-0.05");
+        assert_eq!(dec("+7").to_string(), "7");
+        assert_eq!(dec("1.5e2").to_string(), "150");
+        assert_eq!(dec("12e-3").to_string(), "0.012");
+        assert_eq!(dec(".5").to_string(), "0.5");
+        assert!("1.2.3".parse::<Decimal>().is_err());
+        assert!("abc".parse::<Decimal>().is_err());
+        assert!("1e40".parse::<Decimal>().is_err());
+    }
+
+    #[test]
+    fn test_exact_arithmetic() {
+        assert_eq!(
+            dec("0.1").checked_add(&dec("0.2")).unwrap().to_string(),
+            "0.3"
+        );
+        assert_eq!(
+            dec("10.00").checked_sub(&dec("0.015")).unwrap().to_string(),
+            "9.985"
+        );
+        assert_eq!(
+            dec("19.99").checked_mul(&dec("3")).unwrap().to_string(),
+            "59.97"
+        );
+        assert_eq!(
+            dec("1").checked_div(&dec("3")).unwrap().to_string(),
+            "0.333333"
+        );
+        assert_eq!(
+            dec("2").checked_div(&dec("3")).unwrap().to_string(),
+            "0.666667"
+        );
+        assert!(dec("1").checked_div(&dec("0.00")).is_none());
+    }
+
+    #[test]
+    fn test_round_half_away_from_zero() {
+        assert_eq!(dec("2.345").rescale(2).unwrap().to_string(), "2.35");
+        assert_eq!(dec("-2.345").rescale(2).unwrap().to_string(), "-2.35");
+        assert_eq!(dec("2.344").rescale(2).unwrap().to_string(), "2.34");
+        assert_eq!(dec("0.5").to_i64(), Some(1));
+        assert_eq!(dec("-0.5").to_i64(), Some(-1));
+    }
+
+    #[test]
+    fn test_precision() {
+        assert!(dec("999.994").fits(5, 2));
+        assert!(!dec("999.995").fits(5, 2));
+        assert!(dec("123.456").round_to(3, 1).is_err());
+        assert_eq!(dec("123.456").round_to(4, 1).unwrap().to_string(), "123.5");
+    }
+
+    #[test]
+    fn test_equality_ignores_scale() {
+        assert_eq!(dec("1.0"), dec("1.00"));
+        assert!(dec("1.01") > dec("1.001"));
+        assert!(dec("-1") < dec("0.000001"));
+
+        let huge = Decimal::new(MANTISSA_LIMIT - 1, 0).unwrap();
+        assert!(huge > dec("0.5"));
+        assert!(huge.checked_neg().unwrap() < dec("-0.5"));
+    }
+
+    #[test]
+    fn test_encode_round_trip() {
+        for value in [
+            "0",
+            "1",
+            "-1",
+            "127",
+            "128",
+            "-128",
+            "-129",
+            "19.99",
+            "-12345678.90",
+        ] {
+            let decimal = dec(value);
+            let mut bytes = Vec::new();
+            decimal.encode(&mut bytes);
+
+            let (decoded, read) = Decimal::decode(&bytes).unwrap();
+            assert_eq!(read, bytes.len());
+            assert_eq!(decoded.to_string(), decimal.to_string());
+        }
+
+        let mut bytes = Vec::new();
+        dec("19.99").encode(&mut bytes);
+        assert_eq!(bytes.len(), 4);
+    }
+
+    #[test]
+    fn test_from_f64() {
+        assert_eq!(Decimal::from_f64(0.1).unwrap().to_string(), "0.1");
+        assert_eq!(dec("19.99").to_f64(), 19.99);
+        assert!(Decimal::from_f64(f64::NAN).is_none());
+    }
+}
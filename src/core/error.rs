@@ -26,4 +26,16 @@ pub enum DatabaseError {
     /// The SQL query is invalid or malformed.
     #[error("Invalid query: {0}")]
     InvalidQuery(String),
+
+    /// A page read from disk failed its checksum, e.g. after a torn write.
+    #[error("Page {page_id} of table {table} is corrupted")]
+    Corruption { table: String, page_id: u32 },
+
+    /// The database was opened read-only and the operation would change it.
+    #[error("The database is read-only")]
+    ReadOnly,
+
+    /// Waiting for a lock on the table would have deadlocked with other sessions.
+    #[error("Deadlock detected while locking table {0}")]
+    Deadlock(String),
 }
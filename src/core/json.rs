@@ -0,0 +1,773 @@
+//! JSON documents backing `JSON`/`JSONB` columns.
+//!
+//! Text is validated by [`Json::parse`] and stored in pages in a compact tagged binary
+//! form, so reading a value back does not reparse it. Object keys are kept sorted and
+//! deduplicated (the last duplicate wins), like PostgreSQL's `jsonb`. Numbers are kept
+//! exactly, as decimal text, and must be within the range of a float.
+
+use std::{cmp::Ordering, collections::BTreeMap, fmt, str::FromStr};
+
+use crate::DatabaseError;
+
+/// Maximum nesting depth accepted by the parser and the decoder.
+const MAX_DEPTH: usize = 128;
+
+const TAG_NULL: u8 = 0;
+const TAG_FALSE: u8 = 1;
+const TAG_TRUE: u8 = 2;
+const TAG_NUMBER: u8 = 3;
+const TAG_STRING: u8 = 4;
+const TAG_ARRAY: u8 = 5;
+const TAG_OBJECT: u8 = 6;
+
+/// A parsed JSON document.
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(JsonNumber),
+    String(String),
+    Array(Vec<Json>),
+    Object(BTreeMap<String, Json>),
+}
+
+/// A JSON number, kept as its exact decimal digits rather than as a float.
+///
+/// The text is normalized: exponents are expanded and leading and trailing zeros
+/// dropped, so `-3.50e2` is kept as `-350` and equal numbers have equal text.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct JsonNumber(String);
+
+impl JsonNumber {
+    /// Normalizes the text of a number matching the JSON grammar, `None` if it is too
+    /// large or too small in magnitude to be read as a float.
+    fn normalize(text: &str) -> Option<Self> {
+        let value = text.parse::<f64>().ok()?;
+        let (negative, unsigned) = match text.strip_prefix('-') {
+            Some(unsigned) => (true, unsigned),
+            None => (false, text),
+        };
+        let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+            Some((mantissa, exponent)) => (mantissa, exponent),
+            None => (unsigned, "0"),
+        };
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+        let digits = format!("{integer}{fraction}");
+
+        if digits.bytes().all(|digit| digit == b'0') {
+            return Some(Self("0".to_string()));
+        }
+        // Non-zero numbers within the range of a float have a small exponent
+        if !value.is_finite() || value == 0.0 {
+            return None;
+        }
+        let exponent = exponent.parse::<i64>().ok()?;
+
+        // Position of the decimal point in `digits`
+        let point = integer.len() as i64 + exponent;
+        let (integer, fraction) = if point <= 0 {
+            (String::new(), "0".repeat(-point as usize) + &digits)
+        } else if point as usize >= digits.len() {
+            (
+                digits.clone() + &"0".repeat(point as usize - digits.len()),
+                String::new(),
+            )
+        } else {
+            let (integer, fraction) = digits.split_at(point as usize);
+            (integer.to_string(), fraction.to_string())
+        };
+        let integer = match integer.trim_start_matches('0') {
+            "" => "0",
+            integer => integer,
+        };
+        let fraction = fraction.trim_end_matches('0');
+
+        let sign = if negative { "-" } else { "" };
+        Some(match fraction {
+            "" => Self(format!("{sign}{integer}")),
+            fraction => Self(format!("{sign}{integer}.{fraction}")),
+        })
+    }
+
+    /// The number closest to a float, `None` for NaN and infinities.
+    pub fn from_f64(value: f64) -> Option<Self> {
+        // Floats are displayed without exponent, as the shortest text reading back the same
+        Self::normalize(&value.to_string())
+    }
+
+    /// The float closest to the number.
+    pub fn to_f64(&self) -> f64 {
+        self.0
+            .parse()
+            .expect("numbers are kept as decimal text within the range of a float")
+    }
+
+    /// The sign, integer digits and fractional digits of the number.
+    fn parts(&self) -> (bool, &str, &str) {
+        let (negative, unsigned) = match self.0.strip_prefix('-') {
+            Some(unsigned) => (true, unsigned),
+            None => (false, self.0.as_str()),
+        };
+        let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        (negative, integer, fraction)
+    }
+}
+
+impl Ord for JsonNumber {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (negative, integer, fraction) = self.parts();
+        let (other_negative, other_integer, other_fraction) = other.parts();
+
+        // Without leading and trailing zeros, longer integer parts are larger and
+        // fractions compare digit by digit
+        let magnitude = integer
+            .len()
+            .cmp(&other_integer.len())
+            .then_with(|| integer.cmp(other_integer))
+            .then_with(|| fraction.cmp(other_fraction));
+        match (negative, other_negative) {
+            (false, false) => magnitude,
+            (true, true) => magnitude.reverse(),
+            (false, true) => Ordering::Greater,
+            (true, false) => Ordering::Less,
+        }
+    }
+}
+
+impl PartialOrd for JsonNumber {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for JsonNumber {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// One step of a path into a document: an object key or an array index.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PathStep {
+    Key(String),
+    Index(i64),
+}
+
+impl Json {
+    /// Parses and validates JSON text.
+    pub fn parse(text: &str) -> Result<Self, DatabaseError> {
+        let mut parser = Parser {
+            text,
+            bytes: text.as_bytes(),
+            position: 0,
+        };
+
+        let json = parser.parse_value(0)?;
+        parser.skip_whitespace();
+        if parser.position != parser.bytes.len() {
+            return Err(parser.error("unexpected trailing characters"));
+        }
+        Ok(json)
+    }
+
+    /// The name of the value's type as reported by `json_typeof`.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Json::Null => "null",
+            Json::Bool(_) => "boolean",
+            Json::Number(_) => "number",
+            Json::String(_) => "string",
+            Json::Array(_) => "array",
+            Json::Object(_) => "object",
+        }
+    }
+
+    /// The member of an object by key, `None` for a missing key or a non-object.
+    pub fn get_key(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(members) => members.get(key),
+            _ => None,
+        }
+    }
+
+    /// The element of an array by index, negative indexes count from the end.
+    pub fn get_index(&self, index: i64) -> Option<&Json> {
+        match self {
+            Json::Array(elements) => {
+                let index = if index < 0 {
+                    elements.len() as i64 + index
+                } else {
+                    index
+                };
+                usize::try_from(index).ok().and_then(|i| elements.get(i))
+            }
+            _ => None,
+        }
+    }
+
+    /// Follows `path` from this value, `None` as soon as a step is missing.
+    ///
+    /// A key step applied to an array is treated as an index when it is an integer,
+    /// so the text path `{items,0}` works as in PostgreSQL's `#>`.
+    pub fn get_path(&self, path: &[PathStep]) -> Option<&Json> {
+        path.iter().try_fold(self, |current, step| match step {
+            PathStep::Key(key) => match current {
+                Json::Array(_) => key.parse().ok().and_then(|i| current.get_index(i)),
+                _ => current.get_key(key),
+            },
+            PathStep::Index(index) => current.get_index(*index),
+        })
+    }
+
+    /// The text of a value as returned by `->>`: strings unquoted, everything else
+    /// serialized. `None` for JSON `null`.
+    pub fn to_text(&self) -> Option<String> {
+        match self {
+            Json::Null => None,
+            Json::String(s) => Some(s.clone()),
+            other => Some(other.to_string()),
+        }
+    }
+
+    /// Appends the binary form of the document to `bytes`.
+    pub fn encode(&self, bytes: &mut Vec<u8>) {
+        match self {
+            Json::Null => bytes.push(TAG_NULL),
+            Json::Bool(false) => bytes.push(TAG_FALSE),
+            Json::Bool(true) => bytes.push(TAG_TRUE),
+            Json::Number(n) => {
+                bytes.push(TAG_NUMBER);
+                encode_str(&n.0, bytes);
+            }
+            Json::String(s) => {
+                bytes.push(TAG_STRING);
+                encode_str(s, bytes);
+            }
+            Json::Array(elements) => {
+                bytes.push(TAG_ARRAY);
+                bytes.extend_from_slice(&(elements.len() as u32).to_le_bytes());
+                for element in elements {
+                    element.encode(bytes);
+                }
+            }
+            Json::Object(members) => {
+                bytes.push(TAG_OBJECT);
+                bytes.extend_from_slice(&(members.len() as u32).to_le_bytes());
+                for (key, value) in members {
+                    encode_str(key, bytes);
+                    value.encode(bytes);
+                }
+            }
+        }
+    }
+
+    /// Decodes a value written by [`Json::encode`], returning it and the bytes read.
+    pub fn decode(bytes: &[u8]) -> Option<(Self, usize)> {
+        let mut offset = 0;
+        let json = decode_value(bytes, &mut offset, 0)?;
+        Some((json, offset))
+    }
+}
+
+impl FromStr for Json {
+    type Err = DatabaseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Bool(b) => write!(f, "{b}"),
+            Json::Number(n) => write!(f, "{n}"),
+            Json::String(s) => write_quoted(f, s),
+            Json::Array(elements) => {
+                write!(f, "[")?;
+                for (i, element) in elements.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{element}")?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in members.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_quoted(f, key)?;
+                    write!(f, ": {value}")?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// Parses a PostgreSQL text array path such as `{a,b,0}` into key steps.
+pub fn parse_text_path(text: &str) -> Result<Vec<PathStep>, DatabaseError> {
+    let inner = text
+        .trim()
+        .strip_prefix('{')
+        .and_then(|t| t.strip_suffix('}'))
+        .ok_or_else(|| DatabaseError::TypeMismatch(format!("Malformed path: \"{text}\"")))?;
+
+    if inner.trim().is_empty() {
+        return Ok(vec![]);
+    }
+
+    Ok(inner
+        .split(',')
+        .map(|step| PathStep::Key(step.trim().trim_matches('"').to_string()))
+        .collect())
+}
+
+/// Parses a JSONPath subset used by `json_extract`: `$`, `.key`, `."quoted key"` and
+/// `[index]` steps, e.g. `$.items[0].name`.
+pub fn parse_json_path(text: &str) -> Result<Vec<PathStep>, DatabaseError> {
+    let invalid = || DatabaseError::TypeMismatch(format!("Invalid JSON path: \"{text}\""));
+
+    let mut rest = text.trim().strip_prefix('$').ok_or_else(invalid)?;
+    let mut steps = Vec::new();
+
+    while !rest.is_empty() {
+        if let Some(after_dot) = rest.strip_prefix('.') {
+            let (key, remaining) = if let Some(quoted) = after_dot.strip_prefix('"') {
+                let end = quoted.find('"').ok_or_else(invalid)?;
+                (&quoted[..end], &quoted[end + 1..])
+            } else {
+                let end = after_dot.find(['.', '[']).unwrap_or(after_dot.len());
+                (&after_dot[..end], &after_dot[end..])
+            };
+
+            if key.is_empty() {
+                return Err(invalid());
+            }
+            steps.push(PathStep::Key(key.to_string()));
+            rest = remaining;
+        } else if let Some(after_bracket) = rest.strip_prefix('[') {
+            let end = after_bracket.find(']').ok_or_else(invalid)?;
+            let index = after_bracket[..end].trim().parse().map_err(|_| invalid())?;
+            steps.push(PathStep::Index(index));
+            rest = &after_bracket[end + 1..];
+        } else {
+            return Err(invalid());
+        }
+    }
+
+    Ok(steps)
+}
+
+fn write_quoted(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in s.chars() {
+        match c {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    write!(f, "\"")
+}
+
+fn encode_str(s: &str, bytes: &mut Vec<u8>) {
+    bytes.extend_from_slice(&(s.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(s.as_bytes());
+}
+
+fn decode_u32(bytes: &[u8], offset: &mut usize) -> Option<usize> {
+    let raw = bytes.get(*offset..*offset + 4)?;
+    *offset += 4;
+    Some(u32::from_le_bytes(raw.try_into().ok()?) as usize)
+}
+
+fn decode_str(bytes: &[u8], offset: &mut usize) -> Option<String> {
+    let length = decode_u32(bytes, offset)?;
+    let raw = bytes.get(*offset..*offset + length)?;
+    *offset += length;
+    String::from_utf8(raw.to_vec()).ok()
+}
+
+fn decode_value(bytes: &[u8], offset: &mut usize, depth: usize) -> Option<Json> {
+    if depth > MAX_DEPTH {
+        return None;
+    }
+
+    let tag = *bytes.get(*offset)?;
+    *offset += 1;
+
+    let json = match tag {
+        TAG_NULL => Json::Null,
+        TAG_FALSE => Json::Bool(false),
+        TAG_TRUE => Json::Bool(true),
+        TAG_NUMBER => Json::Number(JsonNumber::normalize(&decode_str(bytes, offset)?)?),
+        TAG_STRING => Json::String(decode_str(bytes, offset)?),
+        TAG_ARRAY => {
+            let count = decode_u32(bytes, offset)?;
+            let elements = (0..count)
+                .map(|_| decode_value(bytes, offset, depth + 1))
+                .collect::<Option<_>>()?;
+            Json::Array(elements)
+        }
+        TAG_OBJECT => {
+            let count = decode_u32(bytes, offset)?;
+            let members = (0..count)
+                .map(|_| {
+                    let key = decode_str(bytes, offset)?;
+                    Some((key, decode_value(bytes, offset, depth + 1)?))
+                })
+                .collect::<Option<_>>()?;
+            Json::Object(members)
+        }
+        _ => return None,
+    };
+
+    Some(json)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, reason: &str) -> DatabaseError {
+        DatabaseError::TypeMismatch(format!(
+            "Invalid input syntax for type Json at position {}: {reason}",
+            self.position
+        ))
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(
+            self.bytes.get(self.position),
+            Some(b' ' | b'\t' | b'\n' | b'\r')
+        ) {
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), DatabaseError> {
+        self.skip_whitespace();
+        if self.bytes.get(self.position) == Some(&byte) {
+            self.position += 1;
+            Ok(())
+        } else {
+            Err(self.error(&format!("expected '{}'", byte as char)))
+        }
+    }
+
+    fn parse_value(&mut self, depth: usize) -> Result<Json, DatabaseError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("document is nested too deeply"));
+        }
+
+        self.skip_whitespace();
+        match self.bytes.get(self.position) {
+            Some(b'{') => self.parse_object(depth),
+            Some(b'[') => self.parse_array(depth),
+            Some(b'"') => Ok(Json::String(self.parse_string()?)),
+            Some(b'-' | b'0'..=b'9') => self.parse_number(),
+            Some(_) => self.parse_literal(),
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn parse_literal(&mut self) -> Result<Json, DatabaseError> {
+        let rest = &self.text[self.position..];
+        for (word, json) in [
+            ("null", Json::Null),
+            ("true", Json::Bool(true)),
+            ("false", Json::Bool(false)),
+        ] {
+            if rest.starts_with(word) {
+                self.position += word.len();
+                return Ok(json);
+            }
+        }
+        Err(self.error("unexpected token"))
+    }
+
+    fn parse_number(&mut self) -> Result<Json, DatabaseError> {
+        let start = self.position;
+        let digits = |parser: &mut Self| {
+            let from = parser.position;
+            while matches!(parser.bytes.get(parser.position), Some(b'0'..=b'9')) {
+                parser.position += 1;
+            }
+            parser.position > from
+        };
+
+        if self.bytes.get(self.position) == Some(&b'-') {
+            self.position += 1;
+        }
+        let integer_start = self.position;
+        if !digits(self) {
+            return Err(self.error("invalid number"));
+        }
+        if self.bytes[integer_start] == b'0' && self.position - integer_start > 1 {
+            return Err(self.error("leading zeros are not allowed"));
+        }
+        if self.bytes.get(self.position) == Some(&b'.') {
+            self.position += 1;
+            if !digits(self) {
+                return Err(self.error("invalid number"));
+            }
+        }
+        if matches!(self.bytes.get(self.position), Some(b'e' | b'E')) {
+            self.position += 1;
+            if matches!(self.bytes.get(self.position), Some(b'+' | b'-')) {
+                self.position += 1;
+            }
+            if !digits(self) {
+                return Err(self.error("invalid number"));
+            }
+        }
+
+        JsonNumber::normalize(&self.text[start..self.position])
+            .map(Json::Number)
+            .ok_or_else(|| self.error("number out of range"))
+    }
+
+    fn parse_string(&mut self) -> Result<String, DatabaseError> {
+        self.expect(b'"')?;
+        let mut result = String::new();
+
+        loop {
+            let Some(c) = self.text[self.position..].chars().next() else {
+                return Err(self.error("unterminated string"));
+            };
+            self.position += c.len_utf8();
+
+            match c {
+                '"' => return Ok(result),
+                '\\' => result.push(self.parse_escape()?),
+                c if (c as u32) < 0x20 => {
+                    return Err(self.error("control characters must be escaped"));
+                }
+                c => result.push(c),
+            }
+        }
+    }
+
+    fn parse_escape(&mut self) -> Result<char, DatabaseError> {
+        let escaped = *self
+            .bytes
+            .get(self.position)
+            .ok_or_else(|| self.error("unterminated string"))?;
+        self.position += 1;
+
+        let c = match escaped {
+            b'"' => '"',
+            b'\\' => '\\',
+            b'/' => '/',
+            b'b' => '\u{8}',
+            b'f' => '\u{c}',
+            b'n' => '\n',
+            b'r' => '\r',
+            b't' => '\t',
+            b'u' => {
+                let high = self.parse_hex4()?;
+                let code = if (0xD800..0xDC00).contains(&high) {
+                    // A high surrogate must be followed by an escaped low surrogate
+                    if !self.text[self.position..].starts_with("\\u") {
+                        return Err(self.error("unpaired surrogate"));
+                    }
+                    self.position += 2;
+                    let low = self.parse_hex4()?;
+                    if !(0xDC00..0xE000).contains(&low) {
+                        return Err(self.error("unpaired surrogate"));
+                    }
+                    0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+                } else {
+                    high
+                };
+                char::from_u32(code).ok_or_else(|| self.error("invalid unicode escape"))?
+            }
+            _ => return Err(self.error("invalid escape")),
+        };
+
+        Ok(c)
+    }
+
+    fn parse_hex4(&mut self) -> Result<u32, DatabaseError> {
+        let hex = self
+            .text
+            .get(self.position..self.position + 4)
+            .filter(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("invalid unicode escape"))?;
+        self.position += 4;
+        u32::from_str_radix(hex, 16).map_err(|_| self.error("invalid unicode escape"))
+    }
+
+    fn parse_array(&mut self, depth: usize) -> Result<Json, DatabaseError> {
+        self.expect(b'[')?;
+        let mut elements = Vec::new();
+
+        self.skip_whitespace();
+        if self.bytes.get(self.position) == Some(&b']') {
+            self.position += 1;
+            return Ok(Json::Array(elements));
+        }
+
+        loop {
+            elements.push(self.parse_value(depth + 1)?);
+            self.skip_whitespace();
+            match self.bytes.get(self.position) {
+                Some(b',') => self.position += 1,
+                Some(b']') => {
+                    self.position += 1;
+                    return Ok(Json::Array(elements));
+                }
+                _ => return Err(self.error("expected ',' or ']'")),
+            }
+        }
+    }
+
+    fn parse_object(&mut self, depth: usize) -> Result<Json, DatabaseError> {
+        self.expect(b'{')?;
+        let mut members = BTreeMap::new();
+
+        self.skip_whitespace();
+        if self.bytes.get(self.position) == Some(&b'}') {
+            self.position += 1;
+            return Ok(Json::Object(members));
+        }
+
+        loop {
+            self.skip_whitespace();
+            let key = self.parse_string()?;
+            self.expect(b':')?;
+            members.insert(key, self.parse_value(depth + 1)?);
+
+            self.skip_whitespace();
+            match self.bytes.get(self.position) {
+                Some(b',') => self.position += 1,
+                Some(b'}') => {
+                    self.position += 1;
+                    return Ok(Json::Object(members));
+                }
+                _ => return Err(self.error("expected ',' or '}'")),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_display() {
+        let json = Json::parse(r#" {"b": [1, 2.5, -3e2], "a": {"x": null, "y": true}} "#).unwrap();
+        assert_eq!(
+            json.to_string(),
+            r#"{"a": {"x": null, "y": true}, "b": [1, 2.5, -300]}"#
+        );
+
+        let escaped = Json::parse(r#""tab\t quote\" \u00e9 \ud83d\ude00""#).unwrap();
+        assert_eq!(escaped, Json::String("tab\t quote\" é 😀".to_string()));
+        assert_eq!(escaped.to_string(), r#""tab\t quote\" é 😀""#);
+
+        let duplicate = Json::parse(r#"{"k": 1, "k": 2}"#).unwrap();
+        assert_eq!(duplicate.to_string(), r#"{"k": 2}"#);
+    }
+
+    #[test]
+    fn test_numbers_are_exact() {
+        for (text, expected) in [
+            ("9007199254740993", "9007199254740993"),
+            ("-12345678901234567890.125", "-12345678901234567890.125"),
+            ("1.50", "1.5"),
+            ("-0.0", "0"),
+            ("2.5e-3", "0.0025"),
+            ("-3.50E2", "-350"),
+            ("1e20", "100000000000000000000"),
+        ] {
+            assert_eq!(Json::parse(text).unwrap().to_string(), expected);
+        }
+
+        let number = |text| match Json::parse(text).unwrap() {
+            Json::Number(number) => number,
+            other => panic!("Expected a number, got {other}"),
+        };
+        assert_eq!(number("1.0"), number("1"));
+        assert!(number("9007199254740993") > number("9007199254740992"));
+        assert!(number("-10") < number("-9.5"));
+        assert!(number("0.25") < number("0.3"));
+        assert_eq!(number("0.1").to_f64(), 0.1);
+        assert_eq!(JsonNumber::from_f64(0.1), Some(number("0.1")));
+        assert_eq!(JsonNumber::from_f64(f64::INFINITY), None);
+    }
+
+    #[test]
+    fn test_parse_rejects_invalid_documents() {
+        for text in [
+            "",
+            "{",
+            "[1,]",
+            "{\"a\" 1}",
+            "01",
+            "1.",
+            "tru",
+            "\"unterminated",
+            "\"\\ud83d\"",
+            "[1] 2",
+            "{'a': 1}",
+            "1e400",
+            "-1e400",
+            "1e-400",
+        ] {
+            assert!(Json::parse(text).is_err(), "{text:?} should be rejected");
+        }
+
+        let deep = "[".repeat(MAX_DEPTH + 2) + &"]".repeat(MAX_DEPTH + 2);
+        assert!(Json::parse(&deep).is_err());
+    }
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        let json =
+            Json::parse(r#"{"items": [1, "two", false, null, {"n": -0.5}], "s": ""}"#).unwrap();
+
+        let mut bytes = vec![];
+        json.encode(&mut bytes);
+        assert_eq!(Json::decode(&bytes), Some((json, bytes.len())));
+        assert_eq!(Json::decode(&bytes[..bytes.len() - 1]), None);
+    }
+
+    #[test]
+    fn test_paths() {
+        let json = Json::parse(r#"{"a": {"b": [10, 20, {"c": "deep"}]}}"#).unwrap();
+
+        let path = parse_text_path("{a,b,2,c}").unwrap();
+        assert_eq!(
+            json.get_path(&path),
+            Some(&Json::String("deep".to_string()))
+        );
+        assert_eq!(json.get_path(&parse_text_path("{a,x}").unwrap()), None);
+
+        let path = parse_json_path("$.a.b[-2]").unwrap();
+        assert_eq!(json.get_path(&path), Some(&Json::parse("20").unwrap()));
+        assert_eq!(json.get_path(&parse_json_path("$").unwrap()), Some(&json));
+        assert!(parse_json_path("a.b").is_err());
+        assert!(parse_json_path("$.a[x]").is_err());
+
+        assert_eq!(Json::Null.to_text(), None);
+        assert_eq!(
+            Json::String("s".to_string()).to_text(),
+            Some("s".to_string())
+        );
+        assert_eq!(json.type_name(), "object");
+    }
+}
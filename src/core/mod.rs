@@ -1,3 +1,8 @@
+pub(crate) mod binary;
+pub(crate) mod convert;
+pub(crate) mod decimal;
 pub(crate) mod error;
+pub(crate) mod json;
 pub(crate) mod serialization;
+pub(crate) mod temporal;
 pub(crate) mod types;
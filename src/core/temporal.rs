@@ -0,0 +1,883 @@
+//! Calendar arithmetic, parsing and formatting for date and time values.
+//!
+//! Timestamps are microseconds since `1970-01-01 00:00:00` UTC and dates are days
+//! since `1970-01-01`, both using the proleptic Gregorian calendar. Conversions
+//! between days and civil dates follow Howard Hinnant's `days_from_civil` and
+//! `civil_from_days` algorithms.
+
+use std::{
+    cmp::Ordering,
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::DatabaseError;
+
+pub const MICROS_PER_MILLI: i64 = 1_000;
+pub const MICROS_PER_SECOND: i64 = 1_000_000;
+pub const MICROS_PER_MINUTE: i64 = 60 * MICROS_PER_SECOND;
+pub const MICROS_PER_HOUR: i64 = 60 * MICROS_PER_MINUTE;
+pub const MICROS_PER_DAY: i64 = 24 * MICROS_PER_HOUR;
+
+/// Days per month when comparing or converting intervals, as PostgreSQL does.
+const DAYS_PER_MONTH: i64 = 30;
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+const DAY_NAMES: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+
+/// A span of time.
+///
+/// Months and days are kept apart from the time part because their length depends
+/// on the date they are applied to, e.g. `'1 month'` added to January 31st.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Interval {
+    pub months: i32,
+    pub days: i32,
+    pub micros: i64,
+}
+
+impl Interval {
+    pub fn new(months: i32, days: i32, micros: i64) -> Self {
+        Self {
+            months,
+            days,
+            micros,
+        }
+    }
+
+    /// Length of the interval in microseconds, counting a month as 30 days.
+    pub fn total_micros(&self) -> i128 {
+        (self.months as i128 * DAYS_PER_MONTH as i128 + self.days as i128) * MICROS_PER_DAY as i128
+            + self.micros as i128
+    }
+
+    pub fn checked_add(&self, other: &Interval) -> Option<Interval> {
+        Some(Interval {
+            months: self.months.checked_add(other.months)?,
+            days: self.days.checked_add(other.days)?,
+            micros: self.micros.checked_add(other.micros)?,
+        })
+    }
+
+    pub fn checked_neg(&self) -> Option<Interval> {
+        Some(Interval {
+            months: self.months.checked_neg()?,
+            days: self.days.checked_neg()?,
+            micros: self.micros.checked_neg()?,
+        })
+    }
+
+    /// Multiplies the interval, fractional months and days spill into the smaller units.
+    pub fn mul_f64(&self, factor: f64) -> Option<Interval> {
+        let months = self.months as f64 * factor;
+        let whole_months = months.trunc();
+
+        let days = self.days as f64 * factor + months.fract() * DAYS_PER_MONTH as f64;
+        let whole_days = days.trunc();
+
+        let micros = (self.micros as f64 * factor + days.fract() * MICROS_PER_DAY as f64).round();
+
+        let in_range =
+            |value: f64, min: f64, max: f64| value.is_finite() && value >= min && value <= max;
+        if !in_range(whole_months, i32::MIN as f64, i32::MAX as f64)
+            || !in_range(whole_days, i32::MIN as f64, i32::MAX as f64)
+            || !in_range(micros, i64::MIN as f64, i64::MAX as f64)
+        {
+            return None;
+        }
+
+        Some(Interval {
+            months: whole_months as i32,
+            days: whole_days as i32,
+            micros: micros as i64,
+        })
+    }
+}
+
+impl PartialOrd for Interval {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        // Order by length first, then by fields so the ordering agrees with equality
+        Some(
+            self.total_micros()
+                .cmp(&other.total_micros())
+                .then_with(|| {
+                    (self.months, self.days, self.micros).cmp(&(
+                        other.months,
+                        other.days,
+                        other.micros,
+                    ))
+                }),
+        )
+    }
+}
+
+impl fmt::Display for Interval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut parts = Vec::new();
+
+        let years = self.months / 12;
+        let months = self.months % 12;
+        let plural = |n: i32, unit: &str| {
+            if n == 1 {
+                format!("{n} {unit}")
+            } else {
+                format!("{n} {unit}s")
+            }
+        };
+
+        if years != 0 {
+            parts.push(plural(years, "year"));
+        }
+        if months != 0 {
+            parts.push(if months == 1 {
+                format!("{months} mon")
+            } else {
+                format!("{months} mons")
+            });
+        }
+        if self.days != 0 {
+            parts.push(plural(self.days, "day"));
+        }
+        if self.micros != 0 || parts.is_empty() {
+            let sign = if self.micros < 0 { "-" } else { "" };
+            let micros = self.micros.unsigned_abs();
+            let mut time = format!(
+                "{sign}{:02}:{:02}:{:02}",
+                micros / MICROS_PER_HOUR as u64,
+                micros / MICROS_PER_MINUTE as u64 % 60,
+                micros / MICROS_PER_SECOND as u64 % 60
+            );
+            push_fraction(&mut time, micros % MICROS_PER_SECOND as u64);
+            parts.push(time);
+        }
+
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+/// Broken down parts of a timestamp.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DateTime {
+    pub year: i64,
+    pub month: u32,
+    pub day: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+    pub micros: u32,
+}
+
+impl DateTime {
+    pub fn from_timestamp(timestamp: i64) -> Self {
+        let days = timestamp.div_euclid(MICROS_PER_DAY);
+        let time = timestamp.rem_euclid(MICROS_PER_DAY);
+        let (year, month, day) = civil_from_days(days);
+
+        Self {
+            year,
+            month,
+            day,
+            hour: (time / MICROS_PER_HOUR) as u32,
+            minute: (time / MICROS_PER_MINUTE % 60) as u32,
+            second: (time / MICROS_PER_SECOND % 60) as u32,
+            micros: (time % MICROS_PER_SECOND) as u32,
+        }
+    }
+
+    pub fn to_timestamp(self) -> Option<i64> {
+        let days = days_from_civil(self.year, self.month, self.day);
+        let time = self.hour as i64 * MICROS_PER_HOUR
+            + self.minute as i64 * MICROS_PER_MINUTE
+            + self.second as i64 * MICROS_PER_SECOND
+            + self.micros as i64;
+
+        days.checked_mul(MICROS_PER_DAY)?.checked_add(time)
+    }
+
+    /// Day of the week, 0 is Sunday.
+    pub fn weekday(&self) -> u32 {
+        (days_from_civil(self.year, self.month, self.day) + 4).rem_euclid(7) as u32
+    }
+
+    /// Day of the year, starting at 1.
+    pub fn day_of_year(&self) -> u32 {
+        (days_from_civil(self.year, self.month, self.day) - days_from_civil(self.year, 1, 1) + 1)
+            as u32
+    }
+}
+
+/// Days since 1970-01-01 of the given civil date.
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Civil date (year, month, day) of the given number of days since 1970-01-01.
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+pub fn is_leap_year(year: i64) -> bool {
+    year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+}
+
+pub fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        _ if is_leap_year(year) => 29,
+        _ => 28,
+    }
+}
+
+/// The current time as a timestamp.
+pub fn now() -> i64 {
+    let elapsed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system clock is after 1970");
+    elapsed.as_micros() as i64
+}
+
+/// Adds an interval to a timestamp.
+///
+/// Months are added first and the day is clamped to the length of the resulting
+/// month, so `2024-01-31 + 1 month` is `2024-02-29`.
+pub fn add_interval(timestamp: i64, interval: &Interval) -> Result<i64, DatabaseError> {
+    let out_of_range = || DatabaseError::TypeMismatch("Timestamp out of range".to_string());
+
+    let mut date_time = DateTime::from_timestamp(timestamp);
+    if interval.months != 0 {
+        let months = date_time.year * 12 + date_time.month as i64 - 1 + interval.months as i64;
+        date_time.year = months.div_euclid(12);
+        date_time.month = months.rem_euclid(12) as u32 + 1;
+        date_time.day = date_time
+            .day
+            .min(days_in_month(date_time.year, date_time.month));
+    }
+
+    date_time
+        .to_timestamp()
+        .and_then(|ts| ts.checked_add((interval.days as i64).checked_mul(MICROS_PER_DAY)?))
+        .and_then(|ts| ts.checked_add(interval.micros))
+        .ok_or_else(out_of_range)
+}
+
+/// The interval between two timestamps, as whole days plus the remaining time.
+pub fn timestamp_difference(left: i64, right: i64) -> Result<Interval, DatabaseError> {
+    let micros = left
+        .checked_sub(right)
+        .ok_or_else(|| DatabaseError::TypeMismatch("Interval out of range".to_string()))?;
+    let days = micros / MICROS_PER_DAY;
+
+    Ok(Interval {
+        months: 0,
+        days: i32::try_from(days)
+            .map_err(|_| DatabaseError::TypeMismatch("Interval out of range".to_string()))?,
+        micros: micros % MICROS_PER_DAY,
+    })
+}
+
+fn invalid_input(type_name: &str, input: &str) -> DatabaseError {
+    DatabaseError::TypeMismatch(format!(
+        "Invalid input syntax for type {type_name}: \"{input}\""
+    ))
+}
+
+/// Parses an ISO-8601 date, `YYYY-MM-DD`.
+pub fn parse_date(input: &str) -> Result<i32, DatabaseError> {
+    let invalid = || invalid_input("Date", input);
+
+    let (year, month, day) = parse_date_part(input.trim()).ok_or_else(invalid)?;
+    i32::try_from(days_from_civil(year, month, day)).map_err(|_| invalid())
+}
+
+fn parse_date_part(input: &str) -> Option<(i64, u32, u32)> {
+    let mut parts = input.splitn(3, '-');
+    let year_part = parts.next()?;
+    let month_part = parts.next()?;
+    let day_part = parts.next()?;
+
+    if year_part.len() < 4 || month_part.len() != 2 || day_part.len() != 2 {
+        return None;
+    }
+
+    let year: i64 = year_part.parse().ok()?;
+    let month: u32 = month_part.parse().ok()?;
+    let day: u32 = day_part.parse().ok()?;
+
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+
+    Some((year, month, day))
+}
+
+/// Parses an ISO-8601 timestamp.
+///
+/// Accepts `YYYY-MM-DD`, optionally followed by a space or `T` and `HH:MM[:SS[.ffffff]]`,
+/// and an optional `Z` or `±HH[:MM]` offset, which is converted to UTC.
+pub fn parse_timestamp(input: &str) -> Result<i64, DatabaseError> {
+    let invalid = || invalid_input("Timestamp", input);
+    let trimmed = input.trim();
+
+    let (date_part, time_part) = match trimmed.find(['T', 't', ' ']) {
+        Some(index) => (&trimmed[..index], Some(trimmed[index + 1..].trim())),
+        None => (trimmed, None),
+    };
+    let (year, month, day) = parse_date_part(date_part).ok_or_else(invalid)?;
+
+    let mut date_time = DateTime {
+        year,
+        month,
+        day,
+        hour: 0,
+        minute: 0,
+        second: 0,
+        micros: 0,
+    };
+
+    let mut offset_micros = 0;
+    if let Some(time_part) = time_part {
+        let (time, offset) = split_offset(time_part).ok_or_else(invalid)?;
+        offset_micros = offset;
+
+        let (hour, minute, second, micros) = parse_time(time).ok_or_else(invalid)?;
+        if hour > 23 || minute > 59 || second > 59 {
+            return Err(invalid());
+        }
+        date_time.hour = hour;
+        date_time.minute = minute;
+        date_time.second = second;
+        date_time.micros = micros;
+    }
+
+    date_time
+        .to_timestamp()
+        .and_then(|ts| ts.checked_sub(offset_micros))
+        .ok_or_else(invalid)
+}
+
+/// Splits a trailing `Z` or `±HH[:MM]` UTC offset from a time, returning the offset in micros.
+fn split_offset(time: &str) -> Option<(&str, i64)> {
+    if let Some(time) = time.strip_suffix(['Z', 'z']) {
+        return Some((time.trim_end(), 0));
+    }
+
+    let Some(index) = time.rfind(['+', '-']) else {
+        return Some((time, 0));
+    };
+
+    let sign = if time[index..].starts_with('-') {
+        -1
+    } else {
+        1
+    };
+    let offset = &time[index + 1..];
+    let (hours, minutes) = match offset.split_once(':') {
+        Some((hours, minutes)) => (hours, minutes),
+        None if offset.len() == 4 => offset.split_at(2),
+        None => (offset, "0"),
+    };
+    let hours: i64 = hours.parse().ok()?;
+    let minutes: i64 = minutes.parse().ok()?;
+    if hours > 15 || minutes > 59 {
+        return None;
+    }
+
+    Some((
+        time[..index].trim_end(),
+        sign * (hours * MICROS_PER_HOUR + minutes * MICROS_PER_MINUTE),
+    ))
+}
+
+/// Parses `HH:MM[:SS[.ffffff]]` into hours, minutes, seconds and microseconds.
+fn parse_time(time: &str) -> Option<(u32, u32, u32, u32)> {
+    let mut parts = time.splitn(3, ':');
+    let hour: u32 = parts.next()?.parse().ok()?;
+    let minute: u32 = parts.next()?.parse().ok()?;
+
+    let (second, micros) = match parts.next() {
+        None => (0, 0),
+        Some(seconds) => {
+            let (whole, fraction) = seconds.split_once('.').unwrap_or((seconds, ""));
+            if fraction.len() > 6 || !fraction.chars().all(|c| c.is_ascii_digit()) {
+                return None;
+            }
+            let micros = if fraction.is_empty() {
+                0
+            } else {
+                format!("{fraction:0<6}").parse().ok()?
+            };
+            (whole.parse().ok()?, micros)
+        }
+    };
+
+    Some((hour, minute, second, micros))
+}
+
+/// Parses a PostgreSQL style interval, e.g. `'1 year 2 months'`, `'3 days 04:05:06'`
+/// or `'-1.5 hours'`.
+pub fn parse_interval(input: &str) -> Result<Interval, DatabaseError> {
+    let invalid = || invalid_input("Interval", input);
+
+    let mut months = 0f64;
+    let mut days = 0f64;
+    let mut micros = 0f64;
+    let mut seen_any = false;
+
+    let mut tokens = input.split_whitespace().peekable();
+    while let Some(token) = tokens.next() {
+        seen_any = true;
+
+        // A bare time, e.g. `04:05:06` or `-01:30`
+        if token.contains(':') {
+            let (sign, time) = match token.strip_prefix('-') {
+                Some(time) => (-1.0, time),
+                None => (1.0, token.strip_prefix('+').unwrap_or(token)),
+            };
+            let (hour, minute, second, fraction) = parse_time(time).ok_or_else(invalid)?;
+            if minute > 59 || second > 59 {
+                return Err(invalid());
+            }
+            micros += sign
+                * (hour as f64 * MICROS_PER_HOUR as f64
+                    + minute as f64 * MICROS_PER_MINUTE as f64
+                    + second as f64 * MICROS_PER_SECOND as f64
+                    + fraction as f64);
+            continue;
+        }
+
+        // A number, either followed by its unit or with the unit attached (`10d`)
+        let split = token
+            .find(|c: char| c.is_alphabetic())
+            .unwrap_or(token.len());
+        let (number, attached_unit) = token.split_at(split);
+        let amount: f64 = number.parse().map_err(|_| invalid())?;
+        let unit = if attached_unit.is_empty() {
+            tokens.next().ok_or_else(invalid)?
+        } else {
+            attached_unit
+        };
+
+        match unit.to_lowercase().trim_end_matches(',') {
+            "microsecond" | "microseconds" | "us" => micros += amount,
+            "millisecond" | "milliseconds" | "ms" => micros += amount * MICROS_PER_MILLI as f64,
+            "second" | "seconds" | "sec" | "secs" | "s" => {
+                micros += amount * MICROS_PER_SECOND as f64;
+            }
+            "minute" | "minutes" | "min" | "mins" | "m" => {
+                micros += amount * MICROS_PER_MINUTE as f64;
+            }
+            "hour" | "hours" | "hr" | "hrs" | "h" => micros += amount * MICROS_PER_HOUR as f64,
+            "day" | "days" | "d" => days += amount,
+            "week" | "weeks" | "w" => days += amount * 7.0,
+            "month" | "months" | "mon" | "mons" => months += amount,
+            "year" | "years" | "yr" | "yrs" | "y" => months += amount * 12.0,
+            "decade" | "decades" => months += amount * 120.0,
+            "century" | "centuries" => months += amount * 1200.0,
+            _ => return Err(invalid()),
+        }
+    }
+
+    if !seen_any {
+        return Err(invalid());
+    }
+
+    // Fractional months and days spill over into the smaller units
+    let interval = Interval::new(0, 0, 0)
+        .checked_add(&Interval::new(1, 0, 0).mul_f64(months).ok_or_else(invalid)?)
+        .and_then(|i| i.checked_add(&Interval::new(0, 1, 0).mul_f64(days)?))
+        .and_then(|i| i.checked_add(&Interval::new(0, 0, 1).mul_f64(micros)?))
+        .ok_or_else(invalid)?;
+
+    Ok(interval)
+}
+
+pub fn format_date(days: i32) -> String {
+    let (year, month, day) = civil_from_days(days as i64);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Formats a timestamp as `YYYY-MM-DD HH:MM:SS[.ffffff]`.
+pub fn format_timestamp(timestamp: i64) -> String {
+    let dt = DateTime::from_timestamp(timestamp);
+    let mut formatted = format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        dt.year, dt.month, dt.day, dt.hour, dt.minute, dt.second
+    );
+    push_fraction(&mut formatted, dt.micros as u64);
+    formatted
+}
+
+/// Appends fractional seconds without trailing zeros, if there are any.
+fn push_fraction(formatted: &mut String, micros: u64) {
+    if micros != 0 {
+        let fraction = format!(".{micros:06}");
+        formatted.push_str(fraction.trim_end_matches('0'));
+    }
+}
+
+/// Extracts a field from a timestamp, as done by `EXTRACT(field FROM timestamp)`.
+pub fn extract(field: &str, timestamp: i64) -> Result<f64, DatabaseError> {
+    let dt = DateTime::from_timestamp(timestamp);
+    let seconds = dt.second as f64 + dt.micros as f64 / MICROS_PER_SECOND as f64;
+
+    let value = match field.to_lowercase().as_str() {
+        "century" => ((dt.year - 1).div_euclid(100) + 1) as f64,
+        "decade" => dt.year.div_euclid(10) as f64,
+        "year" => dt.year as f64,
+        "quarter" => ((dt.month - 1) / 3 + 1) as f64,
+        "month" => dt.month as f64,
+        "week" => iso_week(&dt) as f64,
+        "day" => dt.day as f64,
+        "dow" => dt.weekday() as f64,
+        "isodow" => match dt.weekday() {
+            0 => 7.0,
+            weekday => weekday as f64,
+        },
+        "doy" => dt.day_of_year() as f64,
+        "hour" => dt.hour as f64,
+        "minute" => dt.minute as f64,
+        "second" => seconds,
+        "millisecond" | "milliseconds" => seconds * 1_000.0,
+        "microsecond" | "microseconds" => seconds * 1_000_000.0,
+        "epoch" => timestamp as f64 / MICROS_PER_SECOND as f64,
+        _ => {
+            return Err(DatabaseError::TypeMismatch(format!(
+                "Timestamp unit \"{field}\" not recognized"
+            )));
+        }
+    };
+
+    Ok(value)
+}
+
+/// ISO-8601 week number, weeks start on Monday and week 1 contains January 4th.
+fn iso_week(dt: &DateTime) -> u32 {
+    let days = days_from_civil(dt.year, dt.month, dt.day);
+    let iso_weekday = (days + 3).rem_euclid(7); // 0 is Monday
+    let thursday = days - iso_weekday + 3;
+    let (year, _, _) = civil_from_days(thursday);
+
+    ((thursday - days_from_civil(year, 1, 1)) / 7 + 1) as u32
+}
+
+/// Truncates a timestamp to the given precision, as done by `date_trunc`.
+pub fn date_trunc(field: &str, timestamp: i64) -> Result<i64, DatabaseError> {
+    let mut dt = DateTime::from_timestamp(timestamp);
+
+    let truncate_time = |dt: &mut DateTime| {
+        dt.hour = 0;
+        dt.minute = 0;
+        dt.second = 0;
+        dt.micros = 0;
+    };
+
+    match field.to_lowercase().as_str() {
+        "microseconds" | "microsecond" => return Ok(timestamp),
+        "milliseconds" | "millisecond" => dt.micros -= dt.micros % MICROS_PER_MILLI as u32,
+        "second" => dt.micros = 0,
+        "minute" => {
+            dt.second = 0;
+            dt.micros = 0;
+        }
+        "hour" => {
+            dt.minute = 0;
+            dt.second = 0;
+            dt.micros = 0;
+        }
+        "day" => truncate_time(&mut dt),
+        "week" => {
+            truncate_time(&mut dt);
+            let days = days_from_civil(dt.year, dt.month, dt.day);
+            let monday = days - (days + 3).rem_euclid(7);
+            (dt.year, dt.month, dt.day) = civil_from_days(monday);
+        }
+        "month" => {
+            truncate_time(&mut dt);
+            dt.day = 1;
+        }
+        "quarter" => {
+            truncate_time(&mut dt);
+            dt.day = 1;
+            dt.month -= (dt.month - 1) % 3;
+        }
+        "year" => {
+            truncate_time(&mut dt);
+            dt.day = 1;
+            dt.month = 1;
+        }
+        "decade" => {
+            truncate_time(&mut dt);
+            dt.day = 1;
+            dt.month = 1;
+            dt.year -= dt.year.rem_euclid(10);
+        }
+        "century" => {
+            truncate_time(&mut dt);
+            dt.day = 1;
+            dt.month = 1;
+            dt.year = (dt.year - 1).div_euclid(100) * 100 + 1;
+        }
+        _ => {
+            return Err(DatabaseError::TypeMismatch(format!(
+                "Timestamp unit \"{field}\" not recognized"
+            )));
+        }
+    }
+
+    dt.to_timestamp()
+        .ok_or_else(|| DatabaseError::TypeMismatch("Timestamp out of range".to_string()))
+}
+
+/// Formats a timestamp with a PostgreSQL style template, as done by `to_char`.
+///
+/// Supports `YYYY`, `YY`, `Q`, `MM`, `Month`, `Mon`, `DDD`, `DD`, `D`, `Day`, `Dy`,
+/// `HH24`, `HH12`, `HH`, `MI`, `SS`, `MS`, `US` and `AM`/`PM`. Text in double quotes
+/// is copied as is.
+pub fn to_char(timestamp: i64, template: &str) -> String {
+    let dt = DateTime::from_timestamp(timestamp);
+    let hour12 = match dt.hour % 12 {
+        0 => 12,
+        hour => hour,
+    };
+    let month_name = MONTH_NAMES[dt.month as usize - 1];
+    let day_name = DAY_NAMES[dt.weekday() as usize];
+
+    // Longer patterns first so `MONTH` is not read as `MON` + `TH`
+    let patterns: [(&str, String); 27] = [
+        ("MONTH", month_name.to_uppercase()),
+        ("Month", month_name.to_string()),
+        ("month", month_name.to_lowercase()),
+        ("YYYY", format!("{:04}", dt.year)),
+        ("HH24", format!("{:02}", dt.hour)),
+        ("HH12", format!("{hour12:02}")),
+        ("MON", month_name[..3].to_uppercase()),
+        ("Mon", month_name[..3].to_string()),
+        ("mon", month_name[..3].to_lowercase()),
+        ("DAY", day_name.to_uppercase()),
+        ("Day", day_name.to_string()),
+        ("day", day_name.to_lowercase()),
+        ("DDD", format!("{:03}", dt.day_of_year())),
+        ("YY", format!("{:02}", dt.year.rem_euclid(100))),
+        ("MM", format!("{:02}", dt.month)),
+        ("DD", format!("{:02}", dt.day)),
+        ("DY", day_name[..3].to_uppercase()),
+        ("Dy", day_name[..3].to_string()),
+        ("dy", day_name[..3].to_lowercase()),
+        ("HH", format!("{hour12:02}")),
+        ("MI", format!("{:02}", dt.minute)),
+        ("SS", format!("{:02}", dt.second)),
+        ("MS", format!("{:03}", dt.micros / 1_000)),
+        ("US", format!("{:06}", dt.micros)),
+        ("AM", if dt.hour < 12 { "AM" } else { "PM" }.to_string()),
+        ("PM", if dt.hour < 12 { "AM" } else { "PM" }.to_string()),
+        ("Q", ((dt.month - 1) / 3 + 1).to_string()),
+    ];
+
+    let mut formatted = String::new();
+    let mut rest = template;
+    'outer: while let Some(c) = rest.chars().next() {
+        if c == '"' {
+            let end = rest[1..].find('"').map_or(rest.len(), |end| end + 1);
+            formatted.push_str(&rest[1..end]);
+            rest = rest.get(end + 1..).unwrap_or("");
+            continue;
+        }
+
+        for (pattern, value) in &patterns {
+            if let Some(remaining) = rest.strip_prefix(pattern) {
+                formatted.push_str(value);
+                rest = remaining;
+                continue 'outer;
+            }
+        }
+
+        if let Some(remaining) = rest.strip_prefix('D') {
+            formatted.push_str(&(dt.weekday() + 1).to_string());
+            rest = remaining;
+            continue;
+        }
+
+        formatted.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+
+    formatted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_civil_round_trip() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+
+        for days in [-800_000, -1, 0, 59, 11_016, 19_723, 2_932_896] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn test_parse_timestamp() {
+        let ts = parse_timestamp("2024-01-01 12:00:00").unwrap();
+        assert_eq!(format_timestamp(ts), "2024-01-01 12:00:00");
+
+        let ts = parse_timestamp("2024-02-29T23:59:59.25").unwrap();
+        assert_eq!(format_timestamp(ts), "2024-02-29 23:59:59.25");
+
+        let ts = parse_timestamp("2024-01-01 12:00:00+02:00").unwrap();
+        assert_eq!(format_timestamp(ts), "2024-01-01 10:00:00");
+
+        let ts = parse_timestamp("1969-12-31").unwrap();
+        assert_eq!(ts, -MICROS_PER_DAY);
+
+        assert!(parse_timestamp("2023-02-29").is_err());
+        assert!(parse_timestamp("2024-01-01 24:00:00").is_err());
+        assert!(parse_timestamp("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_parse_date() {
+        assert_eq!(format_date(parse_date("2024-03-15").unwrap()), "2024-03-15");
+        assert!(parse_date("2024-3-15").is_err());
+    }
+
+    #[test]
+    fn test_parse_interval() {
+        assert_eq!(
+            parse_interval("1 year 2 months 3 days").unwrap(),
+            Interval::new(14, 3, 0)
+        );
+        assert_eq!(
+            parse_interval("1 day 02:30:00").unwrap(),
+            Interval::new(0, 1, 2 * MICROS_PER_HOUR + 30 * MICROS_PER_MINUTE)
+        );
+        assert_eq!(
+            parse_interval("1.5 days").unwrap(),
+            Interval::new(0, 1, 12 * MICROS_PER_HOUR)
+        );
+        assert_eq!(
+            parse_interval("-2 hours").unwrap(),
+            Interval::new(0, 0, -2 * MICROS_PER_HOUR)
+        );
+        assert!(parse_interval("3 fortnights").is_err());
+        assert!(parse_interval("").is_err());
+    }
+
+    #[test]
+    fn test_format_interval() {
+        assert_eq!(
+            Interval::new(14, 3, 4 * MICROS_PER_HOUR + 5).to_string(),
+            "1 year 2 mons 3 days 04:00:00.000005"
+        );
+        assert_eq!(Interval::new(0, 0, 0).to_string(), "00:00:00");
+        assert_eq!(Interval::new(0, -1, 0).to_string(), "-1 days");
+    }
+
+    #[test]
+    fn test_add_interval_clamps_month_end() {
+        let ts = parse_timestamp("2024-01-31 08:00:00").unwrap();
+        let added = add_interval(ts, &Interval::new(1, 1, MICROS_PER_HOUR)).unwrap();
+        assert_eq!(format_timestamp(added), "2024-03-01 09:00:00");
+
+        let subtracted = add_interval(ts, &Interval::new(-2, 0, 0)).unwrap();
+        assert_eq!(format_timestamp(subtracted), "2023-11-30 08:00:00");
+    }
+
+    #[test]
+    fn test_timestamp_difference() {
+        let a = parse_timestamp("2024-01-03 12:00:00").unwrap();
+        let b = parse_timestamp("2024-01-01 06:00:00").unwrap();
+        assert_eq!(
+            timestamp_difference(a, b).unwrap(),
+            Interval::new(0, 2, 6 * MICROS_PER_HOUR)
+        );
+    }
+
+    #[test]
+    fn test_interval_ordering() {
+        assert!(Interval::new(1, 0, 0) > Interval::new(0, 29, 0));
+        assert!(Interval::new(0, 0, MICROS_PER_DAY) < Interval::new(0, 2, 0));
+    }
+
+    #[test]
+    fn test_extract_and_trunc() {
+        let ts = parse_timestamp("2024-05-17 13:45:30.5").unwrap();
+        assert_eq!(extract("year", ts).unwrap(), 2024.0);
+        assert_eq!(extract("quarter", ts).unwrap(), 2.0);
+        assert_eq!(extract("dow", ts).unwrap(), 5.0);
+        assert_eq!(extract("doy", ts).unwrap(), 138.0);
+        assert_eq!(extract("second", ts).unwrap(), 30.5);
+        assert_eq!(extract("week", ts).unwrap(), 20.0);
+        assert!(extract("fortnight", ts).is_err());
+
+        let truncated = |field| format_timestamp(date_trunc(field, ts).unwrap());
+        assert_eq!(truncated("hour"), "2024-05-17 13:00:00");
+        assert_eq!(truncated("week"), "2024-05-13 00:00:00");
+        assert_eq!(truncated("quarter"), "2024-04-01 00:00:00");
+        assert_eq!(truncated("year"), "2024-01-01 00:00:00");
+    }
+
+    #[test]
+    fn test_to_char() {
+        let ts = parse_timestamp("2024-05-07 15:04:05.123").unwrap();
+        assert_eq!(
+            to_char(ts, "YYYY-MM-DD HH24:MI:SS.MS"),
+            "2024-05-07 15:04:05.123"
+        );
+        assert_eq!(
+            to_char(ts, "Dy, DD Mon YYYY HH12:MI AM"),
+            "Tue, 07 May 2024 03:04 PM"
+        );
+        assert_eq!(to_char(ts, "\"Q\"Q Month"), "Q2 May");
+    }
+}
@@ -1,9 +1,26 @@
+use crate::core::{
+    binary,
+    decimal::{Decimal, MAX_PRECISION},
+    json::Json,
+    temporal::{self, Interval},
+};
+
 /// SQL data types supported by Scuttle DB.
 ///
 /// These types define the kind of data a column can hold and how
 /// it's encoded/decoded in storage.
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DataType {
+    /// 16-bit signed integer.
+    ///
+    /// Stored as 2 bytes in little-endian format.
+    Int16,
+
+    /// 32-bit signed integer.
+    ///
+    /// Stored as 4 bytes in little-endian format.
+    Int32,
+
     /// 64-bit signed integer.
     ///
     /// Stored as 8 bytes in little-endian format.
@@ -26,17 +43,59 @@ pub enum DataType {
     /// 64-bit floating point number.
     Float64,
 
+    /// Exact decimal number with `precision` significant digits, `scale` of them
+    /// after the decimal point.
+    ///
+    /// Stored as 1-byte scale + 1-byte length + the minimal little-endian mantissa.
+    Decimal(u8, u8),
+
+    /// Variable-length binary data.
+    ///
+    /// Stored as 4-byte length prefix + raw bytes.
+    Bytea,
+
+    /// A 128-bit universally unique identifier.
+    ///
+    /// Stored as 16 bytes in big-endian format.
+    Uuid,
+
+    /// A point in time with microsecond precision.
+    ///
+    /// Stored as 8 bytes in little-endian format, microseconds since 1970-01-01 UTC.
     Timestamp,
+
+    /// A calendar date.
+    ///
+    /// Stored as 4 bytes in little-endian format, days since 1970-01-01.
+    Date,
+
+    /// A span of time.
+    ///
+    /// Stored as 16 bytes: 4-byte months, 4-byte days and 8-byte microseconds.
+    Interval,
+
+    /// A validated JSON document.
+    ///
+    /// Stored as 4-byte length prefix + the document's tagged binary form.
+    Json,
 }
 
 impl std::fmt::Display for DataType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            DataType::Int16 => write!(f, "SmallInt"),
+            DataType::Int32 => write!(f, "Int32"),
             DataType::Int64 => write!(f, "Integer"),
             DataType::Text | DataType::VarChar(_) => write!(f, "String"),
             DataType::Bool => write!(f, "Boolean"),
             DataType::Float64 => write!(f, "Float"),
+            DataType::Decimal(precision, scale) => write!(f, "Numeric({precision}, {scale})"),
+            DataType::Bytea => write!(f, "Bytea"),
+            DataType::Uuid => write!(f, "Uuid"),
             DataType::Timestamp => write!(f, "Timestamp"),
+            DataType::Date => write!(f, "Date"),
+            DataType::Interval => write!(f, "Interval"),
+            DataType::Json => write!(f, "Json"),
         }
     }
 }
@@ -50,12 +109,116 @@ impl DataType {
 
         matches!(
             (from, to),
-            // Int64 can be coerced to Float64
-            (DataType::Int64, DataType::Float64)
+            // Integers widen to larger integers and to Float64
+            (DataType::Int16, DataType::Int32 | DataType::Int64 | DataType::Float64)
+            | (DataType::Int32, DataType::Int64 | DataType::Float64)
+            | (DataType::Int64, DataType::Float64)
             // Text and VarChar are interchangeable for comparison purposes
             | (DataType::Text, DataType::VarChar(_))
             | (DataType::VarChar(_), DataType::Text)
             | (DataType::VarChar(_), DataType::VarChar(_))
+            // A date is midnight of that day
+            | (DataType::Date, DataType::Timestamp)
+            // Integers are exact decimals, decimals are rounded to the target scale
+            | (DataType::Int16 | DataType::Int32 | DataType::Int64, DataType::Decimal(_, _))
+            | (DataType::Decimal(_, _), DataType::Decimal(_, _))
+            // Mixing decimals with floats gives up exactness
+            | (DataType::Decimal(_, _), DataType::Float64)
+        )
+    }
+
+    /// Whether values have a length prefix and may be compressed or stored out of line.
+    pub fn is_variable_length(self) -> bool {
+        matches!(
+            self,
+            DataType::Text | DataType::VarChar(_) | DataType::Bytea | DataType::Json
+        )
+    }
+
+    /// Whether values of the type can be indexed, sorted and hashed: they are totally
+    /// ordered and only equal when identical, unlike floats compared with a tolerance.
+    pub fn is_indexable(self) -> bool {
+        matches!(
+            self,
+            DataType::Int16
+                | DataType::Int32
+                | DataType::Int64
+                | DataType::Text
+                | DataType::VarChar(_)
+                | DataType::Bool
+                | DataType::Decimal(_, _)
+                | DataType::Uuid
+                | DataType::Date
+                | DataType::Timestamp
+        )
+    }
+
+    pub fn is_integer(self) -> bool {
+        matches!(self, DataType::Int16 | DataType::Int32 | DataType::Int64)
+    }
+
+    /// Precision and scale of a type as a decimal, for exact numeric types.
+    pub fn decimal_precision(self) -> Option<(u8, u8)> {
+        match self {
+            DataType::Int16 => Some((5, 0)),
+            DataType::Int32 => Some((10, 0)),
+            DataType::Int64 => Some((19, 0)),
+            DataType::Decimal(precision, scale) => Some((precision, scale)),
+            _ => None,
+        }
+    }
+
+    /// The type both `a` and `b` can be coerced to, if any.
+    ///
+    /// Used to unify the result types of e.g. the branches of a CASE expression.
+    pub fn common_type(a: DataType, b: DataType) -> Option<DataType> {
+        if a == b {
+            return Some(a);
+        }
+
+        if let (Some((p1, s1)), Some((p2, s2))) = (a.decimal_precision(), b.decimal_precision())
+            && (matches!(a, DataType::Decimal(_, _)) || matches!(b, DataType::Decimal(_, _)))
+        {
+            // Wide enough for the integer and fractional digits of both
+            let scale = s1.max(s2);
+            let precision = (p1 - s1).max(p2 - s2) + scale;
+            return Some(DataType::Decimal(precision.min(MAX_PRECISION), scale));
+        }
+
+        match (DataType::can_coerce(a, b), DataType::can_coerce(b, a)) {
+            // Only text types coerce both ways, settle on unbounded text
+            (true, true) => Some(DataType::Text),
+            (true, false) => Some(b),
+            (false, true) => Some(a),
+            (false, false) => None,
+        }
+    }
+
+    /// If a value of type `from` can be explicitly converted with `CAST` to `to`.
+    ///
+    /// Casting may still fail at runtime for specific values, e.g. `'abc'::INT`.
+    pub fn can_cast(from: DataType, to: DataType) -> bool {
+        if DataType::can_coerce(from, to) {
+            return true;
+        }
+
+        matches!(
+            (from, to),
+            // Every type has a text representation, and can be parsed from one
+            (_, DataType::Text | DataType::VarChar(_))
+                | (DataType::Text | DataType::VarChar(_), _)
+                | (
+                    DataType::Float64 | DataType::Decimal(_, _),
+                    DataType::Int16 | DataType::Int32
+                )
+                | (DataType::Int64, DataType::Int16 | DataType::Int32)
+                | (DataType::Int32, DataType::Int16)
+                | (DataType::Float64, DataType::Int64)
+                | (DataType::Int64, DataType::Bool)
+                | (DataType::Bool, DataType::Int64)
+                | (DataType::Timestamp, DataType::Date)
+                | (DataType::Float64, DataType::Decimal(_, _))
+                | (DataType::Decimal(_, _), DataType::Int64)
         )
     }
 }
@@ -66,6 +229,12 @@ impl DataType {
 /// Each variant can be compared, ordered, and checked for type compatibility.
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum Value {
+    /// A 16-bit signed integer value.
+    Int16(i16),
+
+    /// A 32-bit signed integer value.
+    Int32(i32),
+
     /// A 64-bit signed integer value.
     Int64(i64),
 
@@ -78,6 +247,27 @@ pub enum Value {
     /// A boolean value (true/false).
     Bool(bool),
 
+    /// An exact decimal number.
+    Decimal(Decimal),
+
+    /// Binary data.
+    Bytea(Vec<u8>),
+
+    /// A UUID, with the first byte of its text form as most significant byte.
+    Uuid(u128),
+
+    /// A timestamp, microseconds since 1970-01-01 00:00:00 UTC.
+    Timestamp(i64),
+
+    /// A date, days since 1970-01-01.
+    Date(i32),
+
+    /// A span of time.
+    Interval(Interval),
+
+    /// A JSON document.
+    Json(Json),
+
     /// Represents a NULL value (absence of data).
     ///
     /// Only allowed in nullable columns.
@@ -87,10 +277,19 @@ pub enum Value {
 impl std::fmt::Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            Value::Int16(i) => write!(f, "{}", i),
+            Value::Int32(i) => write!(f, "{}", i),
             Value::Int64(i) => write!(f, "{}", i),
             Value::Text(s) => write!(f, "{}", s),
             Value::Bool(b) => write!(f, "{}", b),
             Value::Float64(fl) => write!(f, "{}", fl),
+            Value::Decimal(d) => write!(f, "{}", d),
+            Value::Bytea(bytes) => write!(f, "{}", binary::format_bytea(bytes)),
+            Value::Uuid(uuid) => write!(f, "{}", binary::format_uuid(*uuid)),
+            Value::Timestamp(ts) => write!(f, "{}", temporal::format_timestamp(*ts)),
+            Value::Date(days) => write!(f, "{}", temporal::format_date(*days)),
+            Value::Interval(interval) => write!(f, "{}", interval),
+            Value::Json(json) => write!(f, "{}", json),
             Value::Null => write!(f, "NULL"),
         }
     }
@@ -113,9 +312,26 @@ impl Value {
                     ))
                 }
             }
-            (Value::Int64(_), DataType::Int64)
+            (Value::Decimal(d), DataType::Decimal(precision, scale)) => {
+                if d.fits(*precision, *scale) {
+                    Ok(())
+                } else {
+                    Err(format!(
+                        "Numeric value {d} exceeds NUMERIC({precision}, {scale}) limit"
+                    ))
+                }
+            }
+            (Value::Int16(_), DataType::Int16)
+            | (Value::Int32(_), DataType::Int32)
+            | (Value::Int64(_), DataType::Int64)
+            | (Value::Bytea(_), DataType::Bytea)
+            | (Value::Uuid(_), DataType::Uuid)
             | (Value::Bool(_), DataType::Bool)
             | (Value::Float64(_), DataType::Float64)
+            | (Value::Timestamp(_), DataType::Timestamp)
+            | (Value::Date(_), DataType::Date)
+            | (Value::Interval(_), DataType::Interval)
+            | (Value::Json(_), DataType::Json)
             | (Value::Null, _)
             | (Value::Text(_), DataType::Text) => Ok(()),
 
@@ -0,0 +1,70 @@
+//! The indexes of the tables.
+//!
+//! Every index is one row of the [`INDEXES_RELATION`] catalog relation, keyed by the
+//! table it is on. Only the definition is stored, the entries of an index are built
+//! again when the database is opened.
+
+use miette::{Result, miette};
+
+use crate::{
+    ColumnDef, DataType, Value,
+    db::{
+        catalog::{catalog_rows, delete_catalog_rows, insert_catalog_row},
+        table::{row::Row, schema::Schema},
+    },
+    storage::buffer_pool::BufferPool,
+};
+
+/// Catalog relation holding the name and column of every index.
+pub(crate) const INDEXES_RELATION: &str = "scuttle_index";
+
+/// Schema of the [`INDEXES_RELATION`] catalog relation.
+fn catalog_schema() -> Schema {
+    Schema::new(vec![
+        ColumnDef::new("table_name", DataType::Text, false),
+        ColumnDef::new("index_name", DataType::Text, false),
+        ColumnDef::new("column_name", DataType::Text, false),
+    ])
+}
+
+/// An index as recorded in the catalog.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct IndexDefinition {
+    pub(crate) table_name: String,
+    pub(crate) index_name: String,
+    pub(crate) column_name: String,
+}
+
+/// Records a new index.
+pub(crate) fn store_index(buffer_pool: &BufferPool, definition: &IndexDefinition) -> Result<()> {
+    let row = Row::new(vec![
+        Value::Text(definition.table_name.clone()),
+        Value::Text(definition.index_name.clone()),
+        Value::Text(definition.column_name.clone()),
+    ]);
+    insert_catalog_row(buffer_pool, INDEXES_RELATION, &catalog_schema(), &row)
+}
+
+/// Forgets every index on the table `table_name`.
+pub(crate) fn remove_indexes(buffer_pool: &BufferPool, table_name: &str) -> Result<()> {
+    delete_catalog_rows(buffer_pool, INDEXES_RELATION, &catalog_schema(), table_name)
+}
+
+/// Every recorded index.
+pub(crate) fn load_indexes(buffer_pool: &BufferPool) -> Result<Vec<IndexDefinition>> {
+    catalog_rows(buffer_pool, INDEXES_RELATION, &catalog_schema(), None)?
+        .into_iter()
+        .map(|(_, _, row)| match row.values.as_slice() {
+            [
+                Value::Text(table_name),
+                Value::Text(index_name),
+                Value::Text(column_name),
+            ] => Ok(IndexDefinition {
+                table_name: table_name.clone(),
+                index_name: index_name.clone(),
+                column_name: column_name.clone(),
+            }),
+            values => Err(miette!("Invalid index catalog row: {values:?}")),
+        })
+        .collect()
+}
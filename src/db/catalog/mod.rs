@@ -1 +1,98 @@
+//! Catalog relations: tables the database keeps about itself, stored in pages like
+//! any other table. Every row is keyed by the name of the table it describes, in its
+//! first column.
+
+pub(crate) mod indexes;
+pub(crate) mod statistics;
 pub(crate) mod system_catalog;
+pub(crate) mod tables;
+
+use miette::Result;
+
+use crate::{
+    Value,
+    db::table::{row::Row, schema::Schema},
+    storage::{
+        buffer_pool::BufferPool,
+        page::{ItemId, PageHeader, PageId, PageType},
+        toast::TableToast,
+    },
+};
+
+/// Rows of a catalog relation, decoding only the columns in `columns`.
+pub(crate) fn catalog_rows(
+    buffer_pool: &BufferPool,
+    relation: &str,
+    schema: &Schema,
+    columns: Option<&[usize]>,
+) -> Result<Vec<(PageId, ItemId, Row)>> {
+    let mut rows = Vec::new();
+
+    for page_id in 0..buffer_pool.page_count(relation)? {
+        let items = buffer_pool.read_page(relation, page_id, |page| {
+            page.item_pointers()
+                .enumerate()
+                .filter(|(_, item_pointer)| !item_pointer.is_deleted())
+                .map(|(item_id, item_pointer)| {
+                    let offset = item_pointer.offset as usize - PageHeader::SIZE;
+                    let length = item_pointer.length as usize;
+                    (
+                        item_id as ItemId,
+                        page.data[offset..offset + length].to_vec(),
+                    )
+                })
+                .collect::<Vec<_>>()
+        })?;
+
+        let mut toast = TableToast::new(buffer_pool, relation);
+        for (item_id, bytes) in items {
+            let row = schema.decode_columns(&bytes, columns, &mut toast)?;
+            rows.push((page_id, item_id, row));
+        }
+    }
+
+    Ok(rows)
+}
+
+/// Deletes the rows of a catalog relation about the table `table_name`.
+pub(crate) fn delete_catalog_rows(
+    buffer_pool: &BufferPool,
+    relation: &str,
+    schema: &Schema,
+    table_name: &str,
+) -> Result<()> {
+    let table_name = Value::Text(table_name.to_string());
+    for (page_id, item_id, row) in catalog_rows(buffer_pool, relation, schema, Some(&[0]))? {
+        if row.values[0] == table_name {
+            let bytes = buffer_pool.write_page(relation, page_id, |page| {
+                let bytes = page.get_item(item_id)?.to_vec();
+                page.delete_item(item_id)?;
+                // Nothing refers to catalog rows by position, their space is reused at once
+                page.compact();
+                Ok::<_, miette::Report>(bytes)
+            })??;
+            buffer_pool.save_page(relation, page_id)?;
+
+            schema.delete_toasted(&bytes, &mut TableToast::new(buffer_pool, relation))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Adds a row to a catalog relation.
+pub(crate) fn insert_catalog_row(
+    buffer_pool: &BufferPool,
+    relation: &str,
+    schema: &Schema,
+    row: &Row,
+) -> Result<()> {
+    let encoded_row = {
+        let mut toast = TableToast::new(buffer_pool, relation);
+        schema.encode_row(row, &mut toast)?
+    };
+
+    let page_id = buffer_pool.free_page(relation, encoded_row.len(), PageType::Table)?;
+    buffer_pool.write_page(relation, page_id, |page| page.add_data(&encoded_row))??;
+    buffer_pool.save_page(relation, page_id)
+}
@@ -0,0 +1,569 @@
+//! Table and column statistics gathered by `ANALYZE`.
+//!
+//! Statistics are computed from a sample of a table's pages and stored as one row per
+//! table in the [`STATISTICS_RELATION`] catalog relation, so they survive restarts.
+//! Values are kept as text in a JSON document and cast back to the column types when
+//! loaded.
+
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::HashMap,
+};
+
+use miette::{Result, miette};
+
+use crate::{
+    ColumnDef, DataType, Json, JsonNumber, Value,
+    db::catalog::{catalog_rows, delete_catalog_rows, insert_catalog_row},
+    db::table::{row::Row, schema::Schema},
+    sql::evaluator::{cast_value, values_equal, values_less_than},
+    storage::buffer_pool::BufferPool,
+};
+
+/// Catalog relation holding the statistics of every analyzed table.
+pub(crate) const STATISTICS_RELATION: &str = "scuttle_statistic";
+
+/// Maximum number of most common values and of histogram buckets kept per column.
+pub(crate) const STATISTICS_TARGET: usize = 100;
+
+/// Maximum number of pages read to sample a table.
+pub(crate) const SAMPLE_PAGES: u32 = 300;
+
+/// Values wider than this are too costly to keep and are left out of the most common
+/// values and histograms.
+const MAX_STORED_WIDTH: usize = 1024;
+
+/// Statistics about a table, as recorded by the last `ANALYZE`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableStatistics {
+    /// Estimated number of live rows.
+    pub row_count: u64,
+
+    /// Number of pages of the table.
+    pub page_count: u32,
+
+    /// Statistics of every column, in schema order.
+    pub columns: Vec<ColumnStatistics>,
+}
+
+/// Statistics about the values of one column.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnStatistics {
+    /// The column name.
+    pub name: String,
+
+    /// Fraction of rows where the column is NULL.
+    pub null_fraction: f64,
+
+    /// Estimated number of distinct non-null values.
+    pub distinct_count: f64,
+
+    /// Average size in bytes of the non-null values.
+    pub average_width: usize,
+
+    /// The most common non-null values, most frequent first.
+    pub most_common_values: Vec<Value>,
+
+    /// Fraction of all rows holding each of the most common values.
+    pub most_common_frequencies: Vec<f64>,
+
+    /// Ascending bounds of equi-depth buckets over the non-null values that are not
+    /// among the most common ones. Each bucket holds about the same number of rows.
+    pub histogram_bounds: Vec<Value>,
+}
+
+impl TableStatistics {
+    /// The statistics of a column by name.
+    pub fn column(&self, name: &str) -> Option<&ColumnStatistics> {
+        self.columns.iter().find(|column| column.name == name)
+    }
+
+    /// Computes statistics from rows sampled out of a table of `row_count` rows.
+    pub(crate) fn from_sample(
+        schema: &Schema,
+        sample: &[Row],
+        row_count: u64,
+        page_count: u32,
+    ) -> Self {
+        let columns = schema
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(idx, column)| {
+                let values = sample
+                    .iter()
+                    .map(|row| row.get_value(idx).unwrap_or(&Value::Null))
+                    .collect::<Vec<_>>();
+                ColumnStatistics::from_sample(column, &values, row_count)
+            })
+            .collect();
+
+        Self {
+            row_count,
+            page_count,
+            columns,
+        }
+    }
+
+    fn to_json(&self) -> Json {
+        let columns = self.columns.iter().map(ColumnStatistics::to_json).collect();
+
+        Json::Object(
+            [
+                ("row_count".to_string(), number(self.row_count as f64)),
+                ("page_count".to_string(), number(self.page_count as f64)),
+                ("columns".to_string(), Json::Array(columns)),
+            ]
+            .into_iter()
+            .collect(),
+        )
+    }
+
+    /// Reads statistics back, columns no longer in `schema` are dropped.
+    fn from_json(json: &Json, schema: &Schema) -> Result<Self> {
+        let columns = array_member(json, "columns")?
+            .iter()
+            .filter_map(|column| {
+                let name = match column.get_key("name") {
+                    Some(Json::String(name)) => name,
+                    _ => return Some(Err(miette!("Statistics column without a name"))),
+                };
+                let index = schema.get_column_index(name)?;
+                Some(ColumnStatistics::from_json(column, &schema.columns[index]))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            row_count: number_member(json, "row_count")? as u64,
+            page_count: number_member(json, "page_count")? as u32,
+            columns,
+        })
+    }
+}
+
+impl ColumnStatistics {
+    fn from_sample(column: &ColumnDef, values: &[&Value], row_count: u64) -> Self {
+        let sample_size = values.len();
+        let non_null = values
+            .iter()
+            .copied()
+            .filter(|value| !matches!(value, Value::Null))
+            .collect::<Vec<_>>();
+
+        let null_fraction = if sample_size == 0 {
+            0.0
+        } else {
+            (sample_size - non_null.len()) as f64 / sample_size as f64
+        };
+        let average_width = if non_null.is_empty() {
+            0
+        } else {
+            non_null
+                .iter()
+                .map(|value| value_width(value))
+                .sum::<usize>()
+                / non_null.len()
+        };
+
+        // Equal values share their text form, in order of first appearance
+        let mut groups: Vec<(&Value, usize)> = Vec::new();
+        let mut group_index = HashMap::new();
+        for &value in &non_null {
+            let index = *group_index.entry(value.to_string()).or_insert_with(|| {
+                groups.push((value, 0));
+                groups.len() - 1
+            });
+            groups[index].1 += 1;
+        }
+
+        let table_non_null = row_count as f64 * (1.0 - null_fraction);
+        let distinct_count = estimate_distinct(&groups, non_null.len(), table_non_null);
+
+        // Values seen more than once, and clearly more often than average when there are
+        // too many to keep them all
+        let average_count = non_null.len() as f64 / groups.len().max(1) as f64;
+        let mut common = groups
+            .iter()
+            .filter(|(value, count)| {
+                *count > 1
+                    && value_width(value) <= MAX_STORED_WIDTH
+                    && (groups.len() <= STATISTICS_TARGET || *count as f64 > 1.25 * average_count)
+            })
+            .collect::<Vec<_>>();
+        // Stable, equally frequent values keep their order of appearance
+        common.sort_by_key(|(_, count)| Reverse(*count));
+        common.truncate(STATISTICS_TARGET);
+
+        let most_common_values = common
+            .iter()
+            .map(|(value, _)| (*value).clone())
+            .collect::<Vec<_>>();
+        let most_common_frequencies = common
+            .iter()
+            .map(|(_, count)| *count as f64 / sample_size as f64)
+            .collect();
+
+        let mut rest = non_null
+            .into_iter()
+            .filter(|value| {
+                value_width(value) <= MAX_STORED_WIDTH
+                    && !most_common_values
+                        .iter()
+                        .any(|common| values_equal(common, value) == Value::Bool(true))
+            })
+            .collect::<Vec<_>>();
+        rest.sort_by(|a, b| compare(a, b));
+
+        Self {
+            name: column.name.clone(),
+            null_fraction,
+            distinct_count,
+            average_width,
+            most_common_values,
+            most_common_frequencies,
+            histogram_bounds: histogram_bounds(&rest),
+        }
+    }
+
+    fn to_json(&self) -> Json {
+        let values = |values: &[Value]| {
+            Json::Array(
+                values
+                    .iter()
+                    .map(|value| Json::String(value.to_string()))
+                    .collect(),
+            )
+        };
+        let numbers = |numbers: &[f64]| Json::Array(numbers.iter().copied().map(number).collect());
+
+        Json::Object(
+            [
+                ("name".to_string(), Json::String(self.name.clone())),
+                ("null_fraction".to_string(), number(self.null_fraction)),
+                ("distinct_count".to_string(), number(self.distinct_count)),
+                (
+                    "average_width".to_string(),
+                    number(self.average_width as f64),
+                ),
+                (
+                    "most_common_values".to_string(),
+                    values(&self.most_common_values),
+                ),
+                (
+                    "most_common_frequencies".to_string(),
+                    numbers(&self.most_common_frequencies),
+                ),
+                (
+                    "histogram_bounds".to_string(),
+                    values(&self.histogram_bounds),
+                ),
+            ]
+            .into_iter()
+            .collect(),
+        )
+    }
+
+    fn from_json(json: &Json, column: &ColumnDef) -> Result<Self> {
+        let values = |key: &str| {
+            array_member(json, key)?
+                .iter()
+                .map(|value| match value {
+                    Json::String(text) => {
+                        Ok(cast_value(&Value::Text(text.clone()), column.data_type)?)
+                    }
+                    other => Err(miette!("Invalid statistics value: {other}")),
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+
+        Ok(Self {
+            name: column.name.clone(),
+            null_fraction: number_member(json, "null_fraction")?,
+            distinct_count: number_member(json, "distinct_count")?,
+            average_width: number_member(json, "average_width")? as usize,
+            most_common_values: values("most_common_values")?,
+            most_common_frequencies: array_member(json, "most_common_frequencies")?
+                .iter()
+                .map(|frequency| match frequency {
+                    Json::Number(frequency) => Ok(frequency.to_f64()),
+                    other => Err(miette!("Invalid statistics frequency: {other}")),
+                })
+                .collect::<Result<_>>()?,
+            histogram_bounds: values("histogram_bounds")?,
+        })
+    }
+}
+
+/// Estimates the number of distinct values in the table from the groups of equal values
+/// in a sample of `sample_size` values, out of `table_size` non-null values.
+///
+/// Uses the Haas and Stokes estimator `n*d / (n - f1 + f1*n/N)`, where `f1` is the
+/// number of values seen exactly once.
+fn estimate_distinct(groups: &[(&Value, usize)], sample_size: usize, table_size: f64) -> f64 {
+    let distinct = groups.len() as f64;
+    let seen_once = groups.iter().filter(|(_, count)| *count == 1).count() as f64;
+    let n = sample_size as f64;
+
+    if sample_size == 0 {
+        0.0
+    } else if seen_once == distinct {
+        // Every value is unique, the column most likely is too
+        table_size.max(distinct)
+    } else if n >= table_size {
+        // The whole table was sampled
+        distinct
+    } else {
+        let estimate = n * distinct / (n - seen_once + seen_once * n / table_size);
+        estimate.clamp(distinct, table_size).round()
+    }
+}
+
+/// Picks up to [`STATISTICS_TARGET`] + 1 evenly spaced bounds out of sorted values.
+fn histogram_bounds(sorted: &[&Value]) -> Vec<Value> {
+    if sorted.len() < 2 {
+        return Vec::new();
+    }
+
+    let buckets = STATISTICS_TARGET.min(sorted.len() - 1);
+    let mut bounds: Vec<Value> = Vec::with_capacity(buckets + 1);
+    for bucket in 0..=buckets {
+        let value = sorted[bucket * (sorted.len() - 1) / buckets];
+        if bounds
+            .last()
+            .is_none_or(|last| values_equal(last, value) != Value::Bool(true))
+        {
+            bounds.push(value.clone());
+        }
+    }
+
+    if bounds.len() < 2 {
+        return Vec::new();
+    }
+    bounds
+}
+
+/// Orders two non-null values of the same column.
+pub(crate) fn compare(left: &Value, right: &Value) -> Ordering {
+    match (left, right) {
+        (Value::Text(a), Value::Text(b)) => a.cmp(b),
+        (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+        (Value::Float64(a), Value::Float64(b)) => a.total_cmp(b),
+        _ if values_less_than(left, right) == Value::Bool(true) => Ordering::Less,
+        _ if values_equal(left, right) == Value::Bool(true) => Ordering::Equal,
+        _ => Ordering::Greater,
+    }
+}
+
+/// Size of a value when stored in a row, ignoring compression.
+fn value_width(value: &Value) -> usize {
+    match value {
+        Value::Bool(_) => 1,
+        Value::Int16(_) => 2,
+        Value::Int32(_) | Value::Date(_) => 4,
+        Value::Int64(_) | Value::Float64(_) | Value::Timestamp(_) => 8,
+        Value::Decimal(_) | Value::Uuid(_) | Value::Interval(_) => 16,
+        Value::Text(text) => text.len(),
+        Value::Bytea(bytes) => bytes.len(),
+        Value::Json(json) => {
+            let mut bytes = Vec::new();
+            json.encode(&mut bytes);
+            bytes.len()
+        }
+        Value::Null => 0,
+    }
+}
+
+/// A statistic as a JSON number, statistics are always finite.
+fn number(value: f64) -> Json {
+    JsonNumber::from_f64(value).map_or(Json::Null, Json::Number)
+}
+
+fn number_member(json: &Json, key: &str) -> Result<f64> {
+    match json.get_key(key) {
+        Some(Json::Number(number)) => Ok(number.to_f64()),
+        _ => Err(miette!("Statistics are missing the number {key}")),
+    }
+}
+
+fn array_member<'a>(json: &'a Json, key: &str) -> Result<&'a [Json]> {
+    match json.get_key(key) {
+        Some(Json::Array(elements)) => Ok(elements),
+        _ => Err(miette!("Statistics are missing the array {key}")),
+    }
+}
+
+/// Schema of the [`STATISTICS_RELATION`] catalog relation.
+fn catalog_schema() -> Schema {
+    Schema::new(vec![
+        ColumnDef::new("table_name", DataType::Text, false),
+        ColumnDef::new("statistics", DataType::Json, false),
+    ])
+}
+
+/// Saves the statistics of a table, replacing the ones from an earlier `ANALYZE`.
+pub(crate) fn store_statistics(
+    buffer_pool: &BufferPool,
+    table_name: &str,
+    statistics: &TableStatistics,
+) -> Result<()> {
+    let schema = catalog_schema();
+    remove_statistics(buffer_pool, table_name)?;
+
+    let row = Row::new(vec![
+        Value::Text(table_name.to_string()),
+        Value::Json(statistics.to_json()),
+    ]);
+    insert_catalog_row(buffer_pool, STATISTICS_RELATION, &schema, &row)
+}
+
+/// Deletes the statistics of a table, e.g. when it is dropped.
+pub(crate) fn remove_statistics(buffer_pool: &BufferPool, table_name: &str) -> Result<()> {
+    delete_catalog_rows(
+        buffer_pool,
+        STATISTICS_RELATION,
+        &catalog_schema(),
+        table_name,
+    )
+}
+
+/// Loads the statistics of a table with the given schema, `None` if it was never
+/// analyzed.
+pub(crate) fn load_statistics(
+    buffer_pool: &BufferPool,
+    table_name: &str,
+    schema: &Schema,
+) -> Result<Option<TableStatistics>> {
+    let table_name_value = Value::Text(table_name.to_string());
+
+    catalog_rows(buffer_pool, STATISTICS_RELATION, &catalog_schema(), None)?
+        .into_iter()
+        .find(|(_, _, row)| row.values[0] == table_name_value)
+        .map(|(_, _, row)| match &row.values[1] {
+            Value::Json(json) => TableStatistics::from_json(json, schema),
+            other => Err(miette!("Invalid statistics row: {other:?}")),
+        })
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> Schema {
+        Schema::new(vec![
+            ColumnDef::new("id", DataType::Int64, false),
+            ColumnDef::new("city", DataType::Text, true),
+        ])
+    }
+
+    /// 1000 rows with unique ids, a few common cities, many rare ones and NULLs.
+    fn sample() -> Vec<Row> {
+        (0..1000)
+            .map(|id| {
+                let city = match id % 10 {
+                    0..=3 => Value::Text("Paris".to_string()),
+                    4..=5 => Value::Text("Berlin".to_string()),
+                    6 => Value::Null,
+                    _ => Value::Text(format!("town {id:04}")),
+                };
+                Row::new(vec![Value::Int64(id), city])
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_statistics_from_sample() {
+        let statistics = TableStatistics::from_sample(&schema(), &sample(), 1000, 4);
+        assert_eq!(statistics.row_count, 1000);
+        assert_eq!(statistics.page_count, 4);
+
+        let id = statistics.column("id").unwrap();
+        assert_eq!(id.null_fraction, 0.0);
+        assert_eq!(id.distinct_count, 1000.0);
+        assert_eq!(id.average_width, 8);
+        assert!(id.most_common_values.is_empty());
+        assert_eq!(id.histogram_bounds.len(), STATISTICS_TARGET + 1);
+        assert_eq!(id.histogram_bounds.first(), Some(&Value::Int64(0)));
+        assert_eq!(id.histogram_bounds.last(), Some(&Value::Int64(999)));
+
+        let city = statistics.column("city").unwrap();
+        assert!((city.null_fraction - 0.1).abs() < 1e-9);
+        assert_eq!(
+            city.most_common_values,
+            vec![
+                Value::Text("Paris".to_string()),
+                Value::Text("Berlin".to_string())
+            ]
+        );
+        assert_eq!(city.most_common_frequencies, vec![0.4, 0.2]);
+        assert_eq!(city.distinct_count, 302.0);
+        assert_eq!(
+            city.histogram_bounds.first(),
+            Some(&Value::Text("town 0007".to_string()))
+        );
+        assert_eq!(
+            city.histogram_bounds.last(),
+            Some(&Value::Text("town 0999".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_distinct_count_is_scaled_up_from_a_partial_sample() {
+        // Half the sample is values seen once, the table is ten times the sample
+        let values = (0..100)
+            .map(|n| Value::Int64(if n < 50 { n } else { 1000 + n % 10 }))
+            .collect::<Vec<_>>();
+        let rows = values
+            .into_iter()
+            .map(|value| Row::new(vec![value, Value::Null]))
+            .collect::<Vec<_>>();
+
+        let statistics = TableStatistics::from_sample(&schema(), &rows, 1000, 40);
+        let id = statistics.column("id").unwrap();
+        // 100 * 60 / (100 - 50 + 50 * 100 / 1000)
+        assert_eq!(id.distinct_count, 109.0);
+
+        let city = statistics.column("city").unwrap();
+        assert_eq!(city.null_fraction, 1.0);
+        assert_eq!(city.distinct_count, 0.0);
+        assert!(city.histogram_bounds.is_empty());
+    }
+
+    #[test]
+    fn test_statistics_are_stored_and_replaced() {
+        let directory = std::env::temp_dir().join("scuttle_statistics_tests");
+        std::fs::remove_dir_all(&directory).ok();
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let schema = schema();
+        let statistics = TableStatistics::from_sample(&schema, &sample(), 1000, 4);
+        let buffer_pool = BufferPool::new(&directory);
+        assert_eq!(
+            load_statistics(&buffer_pool, "cities", &schema).unwrap(),
+            None
+        );
+
+        let mut outdated = statistics.clone();
+        outdated.row_count = 10;
+        store_statistics(&buffer_pool, "cities", &outdated).unwrap();
+        store_statistics(&buffer_pool, "cities", &statistics).unwrap();
+        store_statistics(&buffer_pool, "other", &outdated).unwrap();
+
+        let buffer_pool = BufferPool::new(&directory);
+        assert_eq!(
+            load_statistics(&buffer_pool, "cities", &schema).unwrap(),
+            Some(statistics)
+        );
+        assert_eq!(
+            catalog_rows(
+                &buffer_pool,
+                STATISTICS_RELATION,
+                &catalog_schema(),
+                Some(&[0])
+            )
+            .unwrap()
+            .len(),
+            2
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! The tables of a database and their columns.
+//!
+//! Every table is one row of the [`TABLES_RELATION`] catalog relation, so opening a
+//! data directory finds the tables created in it before. Columns are kept in a JSON
+//! array, with their types spelled as by [`type_name`].
+
+use miette::{Result, miette};
+
+use crate::{
+    ColumnDef, DataType, Json, Value,
+    db::{
+        catalog::{catalog_rows, delete_catalog_rows, insert_catalog_row},
+        table::{row::Row, schema::Schema},
+    },
+    storage::buffer_pool::BufferPool,
+};
+
+/// Catalog relation holding the name and columns of every table.
+pub(crate) const TABLES_RELATION: &str = "scuttle_class";
+
+/// Schema of the [`TABLES_RELATION`] catalog relation.
+fn catalog_schema() -> Schema {
+    Schema::new(vec![
+        ColumnDef::new("table_name", DataType::Text, false),
+        ColumnDef::new("columns", DataType::Json, false),
+    ])
+}
+
+/// Records a new table.
+pub(crate) fn store_table(
+    buffer_pool: &BufferPool,
+    table_name: &str,
+    schema: &Schema,
+) -> Result<()> {
+    let columns = schema
+        .columns
+        .iter()
+        .map(|column| {
+            Json::Object(
+                [
+                    ("name".to_string(), Json::String(column.name.clone())),
+                    (
+                        "type".to_string(),
+                        Json::String(type_name(column.data_type)),
+                    ),
+                    ("nullable".to_string(), Json::Bool(column.nullable)),
+                ]
+                .into_iter()
+                .collect(),
+            )
+        })
+        .collect();
+
+    let row = Row::new(vec![
+        Value::Text(table_name.to_string()),
+        Value::Json(Json::Array(columns)),
+    ]);
+    insert_catalog_row(buffer_pool, TABLES_RELATION, &catalog_schema(), &row)
+}
+
+/// Forgets a dropped table.
+pub(crate) fn remove_table(buffer_pool: &BufferPool, table_name: &str) -> Result<()> {
+    delete_catalog_rows(buffer_pool, TABLES_RELATION, &catalog_schema(), table_name)
+}
+
+/// Every recorded table with its schema.
+pub(crate) fn load_tables(buffer_pool: &BufferPool) -> Result<Vec<(String, Schema)>> {
+    catalog_rows(buffer_pool, TABLES_RELATION, &catalog_schema(), None)?
+        .into_iter()
+        .map(|(_, _, row)| match row.values.as_slice() {
+            [Value::Text(table_name), Value::Json(Json::Array(columns))] => {
+                let columns = columns
+                    .iter()
+                    .map(column_from_json)
+                    .collect::<Result<_>>()?;
+                Ok((table_name.clone(), Schema::new(columns)))
+            }
+            values => Err(miette!("Invalid table catalog row: {values:?}")),
+        })
+        .collect()
+}
+
+fn column_from_json(json: &Json) -> Result<ColumnDef> {
+    match (
+        json.get_key("name"),
+        json.get_key("type"),
+        json.get_key("nullable"),
+    ) {
+        (Some(Json::String(name)), Some(Json::String(type_name)), Some(Json::Bool(nullable))) => {
+            Ok(ColumnDef::new(name, parse_type_name(type_name)?, *nullable))
+        }
+        _ => Err(miette!("Invalid column in the table catalog: {json}")),
+    }
+}
+
+/// The name a type is stored under, with its length or precision.
+fn type_name(data_type: DataType) -> String {
+    match data_type {
+        DataType::Int16 => "int16".to_string(),
+        DataType::Int32 => "int32".to_string(),
+        DataType::Int64 => "int64".to_string(),
+        DataType::Text => "text".to_string(),
+        DataType::VarChar(max_len) => format!("varchar({max_len})"),
+        DataType::Bool => "bool".to_string(),
+        DataType::Float64 => "float64".to_string(),
+        DataType::Decimal(precision, scale) => format!("decimal({precision},{scale})"),
+        DataType::Bytea => "bytea".to_string(),
+        DataType::Uuid => "uuid".to_string(),
+        DataType::Timestamp => "timestamp".to_string(),
+        DataType::Date => "date".to_string(),
+        DataType::Interval => "interval".to_string(),
+        DataType::Json => "json".to_string(),
+    }
+}
+
+fn parse_type_name(name: &str) -> Result<DataType> {
+    let invalid = || miette!("Invalid type in the table catalog: {name}");
+
+    let (base, modifiers) = match name.split_once('(') {
+        Some((base, rest)) => (base, rest.strip_suffix(')').ok_or_else(invalid)?),
+        None => (name, ""),
+    };
+    let modifiers = modifiers
+        .split(',')
+        .filter(|modifier| !modifier.is_empty())
+        .map(|modifier| modifier.parse::<usize>().map_err(|_| invalid()))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(match (base, modifiers.as_slice()) {
+        ("int16", []) => DataType::Int16,
+        ("int32", []) => DataType::Int32,
+        ("int64", []) => DataType::Int64,
+        ("text", []) => DataType::Text,
+        ("varchar", [max_len]) => DataType::VarChar(*max_len),
+        ("bool", []) => DataType::Bool,
+        ("float64", []) => DataType::Float64,
+        ("decimal", [precision, scale]) => DataType::Decimal(
+            u8::try_from(*precision).map_err(|_| invalid())?,
+            u8::try_from(*scale).map_err(|_| invalid())?,
+        ),
+        ("bytea", []) => DataType::Bytea,
+        ("uuid", []) => DataType::Uuid,
+        ("timestamp", []) => DataType::Timestamp,
+        ("date", []) => DataType::Date,
+        ("interval", []) => DataType::Interval,
+        ("json", []) => DataType::Json,
+        _ => return Err(invalid()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tables_are_stored_and_removed() {
+        let directory = std::env::temp_dir().join("scuttle_table_catalog_tests");
+        std::fs::remove_dir_all(&directory).ok();
+        std::fs::create_dir_all(&directory).unwrap();
+        let buffer_pool = BufferPool::new(&directory);
+
+        let schema = Schema::new(vec![
+            ColumnDef::new("id", DataType::Int32, false),
+            ColumnDef::new("name", DataType::VarChar(40), true),
+            ColumnDef::new("price", DataType::Decimal(10, 2), true),
+        ]);
+        store_table(&buffer_pool, "items", &schema).unwrap();
+        store_table(&buffer_pool, "tags", &Schema::new(Vec::new())).unwrap();
+        remove_table(&buffer_pool, "tags").unwrap();
+
+        // Read back from the files, not the cached pages
+        let tables = load_tables(&BufferPool::new(&directory)).unwrap();
+        assert_eq!(tables, vec![("items".to_string(), schema)]);
+    }
+
+    #[test]
+    fn test_type_names_round_trip() {
+        for data_type in [
+            DataType::Int16,
+            DataType::Int64,
+            DataType::VarChar(255),
+            DataType::Decimal(38, 0),
+            DataType::Interval,
+            DataType::Json,
+        ] {
+            assert_eq!(parse_type_name(&type_name(data_type)).unwrap(), data_type);
+        }
+        assert!(parse_type_name("varchar").is_err());
+        assert!(parse_type_name("decimal(300,0)").is_err());
+    }
+}
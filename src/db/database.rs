@@ -1,25 +1,61 @@
 use std::{
-    fs::File,
+    collections::{BTreeMap, HashMap},
+    ops::Bound,
     path::{Path, PathBuf},
+    sync::{Arc, PoisonError, RwLock},
 };
 
 use miette::Result;
 
 use crate::{
-    DatabaseError,
-    db::table::{Table, row::Row, schema::Schema, table_def::TableDef},
+    DataType, DatabaseError, Value,
+    db::{
+        catalog::{
+            indexes::{
+                INDEXES_RELATION, IndexDefinition, load_indexes, remove_indexes, store_index,
+            },
+            statistics::{
+                SAMPLE_PAGES, STATISTICS_RELATION, TableStatistics, load_statistics,
+                remove_statistics, store_statistics,
+            },
+            tables::{TABLES_RELATION, load_tables, remove_table, store_table},
+        },
+        index::{Index, RowId},
+        prepared_statement::PreparedStatement,
+        row_stream::RowStream,
+        table::{
+            Table,
+            row::{FromRow, Row},
+            schema::Schema,
+            table_def::TableDef,
+        },
+    },
     sql::{
-        analyzer::{Analyzer, schema::OutputSchema},
+        analyzer::{
+            Analyzer,
+            schema::{Field, OutputSchema},
+        },
+        ast::{expression::Expression, statement::Statement},
         catalog_context::CatalogContext,
+        evaluator::{Evaluator, expression::ExpressionEvaluator},
+        functions::{Accumulator, FunctionRegistry, Signature},
+        optimizer::Optimizer,
         parser::SqlParser,
-        planner::physical::PhysicalPlanner,
+        planner::{explain::explain_plan, logical::LogicalPlan, physical::PhysicalPlanner},
     },
     storage::{
         buffer_pool::BufferPool,
-        page::{ItemId, PageHeader, PageId},
+        page::{ItemId, PageHeader, PageId, PageType},
+        toast::{TableToast, toast_relation},
     },
 };
 
+/// Upper bound on the default number of threads of a parallel scan.
+const MAX_PARALLEL_WORKERS: usize = 8;
+
+/// Default pages of a table per worker of a parallel scan, 512 KB.
+const PARALLEL_SCAN_PAGES: PageId = 64;
+
 /// Response from executing a SQL query.
 ///
 /// Contains the table metadata and the result rows.
@@ -31,13 +67,36 @@ pub struct QueryResponse {
     pub rows: Vec<Row>,
 }
 
+impl QueryResponse {
+    /// Decodes every row into `T`, e.g. a tuple or a struct deriving [`FromRow`].
+    pub fn rows_as<T: FromRow>(&self) -> Result<Vec<T>, DatabaseError> {
+        self.rows
+            .iter()
+            .map(|row| T::from_row(row, &self.schema))
+            .collect()
+    }
+
+    /// The response of a statement returning nothing, e.g. `VACUUM`.
+    fn empty() -> Self {
+        Self {
+            schema: OutputSchema { fields: Vec::new() },
+            rows: Vec::new(),
+        }
+    }
+}
+
 /// The main database handle.
 ///
 /// `Database` is the primary interface for interacting with Scuttle DB. It manages:
 /// - Table definitions and schemas
 /// - Data storage via a buffer pool
 /// - SQL query execution
-/// - Data persistence (work in progress)
+/// - Data persistence: tables are recorded in a catalog and found again by
+///   [`Database::open`]
+///
+/// To use a database from several threads, wrap it in a
+/// [`SharedDatabase`](crate::SharedDatabase) and open a [`Session`](crate::Session) per
+/// thread.
 ///
 /// # Architecture
 ///
@@ -58,8 +117,30 @@ pub struct Database {
     /// Handles reading/writing data pages and caching them for performance.
     pub buffer_manager: BufferPool,
 
+    /// Built-in and user-defined functions callable from SQL.
+    pub(crate) functions: FunctionRegistry,
+
+    /// Table statistics loaded from the catalog or gathered by `ANALYZE`.
+    statistics: RwLock<HashMap<String, Arc<TableStatistics>>>,
+
+    /// Indexes by name, with their entries built in memory.
+    indexes: RwLock<BTreeMap<String, Index>>,
+
+    /// Statements created with `PREPARE name AS ...`, by name.
+    prepared_statements: HashMap<String, PreparedStatement>,
+
     /// Directory where database files are stored.
     data_directory: PathBuf,
+
+    /// Threads a parallel scan may use, 1 disables parallel scans.
+    max_parallel_workers: usize,
+
+    /// Pages of a table per worker of a parallel scan, smaller tables are scanned by
+    /// fewer workers or a single thread.
+    pub(crate) parallel_scan_pages: PageId,
+
+    /// Whether changes are refused, see [`Database::set_read_only`].
+    read_only: bool,
 }
 
 impl Database {
@@ -67,56 +148,94 @@ impl Database {
     ///
     /// Creates the data directory if it doesn't exist. The database starts empty
     /// with no tables loaded. Call [`Database::initialize`] after creation to
-    /// set up any necessary system catalogs (currently a no-op).
+    /// load the tables of an existing database, or use [`Database::open`].
     pub fn new<P: AsRef<Path>>(data_directory: P) -> Self {
         let data_dir = data_directory.as_ref().to_path_buf();
         std::fs::create_dir_all(&data_dir).ok();
 
         Self {
             tables: std::collections::BTreeMap::default(),
-            buffer_manager: BufferPool::new(),
+            buffer_manager: BufferPool::new(&data_dir),
+            functions: FunctionRegistry::new(),
+            statistics: RwLock::new(HashMap::new()),
+            indexes: RwLock::new(BTreeMap::new()),
+            prepared_statements: HashMap::new(),
 
             data_directory: data_directory.as_ref().to_path_buf(),
+            max_parallel_workers: std::thread::available_parallelism()
+                .map(|workers| workers.get().min(MAX_PARALLEL_WORKERS))
+                .unwrap_or(1),
+            parallel_scan_pages: PARALLEL_SCAN_PAGES,
+            read_only: false,
         }
     }
 
-    /// Initializes the database.
+    /// Opens the database in a data directory, with the tables created in it before.
+    pub fn open<P: AsRef<Path>>(data_directory: P) -> Result<Self> {
+        let mut database = Self::new(data_directory);
+        database.initialize()?;
+        Ok(database)
+    }
+
+    /// Limits the number of threads a parallel scan may use, 1 disables parallel scans.
     ///
-    /// Currently a placeholder for future initialization logic such as:
-    /// - Loading system catalogs
-    /// - Setting up metadata tables
-    /// - Recovering from crash (WAL replay)
+    /// Defaults to the available parallelism of the machine, up to 8 threads.
+    pub fn set_max_parallel_workers(&mut self, workers: usize) {
+        self.max_parallel_workers = workers.max(1);
+    }
+
+    /// The number of threads a parallel scan may use.
+    pub fn max_parallel_workers(&self) -> usize {
+        self.max_parallel_workers
+    }
+
+    /// Initializes the database, loading the tables recorded in its catalog.
+    ///
+    /// Future initialization logic, such as recovering from a crash (WAL replay),
+    /// belongs here too.
     pub fn initialize(&mut self) -> Result<()> {
+        self.load_from_file()?;
         Ok(())
     }
 
-    /// Checks if a table exists in the database.
+    /// Refuses changes to the database, e.g. to inspect it without risk.
     ///
-    /// Looks both in-memory (loaded tables) and on-disk (table files).
-    fn table_exists(&self, name: &str) -> bool {
-        if self.tables.contains_key(name) {
-            true
-        } else {
-            let table_path = self.data_directory.join(format!("{name}.table"));
-            table_path.exists()
+    /// Inserting rows, creating or dropping tables, `VACUUM` and `ANALYZE` then fail
+    /// with [`DatabaseError::ReadOnly`].
+    pub fn set_read_only(&mut self, read_only: bool) {
+        self.read_only = read_only;
+    }
+
+    /// Whether changes to the database are refused.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    fn check_writable(&self) -> Result<(), DatabaseError> {
+        if self.read_only {
+            return Err(DatabaseError::ReadOnly);
         }
+        Ok(())
     }
 
     /// Creates a new table with the given schema.
     ///
-    /// The table is created in-memory and ready for use immediately. Currently,
-    /// if a table with the same name already exists, it prints a warning and
-    /// continues (re-creating the table in memory).
+    /// The table is recorded in the catalog and ready for use immediately. Fails if a
+    /// table with the same name already exists.
     pub fn create_table(&mut self, name: &str, schema: Schema) -> Result<(), DatabaseError> {
-        if self.table_exists(name) {
-            // return Err(DatabaseError::InvalidQuery(format!(
-            //     "Table {name} already exists"
-            // )));
-            // Eventually save table information in a catalog table,
-            // but for now just load the table with the schema normally
-            println!("Table {name} already exists");
+        self.check_writable()?;
+        if self.tables.contains_key(name) {
+            return Err(DatabaseError::InvalidQuery(format!(
+                "Table {name} already exists"
+            )));
+        }
+        if [TABLES_RELATION, STATISTICS_RELATION, INDEXES_RELATION].contains(&name) {
+            return Err(DatabaseError::InvalidQuery(format!(
+                "Table name {name} is reserved for the catalog"
+            )));
         }
 
+        store_table(&self.buffer_manager, name, &schema).map_err(storage_error)?;
         let table = TableDef::new(name.to_string(), schema);
         self.tables.insert(name.to_string(), table);
         Ok(())
@@ -131,37 +250,35 @@ impl Database {
 
     /// Gets a mutable reference to a table.
     pub fn get_table_mut(&mut self, name: &str) -> Result<&mut TableDef, DatabaseError> {
-        if self.table_exists(name) {
-            #[expect(clippy::missing_panics_doc, reason = "infallible")]
-            return Ok(self.tables.get_mut(name).unwrap());
-        }
+        self.tables
+            .get_mut(name)
+            .ok_or_else(|| DatabaseError::TableNotFound(name.to_string()))
+    }
 
-        Err(DatabaseError::TableNotFound(name.to_string()))
+    /// Returns the columns of a table.
+    pub fn table_schema(&self, name: &str) -> Result<&Schema, DatabaseError> {
+        Ok(&self.get_table(name)?.schema)
     }
 
-    /// Loads table metadata from disk (work in progress).
+    /// Loads the tables recorded in the catalog, e.g. when opening an existing database,
+    /// and builds their indexes.
     ///
-    /// Currently unimplemented. In the future, this will:
-    /// - Scan the data directory for `.table` files
-    /// - Deserialize table metadata
-    /// - Load schemas into memory
+    /// Tables and indexes already loaded are kept.
     pub fn load_from_file(&mut self) -> Result<(), DatabaseError> {
         std::fs::create_dir_all(&self.data_directory)?;
-        let entries = std::fs::read_dir(&self.data_directory)?;
 
-        for entry in entries {
-            let entry = entry?;
-            let path = entry.path();
-
-            if path.extension().and_then(|s| s.to_str()) == Some("table") {
-                let _file = File::open(&path)?;
-                // TODO: Fix this decode from file
-                // let table: Relation = decode_from_std_read(&mut file, bincode::config::standard())?;
+        for (name, schema) in load_tables(&self.buffer_manager).map_err(storage_error)? {
+            self.tables
+                .entry(name.clone())
+                .or_insert_with(|| TableDef::new(name, schema));
+        }
 
-                // println!("Table: {table:#?}");
-                //
-                // self.tables.insert(table.name.clone(), table);
+        for definition in load_indexes(&self.buffer_manager).map_err(storage_error)? {
+            if self.indexes().contains_key(&definition.index_name) {
+                continue;
             }
+            let index = self.build_index(&definition)?;
+            self.indexes_mut().insert(definition.index_name, index);
         }
 
         Ok(())
@@ -169,39 +286,240 @@ impl Database {
 
     /// Drops a table from the database.
     ///
-    /// Removes the table from memory. Currently does not delete on-disk files.
+    /// Removes the table from the catalog, along with its statistics and its files.
     pub fn drop_table(&mut self, name: &str) -> Result<(), DatabaseError> {
+        self.check_writable()?;
         self.tables
             .remove(name)
             .ok_or_else(|| DatabaseError::TableNotFound(name.to_string()))?;
+
+        remove_table(&self.buffer_manager, name).map_err(storage_error)?;
+        remove_statistics(&self.buffer_manager, name).map_err(storage_error)?;
+        remove_indexes(&self.buffer_manager, name).map_err(storage_error)?;
+        self.indexes_mut()
+            .retain(|_, index| index.table_name != name);
+        self.statistics
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(name);
+
+        self.buffer_manager
+            .drop_relation(name)
+            .map_err(storage_error)?;
+        self.buffer_manager
+            .drop_relation(&toast_relation(name))
+            .map_err(storage_error)?;
+        Ok(())
+    }
+
+    /// Creates an index on a column of a table, the planner then considers index scans
+    /// for conditions comparing the column with a constant.
+    ///
+    /// The index is recorded in the catalog and built from the rows of the table, its
+    /// entries are kept in memory. Columns of floating point, binary, JSON and interval
+    /// types cannot be indexed.
+    pub fn create_index(
+        &mut self,
+        index_name: &str,
+        table_name: &str,
+        column_name: &str,
+    ) -> Result<(), DatabaseError> {
+        self.check_writable()?;
+        if self.indexes().contains_key(index_name) {
+            return Err(DatabaseError::InvalidQuery(format!(
+                "Index {index_name} already exists"
+            )));
+        }
+        let column = self
+            .table_schema(table_name)?
+            .columns
+            .iter()
+            .find(|column| column.name == column_name)
+            .ok_or_else(|| DatabaseError::ColumnNotFound(column_name.to_string()))?;
+        if !column.data_type.is_indexable() {
+            return Err(DatabaseError::InvalidQuery(format!(
+                "Columns of type {} cannot be indexed",
+                column.data_type
+            )));
+        }
+
+        let definition = IndexDefinition {
+            table_name: table_name.to_string(),
+            index_name: index_name.to_string(),
+            column_name: column_name.to_string(),
+        };
+        let index = self.build_index(&definition)?;
+        store_index(&self.buffer_manager, &definition).map_err(storage_error)?;
+        self.indexes_mut().insert(index_name.to_string(), index);
+        Ok(())
+    }
+
+    /// Drops an index, removing it from the catalog.
+    pub fn drop_index(&mut self, index_name: &str) -> Result<(), DatabaseError> {
+        self.check_writable()?;
+        let index = self.indexes_mut().remove(index_name).ok_or_else(|| {
+            DatabaseError::InvalidQuery(format!("Index {index_name} does not exist"))
+        })?;
+
+        // Catalog rows are keyed by table, the other indexes of the table are recorded again
+        remove_indexes(&self.buffer_manager, &index.table_name).map_err(storage_error)?;
+        let schema = self.table_schema(&index.table_name)?;
+        for other in self.indexes().values() {
+            if other.table_name == index.table_name {
+                let definition = IndexDefinition {
+                    table_name: other.table_name.clone(),
+                    index_name: other.name.clone(),
+                    column_name: schema.columns[other.column].name.clone(),
+                };
+                store_index(&self.buffer_manager, &definition).map_err(storage_error)?;
+            }
+        }
         Ok(())
     }
 
+    /// The name and indexed column of every index on a table.
+    pub(crate) fn table_indexes(&self, table_name: &str) -> Vec<(String, usize)> {
+        self.indexes()
+            .values()
+            .filter(|index| index.table_name == table_name)
+            .map(|index| (index.name.clone(), index.column))
+            .collect()
+    }
+
+    /// The positions of the rows whose indexed value lies within the bounds, in the
+    /// order of their values.
+    pub(crate) fn index_rows(
+        &self,
+        index_name: &str,
+        lower: Bound<&Value>,
+        upper: Bound<&Value>,
+    ) -> Result<Vec<RowId>, DatabaseError> {
+        let indexes = self.indexes();
+        let index = indexes.get(index_name).ok_or_else(|| {
+            DatabaseError::InvalidQuery(format!("Index {index_name} does not exist"))
+        })?;
+        Ok(index.rows(lower, upper))
+    }
+
+    /// Reads the rows of a table into an index on it.
+    fn build_index(&self, definition: &IndexDefinition) -> Result<Index, DatabaseError> {
+        let table_name = &definition.table_name;
+        let column = self
+            .table_schema(table_name)?
+            .columns
+            .iter()
+            .position(|column| column.name == definition.column_name)
+            .ok_or_else(|| DatabaseError::ColumnNotFound(definition.column_name.clone()))?;
+
+        let mut index = Index::new(&definition.index_name, table_name, column);
+        self.fill_index(&mut index)?;
+        Ok(index)
+    }
+
+    /// Adds every row of the indexed table to `index`.
+    fn fill_index(&self, index: &mut Index) -> Result<(), DatabaseError> {
+        for page_id in 0..self
+            .buffer_manager
+            .page_count(&index.table_name)
+            .map_err(storage_error)?
+        {
+            for (item_id, row) in
+                self.page_items(&index.table_name, page_id, Some(&[index.column]))?
+            {
+                index.insert(
+                    row.values.into_iter().next().unwrap_or(Value::Null),
+                    (page_id, item_id),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn indexes(&self) -> std::sync::RwLockReadGuard<'_, BTreeMap<String, Index>> {
+        self.indexes.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn indexes_mut(&self) -> std::sync::RwLockWriteGuard<'_, BTreeMap<String, Index>> {
+        self.indexes.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Registers a scalar function callable from SQL.
+    ///
+    /// The analyzer checks calls against `signature`, and `func` is invoked once per row
+    /// with the evaluated arguments. NULL arguments are passed through to `func`.
+    /// Function names are case-insensitive and must not clash with an existing function.
+    pub fn register_scalar_function<F>(
+        &mut self,
+        name: &str,
+        signature: Signature,
+        func: F,
+    ) -> Result<(), DatabaseError>
+    where
+        F: Fn(&[Value]) -> Result<Value, DatabaseError> + Send + Sync + 'static,
+    {
+        self.functions
+            .register_scalar(name, signature, Box::new(func))
+    }
+
+    /// Registers an aggregate function callable from SQL.
+    ///
+    /// `init` creates a fresh [`Accumulator`] for every group. Aggregates are used like
+    /// the built-in ones, either over the whole table or together with `GROUP BY`.
+    pub fn register_aggregate<F>(
+        &mut self,
+        name: &str,
+        signature: Signature,
+        init: F,
+    ) -> Result<(), DatabaseError>
+    where
+        F: Fn() -> Box<dyn Accumulator> + Send + Sync + 'static,
+    {
+        self.functions
+            .register_aggregate(name, signature, Box::new(init))
+    }
+
     /// Inserts a row into a table.
     ///
     /// The row is validated against the table's schema, encoded to bytes,
-    /// and stored in a page managed by the buffer pool. The row is persisted
-    /// to disk immediately.
-    pub fn insert_row(&mut self, table_name: &str, row: Row) -> Result<(PageId, ItemId)> {
-        println!("Inserting row into table: {table_name}");
+    /// and stored in a page managed by the buffer pool. Large values are compressed
+    /// or stored in the table's overflow pages. The row is persisted to disk
+    /// immediately.
+    ///
+    /// Takes `&self`, callers running concurrently must keep other writers out of the
+    /// table, as a [`Session`](crate::Session) does with its table locks.
+    pub fn insert_row(&self, table_name: &str, row: Row) -> Result<(PageId, ItemId)> {
+        self.check_writable()?;
 
         // Get schema first (separate borrow scope)
-        let encoded_data = {
-            let table = self.get_table(table_name)?;
-            table.schema().encode_row(&row)
+        let (row, encoded_data) = {
+            let schema = self
+                .tables
+                .get(table_name)
+                .ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?
+                .schema();
+            let row = schema.parse_json_columns(row)?;
+            let mut toast = TableToast::new(&self.buffer_manager, table_name);
+            let encoded_data = schema.encode_row(&row, &mut toast)?;
+            (row, encoded_data)
         };
 
         // Now get the page and insert data
-        let free_page = self
+        let page_id =
+            self.buffer_manager
+                .free_page(table_name, encoded_data.len(), PageType::Table)?;
+        let item_id = self
             .buffer_manager
-            .get_free_page(table_name, encoded_data.len())?;
-        let page_id = free_page.header.page_id;
-
-        let item_id = free_page.add_data(&encoded_data)?;
+            .write_page(table_name, page_id, |page| page.add_data(&encoded_data))??;
 
         // Note: save_page method needs to be implemented in BufferManager
         self.buffer_manager.save_page(table_name, page_id)?;
 
+        for index in self.indexes_mut().values_mut() {
+            if index.table_name == table_name {
+                index.insert(row.values[index.column].clone(), (page_id, item_id));
+            }
+        }
+
         Ok((page_id, item_id))
     }
 
@@ -210,42 +528,245 @@ impl Database {
     /// Scans all pages for the table and decodes all non-deleted rows.
     /// This is an expensive operation for large tables. Use [`Database::execute_query`]
     /// with a WHERE clause to filter rows efficiently.
-    pub fn get_rows(&mut self, table_name: &str) -> Result<Vec<Row>, DatabaseError> {
+    pub fn get_rows(&self, table_name: &str) -> Result<Vec<Row>, DatabaseError> {
+        self.scan_rows(table_name, None)
+    }
+
+    /// Retrieves the given columns of all rows of a table, see [`Schema::decode_columns`].
+    pub(crate) fn scan_rows(
+        &self,
+        table_name: &str,
+        columns: Option<&[usize]>,
+    ) -> Result<Vec<Row>, DatabaseError> {
         let mut found_rows: Vec<Row> = Vec::new();
-        let max_pages = 1000; // To prevent infinite loops
 
-        for current_page_id in 0..max_pages {
-            // Use get_page instead of get_page_mut (which doesn't exist)
-            let page_res = self.buffer_manager.get_page(table_name, current_page_id);
+        for current_page_id in 0..self
+            .buffer_manager
+            .page_count(table_name)
+            .map_err(storage_error)?
+        {
+            found_rows.extend(self.page_rows(table_name, current_page_id, columns)?);
+        }
+
+        Ok(found_rows)
+    }
+
+    /// Decodes the given columns of the rows stored in one page of a table.
+    pub(crate) fn page_rows(
+        &self,
+        table_name: &str,
+        page_id: PageId,
+        columns: Option<&[usize]>,
+    ) -> Result<Vec<Row>, DatabaseError> {
+        Ok(self
+            .page_items(table_name, page_id, columns)?
+            .into_iter()
+            .map(|(_, row)| row)
+            .collect())
+    }
+
+    /// Decodes the given columns of the rows stored in one page of a table, along with
+    /// their item ids.
+    fn page_items(
+        &self,
+        table_name: &str,
+        page_id: PageId,
+        columns: Option<&[usize]>,
+    ) -> Result<Vec<(ItemId, Row)>, DatabaseError> {
+        let all_items = |item_count| (0..item_count).collect::<Vec<_>>();
+        self.read_items(table_name, page_id, all_items, columns)
+    }
+
+    /// Decodes the given columns of the rows at `row_ids`, in that order.
+    ///
+    /// Rows that were deleted since their position was taken are skipped.
+    pub(crate) fn fetch_rows(
+        &self,
+        table_name: &str,
+        row_ids: &[RowId],
+        columns: Option<&[usize]>,
+    ) -> Result<Vec<Row>, DatabaseError> {
+        let mut rows = Vec::with_capacity(row_ids.len());
+        for page_rows in row_ids.chunk_by(|a, b| a.0 == b.0) {
+            let item_ids = |_| page_rows.iter().map(|(_, item_id)| *item_id).collect();
+            let items = self.read_items(table_name, page_rows[0].0, item_ids, columns)?;
+            rows.extend(items.into_iter().map(|(_, row)| row));
+        }
+        Ok(rows)
+    }
+
+    /// Decodes the live rows among the items of a page picked by `item_ids`, which is
+    /// given the number of items in the page.
+    fn read_items(
+        &self,
+        table_name: &str,
+        page_id: PageId,
+        item_ids: impl FnOnce(ItemId) -> Vec<ItemId>,
+        columns: Option<&[usize]>,
+    ) -> Result<Vec<(ItemId, Row)>, DatabaseError> {
+        // Copied out of the page, detoasting reads other pages
+        let items = self
+            .buffer_manager
+            .read_page(table_name, page_id, |page| {
+                let item_pointers = page.item_pointers().collect::<Vec<_>>();
+                item_ids(item_pointers.len() as ItemId)
+                    .into_iter()
+                    .filter_map(|item_id| {
+                        let item_pointer = item_pointers.get(item_id as usize)?;
+                        if item_pointer.is_deleted() {
+                            return None;
+                        }
+                        let offset = item_pointer.offset as usize - PageHeader::SIZE;
+                        let length = item_pointer.length as usize;
+                        Some((item_id, page.data[offset..offset + length].to_vec()))
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .map_err(storage_error)?;
+
+        let schema = self.tables.get(table_name).unwrap().schema();
+        let mut toast = TableToast::new(&self.buffer_manager, table_name);
+        items
+            .iter()
+            .map(|(item_id, item_data)| {
+                let row = schema
+                    .decode_columns(item_data, columns, &mut toast)
+                    .map_err(storage_error)?;
+                Ok((*item_id, row))
+            })
+            .collect()
+    }
+
+    /// Reclaims the space of deleted rows in a table.
+    ///
+    /// Every page holding deleted rows is compacted and saved, the freed space is then
+    /// reused by later inserts. The out-of-line values of the deleted rows are freed as
+    /// well. Returns the number of bytes reclaimed in the table's pages.
+    pub fn vacuum(&self, table_name: &str) -> Result<usize> {
+        self.check_writable()?;
+        if !self.tables.contains_key(table_name) {
+            return Err(DatabaseError::TableNotFound(table_name.to_string()).into());
+        }
+
+        let schema = self.get_table(table_name)?.schema();
+        let mut toast = TableToast::new(&self.buffer_manager, table_name);
+
+        let mut reclaimed = 0;
+        for page_id in 0..self.buffer_manager.page_count(table_name)? {
+            let compacted = self
+                .buffer_manager
+                .write_page(table_name, page_id, |page| {
+                    if !page.has_dead_items() {
+                        return None;
+                    }
 
-            let page = match page_res {
-                Ok(page) => page,
-                Err(_) => break,
+                    let dead_rows = page
+                        .item_pointers()
+                        .filter(|item_pointer| {
+                            item_pointer.is_deleted() && !item_pointer.is_unused()
+                        })
+                        .map(|item_pointer| {
+                            let offset = item_pointer.offset as usize - PageHeader::SIZE;
+                            let length = item_pointer.length as usize;
+                            page.data[offset..offset + length].to_vec()
+                        })
+                        .collect::<Vec<_>>();
+                    Some((page.compact(), dead_rows))
+                })?;
+            let Some((compacted, dead_rows)) = compacted else {
+                continue;
             };
 
-            for item_pointer in page.item_pointers() {
-                if item_pointer.is_deleted() {
-                    continue;
+            reclaimed += compacted;
+            self.buffer_manager.save_page(table_name, page_id)?;
+
+            // Freed after the page is saved, a crash in between leaks the values instead
+            // of leaving rows that point to reused chunks
+            for row in dead_rows {
+                schema.delete_toasted(&row, &mut toast)?;
+            }
+        }
+
+        // Later inserts reuse the item ids of the deleted rows, which indexes still hold
+        if reclaimed > 0 {
+            let mut indexes = self.indexes_mut();
+            for index in indexes.values_mut() {
+                if index.table_name == table_name {
+                    index.clear();
+                    self.fill_index(index)?;
                 }
+            }
+        }
 
-                let offset = item_pointer.offset as usize - PageHeader::SIZE;
-                let length = item_pointer.length as usize;
+        Ok(reclaimed)
+    }
 
-                let item_data = &page.data[offset..offset + length];
-                let decoded_row = self
-                    .tables
-                    .get(table_name)
-                    .unwrap()
-                    .schema()
-                    .decode_row(item_data)
-                    // TODO: Eventually return a SerializationError
-                    .expect("Row should be decoded");
+    /// Gathers statistics about a table and its columns, see [`TableStatistics`].
+    ///
+    /// Up to [`SAMPLE_PAGES`] pages spread over the table are read, the row count is
+    /// extrapolated from them. The statistics are saved in the catalog, replacing the
+    /// ones from an earlier `ANALYZE`.
+    pub fn analyze(&self, table_name: &str) -> Result<Arc<TableStatistics>> {
+        self.check_writable()?;
+        let schema = self.get_table(table_name)?.schema().clone();
 
-                found_rows.push(decoded_row);
-            }
+        let page_count = self.buffer_manager.page_count(table_name)?;
+        let sampled_pages = page_count.min(SAMPLE_PAGES);
+        let mut sample = Vec::new();
+        for sample_index in 0..sampled_pages {
+            let page_id = (sample_index as u64 * page_count as u64 / sampled_pages as u64) as u32;
+            sample.extend(self.page_rows(table_name, page_id, None)?);
         }
 
-        Ok(found_rows)
+        let row_count = if sampled_pages == 0 {
+            0
+        } else {
+            (sample.len() as f64 * page_count as f64 / sampled_pages as f64).round() as u64
+        };
+        let statistics = TableStatistics::from_sample(&schema, &sample, row_count, page_count);
+        store_statistics(&self.buffer_manager, table_name, &statistics)?;
+
+        let statistics = Arc::new(statistics);
+        self.statistics
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(table_name.to_string(), Arc::clone(&statistics));
+        Ok(statistics)
+    }
+
+    /// The statistics recorded by the last [`Database::analyze`] of a table, `None` if it
+    /// was never analyzed.
+    pub fn statistics(
+        &self,
+        table_name: &str,
+    ) -> Result<Option<Arc<TableStatistics>>, DatabaseError> {
+        let cached = self
+            .statistics
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(table_name)
+            .cloned();
+        if cached.is_some() {
+            return Ok(cached);
+        }
+
+        let schema = self
+            .tables
+            .get(table_name)
+            .ok_or_else(|| DatabaseError::TableNotFound(table_name.to_string()))?
+            .schema();
+        let Some(statistics) =
+            load_statistics(&self.buffer_manager, table_name, schema).map_err(storage_error)?
+        else {
+            return Ok(None);
+        };
+
+        let statistics = Arc::new(statistics);
+        self.statistics
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .insert(table_name.to_string(), Arc::clone(&statistics));
+        Ok(Some(statistics))
     }
 
     /// Executes a SQL query and returns the results.
@@ -254,37 +775,1198 @@ impl Database {
     /// 1. **Lexing** - Tokenize the SQL string
     /// 2. **Parsing** - Build an Abstract Syntax Tree (AST)
     /// 3. **Logical Planning** - Convert AST to logical query plan
-    /// 4. **Physical Planning** - Convert to executable physical plan
-    /// 5. **Execution** - Execute the plan and return rows
+    /// 4. **Optimization** - Rewrite the logical plan, e.g. fold constants and push filters
+    ///    into scans
+    /// 5. **Physical Planning** - Convert to executable physical plan
+    /// 6. **Execution** - Execute the plan and return rows
     pub fn execute_query(&mut self, query: &str) -> Result<QueryResponse> {
-        let mut parser = SqlParser::new(query);
-        let statement = parser
-            .parse()
-            .map_err(|e| DatabaseError::InvalidQuery(format!("Parse error: {e}")))?;
+        let statement = parse_statement(query)?;
+
+        // Taken out while the statement runs through `&self`
+        let mut prepared_statements = std::mem::take(&mut self.prepared_statements);
+        let response = self.execute_statement(statement, &mut prepared_statements);
+        self.prepared_statements = prepared_statements;
+        response
+    }
+
+    /// Runs a parsed statement, with `prepared_statements` holding the statements
+    /// created by `PREPARE` of the caller, e.g. of a [`Session`](crate::Session).
+    pub(crate) fn execute_statement(
+        &self,
+        statement: Statement,
+        prepared_statements: &mut HashMap<String, PreparedStatement>,
+    ) -> Result<QueryResponse> {
+        if let Statement::Vacuum(vacuum) = statement {
+            let table_names = match vacuum.table_name {
+                Some(table_name) => vec![table_name],
+                None => self.tables.keys().cloned().collect(),
+            };
+            for table_name in table_names {
+                self.vacuum(&table_name)?;
+            }
+
+            return Ok(QueryResponse::empty());
+        }
+
+        if let Statement::Explain(explain) = statement {
+            return self.explain(*explain.statement, explain.analyze);
+        }
+
+        if let Statement::Analyze(analyze) = statement {
+            let table_names = match analyze.table_name {
+                Some(table_name) => vec![table_name],
+                None => self.tables.keys().cloned().collect(),
+            };
+            for table_name in table_names {
+                self.analyze(&table_name)?;
+            }
+
+            return Ok(QueryResponse::empty());
+        }
+
+        if let Statement::Prepare(prepare) = statement {
+            if prepared_statements.contains_key(&prepare.name) {
+                return Err(DatabaseError::InvalidQuery(format!(
+                    "Prepared statement {} already exists",
+                    prepare.name
+                ))
+                .into());
+            }
+
+            let prepared = self.prepare_statement(*prepare.statement, &prepare.parameter_types)?;
+            prepared_statements.insert(prepare.name, prepared);
+            return Ok(QueryResponse::empty());
+        }
+
+        if let Statement::Execute(execute) = statement {
+            let prepared = find_prepared(prepared_statements, &execute.name)?.clone();
+            let parameters = execute
+                .parameters
+                .iter()
+                .map(|parameter| self.constant_value(parameter))
+                .collect::<Result<Vec<_>>>()?;
+            return self.execute(&prepared, &parameters);
+        }
+
+        if let Statement::Deallocate(deallocate) = statement {
+            match deallocate.name {
+                Some(name) => {
+                    find_prepared(prepared_statements, &name)?;
+                    prepared_statements.remove(&name);
+                }
+                None => prepared_statements.clear(),
+            }
+            return Ok(QueryResponse::empty());
+        }
+
+        let plan = self.plan_statement(statement)?;
+        self.execute_plan(plan)
+    }
+
+    /// Runs a query and returns its rows as they are produced.
+    ///
+    /// Unlike [`Database::execute_query`], rows are not collected up front: pages are
+    /// read as the stream is consumed, and dropping the stream stops the query. Only
+    /// statements returning rows, such as SELECT, can be streamed.
+    pub fn query(&self, query: &str) -> Result<RowStream<'_>> {
+        let statement = parse_statement(query)?;
+
+        let plan = self.plan_statement(statement)?;
+        self.stream_plan(plan)
+    }
+
+    /// Parses and plans `query` once, to run it with [`Database::execute`].
+    ///
+    /// The query can contain the parameters `$1`, `$2`, ... or `?`, their types are
+    /// inferred from where they are used, e.g. from the column compared against.
+    pub fn prepare(&self, query: &str) -> Result<PreparedStatement> {
+        let statement = parse_statement(query)?;
+
+        self.prepare_statement(statement, &[])
+    }
+
+    /// Runs a prepared statement with the given values for its parameters.
+    ///
+    /// Values are cast to the parameter types, a value that cannot be cast fails
+    /// with [`DatabaseError::TypeMismatch`].
+    pub fn execute(
+        &self,
+        statement: &PreparedStatement,
+        parameters: &[Value],
+    ) -> Result<QueryResponse> {
+        let plan = statement.bind(parameters)?;
+
+        // Values can enable rewrites the parameters prevented, e.g. constant folding
+        self.execute_plan(Optimizer::new().optimize(plan))
+    }
+
+    /// Returns the prepared statement created by `PREPARE name`.
+    pub fn prepared_statement(&self, name: &str) -> Result<&PreparedStatement, DatabaseError> {
+        find_prepared(&self.prepared_statements, name)
+    }
+
+    pub(crate) fn prepare_statement(
+        &self,
+        statement: Statement,
+        parameter_types: &[DataType],
+    ) -> Result<PreparedStatement> {
+        let context = CatalogContext::new(self);
+        let analyzer = Analyzer::with_parameter_types(&context, parameter_types);
+        let analyzed_plan = analyzer.analyze(statement)?;
+        let parameter_types = analyzer.parameter_types()?;
+
+        Ok(PreparedStatement::new(
+            Optimizer::new().optimize(analyzed_plan),
+            parameter_types,
+        ))
+    }
+
+    /// Evaluates an expression that references no columns, e.g. an `EXECUTE` argument.
+    fn constant_value(&self, expr: &Expression) -> Result<Value> {
+        // Untyped NULL is accepted for parameters of any type
+        if let Expression::Literal(Value::Null) = expr {
+            return Ok(Value::Null);
+        }
+
+        let context = CatalogContext::new(self);
+        let analyzed = Analyzer::new(&context).bind_constant(expr)?;
+        ExpressionEvaluator.evaluate(&analyzed, &Row::new(Vec::new()))
+    }
 
-        let mut context = CatalogContext::new(self);
+    /// Analyzes and optimizes a statement that is not prepared.
+    fn plan_statement(&self, statement: Statement) -> Result<LogicalPlan> {
+        let context = CatalogContext::new(self);
         let analyzer = Analyzer::new(&context);
         let anayzed_plan = analyzer.analyze(statement)?;
+        if analyzer.parameter_count() > 0 {
+            return Err(DatabaseError::InvalidQuery(
+                "Parameters can only be used in prepared statements".to_string(),
+            )
+            .into());
+        }
+
+        Ok(Optimizer::new().optimize(anayzed_plan))
+    }
+
+    fn execute_plan(&self, plan: LogicalPlan) -> Result<QueryResponse> {
+        let stream = self.stream_plan(plan)?;
+        let schema = stream.schema().clone();
+        let rows = stream.collect::<Result<_>>()?;
+
+        Ok(QueryResponse { schema, rows })
+    }
+
+    /// Creates the execution nodes of a plan, they borrow the database until dropped.
+    fn stream_plan(&self, plan: LogicalPlan) -> Result<RowStream<'_>> {
+        let context = CatalogContext::new(self);
+        let executor = PhysicalPlanner::new(&context)
+            .create_physical_plan(plan)
+            .map_err(|e| DatabaseError::InvalidQuery(format!("Physical Plan error: {e}")))?;
+
+        Ok(RowStream::new(context, executor))
+    }
 
-        // TODO: Eventually add a optimizer here for logical_plan.
-        // Will do a series of "pushdowns".
-        //
-        //  - Predicate Push Down: move filters as close together to the read source
-        //  - Projection pushdown: only read columns we actually need (I think this is already implemeted)
-        //  - Constant Folding: turn 'age > 10 + 5' to 'age > 25'
+    /// Plans a statement and returns its plan as one text row per line.
+    ///
+    /// With `analyze` the plan is also run, and the rows, batches and time of every
+    /// operator are reported next to its estimates.
+    fn explain(&self, statement: Statement, analyze: bool) -> Result<QueryResponse> {
+        let context = CatalogContext::new(self);
+        let analyzed_plan = Analyzer::new(&context).analyze(statement)?;
+        let optimized_plan = Optimizer::new().optimize(analyzed_plan);
 
-        let mut physical_planner = PhysicalPlanner::new(&mut context);
+        let mut physical_planner = PhysicalPlanner::new(&context).profiled();
         let mut executor = physical_planner
-            .create_physical_plan(anayzed_plan)
+            .create_physical_plan(optimized_plan)
             .map_err(|e| DatabaseError::InvalidQuery(format!("Physical Plan error: {e}")))?;
 
-        let mut batches = Vec::new();
-        while let Some(batch) = executor.next()? {
-            batches.push(batch);
+        if analyze {
+            while executor.next(&context)?.is_some() {}
         }
 
-        let schema = executor.schema().clone(); // ONE clone
-        let rows = batches.into_iter().flat_map(|b| b.rows).collect();
-        Ok(QueryResponse { schema, rows })
+        let rows = explain_plan(executor.as_ref(), analyze)
+            .into_iter()
+            .map(|line| Row::new(vec![Value::Text(line)]))
+            .collect();
+        Ok(QueryResponse {
+            schema: OutputSchema {
+                fields: vec![Field {
+                    name: "QUERY PLAN".to_string(),
+                    alias: None,
+                    relation: None,
+                    data_type: DataType::Text,
+                    is_nullable: false,
+                }],
+            },
+            rows,
+        })
+    }
+}
+
+/// Parses a single SQL statement.
+pub(crate) fn parse_statement(query: &str) -> Result<Statement> {
+    SqlParser::new(query)
+        .parse()
+        .map_err(|e| DatabaseError::InvalidQuery(format!("Parse error: {e}")).into())
+}
+
+/// Looks up a statement created by `PREPARE name`.
+pub(crate) fn find_prepared<'a>(
+    prepared_statements: &'a HashMap<String, PreparedStatement>,
+    name: &str,
+) -> Result<&'a PreparedStatement, DatabaseError> {
+    prepared_statements.get(name).ok_or_else(|| {
+        DatabaseError::InvalidQuery(format!("Prepared statement {name} does not exist"))
+    })
+}
+
+/// Keeps errors raised as [`DatabaseError`] by the storage layer, such as corruption.
+fn storage_error(report: miette::Report) -> DatabaseError {
+    report
+        .downcast::<DatabaseError>()
+        .unwrap_or_else(|report| DatabaseError::SerializationError(report.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ColumnDef, DataType, core::temporal};
+
+    #[test]
+    fn test_insert_rows_larger_than_a_page() {
+        let directory = std::env::temp_dir().join("scuttle_database_toast_tests");
+        std::fs::remove_dir_all(&directory).ok();
+
+        let schema = Schema::new(vec![
+            ColumnDef::new("id", DataType::Int64, false),
+            ColumnDef::new("body", DataType::Text, false),
+        ]);
+        let bodies = (1..=3u64)
+            .map(|seed| {
+                // Pseudo-random letters do not compress, so they have to go out of line
+                let mut state = seed;
+                (0..20_000)
+                    .map(|_| {
+                        state = state
+                            .wrapping_mul(6_364_136_223_846_793_005)
+                            .wrapping_add(1);
+                        char::from(b'a' + (state >> 59) as u8 % 26)
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut db = Database::new(&directory);
+        db.create_table("documents", schema.clone()).unwrap();
+        for (id, body) in bodies.iter().enumerate() {
+            db.insert_row(
+                "documents",
+                Row::new(vec![Value::Int64(id as i64), Value::Text(body.clone())]),
+            )
+            .unwrap();
+        }
+
+        // Reopening finds the table in the catalog and reads everything back from disk
+        let mut db = Database::open(&directory).unwrap();
+        assert_eq!(db.table_schema("documents").unwrap(), &schema);
+        let rows = db.get_rows("documents").unwrap();
+        assert_eq!(rows.len(), 3);
+        for (row, body) in rows.iter().zip(&bodies) {
+            assert_eq!(row.values[1], Value::Text(body.clone()));
+        }
+
+        let response = db
+            .execute_query("SELECT id FROM documents WHERE body = body")
+            .unwrap();
+        assert_eq!(response.rows.len(), 3);
+    }
+
+    #[test]
+    fn test_tables_larger_than_a_thousand_pages() {
+        let directory = std::env::temp_dir().join("scuttle_database_page_count_tests");
+        std::fs::remove_dir_all(&directory).ok();
+
+        let schema = Schema::new(vec![
+            ColumnDef::new("id", DataType::Int64, false),
+            ColumnDef::new("padding", DataType::Text, false),
+        ]);
+        // Just below the TOAST threshold, so only a few rows fit on a page
+        let padding = "x".repeat(1900);
+
+        let mut db = Database::new(&directory);
+        db.create_table("wide", schema.clone()).unwrap();
+        let mut last_page_id = 0;
+        let mut id = 0;
+        while last_page_id < 1000 {
+            let row = Row::new(vec![Value::Int64(id), Value::Text(padding.clone())]);
+            (last_page_id, _) = db.insert_row("wide", row).unwrap();
+            id += 1;
+        }
+
+        // A new handle finds room through the persisted free space map
+        let db = Database::open(&directory).unwrap();
+        assert_eq!(db.table_schema("wide").unwrap(), &schema);
+        let row = Row::new(vec![Value::Int64(id), Value::Text("short".to_string())]);
+        let (page_id, _) = db.insert_row("wide", row).unwrap();
+        assert!(page_id <= last_page_id);
+
+        let rows = db.get_rows("wide").unwrap();
+        assert_eq!(rows.len() as i64, id + 1);
+    }
+
+    #[test]
+    fn test_corrupted_page_is_reported() {
+        use std::io::{Seek, SeekFrom, Write};
+
+        let directory = std::env::temp_dir().join("scuttle_database_checksum_tests");
+        std::fs::remove_dir_all(&directory).ok();
+
+        let schema = Schema::new(vec![ColumnDef::new("id", DataType::Int64, false)]);
+        let mut db = Database::new(&directory);
+        db.create_table("numbers", schema.clone()).unwrap();
+        db.insert_row("numbers", Row::new(vec![Value::Int64(7)]))
+            .unwrap();
+
+        // Simulates a torn write in the middle of page 0
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .open(directory.join("numbers.table"))
+            .unwrap();
+        file.seek(SeekFrom::Start(4096)).unwrap();
+        file.write_all(b"garbage").unwrap();
+
+        let mut db = Database::open(&directory).unwrap();
+        assert_eq!(db.table_schema("numbers").unwrap(), &schema);
+        match db.get_rows("numbers") {
+            Err(DatabaseError::Corruption { table, page_id }) => {
+                assert_eq!(table, "numbers");
+                assert_eq!(page_id, 0);
+            }
+            other => panic!("Expected a corruption error, got {other:?}"),
+        }
+        assert!(db.execute_query("SELECT id FROM numbers").is_err());
+    }
+
+    #[test]
+    fn test_partial_last_page_is_reported() {
+        let directory = std::env::temp_dir().join("scuttle_database_partial_page_tests");
+        std::fs::remove_dir_all(&directory).ok();
+
+        let schema = Schema::new(vec![ColumnDef::new("id", DataType::Int64, false)]);
+        let mut db = Database::new(&directory);
+        db.create_table("numbers", schema).unwrap();
+        db.insert_row("numbers", Row::new(vec![Value::Int64(7)]))
+            .unwrap();
+
+        // Simulates a crash while the second page was appended
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(directory.join("numbers.table"))
+            .unwrap();
+        std::io::Write::write_all(&mut file, b"torn").unwrap();
+
+        let db = Database::open(&directory).unwrap();
+        match db.get_rows("numbers") {
+            Err(DatabaseError::Corruption { table, page_id }) => {
+                assert_eq!(table, "numbers");
+                assert_eq!(page_id, 1);
+            }
+            other => panic!("Expected a corruption error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_vacuum_reuses_space_of_deleted_rows() {
+        let directory = std::env::temp_dir().join("scuttle_database_vacuum_tests");
+        std::fs::remove_dir_all(&directory).ok();
+
+        let schema = Schema::new(vec![
+            ColumnDef::new("id", DataType::Int64, false),
+            ColumnDef::new("name", DataType::Text, false),
+        ]);
+        let row = |id: i64| Row::new(vec![Value::Int64(id), Value::Text(format!("user {id}"))]);
+
+        let mut db = Database::new(&directory);
+        db.create_table("users", schema).unwrap();
+        let locations = (0..10)
+            .map(|id| db.insert_row("users", row(id)).unwrap())
+            .collect::<Vec<_>>();
+
+        for (page_id, item_id) in locations.iter().filter(|(_, item_id)| item_id % 2 == 0) {
+            let page = db.buffer_manager.get_page("users", *page_id).unwrap();
+            page.delete_item(*item_id).unwrap();
+            db.buffer_manager.save_page("users", *page_id).unwrap();
+        }
+        let free_space = |db: &Database| {
+            db.buffer_manager
+                .read_page("users", 0, |page| page.free_space())
+                .unwrap()
+        };
+        let free_before = free_space(&db);
+
+        assert!(db.vacuum("users").unwrap() > 0);
+        assert!(free_space(&db) > free_before);
+        assert_eq!(db.vacuum("users").unwrap(), 0);
+
+        let ids = |db: &mut Database| {
+            db.get_rows("users")
+                .unwrap()
+                .into_iter()
+                .map(|row| row.values[0].clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(&mut db), [1, 3, 5, 7, 9].map(Value::Int64).to_vec());
+
+        // The freed slot is handed out again instead of growing the page
+        assert_eq!(db.insert_row("users", row(10)).unwrap(), (0, 0));
+        assert_eq!(ids(&mut db), [10, 1, 3, 5, 7, 9].map(Value::Int64).to_vec());
+
+        db.execute_query("VACUUM").unwrap();
+        assert!(db.execute_query("VACUUM missing").is_err());
+    }
+
+    #[test]
+    fn test_vacuum_frees_toasted_values() {
+        let directory = std::env::temp_dir().join("scuttle_database_vacuum_toast_tests");
+        std::fs::remove_dir_all(&directory).ok();
+
+        let schema = Schema::new(vec![
+            ColumnDef::new("id", DataType::Int64, false),
+            ColumnDef::new("body", DataType::Bytea, false),
+        ]);
+        let row = |id: i64| {
+            // Pseudo-random bytes do not compress, so they have to go out of line
+            let mut state = id as u64 + 1;
+            let body = (0..3000)
+                .map(|_| {
+                    state = state
+                        .wrapping_mul(6_364_136_223_846_793_005)
+                        .wrapping_add(1);
+                    (state >> 56) as u8
+                })
+                .collect::<Vec<_>>();
+            Row::new(vec![Value::Int64(id), Value::Bytea(body)])
+        };
+
+        let mut db = Database::new(&directory);
+        db.create_table("blobs", schema).unwrap();
+        let locations = (0..6)
+            .map(|id| db.insert_row("blobs", row(id)).unwrap())
+            .collect::<Vec<_>>();
+
+        // Tails of several values share a page
+        let toast_pages = |db: &Database| {
+            db.buffer_manager
+                .page_count(&toast_relation("blobs"))
+                .unwrap()
+        };
+        assert_eq!(toast_pages(&db), 3);
+
+        for (page_id, item_id) in &locations {
+            let page = db.buffer_manager.get_page("blobs", *page_id).unwrap();
+            page.delete_item(*item_id).unwrap();
+            db.buffer_manager.save_page("blobs", *page_id).unwrap();
+        }
+        assert!(db.vacuum("blobs").unwrap() > 0);
+
+        // The freed chunks take the new values instead of growing the relation
+        for id in 6..12 {
+            db.insert_row("blobs", row(id)).unwrap();
+        }
+        assert_eq!(toast_pages(&db), 3);
+
+        let rows = db.get_rows("blobs").unwrap();
+        assert_eq!(
+            rows.iter()
+                .map(|row| row.values.clone())
+                .collect::<Vec<_>>(),
+            (6..12).map(|id| row(id).values).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_analyze_statistics_are_persisted() {
+        let directory = std::env::temp_dir().join("scuttle_database_analyze_tests");
+        std::fs::remove_dir_all(&directory).ok();
+
+        let schema = Schema::new(vec![
+            ColumnDef::new("id", DataType::Int64, false),
+            ColumnDef::new("team", DataType::Text, true),
+        ]);
+        let mut db = Database::new(&directory);
+        db.create_table("players", schema.clone()).unwrap();
+        for id in 0..20 {
+            let team = match id % 4 {
+                0 => Value::Null,
+                1 => Value::Text("red".to_string()),
+                _ => Value::Text("blue".to_string()),
+            };
+            db.insert_row("players", Row::new(vec![Value::Int64(id), team]))
+                .unwrap();
+        }
+        assert_eq!(db.statistics("players").unwrap(), None);
+
+        db.execute_query("ANALYZE players").unwrap();
+        let statistics = db.statistics("players").unwrap().unwrap();
+        assert_eq!(statistics.row_count, 20);
+        assert_eq!(statistics.page_count, 1);
+
+        let team = statistics.column("team").unwrap();
+        assert_eq!(team.null_fraction, 0.25);
+        assert_eq!(team.distinct_count, 2.0);
+        assert_eq!(
+            team.most_common_values,
+            vec![
+                Value::Text("blue".to_string()),
+                Value::Text("red".to_string())
+            ]
+        );
+        assert_eq!(team.most_common_frequencies, vec![0.5, 0.25]);
+
+        let reopened = Database::open(&directory).unwrap();
+        assert_eq!(reopened.table_schema("players").unwrap(), &schema);
+        assert_eq!(reopened.statistics("players").unwrap(), Some(statistics));
+
+        assert!(db.execute_query("ANALYZE missing").is_err());
+    }
+
+    #[test]
+    fn test_user_defined_functions_get_declared_argument_types() {
+        struct FloatSum(f64);
+
+        impl Accumulator for FloatSum {
+            fn update(&mut self, args: &[Value]) -> Result<(), DatabaseError> {
+                match args {
+                    [Value::Float64(value)] => self.0 += value,
+                    [Value::Null] => {}
+                    other => {
+                        return Err(DatabaseError::TypeMismatch(format!("got {other:?}")));
+                    }
+                }
+                Ok(())
+            }
+
+            fn merge(&mut self, state: &[Value]) -> Result<(), DatabaseError> {
+                self.update(state)
+            }
+
+            fn state(&self) -> Vec<Value> {
+                vec![Value::Float64(self.0)]
+            }
+
+            fn finalize(&self) -> Result<Value, DatabaseError> {
+                Ok(Value::Float64(self.0))
+            }
+        }
+
+        let directory = std::env::temp_dir().join("scuttle_database_udf_tests");
+        std::fs::remove_dir_all(&directory).ok();
+
+        let schema = Schema::new(vec![ColumnDef::new("id", DataType::Int64, false)]);
+        let mut db = Database::new(&directory);
+        db.create_table("numbers", schema).unwrap();
+        for id in 1..=3 {
+            db.insert_row("numbers", Row::new(vec![Value::Int64(id)]))
+                .unwrap();
+        }
+
+        db.register_scalar_function(
+            "half",
+            Signature::exact(vec![DataType::Float64], DataType::Float64),
+            |args| match args {
+                [Value::Float64(value)] => Ok(Value::Float64(value / 2.0)),
+                other => Err(DatabaseError::TypeMismatch(format!("got {other:?}"))),
+            },
+        )
+        .unwrap();
+        db.register_aggregate(
+            "float_sum",
+            Signature::exact(vec![DataType::Float64], DataType::Float64),
+            || Box::new(FloatSum(0.0)),
+        )
+        .unwrap();
+
+        let response = db
+            .execute_query("SELECT half(id) FROM numbers WHERE id = 1")
+            .unwrap();
+        assert_eq!(response.rows[0].values, vec![Value::Float64(0.5)]);
+
+        let response = db
+            .execute_query("SELECT float_sum(id) FROM numbers")
+            .unwrap();
+        assert_eq!(response.rows[0].values, vec![Value::Float64(6.0)]);
+    }
+
+    #[test]
+    fn test_now_is_the_statement_start_time() {
+        let directory = std::env::temp_dir().join("scuttle_database_now_tests");
+        std::fs::remove_dir_all(&directory).ok();
+
+        let schema = Schema::new(vec![ColumnDef::new("id", DataType::Int64, false)]);
+        let mut db = Database::new(&directory);
+        db.create_table("numbers", schema).unwrap();
+        for id in 0..2000 {
+            db.insert_row("numbers", Row::new(vec![Value::Int64(id)]))
+                .unwrap();
+        }
+
+        let before = temporal::now();
+        let response = db
+            .execute_query("SELECT now(), id FROM numbers WHERE now() >= now()")
+            .unwrap();
+        assert_eq!(response.rows.len(), 2000);
+        let time = &response.rows[0].values[0];
+        assert!(matches!(time, Value::Timestamp(time) if *time >= before));
+        assert!(response.rows.iter().all(|row| &row.values[0] == time));
+
+        // Every execution of a prepared statement has its own time
+        let statement = db
+            .prepare("SELECT now() FROM numbers WHERE id = 0")
+            .unwrap();
+        let first = db.execute(&statement, &[]).unwrap().rows[0].values[0].clone();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let second = db.execute(&statement, &[]).unwrap().rows[0].values[0].clone();
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_explain() {
+        let directory = std::env::temp_dir().join("scuttle_database_explain_tests");
+        std::fs::remove_dir_all(&directory).ok();
+
+        let schema = Schema::new(vec![
+            ColumnDef::new("name", DataType::Text, false),
+            ColumnDef::new("age", DataType::Int64, true),
+        ]);
+        let mut db = Database::new(&directory);
+        db.create_table("users", schema).unwrap();
+        for (name, age) in [("Alice", 30), ("Bob", 25), ("Charlie", 35)] {
+            let row = Row::new(vec![Value::Text(name.to_string()), Value::Int64(age)]);
+            db.insert_row("users", row).unwrap();
+        }
+        let lines = |response: QueryResponse| {
+            response
+                .rows
+                .into_iter()
+                .map(|row| row.values[0].to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let response = db
+            .execute_query("EXPLAIN SELECT name FROM users WHERE age > 26")
+            .unwrap();
+        assert_eq!(response.schema.fields[0].name, "QUERY PLAN");
+        let plan = lines(response);
+        assert_eq!(plan.len(), 6, "{plan:#?}");
+        assert!(plan[0].starts_with("Projection  (cost="), "{plan:#?}");
+        assert_eq!(plan[1], "  Expressions: name");
+        assert_eq!(plan[2], "  Output: name");
+        assert!(
+            plan[3].starts_with("  ->  Seq Scan on users  (cost="),
+            "{plan:#?}"
+        );
+        assert_eq!(plan[4], "        Filter: (age > 26)");
+        assert_eq!(plan[5], "        Output: name, age");
+        assert!(plan.iter().all(|line| !line.contains("actual")));
+
+        let plan = lines(
+            db.execute_query("EXPLAIN ANALYZE SELECT age, count(*) FROM users GROUP BY age")
+                .unwrap(),
+        );
+        assert!(plan[0].starts_with("Projection"), "{plan:#?}");
+        assert!(
+            plan[0].contains("(actual rows=3 batches=1 time="),
+            "{plan:#?}"
+        );
+        assert!(plan[3].starts_with("  ->  Hash Aggregate"), "{plan:#?}");
+        assert_eq!(plan[4], "        Group Key: age");
+        assert_eq!(plan[5], "        Aggregates: count(*)");
+        assert!(
+            plan[7].contains("(actual rows=3 batches=1 time="),
+            "{plan:#?}"
+        );
+    }
+
+    #[test]
+    fn test_prepared_statements() {
+        let directory = std::env::temp_dir().join("scuttle_database_prepared_tests");
+        std::fs::remove_dir_all(&directory).ok();
+
+        let schema = Schema::new(vec![
+            ColumnDef::new("name", DataType::Text, false),
+            ColumnDef::new("age", DataType::Int64, true),
+        ]);
+        let mut db = Database::new(&directory);
+        db.create_table("users", schema).unwrap();
+        for (name, age) in [("Alice", 30), ("Bob", 25), ("Charlie", 35)] {
+            let row = Row::new(vec![Value::Text(name.to_string()), Value::Int64(age)]);
+            db.insert_row("users", row).unwrap();
+        }
+        let names = |response: QueryResponse| {
+            response
+                .rows
+                .into_iter()
+                .map(|row| row.values[0].to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let statement = db
+            .prepare("SELECT name FROM users WHERE age > $1 AND name != $2")
+            .unwrap();
+        assert_eq!(
+            statement.parameter_types(),
+            [DataType::Int64, DataType::Text]
+        );
+        let response = db
+            .execute(
+                &statement,
+                &[Value::Int32(26), Value::Text("Charlie".to_string())],
+            )
+            .unwrap();
+        assert_eq!(names(response), ["Alice"]);
+        let response = db
+            .execute(&statement, &[Value::Int64(0), Value::Null])
+            .unwrap();
+        assert!(response.rows.is_empty());
+        assert!(db.execute(&statement, &[Value::Int64(0)]).is_err());
+
+        // A parameter used on its own has no type to infer
+        assert!(db.prepare("SELECT $1 FROM users").is_err());
+        assert!(
+            db.execute_query("SELECT name FROM users WHERE age > $1")
+                .is_err()
+        );
+
+        db.execute_query("PREPARE older (INT) AS SELECT name, $2 + 1 FROM users WHERE age > $1")
+            .unwrap();
+        assert!(
+            db.execute_query("PREPARE older AS SELECT name FROM users")
+                .is_err()
+        );
+        let response = db.execute_query("EXECUTE older (20 + 9, 1)").unwrap();
+        assert_eq!(names(response), ["Alice", "Charlie"]);
+
+        db.execute_query("DEALLOCATE older").unwrap();
+        assert!(db.execute_query("EXECUTE older (29, 1)").is_err());
+        assert!(db.execute_query("DEALLOCATE older").is_err());
+    }
+
+    #[test]
+    fn test_query_streams_rows() {
+        let directory = std::env::temp_dir().join("scuttle_database_stream_tests");
+        std::fs::remove_dir_all(&directory).ok();
+
+        let schema = Schema::new(vec![
+            ColumnDef::new("id", DataType::Int64, false),
+            ColumnDef::new("name", DataType::Text, false),
+        ]);
+        let mut db = Database::new(&directory);
+        db.create_table("users", schema).unwrap();
+        for id in 0..5_000 {
+            let row = Row::new(vec![Value::Int64(id), Value::Text(format!("user {id}"))]);
+            db.insert_row("users", row).unwrap();
+        }
+
+        let mut stream = db.query("SELECT id FROM users WHERE id < 2500").unwrap();
+        assert_eq!(stream.schema().fields[0].name, "id");
+        let first = stream.next_batch().unwrap().unwrap();
+        assert!(first.num_rows() < 2_500, "{} rows", first.num_rows());
+        assert_eq!(first.row(0).values, vec![Value::Int64(0)]);
+        let rest = stream.collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(first.num_rows() + rest.len(), 2_500);
+
+        // Dropping a stream early releases the database
+        let first = db.query("SELECT name FROM users").unwrap().next();
+        assert_eq!(
+            first.unwrap().unwrap().values,
+            vec![Value::Text("user 0".to_string())]
+        );
+        let response = db.execute_query("SELECT count(*) FROM users").unwrap();
+        assert_eq!(response.rows[0].values, vec![Value::Int64(5_000)]);
+
+        assert!(db.query("VACUUM users").is_err());
+    }
+
+    #[test]
+    fn test_parallel_scan_matches_serial_scan() {
+        let directory = std::env::temp_dir().join("scuttle_database_parallel_tests");
+        std::fs::remove_dir_all(&directory).ok();
+
+        let schema = Schema::new(vec![
+            ColumnDef::new("id", DataType::Int64, false),
+            ColumnDef::new("grp", DataType::Int64, true),
+            ColumnDef::new("padding", DataType::Text, false),
+        ]);
+        let mut db = Database::new(&directory);
+        db.create_table("items", schema).unwrap();
+        for id in 0..2_000 {
+            let grp = match id % 7 {
+                0 => Value::Null,
+                n => Value::Int64(n),
+            };
+            let row = Row::new(vec![Value::Int64(id), grp, Value::Text("x".repeat(200))]);
+            db.insert_row("items", row).unwrap();
+        }
+        assert!(db.buffer_manager.page_count("items").unwrap() > 40);
+
+        let queries = [
+            "SELECT id, grp FROM items WHERE id > 100 AND grp != 3",
+            "SELECT grp, count(*), sum(id), avg(id), min(id), max(id) FROM items GROUP BY grp",
+            "SELECT count(*), sum(grp) FROM items WHERE id > 5000",
+        ];
+        db.set_max_parallel_workers(1);
+        let serial = queries
+            .map(|sql| db.execute_query(sql).unwrap().rows)
+            .map(|rows| rows.into_iter().map(|row| row.values).collect::<Vec<_>>());
+
+        db.set_max_parallel_workers(4);
+        db.parallel_scan_pages = 4;
+        for (sql, expected) in queries.iter().zip(serial) {
+            let rows = db.execute_query(sql).unwrap().rows;
+            let rows = rows.into_iter().map(|row| row.values).collect::<Vec<_>>();
+            assert_eq!(rows, expected, "{sql}");
+        }
+
+        let plan = db
+            .execute_query("EXPLAIN SELECT grp, count(*) FROM items GROUP BY grp")
+            .unwrap()
+            .rows
+            .into_iter()
+            .map(|row| row.values[0].to_string())
+            .collect::<Vec<_>>();
+        // Detail lines such as "Output: grp" follow every operator
+        let operators = plan
+            .iter()
+            .filter(|line| !line.contains(": "))
+            .map(|line| line.trim_start().trim_start_matches("->  "))
+            .map(|line| line.split("  (").next().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            operators,
+            [
+                "Projection",
+                "Finalize Hash Aggregate",
+                "Gather",
+                "Partial Hash Aggregate",
+                "Parallel Seq Scan on items",
+            ],
+            "{plan:#?}"
+        );
+        assert!(
+            plan.iter().any(|line| line.trim() == "Workers: 4"),
+            "{plan:#?}"
+        );
+    }
+
+    /// The rows of a query, one line of comma separated values each, sorted.
+    fn sorted_rows(db: &mut Database, query: &str) -> Vec<String> {
+        let mut rows = db
+            .execute_query(query)
+            .unwrap()
+            .rows
+            .into_iter()
+            .map(|row| {
+                row.values
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .collect::<Vec<_>>();
+        rows.sort();
+        rows
+    }
+
+    /// The operators of the plan EXPLAIN shows for a query, without their details.
+    fn plan_operators(db: &mut Database, query: &str) -> Vec<String> {
+        db.execute_query(&format!("EXPLAIN {query}"))
+            .unwrap()
+            .rows
+            .into_iter()
+            .map(|row| row.values[0].to_string())
+            .filter(|line| !line.contains(": "))
+            .map(|line| {
+                let line = line.trim_start().trim_start_matches("->  ");
+                line.split("  (").next().unwrap().to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_joins() {
+        let directory = std::env::temp_dir().join("scuttle_database_join_tests");
+        std::fs::remove_dir_all(&directory).ok();
+
+        let mut db = Database::new(&directory);
+        db.create_table(
+            "users",
+            Schema::new(vec![
+                ColumnDef::new("id", DataType::Int64, false),
+                ColumnDef::new("name", DataType::Text, false),
+            ]),
+        )
+        .unwrap();
+        db.create_table(
+            "orders",
+            Schema::new(vec![
+                ColumnDef::new("id", DataType::Int64, false),
+                ColumnDef::new("user_id", DataType::Int64, true),
+                ColumnDef::new("amount", DataType::Int64, false),
+            ]),
+        )
+        .unwrap();
+        for (id, name) in [(1, "Alice"), (2, "Bob"), (3, "Charlie")] {
+            let row = Row::new(vec![Value::Int64(id), Value::Text(name.to_string())]);
+            db.insert_row("users", row).unwrap();
+        }
+        for (id, user_id, amount) in [
+            (1, Some(1), 5),
+            (2, Some(1), 20),
+            (3, Some(3), 15),
+            (4, None, 50),
+        ] {
+            let user_id = user_id.map_or(Value::Null, Value::Int64);
+            let row = Row::new(vec![Value::Int64(id), user_id, Value::Int64(amount)]);
+            db.insert_row("orders", row).unwrap();
+        }
+
+        let expected = ["Alice, 20", "Charlie, 15"];
+        assert_eq!(
+            sorted_rows(
+                &mut db,
+                "SELECT u.name, o.amount FROM users u JOIN orders o ON u.id = o.user_id \
+                 WHERE o.amount > 10"
+            ),
+            expected
+        );
+        assert_eq!(
+            sorted_rows(
+                &mut db,
+                "SELECT users.name, amount FROM orders, users \
+                 WHERE users.id = orders.user_id AND amount > 10"
+            ),
+            expected
+        );
+        // NULL user ids match no user
+        assert_eq!(
+            sorted_rows(
+                &mut db,
+                "SELECT count(*) FROM users INNER JOIN orders ON users.id = orders.user_id"
+            ),
+            ["3"]
+        );
+        assert_eq!(
+            sorted_rows(&mut db, "SELECT count(*) FROM users CROSS JOIN orders"),
+            ["12"]
+        );
+        assert_eq!(
+            sorted_rows(
+                &mut db,
+                "SELECT a.name, b.name FROM users a, users AS b WHERE a.id + 1 = b.id"
+            ),
+            ["Alice, Bob", "Bob, Charlie"]
+        );
+        assert_eq!(
+            sorted_rows(
+                &mut db,
+                "SELECT a.name, count(*) FROM users a JOIN orders o ON a.id = o.user_id \
+                 JOIN users b ON b.id <= o.user_id GROUP BY a.name"
+            ),
+            ["Alice, 2", "Charlie, 3"]
+        );
+
+        assert!(db.execute_query("SELECT id FROM users, orders").is_err());
+        assert!(db.execute_query("SELECT name FROM users, users").is_err());
+        assert!(
+            db.execute_query("SELECT name FROM users LEFT JOIN orders ON true")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_join_methods_are_chosen_by_cost() {
+        let directory = std::env::temp_dir().join("scuttle_database_join_plan_tests");
+        std::fs::remove_dir_all(&directory).ok();
+
+        let schema = Schema::new(vec![
+            ColumnDef::new("id", DataType::Int64, false),
+            ColumnDef::new("padding", DataType::Text, false),
+        ]);
+        let mut db = Database::new(&directory);
+        for (table, rows) in [("small", 3), ("tiny", 3), ("big", 2_000), ("large", 2_000)] {
+            db.create_table(table, schema.clone()).unwrap();
+            for id in 0..rows {
+                let row = Row::new(vec![Value::Int64(id), Value::Text("x".repeat(200))]);
+                db.insert_row(table, row).unwrap();
+            }
+            db.analyze(table).unwrap();
+        }
+
+        // The hash table is built on the few rows of the small table
+        assert_eq!(
+            plan_operators(
+                &mut db,
+                "SELECT big.id FROM small JOIN big ON small.id = big.id"
+            ),
+            [
+                "Projection",
+                "Hash Join",
+                "Seq Scan on big",
+                "Seq Scan on small"
+            ]
+        );
+        // Comparing every pair is cheapest for tiny inputs, and the only way without
+        // an equality
+        assert_eq!(
+            plan_operators(
+                &mut db,
+                "SELECT small.id FROM small JOIN tiny ON small.id = tiny.id"
+            )[1],
+            "Nested Loop"
+        );
+        assert_eq!(
+            plan_operators(
+                &mut db,
+                "SELECT big.id FROM big JOIN large ON big.id < large.id"
+            )[1],
+            "Nested Loop"
+        );
+
+        // Index scans read few rows, in the order a merge join needs
+        db.create_index("big_id", "big", "id").unwrap();
+        db.create_index("large_id", "large", "id").unwrap();
+        let query = "SELECT big.id FROM big JOIN large ON big.id = large.id \
+                     WHERE big.id < 10 AND large.id < 10";
+        let mut operators = plan_operators(&mut db, query);
+        assert_eq!(
+            operators[..2],
+            ["Projection", "Merge Join"],
+            "{operators:#?}"
+        );
+        // Either input may be the outer one, neither needs sorting
+        operators[2..].sort();
+        assert_eq!(
+            operators[2..],
+            [
+                "Index Scan using big_id on big",
+                "Index Scan using large_id on large"
+            ]
+        );
+        assert_eq!(sorted_rows(&mut db, query).len(), 10);
+
+        // The third relation is joined last, not through a cross product with the first
+        let operators = plan_operators(
+            &mut db,
+            "SELECT small.id FROM small, big, tiny WHERE small.id = big.id AND big.id = tiny.id",
+        );
+        let joins = operators
+            .iter()
+            .filter(|operator| operator.ends_with("Join") || *operator == "Nested Loop")
+            .count();
+        assert_eq!(joins, 2, "{operators:#?}");
+        assert_eq!(
+            sorted_rows(
+                &mut db,
+                "SELECT small.id FROM small, big, tiny \
+                 WHERE small.id = big.id AND big.id = tiny.id"
+            ),
+            ["0", "1", "2"]
+        );
+    }
+
+    #[test]
+    fn test_indexes() {
+        let directory = std::env::temp_dir().join("scuttle_database_index_tests");
+        std::fs::remove_dir_all(&directory).ok();
+
+        let schema = Schema::new(vec![
+            ColumnDef::new("id", DataType::Int64, false),
+            ColumnDef::new("score", DataType::Float64, true),
+            ColumnDef::new("padding", DataType::Text, false),
+        ]);
+        let mut db = Database::new(&directory);
+        db.create_table("items", schema).unwrap();
+        let locations = (0..2_000)
+            .map(|id| {
+                let row = Row::new(vec![
+                    Value::Int64(id),
+                    Value::Float64(id as f64),
+                    Value::Text("x".repeat(200)),
+                ]);
+                db.insert_row("items", row).unwrap()
+            })
+            .collect::<Vec<_>>();
+        db.analyze("items").unwrap();
+
+        db.create_index("items_id", "items", "id").unwrap();
+        assert!(db.create_index("items_id", "items", "id").is_err());
+        assert!(
+            db.create_index("items_missing", "items", "missing")
+                .is_err()
+        );
+        assert!(db.create_index("items_score", "items", "score").is_err());
+        assert!(db.create_index("missing_id", "missing", "id").is_err());
+
+        let query = "SELECT id FROM items WHERE id >= 10 AND 13 > id AND id != 11";
+        assert_eq!(
+            plan_operators(&mut db, query),
+            ["Projection", "Index Scan using items_id on items"]
+        );
+        assert_eq!(sorted_rows(&mut db, query), ["10", "12"]);
+        // Most rows are read faster page by page
+        assert_eq!(
+            plan_operators(&mut db, "SELECT id FROM items WHERE id > 10")[1],
+            "Seq Scan on items"
+        );
+        assert_eq!(
+            sorted_rows(&mut db, "SELECT id FROM items WHERE id = 2.5"),
+            Vec::<String>::new()
+        );
+
+        // Rows inserted later are found, rows moved by VACUUM too
+        let row = Row::new(vec![
+            Value::Int64(5_000),
+            Value::Null,
+            Value::Text(String::new()),
+        ]);
+        db.insert_row("items", row).unwrap();
+        assert_eq!(
+            sorted_rows(&mut db, "SELECT id FROM items WHERE id = 5000"),
+            ["5000"]
+        );
+        // Rows missing a column are refused before anything is written
+        let page_count = db.buffer_manager.page_count("items").unwrap();
+        let row = Row::new(vec![Value::Int64(5_001)]);
+        assert!(db.insert_row("items", row).is_err());
+        assert_eq!(db.buffer_manager.page_count("items").unwrap(), page_count);
+        assert_eq!(
+            sorted_rows(&mut db, "SELECT id FROM items WHERE id = 5001"),
+            Vec::<String>::new()
+        );
+        for (page_id, item_id) in &locations[..20] {
+            let page = db.buffer_manager.get_page("items", *page_id).unwrap();
+            page.delete_item(*item_id).unwrap();
+            db.buffer_manager.save_page("items", *page_id).unwrap();
+        }
+        assert!(db.vacuum("items").unwrap() > 0);
+        assert_eq!(
+            sorted_rows(&mut db, "SELECT id FROM items WHERE id < 22"),
+            ["20", "21"]
+        );
+
+        let mut reopened = Database::open(&directory).unwrap();
+        assert_eq!(
+            plan_operators(&mut reopened, "SELECT id FROM items WHERE id = 7")[1],
+            "Index Scan using items_id on items"
+        );
+
+        db.drop_index("items_id").unwrap();
+        assert!(db.drop_index("items_id").is_err());
+        assert_eq!(
+            plan_operators(&mut db, "SELECT id FROM items WHERE id = 7")[1],
+            "Seq Scan on items"
+        );
+        let reopened = Database::open(&directory).unwrap();
+        assert!(reopened.table_indexes("items").is_empty());
     }
 }
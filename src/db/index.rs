@@ -0,0 +1,153 @@
+//! Indexes on a single column of a table.
+//!
+//! Only the definition of an index is stored, in the
+//! [`INDEXES_RELATION`](crate::db::catalog::indexes::INDEXES_RELATION) catalog relation.
+//! Its entries are kept in memory: they are built when the index is created or the
+//! database opened, and kept up to date as rows are inserted.
+
+use std::{cmp::Ordering, collections::BTreeMap, ops::Bound};
+
+use crate::{
+    Value,
+    db::catalog::statistics::compare,
+    storage::page::{ItemId, PageId},
+};
+
+/// The position of a row in the pages of its table.
+pub(crate) type RowId = (PageId, ItemId);
+
+/// An ordered map from the values of a column to the rows holding them.
+///
+/// NULLs are left out, none of the conditions an index scan serves matches them.
+#[derive(Debug)]
+pub(crate) struct Index {
+    pub(crate) name: String,
+    pub(crate) table_name: String,
+
+    /// The indexed column, by position in the table.
+    pub(crate) column: usize,
+    entries: BTreeMap<IndexKey, Vec<RowId>>,
+}
+
+impl Index {
+    pub(crate) fn new(name: &str, table_name: &str, column: usize) -> Self {
+        Self {
+            name: name.to_string(),
+            table_name: table_name.to_string(),
+            column,
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Records that the row at `row_id` holds `value` in the indexed column.
+    pub(crate) fn insert(&mut self, value: Value, row_id: RowId) {
+        if value != Value::Null {
+            self.entries
+                .entry(IndexKey(value))
+                .or_default()
+                .push(row_id);
+        }
+    }
+
+    /// Forgets every row, before the index is built again.
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// The rows whose value lies within the bounds, ordered by value.
+    pub(crate) fn rows(&self, lower: Bound<&Value>, upper: Bound<&Value>) -> Vec<RowId> {
+        // A range whose bounds cross is empty, `BTreeMap::range` panics on it
+        if let (
+            Bound::Included(low) | Bound::Excluded(low),
+            Bound::Included(high) | Bound::Excluded(high),
+        ) = (lower, upper)
+        {
+            match compare(low, high) {
+                Ordering::Greater => return Vec::new(),
+                Ordering::Equal
+                    if matches!(lower, Bound::Excluded(_))
+                        || matches!(upper, Bound::Excluded(_)) =>
+                {
+                    return Vec::new();
+                }
+                _ => {}
+            }
+        }
+
+        let key = |bound: Bound<&Value>| bound.map(|value| IndexKey(value.clone()));
+        self.entries
+            .range((key(lower), key(upper)))
+            .flat_map(|(_, row_ids)| row_ids.iter().copied())
+            .collect()
+    }
+}
+
+/// A non-null value of the indexed column, ordered like `ORDER BY` would.
+#[derive(Debug, Clone)]
+struct IndexKey(Value);
+
+impl PartialEq for IndexKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for IndexKey {}
+
+impl PartialOrd for IndexKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for IndexKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        compare(&self.0, &other.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_rows_in_range() {
+        let mut index = Index::new("users_age", "users", 1);
+        let ages = [
+            Value::Int64(30),
+            Value::Int64(25),
+            Value::Null,
+            Value::Int64(30),
+            Value::Int64(40),
+        ];
+        for (item_id, age) in ages.into_iter().enumerate() {
+            index.insert(age, (0, item_id as ItemId));
+        }
+
+        let thirty = Value::Int64(30);
+        assert_eq!(
+            index.rows(Bound::Included(&thirty), Bound::Included(&thirty)),
+            vec![(0, 0), (0, 3)]
+        );
+        assert_eq!(
+            index.rows(Bound::Excluded(&thirty), Bound::Unbounded),
+            vec![(0, 4)]
+        );
+        assert_eq!(
+            index.rows(Bound::Unbounded, Bound::Excluded(&thirty)),
+            vec![(0, 1)]
+        );
+        // NULLs are not indexed, and crossed bounds match nothing
+        assert_eq!(index.rows(Bound::Unbounded, Bound::Unbounded).len(), 4);
+        assert!(
+            index
+                .rows(Bound::Included(&thirty), Bound::Excluded(&thirty))
+                .is_empty()
+        );
+        assert!(
+            index
+                .rows(Bound::Included(&Value::Int64(50)), Bound::Included(&thirty))
+                .is_empty()
+        );
+    }
+}
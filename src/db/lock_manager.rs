@@ -0,0 +1,207 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Condvar, Mutex, PoisonError},
+};
+
+use crate::DatabaseError;
+
+/// Identifies a [`Session`](crate::Session) to the lock manager.
+pub(crate) type SessionId = u64;
+
+/// How a table is locked by a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LockMode {
+    /// Taken to read a table, held by any number of sessions at once.
+    Shared,
+
+    /// Taken to change a table, keeps every other session out of it.
+    Exclusive,
+}
+
+/// The locks held and waited for, by table.
+#[derive(Debug, Default)]
+struct LockTable {
+    holders: HashMap<String, HashMap<SessionId, LockMode>>,
+
+    /// The lock each blocked session waits for.
+    waiting: HashMap<SessionId, (String, LockMode)>,
+}
+
+impl LockTable {
+    /// The other sessions holding a lock on `table` that conflicts with `mode`.
+    fn blockers(&self, session: SessionId, table: &str, mode: LockMode) -> Vec<SessionId> {
+        self.holders
+            .get(table)
+            .into_iter()
+            .flatten()
+            .filter(|(holder, held)| {
+                **holder != session
+                    && (mode == LockMode::Exclusive || **held == LockMode::Exclusive)
+            })
+            .map(|(holder, _)| *holder)
+            .collect()
+    }
+
+    /// Whether `session` waits for itself through the sessions blocking it.
+    fn in_cycle(&self, session: SessionId) -> bool {
+        let mut visited = HashSet::new();
+        let mut pending = vec![session];
+
+        while let Some(current) = pending.pop() {
+            let Some((table, mode)) = self.waiting.get(&current) else {
+                continue;
+            };
+            for blocker in self.blockers(current, table, *mode) {
+                if blocker == session {
+                    return true;
+                }
+                if visited.insert(blocker) {
+                    pending.push(blocker);
+                }
+            }
+        }
+
+        false
+    }
+}
+
+/// Table locks of the sessions of a [`SharedDatabase`](crate::SharedDatabase).
+///
+/// Sessions wait for conflicting locks to be released. Before waiting, the graph of
+/// sessions waiting for each other is checked, and the lock is refused if it would
+/// close a cycle.
+#[derive(Debug, Default)]
+pub(crate) struct LockManager {
+    locks: Mutex<LockTable>,
+    released: Condvar,
+}
+
+impl LockManager {
+    /// Locks a table for a session, waiting while other sessions hold a conflicting
+    /// lock. Asking for an exclusive lock upgrades a shared one.
+    ///
+    /// Fails with [`DatabaseError::Deadlock`] if waiting would deadlock, the session
+    /// keeps its other locks and should release them.
+    pub(crate) fn lock(
+        &self,
+        session: SessionId,
+        table: &str,
+        mode: LockMode,
+    ) -> Result<(), DatabaseError> {
+        let mut locks = self.locks.lock().unwrap_or_else(PoisonError::into_inner);
+
+        loop {
+            if locks.blockers(session, table, mode).is_empty() {
+                locks.waiting.remove(&session);
+                let held = locks
+                    .holders
+                    .entry(table.to_string())
+                    .or_default()
+                    .entry(session)
+                    .or_insert(mode);
+                *held = (*held).max(mode);
+                return Ok(());
+            }
+
+            locks.waiting.insert(session, (table.to_string(), mode));
+            if locks.in_cycle(session) {
+                locks.waiting.remove(&session);
+                return Err(DatabaseError::Deadlock(table.to_string()));
+            }
+
+            locks = self
+                .released
+                .wait(locks)
+                .unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// The lock a session holds on a table.
+    pub(crate) fn held(&self, session: SessionId, table: &str) -> Option<LockMode> {
+        let locks = self.locks.lock().unwrap_or_else(PoisonError::into_inner);
+        locks.holders.get(table)?.get(&session).copied()
+    }
+
+    /// Sets the lock a session holds on a table back to `mode`, releasing it if `None`.
+    pub(crate) fn restore(&self, session: SessionId, table: &str, mode: Option<LockMode>) {
+        let mut locks = self.locks.lock().unwrap_or_else(PoisonError::into_inner);
+        let holders = locks.holders.entry(table.to_string()).or_default();
+        match mode {
+            Some(mode) => {
+                holders.insert(session, mode);
+            }
+            None => {
+                holders.remove(&session);
+            }
+        }
+        if holders.is_empty() {
+            locks.holders.remove(table);
+        }
+
+        self.released.notify_all();
+    }
+
+    /// Releases every lock of a session.
+    pub(crate) fn unlock_all(&self, session: SessionId) {
+        let mut locks = self.locks.lock().unwrap_or_else(PoisonError::into_inner);
+        locks.holders.retain(|_, holders| {
+            holders.remove(&session);
+            !holders.is_empty()
+        });
+
+        self.released.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shared_locks_are_compatible() {
+        let manager = LockManager::default();
+        manager.lock(1, "users", LockMode::Shared).unwrap();
+        manager.lock(2, "users", LockMode::Shared).unwrap();
+        manager.lock(3, "orders", LockMode::Exclusive).unwrap();
+        assert_eq!(manager.held(2, "users"), Some(LockMode::Shared));
+
+        // Releasing the other shared lock lets the upgrade through
+        manager.restore(2, "users", None);
+        manager.lock(1, "users", LockMode::Exclusive).unwrap();
+        assert_eq!(manager.held(1, "users"), Some(LockMode::Exclusive));
+
+        manager.restore(1, "users", Some(LockMode::Shared));
+        manager.lock(2, "users", LockMode::Shared).unwrap();
+        manager.unlock_all(1);
+        assert_eq!(manager.held(1, "users"), None);
+    }
+
+    #[test]
+    fn test_upgrades_of_shared_locks_deadlock() {
+        let manager = LockManager::default();
+        manager.lock(1, "users", LockMode::Shared).unwrap();
+        manager.lock(2, "users", LockMode::Shared).unwrap();
+
+        std::thread::scope(|scope| {
+            let first = scope.spawn(|| {
+                let result = manager.lock(1, "users", LockMode::Exclusive);
+                if result.is_err() {
+                    manager.unlock_all(1);
+                }
+                result
+            });
+
+            // Whichever session asks second closes the cycle and is refused
+            let second = manager.lock(2, "users", LockMode::Exclusive);
+            if second.is_err() {
+                manager.unlock_all(2);
+                first.join().unwrap().unwrap();
+            } else {
+                assert!(matches!(
+                    first.join().unwrap(),
+                    Err(DatabaseError::Deadlock(_))
+                ));
+            }
+        });
+    }
+}
@@ -1,4 +1,10 @@
 pub(crate) mod catalog;
 pub(crate) mod database;
+pub(crate) mod index;
+pub(crate) mod lock_manager;
 pub(crate) mod null_bitmap;
+pub(crate) mod output_format;
+pub(crate) mod prepared_statement;
+pub(crate) mod row_stream;
+pub(crate) mod session;
 pub(crate) mod table;
@@ -0,0 +1,474 @@
+use std::io::Write;
+
+use strum::{Display, EnumString};
+use unicode_width::UnicodeWidthStr;
+
+use crate::{DataType, DatabaseError, Json, QueryResponse, Value, sql::analyzer::schema::Field};
+
+/// The layout of a result written by [`QueryResponse::write_as`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumString, Display)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum OutputStyle {
+    /// A table with a header and columns as wide as their values, like `psql`.
+    #[default]
+    Aligned,
+
+    /// Comma-separated values with a header line, quoted as in RFC 4180.
+    Csv,
+
+    /// A JSON array with an object per row.
+    Json,
+
+    /// A JSON object per line.
+    #[strum(serialize = "jsonl")]
+    JsonLines,
+
+    /// A GitHub-flavored Markdown table.
+    Markdown,
+}
+
+/// How [`QueryResponse::write_as`] writes a result.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct OutputFormat {
+    pub style: OutputStyle,
+
+    /// Writes every row as a list of column names and values, for wide rows. Only the
+    /// aligned style has an expanded layout.
+    pub expanded: bool,
+
+    /// Text written for NULL, empty by default. JSON always uses `null`.
+    pub null_display: String,
+}
+
+impl OutputFormat {
+    pub fn new(style: OutputStyle) -> Self {
+        Self {
+            style,
+            ..Self::default()
+        }
+    }
+}
+
+impl QueryResponse {
+    /// Writes the columns and rows of the result in the given format.
+    pub fn write_as(
+        &self,
+        format: &OutputFormat,
+        mut writer: impl Write,
+    ) -> Result<(), DatabaseError> {
+        let writer = &mut writer;
+        match format.style {
+            OutputStyle::Aligned if format.expanded => write_expanded(self, format, writer)?,
+            OutputStyle::Aligned => write_aligned(self, format, writer)?,
+            OutputStyle::Csv => write_csv(self, format, writer)?,
+            OutputStyle::Json => {
+                if self.rows.is_empty() {
+                    writeln!(writer, "[]")?;
+                } else {
+                    writeln!(writer, "[")?;
+                    for (index, object) in json_objects(self).enumerate() {
+                        let separator = if index + 1 < self.rows.len() { "," } else { "" };
+                        writeln!(writer, "  {object}{separator}")?;
+                    }
+                    writeln!(writer, "]")?;
+                }
+            }
+            OutputStyle::JsonLines => {
+                for object in json_objects(self) {
+                    writeln!(writer, "{object}")?;
+                }
+            }
+            OutputStyle::Markdown => write_markdown(self, format, writer)?,
+        }
+        Ok(())
+    }
+}
+
+/// The columns of the aligned and Markdown tables, with the text of every cell.
+struct Table<'a> {
+    names: Vec<&'a str>,
+    right_aligned: Vec<bool>,
+    cells: Vec<Vec<String>>,
+    widths: Vec<usize>,
+}
+
+impl<'a> Table<'a> {
+    fn new(response: &'a QueryResponse, cell: impl Fn(String) -> String, null: &str) -> Self {
+        let fields = &response.schema.fields;
+        let names = fields.iter().map(Field::output_name).collect::<Vec<_>>();
+        let cells = response
+            .rows
+            .iter()
+            .map(|row| {
+                row.values
+                    .iter()
+                    .map(|value| match value {
+                        Value::Null => null.to_string(),
+                        value => cell(value.to_string()),
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let mut widths = names
+            .iter()
+            .map(|name| text_width(name))
+            .collect::<Vec<_>>();
+        for row in &cells {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.split('\n').map(text_width).max().unwrap_or(0));
+            }
+        }
+
+        Self {
+            names,
+            right_aligned: fields
+                .iter()
+                .map(|field| is_numeric(field.data_type))
+                .collect(),
+            cells,
+            widths,
+        }
+    }
+
+    fn name_width(&self) -> usize {
+        self.names
+            .iter()
+            .map(|name| text_width(name))
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+fn write_aligned(
+    response: &QueryResponse,
+    format: &OutputFormat,
+    writer: &mut impl Write,
+) -> Result<(), DatabaseError> {
+    let table = Table::new(response, |text| text, &format.null_display);
+    let last = table.names.len().saturating_sub(1);
+
+    // psql centers the column names
+    let header = table
+        .names
+        .iter()
+        .zip(&table.widths)
+        .map(|(name, width)| {
+            let padding = width - text_width(name);
+            let left = padding / 2;
+            format!(" {}{name}{} ", " ".repeat(left), " ".repeat(padding - left))
+        })
+        .collect::<Vec<_>>();
+    writeln!(writer, "{}", header.join("|"))?;
+    let separator = table
+        .widths
+        .iter()
+        .map(|width| "-".repeat(width + 2))
+        .collect::<Vec<_>>();
+    writeln!(writer, "{}", separator.join("+"))?;
+
+    for row in &table.cells {
+        let lines = row.iter().map(|cell| cell.split('\n').collect::<Vec<_>>());
+        let lines = lines.collect::<Vec<_>>();
+        let height = lines.iter().map(Vec::len).max().unwrap_or(1);
+
+        for line in 0..height {
+            let mut text = String::new();
+            for (column, cell_lines) in lines.iter().enumerate() {
+                let cell = cell_lines.get(line).copied().unwrap_or("");
+                let padding = " ".repeat(table.widths[column] - text_width(cell));
+                if column > 0 {
+                    text.push('|');
+                }
+                if table.right_aligned[column] {
+                    text.push_str(&format!(" {padding}{cell} "));
+                } else if column == last {
+                    // Nothing follows the last column to align
+                    text.push_str(&format!(" {cell}"));
+                } else {
+                    text.push_str(&format!(" {cell}{padding} "));
+                }
+            }
+            writeln!(writer, "{text}")?;
+        }
+    }
+
+    write_row_count(response.rows.len(), writer)
+}
+
+/// Writes every row as a record of `name | value` lines, like `psql` with `\x`.
+fn write_expanded(
+    response: &QueryResponse,
+    format: &OutputFormat,
+    writer: &mut impl Write,
+) -> Result<(), DatabaseError> {
+    let table = Table::new(response, |text| text, &format.null_display);
+    if table.cells.is_empty() {
+        return write_row_count(0, writer);
+    }
+
+    let name_width = table.name_width();
+    let value_width = table
+        .cells
+        .iter()
+        .flatten()
+        .flat_map(|cell| cell.split('\n'))
+        .map(text_width)
+        .max()
+        .unwrap_or(0);
+
+    for (index, row) in table.cells.iter().enumerate() {
+        let title = format!("-[ RECORD {} ]", index + 1);
+        let fill = (name_width + 3 + value_width).saturating_sub(text_width(&title));
+        writeln!(writer, "{title}{}", "-".repeat(fill))?;
+
+        for (name, cell) in table.names.iter().zip(row) {
+            for (line, text) in cell.split('\n').enumerate() {
+                let label = if line == 0 { name } else { "" };
+                let padding = " ".repeat(name_width - text_width(label));
+                writeln!(writer, "{label}{padding} | {text}")?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn write_markdown(
+    response: &QueryResponse,
+    format: &OutputFormat,
+    writer: &mut impl Write,
+) -> Result<(), DatabaseError> {
+    let escape = |text: String| text.replace('|', "\\|").replace('\n', "<br>");
+    let null_display = escape(format.null_display.clone());
+    let table = Table::new(response, escape, &null_display);
+    // A delimiter cell needs at least three dashes
+    let widths = table.widths.iter().map(|width| (*width).max(3));
+    let widths = widths.collect::<Vec<_>>();
+
+    let line = |cells: Vec<String>| format!("| {} |", cells.join(" | "));
+    let pad = |text: &str, width: usize, right: bool| {
+        let padding = " ".repeat(width - text_width(text));
+        if right {
+            format!("{padding}{text}")
+        } else {
+            format!("{text}{padding}")
+        }
+    };
+
+    let names = table.names.iter().map(|name| escape(name.to_string()));
+    let header = names
+        .zip(&widths)
+        .map(|(name, width)| pad(&name, *width, false))
+        .collect();
+    writeln!(writer, "{}", line(header))?;
+    let delimiters = widths
+        .iter()
+        .zip(&table.right_aligned)
+        .map(|(width, right)| match right {
+            true => format!("{}:", "-".repeat(width - 1)),
+            false => "-".repeat(*width),
+        })
+        .collect();
+    writeln!(writer, "{}", line(delimiters))?;
+
+    for row in &table.cells {
+        let cells = row
+            .iter()
+            .enumerate()
+            .map(|(column, cell)| pad(cell, widths[column], table.right_aligned[column]))
+            .collect();
+        writeln!(writer, "{}", line(cells))?;
+    }
+
+    Ok(())
+}
+
+fn write_csv(
+    response: &QueryResponse,
+    format: &OutputFormat,
+    writer: &mut impl Write,
+) -> Result<(), DatabaseError> {
+    let names = response.schema.fields.iter().map(Field::output_name);
+    let header = names.map(csv_field).collect::<Vec<_>>();
+    writeln!(writer, "{}", header.join(","))?;
+
+    for row in &response.rows {
+        let fields = row
+            .values
+            .iter()
+            .map(|value| match value {
+                Value::Null => csv_field(&format.null_display),
+                value => csv_field(&value.to_string()),
+            })
+            .collect::<Vec<_>>();
+        writeln!(writer, "{}", fields.join(","))?;
+    }
+
+    Ok(())
+}
+
+/// Quotes a field containing a separator, a quote or a line break.
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Every row as a JSON object, with its keys in the order of the columns.
+fn json_objects(response: &QueryResponse) -> impl Iterator<Item = String> {
+    let keys = response
+        .schema
+        .fields
+        .iter()
+        .map(|field| Json::String(field.output_name().to_string()).to_string())
+        .collect::<Vec<_>>();
+
+    response.rows.iter().map(move |row| {
+        let members = keys
+            .iter()
+            .zip(&row.values)
+            .map(|(key, value)| format!("{key}: {}", json_value(value)))
+            .collect::<Vec<_>>();
+        format!("{{{}}}", members.join(", "))
+    })
+}
+
+/// Numbers, booleans and JSON documents are written as such, anything else as a string.
+fn json_value(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::Int16(_) | Value::Int32(_) | Value::Int64(_) | Value::Decimal(_) => {
+            value.to_string()
+        }
+        // JSON has no NaN or infinity
+        Value::Float64(float) if float.is_finite() => value.to_string(),
+        Value::Bool(bool) => bool.to_string(),
+        Value::Json(json) => json.to_string(),
+        value => Json::String(value.to_string()).to_string(),
+    }
+}
+
+fn write_row_count(count: usize, writer: &mut impl Write) -> Result<(), DatabaseError> {
+    let plural = if count == 1 { "" } else { "s" };
+    writeln!(writer, "({count} row{plural})")?;
+    Ok(())
+}
+
+fn is_numeric(data_type: DataType) -> bool {
+    matches!(
+        data_type,
+        DataType::Int16
+            | DataType::Int32
+            | DataType::Int64
+            | DataType::Float64
+            | DataType::Decimal(..)
+    )
+}
+
+/// The number of terminal columns `text` takes, wide characters such as CJK count twice.
+fn text_width(text: &str) -> usize {
+    text.width()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{OutputSchema, Row};
+
+    fn response() -> QueryResponse {
+        let field = |name: &str, data_type| Field {
+            name: name.to_string(),
+            alias: None,
+            relation: None,
+            data_type,
+            is_nullable: true,
+        };
+        QueryResponse {
+            schema: OutputSchema {
+                fields: vec![field("id", DataType::Int32), field("name", DataType::Text)],
+            },
+            rows: vec![
+                Row::new(vec![Value::Int32(7), Value::Text("Zoë, \"Z\"".to_string())]),
+                Row::new(vec![Value::Int32(10), Value::Text("東京".to_string())]),
+                Row::new(vec![Value::Int32(123), Value::Null]),
+            ],
+        }
+    }
+
+    fn write(format: OutputFormat) -> String {
+        let mut output = Vec::new();
+        response().write_as(&format, &mut output).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_aligned_widths_follow_the_data() {
+        let format = OutputFormat {
+            null_display: "∅".to_string(),
+            ..OutputFormat::default()
+        };
+        assert_eq!(
+            write(format),
+            [
+                " id  |   name   ",
+                "-----+----------",
+                "   7 | Zoë, \"Z\"",
+                "  10 | 東京",
+                " 123 | ∅",
+                "(3 rows)\n",
+            ]
+            .join("\n")
+        );
+
+        let format = OutputFormat {
+            expanded: true,
+            ..OutputFormat::default()
+        };
+        assert!(write(format).starts_with("-[ RECORD 1 ]--\nid   | 7\nname | Zoë, \"Z\"\n"));
+    }
+
+    #[test]
+    fn test_machine_readable_styles() {
+        assert_eq!(
+            write(OutputFormat::new(OutputStyle::Csv)),
+            "id,name\n7,\"Zoë, \"\"Z\"\"\"\n10,東京\n123,\n"
+        );
+        assert_eq!(
+            write(OutputFormat::new(OutputStyle::JsonLines)),
+            [
+                r#"{"id": 7, "name": "Zoë, \"Z\""}"#,
+                r#"{"id": 10, "name": "東京"}"#,
+                r#"{"id": 123, "name": null}"#,
+                "",
+            ]
+            .join("\n")
+        );
+        assert!(write(OutputFormat::new(OutputStyle::Json)).starts_with("[\n  {\"id\": 7, "));
+        assert_eq!(
+            write(OutputFormat::new(OutputStyle::Markdown)),
+            [
+                "| id  | name     |",
+                "| --: | -------- |",
+                "|   7 | Zoë, \"Z\" |",
+                "|  10 | 東京     |",
+                "| 123 |          |",
+                "",
+            ]
+            .join("\n")
+        );
+        assert_eq!("JSONL".parse(), Ok(OutputStyle::JsonLines));
+    }
+
+    #[test]
+    fn test_json_numbers_are_exact() {
+        assert_eq!(json_value(&Value::Int64(i64::MAX)), "9223372036854775807");
+        let document = Json::parse(r#"{"id": 12345678901234567891, "ratio": 0.1}"#).unwrap();
+        assert_eq!(
+            json_value(&Value::Json(document)),
+            r#"{"id": 12345678901234567891, "ratio": 0.1}"#
+        );
+    }
+}
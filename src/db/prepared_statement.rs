@@ -0,0 +1,144 @@
+use std::sync::Arc;
+
+use crate::{
+    DataType, DatabaseError, Value,
+    sql::{
+        analyzer::{AnalyzedExpression, schema::OutputSchema},
+        evaluator::cast_value,
+        optimizer::{Transformed, map_expressions, transform_up},
+        planner::logical::LogicalPlan,
+    },
+};
+
+/// A query planned once and run many times with different parameter values.
+///
+/// Created by [`Database::prepare`](crate::Database::prepare) and run with
+/// [`Database::execute`](crate::Database::execute). The parameters `$1`, `$2`, ...
+/// (or `?` in order of appearance) are typed when the statement is prepared.
+#[derive(Debug, Clone)]
+pub struct PreparedStatement {
+    plan: Arc<LogicalPlan>,
+    parameter_types: Vec<DataType>,
+}
+
+impl PreparedStatement {
+    pub(crate) fn new(plan: LogicalPlan, parameter_types: Vec<DataType>) -> Self {
+        Self {
+            plan: Arc::new(plan),
+            parameter_types,
+        }
+    }
+
+    /// The types of the parameters, `$1` first.
+    pub fn parameter_types(&self) -> &[DataType] {
+        &self.parameter_types
+    }
+
+    /// The columns of the rows the statement returns.
+    pub fn schema(&self) -> &OutputSchema {
+        self.plan.schema()
+    }
+
+    /// The tables the statement reads.
+    pub(crate) fn table_names(&self) -> Vec<&str> {
+        self.plan.table_names()
+    }
+
+    /// Returns the plan with every parameter replaced by its value.
+    ///
+    /// Values are cast to the type of their parameter, as by `CAST`.
+    pub(crate) fn bind(&self, parameters: &[Value]) -> Result<LogicalPlan, DatabaseError> {
+        if parameters.len() != self.parameter_types.len() {
+            return Err(DatabaseError::InvalidQuery(format!(
+                "Prepared statement takes {} parameters but {} were given",
+                self.parameter_types.len(),
+                parameters.len()
+            )));
+        }
+
+        let values = parameters
+            .iter()
+            .zip(&self.parameter_types)
+            .map(|(value, data_type)| cast_value(value, *data_type))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let substitute = |expr| match expr {
+            AnalyzedExpression::Parameter { number, data_type } => {
+                Transformed::yes(match &values[number - 1] {
+                    // A bare NULL literal has no type, keep the one of the parameter
+                    Value::Null => AnalyzedExpression::Cast {
+                        expr: Box::new(AnalyzedExpression::Literal(Value::Null)),
+                        data_type,
+                    },
+                    value => AnalyzedExpression::Literal(value.clone()),
+                })
+            }
+            expr => Transformed::no(expr),
+        };
+
+        Ok(transform_up((*self.plan).clone(), &|node| {
+            map_expressions(node, &substitute)
+        })
+        .value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sql::analyzer::ColumnRef;
+
+    #[test]
+    fn test_bind_replaces_parameters() {
+        let schema = crate::sql::analyzer::schema::OutputSchema { fields: Vec::new() };
+        let plan = LogicalPlan::Projection {
+            input: Box::new(LogicalPlan::Empty {
+                schema: schema.clone(),
+            }),
+            expressions: vec![
+                AnalyzedExpression::Parameter {
+                    number: 1,
+                    data_type: DataType::Int64,
+                },
+                AnalyzedExpression::Parameter {
+                    number: 2,
+                    data_type: DataType::Text,
+                },
+                AnalyzedExpression::Column(
+                    ColumnRef {
+                        index: 0,
+                        relation: None,
+                    },
+                    DataType::Int64,
+                ),
+            ],
+            schema,
+        };
+        let statement = PreparedStatement::new(plan, vec![DataType::Int64, DataType::Text]);
+
+        let LogicalPlan::Projection { expressions, .. } =
+            statement.bind(&[Value::Int32(7), Value::Null]).unwrap()
+        else {
+            panic!("Expected a projection");
+        };
+        assert!(matches!(
+            expressions[0],
+            AnalyzedExpression::Literal(Value::Int64(7))
+        ));
+        assert!(matches!(
+            expressions[1],
+            AnalyzedExpression::Cast {
+                data_type: DataType::Text,
+                ..
+            }
+        ));
+        assert!(matches!(expressions[2], AnalyzedExpression::Column(..)));
+
+        assert!(statement.bind(&[Value::Int64(1)]).is_err());
+        assert!(
+            statement
+                .bind(&[Value::Text("x".to_string()), Value::Null])
+                .is_err()
+        );
+    }
+}
@@ -0,0 +1,81 @@
+use miette::Result;
+
+use crate::{
+    Row,
+    sql::{
+        analyzer::schema::OutputSchema,
+        catalog_context::CatalogContext,
+        planner::{batch::RecordBatch, physical::ExecutionNode},
+    },
+};
+
+/// Rows of a query, produced while they are read.
+///
+/// Returned by [`Database::query`](crate::Database::query). Pages are only read when
+/// rows are asked for, so a large result is never held in memory at once. The
+/// stream holds a shared borrow of the database until it is dropped.
+#[derive(Debug)]
+pub struct RowStream<'db> {
+    context: CatalogContext<'db>,
+    root: Box<dyn ExecutionNode>,
+
+    /// Rows of the current batch not returned yet.
+    rows: std::vec::IntoIter<Row>,
+
+    /// Set once the plan is exhausted or failed, the stream then stays empty.
+    done: bool,
+}
+
+impl<'db> RowStream<'db> {
+    pub(crate) fn new(context: CatalogContext<'db>, root: Box<dyn ExecutionNode>) -> Self {
+        Self {
+            context,
+            root,
+            rows: Vec::new().into_iter(),
+            done: false,
+        }
+    }
+
+    /// The columns of every row.
+    pub fn schema(&self) -> &OutputSchema {
+        self.root.schema()
+    }
+
+    /// Returns the rows of the next batch, `None` once all rows were returned.
+    ///
+    /// Rows already taken one by one through the iterator are not returned again.
+    pub fn next_batch(&mut self) -> Result<Option<RecordBatch>> {
+        let pending = std::mem::take(&mut self.rows).collect::<Vec<_>>();
+        if !pending.is_empty() {
+            let width = self.schema().fields.len();
+            return Ok(Some(RecordBatch::from_rows(pending, width)));
+        }
+        if self.done {
+            return Ok(None);
+        }
+
+        let batch = self.root.next(&self.context);
+        if !matches!(batch, Ok(Some(_))) {
+            self.done = true;
+        }
+        batch
+    }
+}
+
+impl Iterator for RowStream<'_> {
+    type Item = Result<Row>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(row) = self.rows.next() {
+                return Some(Ok(row));
+            }
+
+            match self.next_batch() {
+                Ok(Some(batch)) => self.rows = batch.into_rows().into_iter(),
+                Ok(None) => return None,
+                Err(error) => return Some(Err(error)),
+            }
+        }
+    }
+}
@@ -0,0 +1,428 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc, PoisonError, RwLock, RwLockWriteGuard,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use miette::Result;
+
+use crate::{
+    DataType, DatabaseError, Row, Value,
+    db::{
+        catalog::statistics::STATISTICS_RELATION,
+        database::{Database, QueryResponse, find_prepared, parse_statement},
+        lock_manager::{LockManager, LockMode, SessionId},
+        prepared_statement::PreparedStatement,
+        table::schema::Schema,
+    },
+    sql::ast::statement::Statement,
+    storage::page::{ItemId, PageId},
+};
+
+/// A database used from several threads, each through its own [`Session`].
+///
+/// Cloning the handle is cheap, every clone refers to the same database. The catalog
+/// is behind a lock: statements hold it shared while they run, creating or dropping
+/// tables and registering functions hold it exclusively.
+#[derive(Debug, Clone)]
+pub struct SharedDatabase {
+    inner: Arc<Shared>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    database: RwLock<Database>,
+    locks: LockManager,
+    next_session_id: AtomicU64,
+}
+
+impl SharedDatabase {
+    pub fn new(database: Database) -> Self {
+        Self {
+            inner: Arc::new(Shared {
+                database: RwLock::new(database),
+                locks: LockManager::default(),
+                next_session_id: AtomicU64::new(1),
+            }),
+        }
+    }
+
+    /// Opens a new session, e.g. for a client connection.
+    pub fn session(&self) -> Session {
+        Session {
+            id: self.inner.next_session_id.fetch_add(1, Ordering::Relaxed),
+            shared: Arc::clone(&self.inner),
+            prepared_statements: HashMap::new(),
+        }
+    }
+
+    /// Gives exclusive access to the database, e.g. to register functions.
+    ///
+    /// Waits for the running statements to finish, and keeps new ones waiting.
+    pub fn catalog_mut(&self) -> RwLockWriteGuard<'_, Database> {
+        self.inner
+            .database
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// A connection to a [`SharedDatabase`], used by one thread at a time.
+///
+/// Every statement locks the tables it uses until it finishes: reads take shared
+/// locks, changes take exclusive ones. Locks taken with [`Session::lock_table`] are
+/// kept across statements until [`Session::unlock_tables`], a session asking for a
+/// lock that would deadlock gets [`DatabaseError::Deadlock`] instead of waiting.
+///
+/// Prepared statements belong to the session that created them.
+#[derive(Debug)]
+pub struct Session {
+    id: SessionId,
+    shared: Arc<Shared>,
+    prepared_statements: HashMap<String, PreparedStatement>,
+}
+
+impl Session {
+    /// Executes a SQL query, see [`Database::execute_query`].
+    pub fn execute_query(&mut self, query: &str) -> Result<QueryResponse> {
+        let statement = parse_statement(query)?;
+        let locks = self.statement_locks(&statement)?;
+
+        self.with_locks(locks, |database, prepared_statements| {
+            database.execute_statement(statement, prepared_statements)
+        })
+    }
+
+    /// Plans a query to run it with [`Session::execute`], see [`Database::prepare`].
+    ///
+    /// `parameter_types` fixes the types of the first parameters, the others are
+    /// inferred from where they are used.
+    pub fn prepare(
+        &mut self,
+        query: &str,
+        parameter_types: &[DataType],
+    ) -> Result<PreparedStatement> {
+        let statement = parse_statement(query)?;
+        let locks = self.statement_locks(&statement)?;
+
+        self.with_locks(locks, |database, _| {
+            database.prepare_statement(statement, parameter_types)
+        })
+    }
+
+    /// Runs a prepared statement, see [`Database::execute`].
+    pub fn execute(
+        &mut self,
+        statement: &PreparedStatement,
+        parameters: &[Value],
+    ) -> Result<QueryResponse> {
+        let locks = statement
+            .table_names()
+            .into_iter()
+            .map(|table_name| (table_name.to_string(), LockMode::Shared))
+            .collect();
+
+        self.with_locks(locks, |database, _| database.execute(statement, parameters))
+    }
+
+    /// Inserts a row into a table, see [`Database::insert_row`].
+    pub fn insert_row(&mut self, table_name: &str, row: Row) -> Result<(PageId, ItemId)> {
+        let locks = vec![(table_name.to_string(), LockMode::Exclusive)];
+        self.with_locks(locks, |database, _| database.insert_row(table_name, row))
+    }
+
+    /// Retrieves all rows of a table, see [`Database::get_rows`].
+    pub fn get_rows(&mut self, table_name: &str) -> Result<Vec<Row>> {
+        let locks = vec![(table_name.to_string(), LockMode::Shared)];
+        self.with_locks(locks, |database, _| Ok(database.get_rows(table_name)?))
+    }
+
+    /// Creates a table, see [`Database::create_table`].
+    pub fn create_table(&mut self, name: &str, schema: Schema) -> Result<()> {
+        self.change_catalog(name, |database| database.create_table(name, schema))
+    }
+
+    /// Drops a table, see [`Database::drop_table`].
+    pub fn drop_table(&mut self, name: &str) -> Result<()> {
+        self.change_catalog(name, |database| database.drop_table(name))
+    }
+
+    /// Locks a table until [`Session::unlock_tables`], waiting for conflicting locks of
+    /// other sessions. Locking a table again with [`LockMode::Exclusive`] upgrades the
+    /// lock.
+    pub fn lock_table(&mut self, table_name: &str, mode: LockMode) -> Result<(), DatabaseError> {
+        self.shared.locks.lock(self.id, table_name, mode)
+    }
+
+    /// Releases every lock taken with [`Session::lock_table`].
+    pub fn unlock_tables(&mut self) {
+        self.shared.locks.unlock_all(self.id);
+    }
+
+    /// The tables a statement uses and how it locks them.
+    fn statement_locks(&self, statement: &Statement) -> Result<Vec<(String, LockMode)>> {
+        let all_tables = || {
+            let database = self
+                .shared
+                .database
+                .read()
+                .unwrap_or_else(PoisonError::into_inner);
+            database.tables.keys().cloned().collect::<Vec<_>>()
+        };
+
+        Ok(match statement {
+            Statement::Select(select) => {
+                let mut table_names = select.from_clause.table_names().collect::<Vec<_>>();
+                // A table joined with itself is locked once
+                table_names.sort_unstable();
+                table_names.dedup();
+                table_names
+                    .into_iter()
+                    .map(|table_name| (table_name.to_string(), LockMode::Shared))
+                    .collect()
+            }
+            Statement::Explain(explain) => self.statement_locks(&explain.statement)?,
+            Statement::Prepare(prepare) => self.statement_locks(&prepare.statement)?,
+            Statement::Execute(execute) => find_prepared(&self.prepared_statements, &execute.name)?
+                .table_names()
+                .into_iter()
+                .map(|table_name| (table_name.to_string(), LockMode::Shared))
+                .collect(),
+            Statement::Vacuum(vacuum) => vacuum
+                .table_name
+                .clone()
+                .map_or_else(all_tables, |table_name| vec![table_name])
+                .into_iter()
+                .map(|table_name| (table_name, LockMode::Exclusive))
+                .collect(),
+            Statement::Analyze(analyze) => {
+                // Statistics of every table are kept in the same catalog relation
+                let mut locks = analyze
+                    .table_name
+                    .clone()
+                    .map_or_else(all_tables, |table_name| vec![table_name])
+                    .into_iter()
+                    .map(|table_name| (table_name, LockMode::Shared))
+                    .collect::<Vec<_>>();
+                locks.push((STATISTICS_RELATION.to_string(), LockMode::Exclusive));
+                locks
+            }
+            _ => Vec::new(),
+        })
+    }
+
+    /// Runs `run` while holding the given table locks and the catalog lock shared.
+    ///
+    /// Table locks are taken first, so a session never waits for a table while
+    /// holding the catalog. Locks the session already held are kept afterwards.
+    fn with_locks<R>(
+        &mut self,
+        mut locks: Vec<(String, LockMode)>,
+        run: impl FnOnce(&Database, &mut HashMap<String, PreparedStatement>) -> Result<R>,
+    ) -> Result<R> {
+        // Taken in the same order by every session, the strongest mode per table
+        locks.sort_by(|a, b| a.0.cmp(&b.0).then(b.1.cmp(&a.1)));
+        locks.dedup_by(|a, b| a.0 == b.0);
+
+        let mut taken = Vec::new();
+        let mut locked = Ok(());
+        for (table_name, mode) in locks {
+            let held = self.shared.locks.held(self.id, &table_name);
+            if held >= Some(mode) {
+                continue;
+            }
+
+            locked = self.shared.locks.lock(self.id, &table_name, mode);
+            if locked.is_err() {
+                break;
+            }
+            taken.push((table_name, held));
+        }
+
+        let result = locked.map_err(Into::into).and_then(|()| {
+            let database = self
+                .shared
+                .database
+                .read()
+                .unwrap_or_else(PoisonError::into_inner);
+            run(&database, &mut self.prepared_statements)
+        });
+
+        for (table_name, held) in taken {
+            self.shared.locks.restore(self.id, &table_name, held);
+        }
+        result
+    }
+
+    /// Runs `change` with exclusive access to the catalog and an exclusive lock on
+    /// the table it changes.
+    fn change_catalog<R>(
+        &mut self,
+        table_name: &str,
+        change: impl FnOnce(&mut Database) -> Result<R, DatabaseError>,
+    ) -> Result<R> {
+        let held = self.shared.locks.held(self.id, table_name);
+        self.shared
+            .locks
+            .lock(self.id, table_name, LockMode::Exclusive)?;
+
+        let result = change(
+            &mut self
+                .shared
+                .database
+                .write()
+                .unwrap_or_else(PoisonError::into_inner),
+        );
+
+        self.shared.locks.restore(self.id, table_name, held);
+        Ok(result?)
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.shared.locks.unlock_all(self.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicBool;
+
+    use super::*;
+    use crate::ColumnDef;
+
+    fn shared_database(name: &str) -> SharedDatabase {
+        let directory = std::env::temp_dir()
+            .join("scuttle_session_tests")
+            .join(name);
+        std::fs::remove_dir_all(&directory).ok();
+
+        let shared = SharedDatabase::new(Database::new(&directory));
+        let mut session = shared.session();
+        for table_name in ["users", "orders"] {
+            let schema = Schema::new(vec![ColumnDef::new("id", DataType::Int64, false)]);
+            session.create_table(table_name, schema).unwrap();
+        }
+        shared
+    }
+
+    fn id(id: i64) -> Row {
+        Row::new(vec![Value::Int64(id)])
+    }
+
+    #[test]
+    fn test_sessions_are_used_from_several_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Database>();
+        assert_send_sync::<SharedDatabase>();
+        assert_send_sync::<Session>();
+
+        let shared = shared_database("threads");
+        std::thread::scope(|scope| {
+            for thread in 0..4 {
+                let mut session = shared.session();
+                scope.spawn(move || {
+                    let table_name = ["users", "orders"][thread % 2];
+                    for n in 0..50 {
+                        session.insert_row(table_name, id(n)).unwrap();
+                        session
+                            .execute_query(&format!("SELECT count(*) FROM {table_name}"))
+                            .unwrap();
+                    }
+                });
+            }
+        });
+
+        let mut session = shared.session();
+        for table_name in ["users", "orders"] {
+            let response = session
+                .execute_query(&format!("SELECT count(*) FROM {table_name}"))
+                .unwrap();
+            assert_eq!(response.rows[0].values, vec![Value::Int64(100)]);
+        }
+    }
+
+    #[test]
+    fn test_table_locks_block_other_sessions() {
+        let shared = shared_database("blocking");
+        let mut reader = shared.session();
+        reader.lock_table("users", LockMode::Shared).unwrap();
+
+        let inserted = AtomicBool::new(false);
+        std::thread::scope(|scope| {
+            let mut writer = shared.session();
+            let inserted = &inserted;
+            scope.spawn(move || {
+                writer.insert_row("users", id(1)).unwrap();
+                inserted.store(true, Ordering::SeqCst);
+            });
+
+            // Readers and other tables are not blocked
+            assert!(reader.get_rows("users").unwrap().is_empty());
+            shared.session().insert_row("orders", id(1)).unwrap();
+
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            assert!(!inserted.load(Ordering::SeqCst));
+            reader.unlock_tables();
+        });
+
+        assert!(inserted.load(Ordering::SeqCst));
+        assert_eq!(reader.get_rows("users").unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_deadlocks_are_detected() {
+        let shared = shared_database("deadlock");
+        let mut first = shared.session();
+        let mut second = shared.session();
+        first.lock_table("users", LockMode::Exclusive).unwrap();
+        second.lock_table("orders", LockMode::Exclusive).unwrap();
+
+        // Each session reads the table the other one locked
+        let results = std::thread::scope(|scope| {
+            [(&mut first, "orders"), (&mut second, "users")]
+                .map(|(session, table_name)| {
+                    scope.spawn(move || {
+                        let result = session.execute_query(&format!("SELECT id FROM {table_name}"));
+                        if result.is_err() {
+                            session.unlock_tables();
+                        }
+                        result
+                    })
+                })
+                .map(|handle| handle.join().unwrap())
+        });
+
+        let deadlocks = results
+            .iter()
+            .filter_map(|result| result.as_ref().err())
+            .map(|error| error.downcast_ref::<DatabaseError>())
+            .collect::<Vec<_>>();
+        assert!(
+            matches!(deadlocks.as_slice(), [Some(DatabaseError::Deadlock(_))]),
+            "{results:?}"
+        );
+    }
+
+    #[test]
+    fn test_prepared_statements_belong_to_their_session() {
+        let shared = shared_database("prepared");
+        let mut first = shared.session();
+        let mut second = shared.session();
+        first.insert_row("users", id(7)).unwrap();
+
+        first
+            .execute_query("PREPARE by_id AS SELECT id FROM users WHERE id = $1")
+            .unwrap();
+        let response = first.execute_query("EXECUTE by_id (7)").unwrap();
+        assert_eq!(response.rows[0].values, vec![Value::Int64(7)]);
+
+        assert!(second.execute_query("EXECUTE by_id (7)").is_err());
+        second
+            .execute_query("PREPARE by_id AS SELECT count(*) FROM users")
+            .unwrap();
+    }
+}
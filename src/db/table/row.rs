@@ -1,4 +1,8 @@
-use crate::core::types::Value;
+use crate::{
+    DatabaseError,
+    core::{convert::FromValue, types::Value},
+    sql::analyzer::schema::OutputSchema,
+};
 
 /// A row of data containing values for each column.
 ///
@@ -20,4 +24,137 @@ impl Row {
     pub fn get_value(&self, index: usize) -> Option<&Value> {
         self.values.get(index)
     }
+
+    /// Decodes the value of a column, given by name or index, into a Rust type.
+    ///
+    /// ```ignore
+    /// let age: Option<i64> = row.get(&response.schema, "age")?;
+    /// ```
+    pub fn get<T: FromValue>(
+        &self,
+        schema: &OutputSchema,
+        column: impl ColumnIndex,
+    ) -> Result<T, DatabaseError> {
+        let index = column.index(schema)?;
+        let value = self
+            .values
+            .get(index)
+            .cloned()
+            .ok_or_else(|| DatabaseError::ColumnNotFound(format!("#{index}")))?;
+
+        T::from_value(value).map_err(|error| match error {
+            DatabaseError::TypeMismatch(message) => DatabaseError::TypeMismatch(format!(
+                "{message} in column {}",
+                schema.fields[index].output_name()
+            )),
+            error => error,
+        })
+    }
+}
+
+/// Identifies a column of a result row, by position or by name.
+pub trait ColumnIndex {
+    fn index(&self, schema: &OutputSchema) -> Result<usize, DatabaseError>;
+}
+
+impl ColumnIndex for usize {
+    fn index(&self, schema: &OutputSchema) -> Result<usize, DatabaseError> {
+        if *self < schema.fields.len() {
+            Ok(*self)
+        } else {
+            Err(DatabaseError::ColumnNotFound(format!("#{self}")))
+        }
+    }
+}
+
+impl ColumnIndex for &str {
+    /// Matches the output name of a column, its alias if it has one.
+    fn index(&self, schema: &OutputSchema) -> Result<usize, DatabaseError> {
+        schema
+            .fields
+            .iter()
+            .position(|field| field.output_name() == *self)
+            .ok_or_else(|| DatabaseError::ColumnNotFound(self.to_string()))
+    }
+}
+
+/// A type a whole result row can be decoded into.
+///
+/// Implemented for tuples of [`FromValue`] types, decoding the columns in order.
+/// Structs can derive it with the `derive` feature, decoding each field from the
+/// column of the same name.
+pub trait FromRow: Sized {
+    fn from_row(row: &Row, schema: &OutputSchema) -> Result<Self, DatabaseError>;
+}
+
+macro_rules! tuple_from_row {
+    ($($index:tt: $type:ident),+) => {
+        impl<$($type: FromValue),+> FromRow for ($($type,)+) {
+            fn from_row(row: &Row, schema: &OutputSchema) -> Result<Self, DatabaseError> {
+                Ok(($(row.get::<$type>(schema, $index as usize)?,)+))
+            }
+        }
+    };
+}
+
+tuple_from_row!(0: A);
+tuple_from_row!(0: A, 1: B);
+tuple_from_row!(0: A, 1: B, 2: C);
+tuple_from_row!(0: A, 1: B, 2: C, 3: D);
+tuple_from_row!(0: A, 1: B, 2: C, 3: D, 4: E);
+tuple_from_row!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F);
+tuple_from_row!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G);
+tuple_from_row!(0: A, 1: B, 2: C, 3: D, 4: E, 5: F, 6: G, 7: H);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DataType, sql::analyzer::schema::Field};
+
+    fn schema() -> OutputSchema {
+        let field = |name: &str, alias: Option<&str>, data_type| Field {
+            name: name.to_string(),
+            relation: None,
+            alias: alias.map(str::to_string),
+            data_type,
+            is_nullable: true,
+        };
+        OutputSchema {
+            fields: vec![
+                field("name", None, DataType::Text),
+                field("age", Some("years"), DataType::Int64),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_get_decodes_columns() {
+        let schema = schema();
+        let row = Row::new(vec![Value::Text("Bob".to_string()), Value::Null]);
+
+        assert_eq!(row.get::<String>(&schema, "name").unwrap(), "Bob");
+        assert_eq!(row.get::<String>(&schema, 0).unwrap(), "Bob");
+        assert_eq!(row.get::<Option<i64>>(&schema, "years").unwrap(), None);
+
+        let error = row.get::<i64>(&schema, "years").unwrap_err();
+        assert!(error.to_string().contains("in column years"), "{error}");
+        assert!(matches!(
+            row.get::<i64>(&schema, "age"),
+            Err(DatabaseError::ColumnNotFound(_))
+        ));
+        assert!(matches!(
+            row.get::<i64>(&schema, 2),
+            Err(DatabaseError::ColumnNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_tuples_from_row() {
+        let schema = schema();
+        let row = Row::new(vec![Value::Text("Alice".to_string()), Value::Int64(30)]);
+
+        let (name, age) = <(String, Option<i64>)>::from_row(&row, &schema).unwrap();
+        assert_eq!((name.as_str(), age), ("Alice", Some(30)));
+        assert!(<(String, bool)>::from_row(&row, &schema).is_err());
+    }
 }
@@ -1,7 +1,15 @@
+use std::borrow::Cow;
+
 use miette::{Result, miette};
 
 use super::{column_def::ColumnDef, row::Row};
-use crate::{Value, core::types::DataType, db::null_bitmap::NullBitmap};
+use crate::{
+    DatabaseError, Decimal, Interval, Json, Value,
+    core::types::DataType,
+    db::null_bitmap::NullBitmap,
+    sql::evaluator::cast_value,
+    storage::toast::{self, TOAST_THRESHOLD, ToastStore},
+};
 
 /// A table schema defining the structure of rows.
 ///
@@ -24,6 +32,22 @@ impl Schema {
         self.columns.iter().position(|col| col.name == name)
     }
 
+    /// Converts text values of JSON columns into parsed documents.
+    ///
+    /// Applied to rows before they are stored, so invalid JSON is rejected on insert
+    /// and documents are kept in their binary form.
+    pub(crate) fn parse_json_columns(&self, mut row: Row) -> Result<Row, DatabaseError> {
+        for (column, value) in self.columns.iter().zip(row.values.iter_mut()) {
+            if column.data_type == DataType::Json
+                && let Value::Text(text) = value
+            {
+                *value = Value::Json(Json::parse(text)?);
+            }
+        }
+
+        Ok(row)
+    }
+
     /// Encodes a row to bytes for storage.
     ///
     /// Internal method used by the storage layer to serialize rows into pages.
@@ -31,8 +55,18 @@ impl Schema {
     /// - Integer/Float: 8 bytes (little-endian i64)
     /// - Text/VarChar: 4-byte length + UTF-8 bytes
     /// - Boolean: 1 Bit
-    pub(crate) fn encode_row(&self, row: &Row) -> Vec<u8> {
-        let mut bytes = vec![];
+    ///
+    /// Rows larger than [`TOAST_THRESHOLD`] have their largest variable-length values
+    /// compressed or moved out of line into `toast`.
+    pub(crate) fn encode_row(&self, row: &Row, toast: &mut impl ToastStore) -> Result<Vec<u8>> {
+        if row.values.len() != self.columns.len() {
+            return Err(DatabaseError::InvalidQuery(format!(
+                "Row has {} values but the table has {} columns",
+                row.values.len(),
+                self.columns.len()
+            ))
+            .into());
+        }
 
         let mut bitmap = NullBitmap::new(self.columns.len());
         for (i, value) in row.values.iter().enumerate() {
@@ -41,14 +75,25 @@ impl Schema {
             }
         }
 
-        bytes.extend_from_slice(&bitmap.bytes);
+        // Every non-null value, encoded on its own so large ones can be toasted
+        let mut fields = vec![];
 
         for (value, column) in row.values.iter().zip(self.columns.iter()) {
             if let Value::Null = value {
                 continue;
             }
 
-            match (column.data_type, value) {
+            // Integers of another width are converted, with a range check when narrowing
+            let value = match (column.data_type, value) {
+                (
+                    DataType::Int16 | DataType::Int32 | DataType::Int64,
+                    Value::Int16(_) | Value::Int32(_) | Value::Int64(_),
+                ) => Cow::Owned(cast_value(value, column.data_type)?),
+                _ => Cow::Borrowed(value),
+            };
+
+            let mut bytes = vec![];
+            match (column.data_type, value.as_ref()) {
                 (DataType::Int64, Value::Int64(number)) => {
                     bytes.extend_from_slice(&number.to_le_bytes());
                 }
@@ -56,108 +101,491 @@ impl Schema {
                     bytes.extend_from_slice(&number.to_le_bytes());
                 }
                 (DataType::Text, Value::Text(text)) => {
-                    let text_bytes = text.as_bytes();
-                    let length = text_bytes.len() as u32;
-                    bytes.extend_from_slice(&length.to_le_bytes());
-                    bytes.extend_from_slice(text_bytes);
+                    toast::write_varlena(text.as_bytes(), &mut bytes);
                 }
                 (DataType::VarChar(max_length), Value::Text(text)) => {
-                    let text_bytes = text.as_bytes();
-                    let length = text_bytes.len() as u32;
-                    assert!(
-                        length as usize <= max_length,
-                        "Text exceeds VARCHAR({max_length}) limit"
-                    );
-                    bytes.extend_from_slice(&length.to_le_bytes());
-                    bytes.extend_from_slice(text_bytes);
+                    if text.len() > max_length {
+                        return Err(DatabaseError::TypeMismatch(format!(
+                            "Text length {} exceeds VARCHAR({max_length}) limit",
+                            text.len()
+                        ))
+                        .into());
+                    }
+                    toast::write_varlena(text.as_bytes(), &mut bytes);
                 }
                 (DataType::Bool, Value::Bool(b)) => {
                     bytes.push(if *b { 1 } else { 0 });
                 }
-                _ => panic!(
-                    "Column type ({:?}) and value ({:?}) combination not implemented",
-                    column.data_type, value
-                ),
+                (DataType::Int16, Value::Int16(num)) => {
+                    bytes.extend_from_slice(&num.to_le_bytes());
+                }
+                (DataType::Int32, Value::Int32(num)) => {
+                    bytes.extend_from_slice(&num.to_le_bytes());
+                }
+                (DataType::Bytea, Value::Bytea(data)) => {
+                    toast::write_varlena(data, &mut bytes);
+                }
+                (DataType::Uuid, Value::Uuid(uuid)) => {
+                    bytes.extend_from_slice(&uuid.to_be_bytes());
+                }
+                (DataType::Decimal(precision, scale), Value::Decimal(decimal)) => {
+                    decimal.round_to(precision, scale)?.encode(&mut bytes);
+                }
+                (DataType::Timestamp, Value::Timestamp(ts)) => {
+                    bytes.extend_from_slice(&ts.to_le_bytes());
+                }
+                (DataType::Date, Value::Date(days)) => {
+                    bytes.extend_from_slice(&days.to_le_bytes());
+                }
+                (DataType::Interval, Value::Interval(interval)) => {
+                    bytes.extend_from_slice(&interval.months.to_le_bytes());
+                    bytes.extend_from_slice(&interval.days.to_le_bytes());
+                    bytes.extend_from_slice(&interval.micros.to_le_bytes());
+                }
+                (DataType::Json, Value::Json(json)) => {
+                    let mut document = vec![];
+                    json.encode(&mut document);
+                    toast::write_varlena(&document, &mut bytes);
+                }
+                (data_type, value) => {
+                    return Err(DatabaseError::TypeMismatch(format!(
+                        "{value:?} cannot be stored in column {} of type {data_type}",
+                        column.name
+                    ))
+                    .into());
+                }
             }
+            fields.push((bytes, column.data_type.is_variable_length()));
+        }
+
+        let row_length = bitmap.bytes.len() + fields.iter().map(|(f, _)| f.len()).sum::<usize>();
+        if row_length > TOAST_THRESHOLD {
+            toast::toast_row(&mut fields, row_length, toast)?;
         }
 
-        bytes
+        let mut bytes = bitmap.bytes;
+        for (field, _) in fields {
+            bytes.extend_from_slice(&field);
+        }
+        Ok(bytes)
     }
 
     /// Decodes a row from bytes read from storage.
     ///
     /// Internal method used by the storage layer to deserialize rows from pages.
-    /// Decodes values according to the schema's column types.
-    pub(crate) fn decode_row(&self, bytes: &[u8]) -> Result<Row> {
-        let mut values = Vec::new();
+    /// Decodes values according to the schema's column types, compressed and
+    /// out-of-line values are read back through `toast`.
+    ///
+    /// Only the columns in `columns` are decoded, all of them if it is `None`. It holds
+    /// column indices in ascending order, the row has one value per index. Other values
+    /// are skipped using their length, so large values are never decompressed or
+    /// fetched from their overflow pages.
+    pub(crate) fn decode_columns(
+        &self,
+        bytes: &[u8],
+        columns: Option<&[usize]>,
+        toast: &mut impl ToastStore,
+    ) -> Result<Row> {
+        let mut values = Vec::with_capacity(columns.map_or(self.columns.len(), <[_]>::len));
+        let mut wanted = columns.map(|columns| columns.iter().copied().peekable());
         let mut offset = 0;
 
         let bitmap = NullBitmap::from_bytes(bytes, self.columns.len())?;
         offset += bitmap.bytes.len();
 
         for (idx, column) in self.columns.iter().enumerate() {
+            let needed = match &mut wanted {
+                None => true,
+                Some(wanted) => match wanted.peek() {
+                    // Nothing is left to decode once the last wanted column is read
+                    None => break,
+                    Some(_) => wanted.next_if_eq(&idx).is_some(),
+                },
+            };
+
             if bitmap.is_null(idx) {
-                values.push(Value::Null);
+                if needed {
+                    values.push(Value::Null);
+                }
                 continue;
             }
 
-            match column.data_type {
-                DataType::Int64 => {
-                    if offset + 8 > bytes.len() {
-                        return Err(miette!("Not enough bytes for integer value"));
-                    }
-                    let mut num_bytes = [0u8; 8];
-                    num_bytes.copy_from_slice(&bytes[offset..offset + 8]);
-                    let value = i64::from_le_bytes(num_bytes);
-                    values.push(Value::Int64(value));
-                    offset += 8;
-                }
-                DataType::Float64 => {
-                    if offset + 8 > bytes.len() {
-                        return Err(miette!("Not enough bytes for float value"));
-                    }
-                    let mut num_bytes = [0u8; 8];
-                    num_bytes.copy_from_slice(&bytes[offset..offset + 8]);
-                    let value = f64::from_le_bytes(num_bytes);
-                    values.push(Value::Float64(value));
-                    offset += 8;
-                }
-                DataType::Bool => {
-                    if offset + 1 > bytes.len() {
-                        return Err(miette!("Not enough bytes for boolean value"));
-                    }
-                    let raw_byte = bytes[offset];
-                    let bool_val = raw_byte != 0;
-                    values.push(Value::Bool(bool_val));
-                    offset += 1;
-                }
-                DataType::Text | DataType::VarChar(_) => {
-                    if offset + 4 > bytes.len() {
-                        return Err(miette!("Not enough bytes for string length"));
-                    }
-                    let mut len_bytes = [0u8; 4];
-                    len_bytes.copy_from_slice(&bytes[offset..offset + 4]);
-                    let length = u32::from_le_bytes(len_bytes) as usize;
-                    offset += 4;
+            if needed {
+                values.push(Self::decode_value(
+                    column.data_type,
+                    bytes,
+                    &mut offset,
+                    toast,
+                )?);
+            } else {
+                Self::skip_value(column.data_type, bytes, &mut offset)?;
+            }
+        }
 
-                    if offset + length > bytes.len() {
-                        return Err(miette!("Not enough bytes for string content"));
-                    }
-                    let text_bytes = &bytes[offset..offset + length];
-                    match std::str::from_utf8(text_bytes) {
-                        Ok(text) => {
-                            values.push(Value::Text(text.to_owned()));
-                            offset += length;
-                        }
-                        Err(_) => {
-                            return Err(miette!("Invalid UTF-8 sequence"));
-                        }
-                    }
+        if let Some(mut wanted) = wanted
+            && let Some(idx) = wanted.next()
+        {
+            return Err(miette!("Column {idx} is not part of the schema"));
+        }
+
+        Ok(Row::new(values))
+    }
+
+    fn decode_value(
+        data_type: DataType,
+        bytes: &[u8],
+        offset: &mut usize,
+        toast: &mut impl ToastStore,
+    ) -> Result<Value> {
+        let value = match data_type {
+            DataType::Int64 => {
+                Value::Int64(i64::from_le_bytes(read_fixed(bytes, offset, "integer")?))
+            }
+            DataType::Float64 => {
+                Value::Float64(f64::from_le_bytes(read_fixed(bytes, offset, "float")?))
+            }
+            DataType::Bool => {
+                let [raw_byte] = read_fixed(bytes, offset, "boolean")?;
+                Value::Bool(raw_byte != 0)
+            }
+            DataType::Text | DataType::VarChar(_) => {
+                let text_bytes = toast::read_varlena(bytes, offset, toast)?;
+                match String::from_utf8(text_bytes) {
+                    Ok(text) => Value::Text(text),
+                    Err(_) => return Err(miette!("Invalid UTF-8 sequence")),
+                }
+            }
+            DataType::Int16 => {
+                Value::Int16(i16::from_le_bytes(read_fixed(bytes, offset, "smallint")?))
+            }
+            DataType::Int32 => {
+                Value::Int32(i32::from_le_bytes(read_fixed(bytes, offset, "int32")?))
+            }
+            DataType::Bytea => Value::Bytea(toast::read_varlena(bytes, offset, toast)?),
+            DataType::Uuid => Value::Uuid(u128::from_be_bytes(read_fixed(bytes, offset, "uuid")?)),
+            DataType::Decimal(_, _) => {
+                let (decimal, length) = read_decimal(bytes, *offset)?;
+                *offset += length;
+                Value::Decimal(decimal)
+            }
+            DataType::Timestamp => {
+                Value::Timestamp(i64::from_le_bytes(read_fixed(bytes, offset, "timestamp")?))
+            }
+            DataType::Date => Value::Date(i32::from_le_bytes(read_fixed(bytes, offset, "date")?)),
+            DataType::Interval => {
+                let months = i32::from_le_bytes(read_fixed(bytes, offset, "interval")?);
+                let days = i32::from_le_bytes(read_fixed(bytes, offset, "interval")?);
+                let micros = i64::from_le_bytes(read_fixed(bytes, offset, "interval")?);
+                Value::Interval(Interval::new(months, days, micros))
+            }
+            DataType::Json => {
+                let document = toast::read_varlena(bytes, offset, toast)?;
+                match Json::decode(&document) {
+                    Some((json, read)) if read == document.len() => Value::Json(json),
+                    _ => return Err(miette!("Invalid json document")),
                 }
-                DataType::Timestamp => todo!(),
+            }
+        };
+
+        Ok(value)
+    }
+
+    /// Frees the out-of-line values of a row that was removed from its table.
+    ///
+    /// Only called once nothing can read the row anymore, its pointers into `toast`
+    /// become dangling.
+    pub(crate) fn delete_toasted(&self, bytes: &[u8], toast: &mut impl ToastStore) -> Result<()> {
+        let bitmap = NullBitmap::from_bytes(bytes, self.columns.len())?;
+        let mut offset = bitmap.bytes.len();
+
+        for (idx, column) in self.columns.iter().enumerate() {
+            if bitmap.is_null(idx) {
+                continue;
+            }
+
+            if column.data_type.is_variable_length() {
+                toast::delete_varlena(bytes, &mut offset, toast)?;
+            } else {
+                Self::skip_value(column.data_type, bytes, &mut offset)?;
             }
         }
 
-        Ok(Row::new(values))
+        Ok(())
+    }
+
+    /// Moves `offset` past a value without decoding it.
+    fn skip_value(data_type: DataType, bytes: &[u8], offset: &mut usize) -> Result<()> {
+        let length = match data_type {
+            DataType::Bool => 1,
+            DataType::Int16 => 2,
+            DataType::Int32 | DataType::Date => 4,
+            DataType::Int64 | DataType::Float64 | DataType::Timestamp => 8,
+            DataType::Uuid | DataType::Interval => 16,
+            DataType::Decimal(_, _) => read_decimal(bytes, *offset)?.1,
+            DataType::Text | DataType::VarChar(_) | DataType::Bytea | DataType::Json => {
+                return toast::skip_varlena(bytes, offset);
+            }
+        };
+
+        if *offset + length > bytes.len() {
+            return Err(miette!("Not enough bytes for {data_type} value"));
+        }
+        *offset += length;
+        Ok(())
+    }
+}
+
+/// Reads the numeric value at `offset` and its length, failing on a truncated row.
+fn read_decimal(bytes: &[u8], offset: usize) -> Result<(Decimal, usize)> {
+    bytes
+        .get(offset..)
+        .and_then(Decimal::decode)
+        .ok_or_else(|| miette!("Invalid numeric value"))
+}
+
+/// Reads the `N` bytes of a fixed size value at `offset`.
+fn read_fixed<const N: usize>(bytes: &[u8], offset: &mut usize, what: &str) -> Result<[u8; N]> {
+    let raw = bytes
+        .get(*offset..*offset + N)
+        .ok_or_else(|| miette!("Not enough bytes for {what} value"))?;
+    *offset += N;
+    Ok(raw.try_into().unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::toast::MemoryToast;
+
+    #[test]
+    fn test_encode_decode_values() {
+        let schema = Schema::new(vec![
+            ColumnDef::new("created_at", DataType::Timestamp, false),
+            ColumnDef::new("birthday", DataType::Date, true),
+            ColumnDef::new("timeout", DataType::Interval, false),
+            ColumnDef::new("price", DataType::Decimal(10, 2), false),
+            ColumnDef::new("quantity", DataType::Int16, false),
+            ColumnDef::new("stock", DataType::Int32, false),
+            ColumnDef::new("thumbnail", DataType::Bytea, false),
+            ColumnDef::new("external_id", DataType::Uuid, false),
+        ]);
+        let row = Row::new(vec![
+            Value::Timestamp(-1_234_567),
+            Value::Null,
+            Value::Interval(Interval::new(-1, 2, 3_000_000)),
+            Value::Decimal("-19.99".parse().unwrap()),
+            Value::Int16(-7),
+            Value::Int32(70_000),
+            Value::Bytea(vec![0xDE, 0xAD, 0xBE, 0xEF]),
+            Value::Uuid(0xa0ee_bc99_9c0b_4ef8_bb6d_6bb9_bd38_0a11),
+        ]);
+
+        let mut toast = MemoryToast::default();
+        let bytes = schema.encode_row(&row, &mut toast).unwrap();
+        assert_eq!(
+            schema
+                .decode_columns(&bytes, None, &mut toast)
+                .unwrap()
+                .values,
+            row.values
+        );
+    }
+
+    #[test]
+    fn test_numeric_out_of_range_or_truncated_is_an_error() {
+        let schema = Schema::new(vec![
+            ColumnDef::new("price", DataType::Decimal(5, 2), false),
+            ColumnDef::new("id", DataType::Int64, false),
+        ]);
+        let mut toast = MemoryToast::default();
+
+        let row = Row::new(vec![
+            Value::Decimal("1000".parse().unwrap()),
+            Value::Int64(1),
+        ]);
+        assert!(schema.encode_row(&row, &mut toast).is_err());
+
+        // Truncated rows fail whether the numeric is decoded or skipped
+        let row = Row::new(vec![
+            Value::Decimal("999.99".parse().unwrap()),
+            Value::Int64(1),
+        ]);
+        let bytes = schema.encode_row(&row, &mut toast).unwrap();
+        for length in 0..bytes.len() {
+            let truncated = &bytes[..length];
+            assert!(schema.decode_columns(truncated, None, &mut toast).is_err());
+            assert!(
+                schema
+                    .decode_columns(truncated, Some(&[1]), &mut toast)
+                    .is_err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_integers_are_narrowed_to_the_column_type() {
+        let schema = Schema::new(vec![
+            ColumnDef::new("quantity", DataType::Int16, false),
+            ColumnDef::new("stock", DataType::Int32, false),
+            ColumnDef::new("total", DataType::Int64, false),
+        ]);
+        let mut toast = MemoryToast::default();
+
+        let row = Row::new(vec![
+            Value::Int64(-7),
+            Value::Int64(70_000),
+            Value::Int16(3),
+        ]);
+        let bytes = schema.encode_row(&row, &mut toast).unwrap();
+        assert_eq!(
+            schema
+                .decode_columns(&bytes, None, &mut toast)
+                .unwrap()
+                .values,
+            vec![Value::Int16(-7), Value::Int32(70_000), Value::Int64(3)]
+        );
+
+        let row = Row::new(vec![Value::Int64(40_000), Value::Int64(0), Value::Int64(0)]);
+        assert!(schema.encode_row(&row, &mut toast).is_err());
+        let row = Row::new(vec![Value::Bool(true), Value::Int64(0), Value::Int64(0)]);
+        assert!(schema.encode_row(&row, &mut toast).is_err());
+    }
+
+    #[test]
+    fn test_json_columns_are_validated_and_stored_binary() {
+        let schema = Schema::new(vec![
+            ColumnDef::new("id", DataType::Int64, false),
+            ColumnDef::new("payload", DataType::Json, true),
+        ]);
+
+        let row = Row::new(vec![
+            Value::Int64(1),
+            Value::Text(r#"{"kind": "click", "tags": ["a", "b"]}"#.to_string()),
+        ]);
+        let row = schema.parse_json_columns(row).unwrap();
+        assert_eq!(
+            row.values[1],
+            Value::Json(Json::parse(r#"{"tags": ["a", "b"], "kind": "click"}"#).unwrap())
+        );
+
+        let mut toast = MemoryToast::default();
+        let bytes = schema.encode_row(&row, &mut toast).unwrap();
+        assert_eq!(
+            schema
+                .decode_columns(&bytes, None, &mut toast)
+                .unwrap()
+                .values,
+            row.values
+        );
+
+        let invalid = Row::new(vec![Value::Int64(2), Value::Text("{oops".to_string())]);
+        assert!(schema.parse_json_columns(invalid).is_err());
+    }
+
+    #[test]
+    fn test_large_values_are_toasted() {
+        let schema = Schema::new(vec![
+            ColumnDef::new("id", DataType::Int64, false),
+            ColumnDef::new("body", DataType::Text, false),
+            ColumnDef::new("attachment", DataType::Bytea, true),
+            ColumnDef::new("title", DataType::VarChar(64), false),
+        ]);
+
+        // Incompressible bytes must go out of line, the text compresses well
+        let mut state = 7u32;
+        let attachment = (0..20_000)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (state >> 16) as u8
+            })
+            .collect::<Vec<_>>();
+        let row = Row::new(vec![
+            Value::Int64(1),
+            Value::Text("All work and no play. ".repeat(1_000)),
+            Value::Bytea(attachment.clone()),
+            Value::Text("small".to_string()),
+        ]);
+
+        let mut toast = MemoryToast::default();
+        let bytes = schema.encode_row(&row, &mut toast).unwrap();
+        assert!(bytes.len() <= TOAST_THRESHOLD);
+        assert_eq!(toast.values.len(), 1);
+        assert_eq!(toast.values[0], attachment);
+        assert_eq!(
+            schema
+                .decode_columns(&bytes, None, &mut toast)
+                .unwrap()
+                .values,
+            row.values
+        );
+
+        // Rows under the threshold are stored as they are
+        let small = Row::new(vec![
+            Value::Int64(2),
+            Value::Text("x".repeat(500)),
+            Value::Null,
+            Value::Text("t".to_string()),
+        ]);
+        let bytes = schema.encode_row(&small, &mut toast).unwrap();
+        assert_eq!(bytes.len(), 1 + 8 + 4 + 500 + 4 + 1);
+        assert_eq!(toast.values.len(), 1);
+    }
+    #[test]
+    fn test_decode_some_columns() {
+        let schema = Schema::new(vec![
+            ColumnDef::new("id", DataType::Int64, false),
+            ColumnDef::new("attachment", DataType::Bytea, false),
+            ColumnDef::new("price", DataType::Decimal(10, 2), true),
+            ColumnDef::new("note", DataType::Text, true),
+            ColumnDef::new("title", DataType::Text, false),
+        ]);
+        let mut state = 11u32;
+        let attachment = (0..20_000)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (state >> 16) as u8
+            })
+            .collect::<Vec<_>>();
+        let row = Row::new(vec![
+            Value::Int64(1),
+            Value::Bytea(attachment),
+            Value::Decimal("12.50".parse().unwrap()),
+            Value::Null,
+            Value::Text("report".to_string()),
+        ]);
+
+        let mut toast = MemoryToast::default();
+        let bytes = schema.encode_row(&row, &mut toast).unwrap();
+        assert_eq!(toast.values.len(), 1);
+
+        // Skipped values are never fetched, the store has nothing to return anymore
+        let mut empty = MemoryToast::default();
+        let decoded = schema
+            .decode_columns(&bytes, Some(&[0, 2, 3, 4]), &mut empty)
+            .unwrap();
+        assert_eq!(
+            decoded.values,
+            vec![
+                Value::Int64(1),
+                Value::Decimal("12.50".parse().unwrap()),
+                Value::Null,
+                Value::Text("report".to_string()),
+            ]
+        );
+        assert!(
+            schema
+                .decode_columns(&bytes, Some(&[1]), &mut empty)
+                .is_err()
+        );
+
+        let decoded = schema
+            .decode_columns(&bytes, Some(&[]), &mut empty)
+            .unwrap();
+        assert!(decoded.values.is_empty());
+        assert!(
+            schema
+                .decode_columns(&bytes, Some(&[4, 9]), &mut empty)
+                .is_err()
+        );
     }
 }
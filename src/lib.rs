@@ -1,13 +1,37 @@
 pub(crate) mod core;
 pub(crate) mod db;
+pub(crate) mod server;
 pub(crate) mod sql;
 pub(crate) mod storage;
 
 pub use core::{
+    convert::FromValue,
+    decimal::Decimal,
     error::DatabaseError,
+    json::{Json, JsonNumber},
+    temporal::Interval,
     types::{DataType, Value},
 };
 pub use db::{
-    database::Database,
-    table::{column_def::ColumnDef, row::Row, schema::Schema},
+    catalog::statistics::{ColumnStatistics, TableStatistics},
+    database::{Database, QueryResponse},
+    lock_manager::LockMode,
+    output_format::{OutputFormat, OutputStyle},
+    prepared_statement::PreparedStatement,
+    row_stream::RowStream,
+    session::{Session, SharedDatabase},
+    table::{
+        column_def::ColumnDef,
+        row::{ColumnIndex, FromRow, Row},
+        schema::Schema,
+    },
 };
+pub use server::Server;
+pub use sql::{
+    analyzer::schema::{Field, OutputSchema},
+    functions::{Accumulator, ArgumentTypes, ReturnType, Signature},
+    planner::batch::{Column, ColumnValues, RecordBatch},
+};
+
+#[cfg(feature = "derive")]
+pub use scuttle_db_derive::FromRow;
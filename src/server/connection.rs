@@ -0,0 +1,593 @@
+use std::{
+    collections::HashMap,
+    io::{self, Read, Write},
+};
+
+use crate::{
+    OutputSchema, PreparedStatement, Row, Session, Value,
+    db::database::parse_statement,
+    server::{
+        protocol::{
+            BackendMessage, FieldDescription, FrontendMessage, PROTOCOL_VERSION, StartupMessage,
+            Target, read_message, read_startup,
+        },
+        types::{
+            Format, ServerError, data_type, decode_parameter, encode_value, type_modifier,
+            type_oid, type_size,
+        },
+    },
+    sql::ast::statement::Statement,
+};
+
+/// Output is sent once this much is buffered, even before the client waits for it.
+const SEND_BUFFER_SIZE: usize = 64 * 1024;
+
+/// A statement created by a `Parse` message.
+#[derive(Debug, Clone)]
+enum ParsedStatement {
+    /// A query planned when it is parsed, it can take parameters.
+    Prepared(PreparedStatement),
+
+    /// Any other statement, run from its text as a simple query would be.
+    Unprepared(String),
+
+    /// A query string without any statement.
+    Empty,
+}
+
+/// A statement bound to the values of its parameters by a `Bind` message.
+#[derive(Debug)]
+struct Portal {
+    statement: ParsedStatement,
+    parameters: Vec<Value>,
+    result_formats: Vec<i16>,
+
+    /// The rows not sent yet, once the statement ran.
+    pending: Option<std::vec::IntoIter<Row>>,
+}
+
+/// The state of one client connection: its session, statements and portals.
+pub(crate) struct Connection<W: Write> {
+    session: Session,
+    writer: W,
+    buffer: Vec<u8>,
+    statements: HashMap<String, ParsedStatement>,
+    portals: HashMap<String, Portal>,
+}
+
+impl<W: Write> Connection<W> {
+    pub(crate) fn new(session: Session, writer: W) -> Self {
+        Self {
+            session,
+            writer,
+            buffer: Vec::new(),
+            statements: HashMap::new(),
+            portals: HashMap::new(),
+        }
+    }
+
+    /// Serves the client until it disconnects.
+    ///
+    /// `process_id` identifies the connection to the client, it is not used to cancel
+    /// queries.
+    pub(crate) fn run(mut self, reader: &mut impl Read, process_id: i32) -> io::Result<()> {
+        if !self.start(reader, process_id)? {
+            return Ok(());
+        }
+
+        // After an error, messages of the extended protocol are skipped up to `Sync`
+        let mut failed = false;
+        while let Some(message) = read_message(reader)? {
+            match message {
+                FrontendMessage::Terminate => break,
+                FrontendMessage::Sync => {
+                    failed = false;
+                    self.send(BackendMessage::ReadyForQuery)?;
+                    self.flush()?;
+                }
+                _ if failed => {}
+                FrontendMessage::Query(query) => {
+                    if let Err(error) = self.simple_query(&query)? {
+                        self.send_error(error)?;
+                    }
+                    self.send(BackendMessage::ReadyForQuery)?;
+                    self.flush()?;
+                }
+                FrontendMessage::Flush => self.flush()?,
+                message => {
+                    if let Err(error) = self.extended_query(message)? {
+                        self.send_error(error)?;
+                        failed = true;
+                    }
+                }
+            }
+        }
+
+        self.flush()
+    }
+
+    /// Answers the startup messages, returns whether the client went on to send
+    /// queries.
+    fn start(&mut self, reader: &mut impl Read, process_id: i32) -> io::Result<bool> {
+        loop {
+            match read_startup(reader)? {
+                // Encryption is not supported, the client goes on in plain text
+                StartupMessage::SslRequest | StartupMessage::GssEncRequest => {
+                    self.writer.write_all(b"N")?;
+                    self.writer.flush()?;
+                }
+                StartupMessage::CancelRequest => return Ok(false),
+                StartupMessage::Startup { version, .. } if version != PROTOCOL_VERSION => {
+                    self.send_error(ServerError::new(
+                        "0A000",
+                        format!(
+                            "Unsupported frontend protocol {}.{}",
+                            version >> 16,
+                            version & 0xffff
+                        ),
+                    ))?;
+                    self.flush()?;
+                    return Ok(false);
+                }
+                StartupMessage::Startup { .. } => break,
+            }
+        }
+
+        self.send(BackendMessage::AuthenticationOk)?;
+        for (name, value) in [
+            ("server_version", "16.0"),
+            ("server_encoding", "UTF8"),
+            ("client_encoding", "UTF8"),
+            ("DateStyle", "ISO, MDY"),
+            ("integer_datetimes", "on"),
+            ("standard_conforming_strings", "on"),
+        ] {
+            self.send(BackendMessage::ParameterStatus {
+                name,
+                value: value.to_string(),
+            })?;
+        }
+        self.send(BackendMessage::BackendKeyData {
+            process_id,
+            secret_key: 0,
+        })?;
+        self.send(BackendMessage::ReadyForQuery)?;
+        self.flush()?;
+
+        Ok(true)
+    }
+
+    /// Runs every statement of a `Query` message, stopping at the first error.
+    fn simple_query(&mut self, query: &str) -> io::Result<Result<(), ServerError>> {
+        // A simple query replaces the unnamed statement and portal
+        self.statements.remove("");
+        self.portals.remove("");
+
+        let statements = split_statements(query);
+        if statements.is_empty() {
+            self.send(BackendMessage::EmptyQueryResponse)?;
+        }
+
+        for statement in statements {
+            let response = match self.session.execute_query(statement) {
+                Ok(response) => response,
+                Err(error) => return Ok(Err(error.into())),
+            };
+
+            let formats = vec![Format::Text; response.schema.fields.len()];
+            if !formats.is_empty() {
+                self.send(row_description(&response.schema, &formats))?;
+            }
+            let sent = match self.send_rows(response.rows.into_iter(), &formats, 0)? {
+                Ok(sent) => sent,
+                Err(error) => return Ok(Err(error)),
+            };
+            self.send(BackendMessage::CommandComplete(command_tag(
+                statement, sent,
+            )))?;
+        }
+
+        Ok(Ok(()))
+    }
+
+    /// Handles a message of the extended query protocol.
+    fn extended_query(&mut self, message: FrontendMessage) -> io::Result<Result<(), ServerError>> {
+        match message {
+            FrontendMessage::Parse {
+                name,
+                query,
+                parameter_types,
+            } => match self.parse(name, &query, &parameter_types) {
+                Ok(()) => self.send(BackendMessage::ParseComplete)?,
+                Err(error) => return Ok(Err(error)),
+            },
+            FrontendMessage::Bind {
+                portal,
+                statement,
+                parameter_formats,
+                parameters,
+                result_formats,
+            } => match self.bind(
+                portal,
+                &statement,
+                &parameter_formats,
+                parameters,
+                result_formats,
+            ) {
+                Ok(()) => self.send(BackendMessage::BindComplete)?,
+                Err(error) => return Ok(Err(error)),
+            },
+            FrontendMessage::Describe { target, name } => {
+                let described = match target {
+                    Target::Statement => self.describe_statement(&name),
+                    Target::Portal => self.describe_portal(&name),
+                };
+                match described {
+                    Ok(messages) => {
+                        for message in messages {
+                            self.send(message)?;
+                        }
+                    }
+                    Err(error) => return Ok(Err(error)),
+                }
+            }
+            FrontendMessage::Execute { portal, max_rows } => {
+                return self.execute(&portal, max_rows);
+            }
+            FrontendMessage::Close { target, name } => {
+                match target {
+                    Target::Statement => self.statements.remove(&name).map(drop),
+                    Target::Portal => self.portals.remove(&name).map(drop),
+                };
+                self.send(BackendMessage::CloseComplete)?;
+            }
+            FrontendMessage::Query(_)
+            | FrontendMessage::Sync
+            | FrontendMessage::Flush
+            | FrontendMessage::Terminate => unreachable!("handled by the connection loop"),
+        }
+
+        Ok(Ok(()))
+    }
+
+    fn parse(&mut self, name: String, query: &str, type_oids: &[u32]) -> Result<(), ServerError> {
+        if !name.is_empty() && self.statements.contains_key(&name) {
+            return Err(ServerError::new(
+                "42P05",
+                format!("Prepared statement \"{name}\" already exists"),
+            ));
+        }
+
+        // Types are fixed up to the first one left unspecified, the rest are inferred
+        let parameter_types = type_oids
+            .iter()
+            .map_while(|oid| data_type(*oid))
+            .collect::<Vec<_>>();
+
+        let statement = if split_statements(query).is_empty() {
+            ParsedStatement::Empty
+        } else if let Statement::Select(_) = parse_statement(query)? {
+            ParsedStatement::Prepared(self.session.prepare(query, &parameter_types)?)
+        } else if type_oids.is_empty() {
+            ParsedStatement::Unprepared(query.to_string())
+        } else {
+            return Err(ServerError::new(
+                "0A000",
+                "Only SELECT statements can take parameters",
+            ));
+        };
+
+        self.statements.insert(name, statement);
+        Ok(())
+    }
+
+    fn bind(
+        &mut self,
+        portal: String,
+        statement: &str,
+        parameter_formats: &[i16],
+        parameters: Vec<Option<Vec<u8>>>,
+        result_formats: Vec<i16>,
+    ) -> Result<(), ServerError> {
+        if !portal.is_empty() && self.portals.contains_key(&portal) {
+            return Err(ServerError::new(
+                "42P03",
+                format!("Portal \"{portal}\" already exists"),
+            ));
+        }
+
+        let statement = self.statement(statement)?.clone();
+        let parameter_types = match &statement {
+            ParsedStatement::Prepared(prepared) => prepared.parameter_types(),
+            ParsedStatement::Unprepared(_) | ParsedStatement::Empty => &[],
+        };
+        if parameters.len() != parameter_types.len() {
+            return Err(ServerError::protocol(format!(
+                "Bind message supplies {} parameters, but the statement requires {}",
+                parameters.len(),
+                parameter_types.len()
+            )));
+        }
+
+        let formats = Format::for_values(parameter_formats, parameters.len())?;
+        let parameters = parameters
+            .into_iter()
+            .zip(formats)
+            .zip(parameter_types)
+            .map(|((bytes, format), data_type)| match bytes {
+                Some(bytes) => decode_parameter(&bytes, format, *data_type),
+                None => Ok(Value::Null),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        // Checked now, the number of columns is not known before the statement runs
+        for code in &result_formats {
+            Format::from_code(*code)?;
+        }
+
+        self.portals.insert(
+            portal,
+            Portal {
+                statement,
+                parameters,
+                result_formats,
+                pending: None,
+            },
+        );
+        Ok(())
+    }
+
+    fn describe_statement(&self, name: &str) -> Result<Vec<BackendMessage>, ServerError> {
+        Ok(match self.statement(name)? {
+            ParsedStatement::Prepared(prepared) => {
+                let type_oids = prepared
+                    .parameter_types()
+                    .iter()
+                    .map(|data_type| type_oid(*data_type))
+                    .collect();
+                vec![
+                    BackendMessage::ParameterDescription(type_oids),
+                    // The formats of the results are not known before they are bound
+                    describe_rows(prepared.schema(), &[]),
+                ]
+            }
+            ParsedStatement::Unprepared(_) | ParsedStatement::Empty => vec![
+                BackendMessage::ParameterDescription(Vec::new()),
+                BackendMessage::NoData,
+            ],
+        })
+    }
+
+    fn describe_portal(&self, name: &str) -> Result<Vec<BackendMessage>, ServerError> {
+        let portal = self.portal(name)?;
+        Ok(vec![match &portal.statement {
+            ParsedStatement::Prepared(prepared) => {
+                describe_rows(prepared.schema(), &portal.result_formats)
+            }
+            ParsedStatement::Unprepared(_) | ParsedStatement::Empty => BackendMessage::NoData,
+        }])
+    }
+
+    /// Runs a portal, or resumes it, sending at most `max_rows` rows if it is positive.
+    fn execute(&mut self, name: &str, max_rows: i32) -> io::Result<Result<(), ServerError>> {
+        let Some(mut portal) = self.portals.remove(name) else {
+            return Ok(Err(unknown_portal(name)));
+        };
+        let result = self.run_portal(&mut portal, max_rows);
+        self.portals.insert(name.to_string(), portal);
+        result
+    }
+
+    fn run_portal(
+        &mut self,
+        portal: &mut Portal,
+        max_rows: i32,
+    ) -> io::Result<Result<(), ServerError>> {
+        let rows = match (portal.pending.take(), &portal.statement) {
+            (Some(rows), _) => Ok(rows),
+            (None, ParsedStatement::Prepared(prepared)) => self
+                .session
+                .execute(prepared, &portal.parameters)
+                .map(|response| response.rows.into_iter()),
+            (None, ParsedStatement::Unprepared(query)) => self
+                .session
+                .execute_query(query)
+                .map(|response| response.rows.into_iter()),
+            (None, ParsedStatement::Empty) => {
+                self.send(BackendMessage::EmptyQueryResponse)?;
+                return Ok(Ok(()));
+            }
+        };
+        let mut rows = match rows {
+            Ok(rows) => rows,
+            Err(error) => return Ok(Err(error.into())),
+        };
+
+        let (column_count, query) = match &portal.statement {
+            ParsedStatement::Prepared(prepared) => (prepared.schema().fields.len(), "SELECT"),
+            ParsedStatement::Unprepared(query) => (
+                rows.as_slice().first().map_or(0, |row| row.values.len()),
+                query.as_str(),
+            ),
+            ParsedStatement::Empty => (0, ""),
+        };
+        let formats = match Format::for_values(&portal.result_formats, column_count) {
+            Ok(formats) => formats,
+            Err(error) => return Ok(Err(error)),
+        };
+
+        let limit = usize::try_from(max_rows).unwrap_or(0);
+        let sent = match self.send_rows(rows.by_ref(), &formats, limit)? {
+            Ok(sent) => sent,
+            Err(error) => return Ok(Err(error)),
+        };
+
+        if rows.as_slice().is_empty() {
+            self.send(BackendMessage::CommandComplete(command_tag(query, sent)))?;
+        } else {
+            self.send(BackendMessage::PortalSuspended)?;
+        }
+        portal.pending = Some(rows);
+
+        Ok(Ok(()))
+    }
+
+    fn statement(&self, name: &str) -> Result<&ParsedStatement, ServerError> {
+        self.statements.get(name).ok_or_else(|| {
+            ServerError::new(
+                "26000",
+                format!("Prepared statement \"{name}\" does not exist"),
+            )
+        })
+    }
+
+    fn portal(&self, name: &str) -> Result<&Portal, ServerError> {
+        self.portals.get(name).ok_or_else(|| unknown_portal(name))
+    }
+
+    /// Sends rows as `DataRow` messages, at most `limit` of them if it is not 0, and
+    /// returns how many were sent.
+    fn send_rows(
+        &mut self,
+        rows: impl Iterator<Item = Row>,
+        formats: &[Format],
+        limit: usize,
+    ) -> io::Result<Result<usize, ServerError>> {
+        let limit = if limit == 0 { usize::MAX } else { limit };
+
+        let mut sent = 0;
+        for row in rows.take(limit) {
+            let values = row
+                .values
+                .iter()
+                .zip(formats)
+                .map(|(value, format)| match value {
+                    Value::Null => Ok(None),
+                    value => encode_value(value, *format).map(Some),
+                })
+                .collect::<Result<_, _>>();
+            match values {
+                Ok(values) => self.send(BackendMessage::DataRow(values))?,
+                Err(error) => return Ok(Err(error)),
+            }
+            sent += 1;
+        }
+
+        Ok(Ok(sent))
+    }
+
+    fn send_error(&mut self, error: ServerError) -> io::Result<()> {
+        self.send(BackendMessage::ErrorResponse {
+            code: error.code,
+            message: error.message,
+        })
+    }
+
+    fn send(&mut self, message: BackendMessage) -> io::Result<()> {
+        message.encode(&mut self.buffer);
+        if self.buffer.len() >= SEND_BUFFER_SIZE {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.write_all(&self.buffer)?;
+        self.buffer.clear();
+        self.writer.flush()
+    }
+}
+
+fn unknown_portal(name: &str) -> ServerError {
+    ServerError::new("34000", format!("Portal \"{name}\" does not exist"))
+}
+
+/// Describes the rows of a statement, sent in the given formats.
+fn describe_rows(schema: &OutputSchema, format_codes: &[i16]) -> BackendMessage {
+    if schema.fields.is_empty() {
+        return BackendMessage::NoData;
+    }
+
+    // The codes were checked when the portal was bound
+    let formats = Format::for_values(format_codes, schema.fields.len())
+        .unwrap_or_else(|_| vec![Format::Text; schema.fields.len()]);
+    row_description(schema, &formats)
+}
+
+fn row_description(schema: &OutputSchema, formats: &[Format]) -> BackendMessage {
+    BackendMessage::RowDescription(
+        schema
+            .fields
+            .iter()
+            .zip(formats)
+            .map(|(field, format)| FieldDescription {
+                name: field.output_name().to_string(),
+                type_oid: type_oid(field.data_type),
+                type_size: type_size(field.data_type),
+                type_modifier: type_modifier(field.data_type),
+                format: format.code(),
+            })
+            .collect(),
+    )
+}
+
+/// The tag of `CommandComplete`: the command, with the number of rows it returned.
+fn command_tag(query: &str, rows: usize) -> String {
+    let command = query
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_uppercase();
+
+    match command.as_str() {
+        "SELECT" | "EXECUTE" => format!("SELECT {rows}"),
+        _ => command,
+    }
+}
+
+/// Splits a query string into its statements, on semicolons outside of quotes.
+fn split_statements(query: &str) -> Vec<&str> {
+    let mut statements = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+
+    for (index, char) in query.char_indices() {
+        match (quote, char) {
+            (None, '\'' | '"') => quote = Some(char),
+            (Some(open), _) if open == char => quote = None,
+            (None, ';') => {
+                statements.push(&query[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    statements.push(&query[start..]);
+
+    statements
+        .into_iter()
+        .map(str::trim)
+        .filter(|statement| !statement.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_statements() {
+        assert_eq!(
+            split_statements("SELECT 1; SELECT ';' AS \"a;b\";;"),
+            vec!["SELECT 1", "SELECT ';' AS \"a;b\""]
+        );
+        assert!(split_statements(" ; ").is_empty());
+    }
+
+    #[test]
+    fn test_command_tag() {
+        assert_eq!(command_tag("select * from users", 3), "SELECT 3");
+        assert_eq!(command_tag("EXECUTE by_id (1)", 1), "SELECT 1");
+        assert_eq!(command_tag("vacuum users", 0), "VACUUM");
+    }
+}
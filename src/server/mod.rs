@@ -0,0 +1,302 @@
+pub(crate) mod connection;
+pub(crate) mod protocol;
+pub(crate) mod types;
+
+use std::{
+    io::{self, BufReader, BufWriter, Read, Write},
+    net::{TcpListener, TcpStream},
+    sync::{
+        Arc,
+        atomic::{AtomicI32, Ordering},
+    },
+};
+
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+
+use crate::{SharedDatabase, server::connection::Connection};
+
+/// Serves a [`SharedDatabase`] to PostgreSQL clients, such as `psql` or a driver,
+/// over version 3 of the PostgreSQL protocol.
+///
+/// Every connection runs on its own thread with its own [`Session`](crate::Session).
+/// Clients are trusted: there is no authentication and no encryption. Both the simple
+/// and the extended query protocol are supported, statements other than SELECT are
+/// run from their text and take no parameters.
+#[derive(Debug, Clone)]
+pub struct Server {
+    database: SharedDatabase,
+    next_process_id: Arc<AtomicI32>,
+}
+
+impl Server {
+    pub fn new(database: SharedDatabase) -> Self {
+        Self {
+            database,
+            next_process_id: Arc::new(AtomicI32::new(1)),
+        }
+    }
+
+    /// Accepts TCP connections until the listener fails.
+    pub fn serve_tcp(&self, listener: TcpListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let server = self.clone();
+            std::thread::spawn(move || {
+                stream.set_nodelay(true).ok();
+                server.log_error(server.serve_tcp_stream(stream));
+            });
+        }
+        Ok(())
+    }
+
+    /// Accepts connections on a Unix domain socket until the listener fails.
+    #[cfg(unix)]
+    pub fn serve_unix(&self, listener: UnixListener) -> io::Result<()> {
+        for stream in listener.incoming() {
+            let stream = stream?;
+            let server = self.clone();
+            std::thread::spawn(move || server.log_error(server.serve_unix_stream(stream)));
+        }
+        Ok(())
+    }
+
+    /// Serves one client until it disconnects, reading its messages from `reader` and
+    /// writing the answers to `writer`.
+    pub fn serve_connection(&self, mut reader: impl Read, writer: impl Write) -> io::Result<()> {
+        let process_id = self.next_process_id.fetch_add(1, Ordering::Relaxed);
+        Connection::new(self.database.session(), writer).run(&mut reader, process_id)
+    }
+
+    fn serve_tcp_stream(&self, stream: TcpStream) -> io::Result<()> {
+        let reader = BufReader::new(stream.try_clone()?);
+        self.serve_connection(reader, BufWriter::new(stream))
+    }
+
+    #[cfg(unix)]
+    fn serve_unix_stream(&self, stream: UnixStream) -> io::Result<()> {
+        let reader = BufReader::new(stream.try_clone()?);
+        self.serve_connection(reader, BufWriter::new(stream))
+    }
+
+    /// Connections end on errors of their own, they do not stop the server.
+    fn log_error(&self, result: io::Result<()>) {
+        if let Err(error) = result {
+            eprintln!("Connection failed: {error}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Shutdown;
+
+    use super::*;
+    use crate::{ColumnDef, DataType, Database, Row, Schema, Value};
+
+    /// A client speaking just enough of the protocol to check the server's answers.
+    struct Client {
+        stream: TcpStream,
+    }
+
+    impl Client {
+        fn connect(server: &Server) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+            let address = listener.local_addr().unwrap();
+            let server = server.clone();
+            std::thread::spawn(move || server.serve_tcp(listener));
+
+            let mut client = Self {
+                stream: TcpStream::connect(address).unwrap(),
+            };
+
+            let mut startup = protocol::PROTOCOL_VERSION.to_be_bytes().to_vec();
+            startup.extend_from_slice(b"user\0test\0\0");
+            let mut length = (startup.len() as i32 + 4).to_be_bytes().to_vec();
+            length.extend_from_slice(&startup);
+            client.stream.write_all(&length).unwrap();
+
+            let messages = client.read_until_ready();
+            assert_eq!(messages[0], (b'R', 0i32.to_be_bytes().to_vec()));
+            assert!(messages.iter().any(|(tag, _)| *tag == b'K'));
+            client
+        }
+
+        fn send(&mut self, tag: u8, body: &[u8]) {
+            let mut message = vec![tag];
+            message.extend_from_slice(&(body.len() as i32 + 4).to_be_bytes());
+            message.extend_from_slice(body);
+            self.stream.write_all(&message).unwrap();
+        }
+
+        fn read(&mut self) -> (u8, Vec<u8>) {
+            let mut header = [0; 5];
+            self.stream.read_exact(&mut header).unwrap();
+            let length = i32::from_be_bytes(header[1..].try_into().unwrap()) as usize;
+            let mut body = vec![0; length - 4];
+            self.stream.read_exact(&mut body).unwrap();
+            (header[0], body)
+        }
+
+        fn read_until_ready(&mut self) -> Vec<(u8, Vec<u8>)> {
+            let mut messages = Vec::new();
+            loop {
+                let message = self.read();
+                if message.0 == b'Z' {
+                    return messages;
+                }
+                messages.push(message);
+            }
+        }
+    }
+
+    fn server(name: &str) -> Server {
+        let directory = std::env::temp_dir().join("scuttle_server_tests").join(name);
+        std::fs::remove_dir_all(&directory).ok();
+
+        let shared = SharedDatabase::new(Database::new(&directory));
+        let mut session = shared.session();
+        let schema = Schema::new(vec![
+            ColumnDef::new("id", DataType::Int32, false),
+            ColumnDef::new("name", DataType::Text, true),
+        ]);
+        session.create_table("users", schema).unwrap();
+        for (id, name) in [(1, Value::Text("Alice".to_string())), (2, Value::Null)] {
+            let row = Row::new(vec![Value::Int32(id), name]);
+            session.insert_row("users", row).unwrap();
+        }
+        Server::new(shared)
+    }
+
+    /// The values of a `DataRow` message, as text.
+    fn data_row(body: &[u8]) -> Vec<Option<String>> {
+        let count = i16::from_be_bytes([body[0], body[1]]);
+        let mut position = 2;
+        (0..count)
+            .map(|_| {
+                let length = i32::from_be_bytes(body[position..position + 4].try_into().unwrap());
+                position += 4;
+                (length >= 0).then(|| {
+                    let value = &body[position..position + length as usize];
+                    position += length as usize;
+                    String::from_utf8_lossy(value).to_string()
+                })
+            })
+            .collect()
+    }
+
+    /// The fields of an `ErrorResponse` message by type.
+    fn error_fields(body: &[u8]) -> Vec<(u8, String)> {
+        body.split(|byte| *byte == 0)
+            .filter(|field| !field.is_empty())
+            .map(|field| (field[0], String::from_utf8_lossy(&field[1..]).to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_simple_query() {
+        let mut client = Client::connect(&server("simple"));
+        client.send(
+            b'Q',
+            b"SELECT id, name FROM users ORDER BY id; SELECT count(*) FROM users\0",
+        );
+
+        let messages = client.read_until_ready();
+        let tags = messages.iter().map(|(tag, _)| *tag).collect::<Vec<_>>();
+        assert_eq!(tags, b"TDDCTDC");
+
+        // Two columns, the first is named id and typed int4
+        let description = &messages[0].1;
+        assert_eq!(&description[..5], b"\0\x02id\0");
+        assert_eq!(&description[11..15], &23u32.to_be_bytes());
+
+        assert_eq!(
+            data_row(&messages[1].1),
+            vec![Some("1".to_string()), Some("Alice".to_string())]
+        );
+        assert_eq!(data_row(&messages[2].1), vec![Some("2".to_string()), None]);
+        assert_eq!(messages[3].1, b"SELECT 2\0");
+        assert_eq!(data_row(&messages[5].1), vec![Some("2".to_string())]);
+    }
+
+    #[test]
+    fn test_errors_carry_sqlstate() {
+        let mut client = Client::connect(&server("errors"));
+        client.send(b'Q', b"SELECT * FROM missing; SELECT 1\0");
+
+        let messages = client.read_until_ready();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].0, b'E');
+        let fields = error_fields(&messages[0].1);
+        assert!(fields.contains(&(b'C', "42P01".to_string())), "{fields:?}");
+
+        client.send(b'Q', b" ; \0");
+        assert_eq!(client.read_until_ready(), vec![(b'I', Vec::new())]);
+    }
+
+    #[test]
+    fn test_extended_query() {
+        let mut client = Client::connect(&server("extended"));
+
+        // Parse with $1 typed int4, bind 2 in binary, fetch one row at a time
+        let mut parse = b"by_id\0SELECT name FROM users WHERE id >= $1 ORDER BY id\0".to_vec();
+        parse.extend_from_slice(&1i16.to_be_bytes());
+        parse.extend_from_slice(&23u32.to_be_bytes());
+        client.send(b'P', &parse);
+        client.send(b'D', b"Sby_id\0");
+
+        let mut bind = b"\0by_id\0".to_vec();
+        bind.extend_from_slice(&1i16.to_be_bytes());
+        bind.extend_from_slice(&1i16.to_be_bytes());
+        bind.extend_from_slice(&1i16.to_be_bytes());
+        bind.extend_from_slice(&4i32.to_be_bytes());
+        bind.extend_from_slice(&1i32.to_be_bytes());
+        bind.extend_from_slice(&0i16.to_be_bytes());
+        client.send(b'B', &bind);
+
+        let mut execute = b"\0".to_vec();
+        execute.extend_from_slice(&1i32.to_be_bytes());
+        client.send(b'E', &execute);
+        client.send(b'E', &execute);
+        client.send(b'E', &execute);
+        client.send(b'S', b"");
+
+        let messages = client.read_until_ready();
+        let tags = messages.iter().map(|(tag, _)| *tag).collect::<Vec<_>>();
+        assert_eq!(tags, b"1tT2DsDCC");
+        assert_eq!(
+            messages[1].1,
+            [&1i16.to_be_bytes()[..], &23u32.to_be_bytes()].concat()
+        );
+        assert_eq!(data_row(&messages[4].1), vec![Some("Alice".to_string())]);
+        assert_eq!(data_row(&messages[6].1), vec![None]);
+        assert_eq!(messages[7].1, b"SELECT 1\0");
+        assert_eq!(messages[8].1, b"SELECT 0\0");
+
+        // After an error, messages are skipped up to Sync
+        client.send(b'P', b"\0SELECT nope FROM users\0\0\0");
+        client.send(b'B', b"\0\0\0\0\0\0\0\0");
+        client.send(b'S', b"");
+        let messages = client.read_until_ready();
+        assert_eq!(messages.len(), 1);
+        let fields = error_fields(&messages[0].1);
+        assert!(fields.contains(&(b'C', "42703".to_string())), "{fields:?}");
+
+        // A text parameter that is not valid for its type fails the Bind
+        let mut bind = b"\0by_id\0\0\0".to_vec();
+        bind.extend_from_slice(&1i16.to_be_bytes());
+        bind.extend_from_slice(&3i32.to_be_bytes());
+        bind.extend_from_slice(b"abc");
+        bind.extend_from_slice(&0i16.to_be_bytes());
+        client.send(b'B', &bind);
+        client.send(b'S', b"");
+        let messages = client.read_until_ready();
+        assert_eq!(messages.len(), 1);
+        let fields = error_fields(&messages[0].1);
+        assert!(fields.contains(&(b'C', "22P02".to_string())), "{fields:?}");
+
+        client.send(b'X', b"");
+        client.stream.shutdown(Shutdown::Write).unwrap();
+        assert_eq!(client.stream.read(&mut [0; 1]).unwrap(), 0);
+    }
+}
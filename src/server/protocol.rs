@@ -0,0 +1,485 @@
+use std::{
+    collections::HashMap,
+    io::{self, Read},
+};
+
+/// Version 3.0 of the protocol, the only one supported.
+pub(crate) const PROTOCOL_VERSION: i32 = 196_608;
+
+const CANCEL_REQUEST_CODE: i32 = 80_877_102;
+const SSL_REQUEST_CODE: i32 = 80_877_103;
+const GSSENC_REQUEST_CODE: i32 = 80_877_104;
+
+/// Startup packets and frontend messages larger than this are refused.
+const MAX_MESSAGE_LENGTH: usize = 1 << 30;
+
+/// The first message of a connection, sent without a type byte.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum StartupMessage {
+    Startup {
+        version: i32,
+        parameters: HashMap<String, String>,
+    },
+    SslRequest,
+    GssEncRequest,
+    CancelRequest,
+}
+
+/// Whether a `Describe` or `Close` message is about a statement or a portal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Target {
+    Statement,
+    Portal,
+}
+
+/// A message sent by the client after startup.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum FrontendMessage {
+    /// A string of statements separated by semicolons, run one after the other.
+    Query(String),
+    Parse {
+        name: String,
+        query: String,
+        parameter_types: Vec<u32>,
+    },
+    Bind {
+        portal: String,
+        statement: String,
+        parameter_formats: Vec<i16>,
+        parameters: Vec<Option<Vec<u8>>>,
+        result_formats: Vec<i16>,
+    },
+    Describe {
+        target: Target,
+        name: String,
+    },
+    Execute {
+        portal: String,
+        max_rows: i32,
+    },
+    Close {
+        target: Target,
+        name: String,
+    },
+    Sync,
+    Flush,
+    Terminate,
+}
+
+/// Reads the first message of a connection.
+pub(crate) fn read_startup(reader: &mut impl Read) -> io::Result<StartupMessage> {
+    let length = read_length(reader, 4)?;
+    let mut body = MessageBody::read(reader, length)?;
+
+    Ok(match body.get_i32()? {
+        SSL_REQUEST_CODE => StartupMessage::SslRequest,
+        GSSENC_REQUEST_CODE => StartupMessage::GssEncRequest,
+        CANCEL_REQUEST_CODE => StartupMessage::CancelRequest,
+        version => {
+            let mut parameters = HashMap::new();
+            loop {
+                let name = body.get_cstring()?;
+                if name.is_empty() {
+                    break;
+                }
+                parameters.insert(name, body.get_cstring()?);
+            }
+            StartupMessage::Startup {
+                version,
+                parameters,
+            }
+        }
+    })
+}
+
+/// Reads the next message, `None` if the client closed the connection.
+pub(crate) fn read_message(reader: &mut impl Read) -> io::Result<Option<FrontendMessage>> {
+    let mut tag = [0; 1];
+    if let Err(error) = reader.read_exact(&mut tag) {
+        return match error.kind() {
+            io::ErrorKind::UnexpectedEof => Ok(None),
+            _ => Err(error),
+        };
+    }
+
+    let length = read_length(reader, 0)?;
+    let mut body = MessageBody::read(reader, length)?;
+
+    let message = match tag[0] {
+        b'Q' => FrontendMessage::Query(body.get_cstring()?),
+        b'P' => {
+            let name = body.get_cstring()?;
+            let query = body.get_cstring()?;
+            let count = body.get_count()?;
+            let parameter_types = (0..count)
+                .map(|_| body.get_i32().map(|oid| oid as u32))
+                .collect::<io::Result<_>>()?;
+            FrontendMessage::Parse {
+                name,
+                query,
+                parameter_types,
+            }
+        }
+        b'B' => {
+            let portal = body.get_cstring()?;
+            let statement = body.get_cstring()?;
+            let parameter_formats = body.get_formats()?;
+            let count = body.get_count()?;
+            let parameters = (0..count)
+                .map(|_| match body.get_i32()? {
+                    -1 => Ok(None),
+                    length => body.get_bytes(length).map(Some),
+                })
+                .collect::<io::Result<_>>()?;
+            let result_formats = body.get_formats()?;
+            FrontendMessage::Bind {
+                portal,
+                statement,
+                parameter_formats,
+                parameters,
+                result_formats,
+            }
+        }
+        b'D' => FrontendMessage::Describe {
+            target: body.get_target()?,
+            name: body.get_cstring()?,
+        },
+        b'E' => FrontendMessage::Execute {
+            portal: body.get_cstring()?,
+            max_rows: body.get_i32()?,
+        },
+        b'C' => FrontendMessage::Close {
+            target: body.get_target()?,
+            name: body.get_cstring()?,
+        },
+        b'S' => FrontendMessage::Sync,
+        b'H' => FrontendMessage::Flush,
+        b'X' => FrontendMessage::Terminate,
+        tag => {
+            return Err(invalid_data(format!(
+                "unsupported message type {:?}",
+                tag as char
+            )));
+        }
+    };
+
+    Ok(Some(message))
+}
+
+/// Reads the length that starts a message and returns the length of its body.
+///
+/// `consumed` bytes of the body were already read along with the length.
+fn read_length(reader: &mut impl Read, consumed: usize) -> io::Result<usize> {
+    let mut length = [0; 4];
+    reader.read_exact(&mut length)?;
+
+    let length = i32::from_be_bytes(length);
+    match usize::try_from(length) {
+        Ok(length) if (4 + consumed..=MAX_MESSAGE_LENGTH).contains(&length) => Ok(length - 4),
+        _ => Err(invalid_data(format!("invalid message length {length}"))),
+    }
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// The body of a frontend message, read field by field.
+struct MessageBody {
+    bytes: Vec<u8>,
+    position: usize,
+}
+
+impl MessageBody {
+    fn read(reader: &mut impl Read, length: usize) -> io::Result<Self> {
+        let mut bytes = vec![0; length];
+        reader.read_exact(&mut bytes)?;
+        Ok(Self { bytes, position: 0 })
+    }
+
+    fn take(&mut self, length: usize) -> io::Result<&[u8]> {
+        let end = self.position + length;
+        let bytes = self
+            .bytes
+            .get(self.position..end)
+            .ok_or_else(|| invalid_data("message is too short".to_string()))?;
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn get_u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn get_i16(&mut self) -> io::Result<i16> {
+        Ok(i16::from_be_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn get_i32(&mut self) -> io::Result<i32> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    /// A count of the items that follow.
+    fn get_count(&mut self) -> io::Result<usize> {
+        let count = self.get_i16()?;
+        usize::try_from(count).map_err(|_| invalid_data(format!("invalid count {count}")))
+    }
+
+    fn get_bytes(&mut self, length: i32) -> io::Result<Vec<u8>> {
+        let length = usize::try_from(length)
+            .map_err(|_| invalid_data(format!("invalid value length {length}")))?;
+        Ok(self.take(length)?.to_vec())
+    }
+
+    /// A null-terminated string.
+    fn get_cstring(&mut self) -> io::Result<String> {
+        let rest = &self.bytes[self.position..];
+        let end = rest
+            .iter()
+            .position(|byte| *byte == 0)
+            .ok_or_else(|| invalid_data("string is not terminated".to_string()))?;
+        let string = String::from_utf8(rest[..end].to_vec())
+            .map_err(|_| invalid_data("string is not valid UTF-8".to_string()))?;
+        self.position += end + 1;
+        Ok(string)
+    }
+
+    /// Format codes, 0 for text and 1 for binary.
+    fn get_formats(&mut self) -> io::Result<Vec<i16>> {
+        let count = self.get_count()?;
+        (0..count).map(|_| self.get_i16()).collect()
+    }
+
+    fn get_target(&mut self) -> io::Result<Target> {
+        match self.get_u8()? {
+            b'S' => Ok(Target::Statement),
+            b'P' => Ok(Target::Portal),
+            target => Err(invalid_data(format!("invalid target {:?}", target as char))),
+        }
+    }
+}
+
+/// A column of a `RowDescription` message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct FieldDescription {
+    pub name: String,
+    pub type_oid: u32,
+    pub type_size: i16,
+    pub type_modifier: i32,
+    pub format: i16,
+}
+
+/// A message sent by the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum BackendMessage {
+    AuthenticationOk,
+    ParameterStatus {
+        name: &'static str,
+        value: String,
+    },
+    BackendKeyData {
+        process_id: i32,
+        secret_key: i32,
+    },
+    /// The server waits for a new query, outside of any transaction.
+    ReadyForQuery,
+    RowDescription(Vec<FieldDescription>),
+    ParameterDescription(Vec<u32>),
+    NoData,
+    /// A row, every value already encoded in the format asked for.
+    DataRow(Vec<Option<Vec<u8>>>),
+    CommandComplete(String),
+    EmptyQueryResponse,
+    ErrorResponse {
+        code: &'static str,
+        message: String,
+    },
+    ParseComplete,
+    BindComplete,
+    CloseComplete,
+    PortalSuspended,
+}
+
+impl BackendMessage {
+    /// Appends the message to `buffer`.
+    pub(crate) fn encode(&self, buffer: &mut Vec<u8>) {
+        let (tag, body) = match self {
+            BackendMessage::AuthenticationOk => (b'R', 0i32.to_be_bytes().to_vec()),
+            BackendMessage::ParameterStatus { name, value } => {
+                let mut body = Vec::new();
+                put_cstring(&mut body, name);
+                put_cstring(&mut body, value);
+                (b'S', body)
+            }
+            BackendMessage::BackendKeyData {
+                process_id,
+                secret_key,
+            } => {
+                let mut body = process_id.to_be_bytes().to_vec();
+                body.extend_from_slice(&secret_key.to_be_bytes());
+                (b'K', body)
+            }
+            BackendMessage::ReadyForQuery => (b'Z', vec![b'I']),
+            BackendMessage::RowDescription(fields) => {
+                let mut body = (fields.len() as i16).to_be_bytes().to_vec();
+                for field in fields {
+                    put_cstring(&mut body, &field.name);
+                    // Not a column of a table: no table OID or attribute number
+                    body.extend_from_slice(&0i32.to_be_bytes());
+                    body.extend_from_slice(&0i16.to_be_bytes());
+                    body.extend_from_slice(&field.type_oid.to_be_bytes());
+                    body.extend_from_slice(&field.type_size.to_be_bytes());
+                    body.extend_from_slice(&field.type_modifier.to_be_bytes());
+                    body.extend_from_slice(&field.format.to_be_bytes());
+                }
+                (b'T', body)
+            }
+            BackendMessage::ParameterDescription(type_oids) => {
+                let mut body = (type_oids.len() as i16).to_be_bytes().to_vec();
+                for oid in type_oids {
+                    body.extend_from_slice(&oid.to_be_bytes());
+                }
+                (b't', body)
+            }
+            BackendMessage::NoData => (b'n', Vec::new()),
+            BackendMessage::DataRow(values) => {
+                let mut body = (values.len() as i16).to_be_bytes().to_vec();
+                for value in values {
+                    match value {
+                        Some(bytes) => {
+                            body.extend_from_slice(&(bytes.len() as i32).to_be_bytes());
+                            body.extend_from_slice(bytes);
+                        }
+                        None => body.extend_from_slice(&(-1i32).to_be_bytes()),
+                    }
+                }
+                (b'D', body)
+            }
+            BackendMessage::CommandComplete(tag) => {
+                let mut body = Vec::new();
+                put_cstring(&mut body, tag);
+                (b'C', body)
+            }
+            BackendMessage::EmptyQueryResponse => (b'I', Vec::new()),
+            BackendMessage::ErrorResponse { code, message } => {
+                let mut body = Vec::new();
+                for (field, value) in [
+                    (b'S', "ERROR"),
+                    (b'V', "ERROR"),
+                    (b'C', code),
+                    (b'M', message.as_str()),
+                ] {
+                    body.push(field);
+                    put_cstring(&mut body, value);
+                }
+                body.push(0);
+                (b'E', body)
+            }
+            BackendMessage::ParseComplete => (b'1', Vec::new()),
+            BackendMessage::BindComplete => (b'2', Vec::new()),
+            BackendMessage::CloseComplete => (b'3', Vec::new()),
+            BackendMessage::PortalSuspended => (b's', Vec::new()),
+        };
+
+        buffer.push(tag);
+        buffer.extend_from_slice(&(body.len() as i32 + 4).to_be_bytes());
+        buffer.extend_from_slice(&body);
+    }
+}
+
+fn put_cstring(buffer: &mut Vec<u8>, value: &str) {
+    // A string cannot contain the terminator, cut it at the first one
+    let value = value.split('\0').next().unwrap_or_default();
+    buffer.extend_from_slice(value.as_bytes());
+    buffer.push(0);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(tag: u8, body: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![tag];
+        bytes.extend_from_slice(&(body.len() as i32 + 4).to_be_bytes());
+        bytes.extend_from_slice(body);
+        bytes
+    }
+
+    #[test]
+    fn test_read_startup_parameters() {
+        let mut body = PROTOCOL_VERSION.to_be_bytes().to_vec();
+        body.extend_from_slice(b"user\0alice\0database\0db\0\0");
+        let mut bytes = (body.len() as i32 + 4).to_be_bytes().to_vec();
+        bytes.extend_from_slice(&body);
+
+        let StartupMessage::Startup {
+            version,
+            parameters,
+        } = read_startup(&mut bytes.as_slice()).unwrap()
+        else {
+            panic!("expected a startup message");
+        };
+        assert_eq!(version, PROTOCOL_VERSION);
+        assert_eq!(parameters["user"], "alice");
+        assert_eq!(parameters["database"], "db");
+
+        let mut ssl_request = 8i32.to_be_bytes().to_vec();
+        ssl_request.extend_from_slice(&SSL_REQUEST_CODE.to_be_bytes());
+        assert_eq!(
+            read_startup(&mut ssl_request.as_slice()).unwrap(),
+            StartupMessage::SslRequest
+        );
+    }
+
+    #[test]
+    fn test_read_bind_message() {
+        let mut body = b"portal\0stmt\0".to_vec();
+        body.extend_from_slice(&1i16.to_be_bytes());
+        body.extend_from_slice(&1i16.to_be_bytes());
+        body.extend_from_slice(&2i16.to_be_bytes());
+        body.extend_from_slice(&4i32.to_be_bytes());
+        body.extend_from_slice(&7i32.to_be_bytes());
+        body.extend_from_slice(&(-1i32).to_be_bytes());
+        body.extend_from_slice(&0i16.to_be_bytes());
+
+        let mut bytes = frame(b'B', &body);
+        bytes.extend_from_slice(&frame(b'S', &[]));
+        let mut reader = bytes.as_slice();
+
+        assert_eq!(
+            read_message(&mut reader).unwrap(),
+            Some(FrontendMessage::Bind {
+                portal: "portal".to_string(),
+                statement: "stmt".to_string(),
+                parameter_formats: vec![1],
+                parameters: vec![Some(7i32.to_be_bytes().to_vec()), None],
+                result_formats: Vec::new(),
+            })
+        );
+        assert_eq!(
+            read_message(&mut reader).unwrap(),
+            Some(FrontendMessage::Sync)
+        );
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+    }
+
+    #[test]
+    fn test_truncated_message_is_invalid() {
+        let bytes = frame(b'E', b"portal\0");
+        let error = read_message(&mut bytes.as_slice()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_encode_data_row() {
+        let mut buffer = Vec::new();
+        BackendMessage::DataRow(vec![Some(b"42".to_vec()), None]).encode(&mut buffer);
+        assert_eq!(
+            buffer,
+            frame(
+                b'D',
+                &[0, 2, 0, 0, 0, 2, b'4', b'2', 0xff, 0xff, 0xff, 0xff]
+            )
+        );
+    }
+}
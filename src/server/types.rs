@@ -0,0 +1,358 @@
+use crate::{
+    DataType, DatabaseError, Interval, Json, Value, core::temporal::MICROS_PER_DAY,
+    sql::evaluator::cast_value,
+};
+
+/// Days from 1970-01-01, where dates start, to 2000-01-01, where PostgreSQL's start.
+const POSTGRES_EPOCH_DAYS: i32 = 10_957;
+
+/// A value of a parameter or column, as text or in PostgreSQL's binary form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Format {
+    Text,
+    Binary,
+}
+
+impl Format {
+    pub(crate) fn from_code(code: i16) -> Result<Self, ServerError> {
+        match code {
+            0 => Ok(Format::Text),
+            1 => Ok(Format::Binary),
+            code => Err(ServerError::protocol(format!("Invalid format code {code}"))),
+        }
+    }
+
+    pub(crate) fn code(self) -> i16 {
+        match self {
+            Format::Text => 0,
+            Format::Binary => 1,
+        }
+    }
+
+    /// The format of each of `count` values, from the codes of a `Bind` message: no
+    /// code means text, a single code applies to every value.
+    pub(crate) fn for_values(codes: &[i16], count: usize) -> Result<Vec<Self>, ServerError> {
+        match codes {
+            [] => Ok(vec![Format::Text; count]),
+            [code] => Ok(vec![Format::from_code(*code)?; count]),
+            codes if codes.len() == count => {
+                codes.iter().map(|code| Format::from_code(*code)).collect()
+            }
+            codes => Err(ServerError::protocol(format!(
+                "Got {} format codes for {count} values",
+                codes.len()
+            ))),
+        }
+    }
+}
+
+/// An error reported to the client, with its SQLSTATE code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct ServerError {
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl ServerError {
+    pub(crate) fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    /// The client broke the protocol, e.g. bound the wrong number of parameters.
+    pub(crate) fn protocol(message: impl Into<String>) -> Self {
+        Self::new("08P01", message)
+    }
+}
+
+impl From<miette::Report> for ServerError {
+    fn from(report: miette::Report) -> Self {
+        let code = report
+            .downcast_ref::<DatabaseError>()
+            .map_or("XX000", sqlstate);
+        Self::new(code, report.to_string())
+    }
+}
+
+impl From<DatabaseError> for ServerError {
+    fn from(error: DatabaseError) -> Self {
+        Self::new(sqlstate(&error), error.to_string())
+    }
+}
+
+/// The SQLSTATE code PostgreSQL reports for the same kind of error.
+pub(crate) fn sqlstate(error: &DatabaseError) -> &'static str {
+    match error {
+        DatabaseError::IoError(_) => "58030",
+        DatabaseError::SerializationError(_) => "XX000",
+        DatabaseError::TableNotFound(_) => "42P01",
+        DatabaseError::ColumnNotFound(_) => "42703",
+        DatabaseError::TypeMismatch(_) => "42804",
+        DatabaseError::InvalidQuery(_) => "42601",
+        DatabaseError::Corruption { .. } => "XX001",
+        DatabaseError::ReadOnly => "25006",
+        DatabaseError::Deadlock(_) => "40P01",
+    }
+}
+
+/// The OID of the PostgreSQL type a type is sent as.
+pub(crate) fn type_oid(data_type: DataType) -> u32 {
+    match data_type {
+        DataType::Int16 => 21,
+        DataType::Int32 => 23,
+        DataType::Int64 => 20,
+        DataType::Text => 25,
+        DataType::VarChar(_) => 1043,
+        DataType::Bool => 16,
+        DataType::Float64 => 701,
+        DataType::Decimal(_, _) => 1700,
+        DataType::Bytea => 17,
+        DataType::Uuid => 2950,
+        DataType::Timestamp => 1114,
+        DataType::Date => 1082,
+        DataType::Interval => 1186,
+        DataType::Json => 114,
+    }
+}
+
+/// The type of a parameter declared by its OID, `None` if it is left unspecified
+/// or has no equivalent.
+pub(crate) fn data_type(oid: u32) -> Option<DataType> {
+    Some(match oid {
+        21 => DataType::Int16,
+        23 => DataType::Int32,
+        20 => DataType::Int64,
+        // The length of a VARCHAR is not checked, NUMERIC is inferred like an unknown type
+        25 | 1043 => DataType::Text,
+        16 => DataType::Bool,
+        701 => DataType::Float64,
+        17 => DataType::Bytea,
+        2950 => DataType::Uuid,
+        1114 => DataType::Timestamp,
+        1082 => DataType::Date,
+        1186 => DataType::Interval,
+        114 => DataType::Json,
+        _ => return None,
+    })
+}
+
+/// The size of a value of the type in bytes, -1 for variable-length types.
+pub(crate) fn type_size(data_type: DataType) -> i16 {
+    match data_type {
+        DataType::Bool => 1,
+        DataType::Int16 => 2,
+        DataType::Int32 | DataType::Date => 4,
+        DataType::Int64 | DataType::Float64 | DataType::Timestamp => 8,
+        DataType::Uuid | DataType::Interval => 16,
+        DataType::Text
+        | DataType::VarChar(_)
+        | DataType::Decimal(_, _)
+        | DataType::Bytea
+        | DataType::Json => -1,
+    }
+}
+
+/// The type modifier, encoded as PostgreSQL does: the declared length or precision
+/// plus 4, -1 if there is none.
+pub(crate) fn type_modifier(data_type: DataType) -> i32 {
+    match data_type {
+        DataType::VarChar(max_len) => i32::try_from(max_len).map_or(-1, |len| len + 4),
+        DataType::Decimal(precision, scale) => ((precision as i32) << 16 | scale as i32) + 4,
+        _ => -1,
+    }
+}
+
+/// Encodes a non-NULL value of a column.
+pub(crate) fn encode_value(value: &Value, format: Format) -> Result<Vec<u8>, ServerError> {
+    match format {
+        Format::Text => Ok(encode_text(value).into_bytes()),
+        Format::Binary => encode_binary(value),
+    }
+}
+
+fn encode_text(value: &Value) -> String {
+    match value {
+        Value::Bool(true) => "t".to_string(),
+        Value::Bool(false) => "f".to_string(),
+        Value::Float64(n) if n.is_infinite() => {
+            if *n > 0.0 { "Infinity" } else { "-Infinity" }.to_string()
+        }
+        value => value.to_string(),
+    }
+}
+
+fn encode_binary(value: &Value) -> Result<Vec<u8>, ServerError> {
+    Ok(match value {
+        Value::Int16(n) => n.to_be_bytes().to_vec(),
+        Value::Int32(n) => n.to_be_bytes().to_vec(),
+        Value::Int64(n) => n.to_be_bytes().to_vec(),
+        Value::Float64(n) => n.to_be_bytes().to_vec(),
+        Value::Bool(b) => vec![u8::from(*b)],
+        Value::Text(s) => s.as_bytes().to_vec(),
+        Value::Json(json) => json.to_string().into_bytes(),
+        Value::Bytea(bytes) => bytes.clone(),
+        Value::Uuid(uuid) => uuid.to_be_bytes().to_vec(),
+        Value::Date(days) => (days - POSTGRES_EPOCH_DAYS).to_be_bytes().to_vec(),
+        Value::Timestamp(ts) => (ts - POSTGRES_EPOCH_DAYS as i64 * MICROS_PER_DAY)
+            .to_be_bytes()
+            .to_vec(),
+        Value::Interval(interval) => {
+            let mut bytes = interval.micros.to_be_bytes().to_vec();
+            bytes.extend_from_slice(&interval.days.to_be_bytes());
+            bytes.extend_from_slice(&interval.months.to_be_bytes());
+            bytes
+        }
+        Value::Decimal(_) | Value::Null => {
+            return Err(ServerError::new(
+                "0A000",
+                format!("Binary format is not supported for {value:?}"),
+            ));
+        }
+    })
+}
+
+/// Decodes a non-NULL parameter bound to a parameter of type `data_type`.
+///
+/// Text is converted here, so a value that is not valid for the type fails the `Bind`
+/// instead of the `Execute` after it.
+pub(crate) fn decode_parameter(
+    bytes: &[u8],
+    format: Format,
+    data_type: DataType,
+) -> Result<Value, ServerError> {
+    let invalid = || {
+        ServerError::new(
+            "22P03",
+            format!("Invalid binary representation for {data_type}"),
+        )
+    };
+
+    if format == Format::Text || matches!(data_type, DataType::Text | DataType::VarChar(_)) {
+        let text = String::from_utf8(bytes.to_vec())
+            .map_err(|_| ServerError::new("22021", "Parameter is not valid UTF-8"))?;
+        return cast_value(&Value::Text(text), data_type)
+            .map_err(|error| ServerError::new("22P02", error.to_string()));
+    }
+
+    Ok(match data_type {
+        DataType::Int16 => {
+            Value::Int16(i16::from_be_bytes(bytes.try_into().map_err(|_| invalid())?))
+        }
+        DataType::Int32 => {
+            Value::Int32(i32::from_be_bytes(bytes.try_into().map_err(|_| invalid())?))
+        }
+        DataType::Int64 => {
+            Value::Int64(i64::from_be_bytes(bytes.try_into().map_err(|_| invalid())?))
+        }
+        DataType::Float64 => {
+            Value::Float64(f64::from_be_bytes(bytes.try_into().map_err(|_| invalid())?))
+        }
+        DataType::Bool => match bytes {
+            [byte] => Value::Bool(*byte != 0),
+            _ => return Err(invalid()),
+        },
+        DataType::Bytea => Value::Bytea(bytes.to_vec()),
+        DataType::Uuid => Value::Uuid(u128::from_be_bytes(
+            bytes.try_into().map_err(|_| invalid())?,
+        )),
+        DataType::Date => {
+            let days = i32::from_be_bytes(bytes.try_into().map_err(|_| invalid())?);
+            Value::Date(days.checked_add(POSTGRES_EPOCH_DAYS).ok_or_else(invalid)?)
+        }
+        DataType::Timestamp => {
+            let ts = i64::from_be_bytes(bytes.try_into().map_err(|_| invalid())?);
+            Value::Timestamp(
+                ts.checked_add(POSTGRES_EPOCH_DAYS as i64 * MICROS_PER_DAY)
+                    .ok_or_else(invalid)?,
+            )
+        }
+        DataType::Interval => {
+            let [micros @ .., d0, d1, d2, d3, m0, m1, m2, m3] = bytes else {
+                return Err(invalid());
+            };
+            Value::Interval(Interval::new(
+                i32::from_be_bytes([*m0, *m1, *m2, *m3]),
+                i32::from_be_bytes([*d0, *d1, *d2, *d3]),
+                i64::from_be_bytes(micros.try_into().map_err(|_| invalid())?),
+            ))
+        }
+        DataType::Json => {
+            let text = std::str::from_utf8(bytes).map_err(|_| invalid())?;
+            Value::Json(Json::parse(text)?)
+        }
+        DataType::Decimal(_, _) => {
+            return Err(ServerError::new(
+                "0A000",
+                format!("Binary format is not supported for {data_type}"),
+            ));
+        }
+        DataType::Text | DataType::VarChar(_) => unreachable!("text is decoded above"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::temporal;
+
+    #[test]
+    fn test_binary_values_round_trip() {
+        let values = [
+            (Value::Int16(-2), DataType::Int16),
+            (Value::Int32(7), DataType::Int32),
+            (Value::Int64(1 << 40), DataType::Int64),
+            (Value::Float64(0.5), DataType::Float64),
+            (Value::Bool(true), DataType::Bool),
+            (Value::Bytea(vec![0, 255]), DataType::Bytea),
+            (Value::Uuid(42), DataType::Uuid),
+            (
+                Value::Date(temporal::parse_date("2024-02-29").unwrap()),
+                DataType::Date,
+            ),
+            (Value::Timestamp(-5), DataType::Timestamp),
+            (Value::Interval(Interval::new(1, 2, 3)), DataType::Interval),
+        ];
+
+        for (value, data_type) in values {
+            let bytes = encode_value(&value, Format::Binary).unwrap();
+            if type_size(data_type) > 0 {
+                assert_eq!(type_size(data_type), bytes.len() as i16);
+            }
+            let decoded = decode_parameter(&bytes, Format::Binary, data_type).unwrap();
+            assert_eq!(decoded, value);
+        }
+    }
+
+    #[test]
+    fn test_text_parameters_are_converted() {
+        assert_eq!(
+            decode_parameter(b" 42", Format::Text, DataType::Int32),
+            Ok(Value::Int32(42))
+        );
+        assert_eq!(
+            decode_parameter(b"abc", Format::Text, DataType::Int32)
+                .unwrap_err()
+                .code,
+            "22P02"
+        );
+    }
+
+    #[test]
+    fn test_dates_count_from_postgres_epoch() {
+        let date = Value::Date(temporal::parse_date("2000-01-02").unwrap());
+        assert_eq!(
+            encode_value(&date, Format::Binary).unwrap(),
+            1i32.to_be_bytes()
+        );
+        assert_eq!(
+            encode_value(&Value::Bool(false), Format::Text).unwrap(),
+            b"f"
+        );
+        assert_eq!(
+            encode_value(&Value::Bytea(vec![1]), Format::Text).unwrap(),
+            b"\\x01"
+        );
+    }
+}
@@ -1,7 +1,10 @@
+use std::{cell::RefCell, fmt, sync::Arc};
+
 use miette::{Result, miette};
 
 use crate::{
-    DataType, Value,
+    DataType, DatabaseError, Decimal, Value,
+    core::decimal::MAX_PRECISION,
     db::table::Table,
     sql::{
         analyzer::schema::{Field, OutputSchema},
@@ -9,23 +12,24 @@ use crate::{
             expression::Expression,
             operator::Operator,
             predicate::IsPredicate,
-            statement::{FromClause, SelectStatement, Statement},
+            statement::{FromClause, SelectStatement, Statement, TableReference},
             target::{SelectList, SelectTarget},
         },
         catalog_context::CatalogContext,
-        planner::logical::LogicalPlan,
+        functions::{ReturnType, ScalarFunction, Signature},
+        planner::logical::{AggregateExpr, LogicalPlan},
     },
 };
 
 pub(crate) mod schema;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ColumnRef {
     pub index: usize,
     pub relation: Option<String>, // 'u' in 'u.name'
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum AnalyzedExpression {
     Literal(Value),
     Column(ColumnRef, DataType),
@@ -40,6 +44,69 @@ pub enum AnalyzedExpression {
         predicate: IsPredicateTarget,
         negated: bool,
     },
+    ScalarFunction {
+        function: Arc<ScalarFunction>,
+        args: Vec<AnalyzedExpression>,
+        return_type: DataType,
+    },
+    Case {
+        operand: Option<Box<AnalyzedExpression>>,
+        branches: Vec<(AnalyzedExpression, AnalyzedExpression)>,
+        else_result: Option<Box<AnalyzedExpression>>,
+        return_type: DataType,
+    },
+    Cast {
+        expr: Box<AnalyzedExpression>,
+        data_type: DataType,
+    },
+    /// A parameter of a prepared statement, replaced by its value before execution.
+    Parameter {
+        number: usize,
+        data_type: DataType,
+    },
+}
+
+/// What the column references of an expression are resolved against.
+enum BindScope<'s> {
+    /// The columns of the input plan, aggregate calls are not allowed.
+    Input(&'s OutputSchema),
+
+    /// The output of an aggregate node.
+    ///
+    /// Only GROUP BY expressions and aggregate calls can be referenced, each
+    /// resolving to its column in `schema`.
+    Grouped {
+        group_by: &'s [Expression],
+        aggregate_calls: &'s [Expression],
+        schema: &'s OutputSchema,
+    },
+}
+
+impl BindScope<'_> {
+    fn schema(&self) -> &OutputSchema {
+        match self {
+            BindScope::Input(schema) | BindScope::Grouped { schema, .. } => schema,
+        }
+    }
+
+    /// Position of `expr` in the aggregate output, if it is a GROUP BY expression or aggregate call.
+    fn grouped_position(&self, expr: &Expression) -> Option<usize> {
+        let BindScope::Grouped {
+            group_by,
+            aggregate_calls,
+            ..
+        } = self
+        else {
+            return None;
+        };
+
+        group_by.iter().position(|group| group == expr).or_else(|| {
+            aggregate_calls
+                .iter()
+                .position(|call| call == expr)
+                .map(|i| group_by.len() + i)
+        })
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -49,6 +116,16 @@ pub enum IsPredicateTarget {
     Null,
 }
 
+impl fmt::Display for IsPredicateTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IsPredicateTarget::True => write!(f, "TRUE"),
+            IsPredicateTarget::False => write!(f, "FALSE"),
+            IsPredicateTarget::Null => write!(f, "NULL"),
+        }
+    }
+}
+
 impl From<&IsPredicate> for IsPredicateTarget {
     fn from(value: &IsPredicate) -> Self {
         match value {
@@ -64,23 +141,49 @@ impl AnalyzedExpression {
     pub fn get_type(&self) -> DataType {
         match self {
             AnalyzedExpression::Literal(value) => match value {
+                Value::Int16(_) => DataType::Int16,
+                Value::Int32(_) => DataType::Int32,
                 Value::Int64(_) => DataType::Int64,
+                Value::Bytea(_) => DataType::Bytea,
+                Value::Uuid(_) => DataType::Uuid,
                 Value::Float64(_) => DataType::Float64,
                 Value::Text(_) => DataType::Text,
                 Value::Bool(_) => DataType::Bool,
-                Value::Null => unreachable!("Null has no definite type."),
+                Value::Decimal(d) => {
+                    DataType::Decimal((d.integer_digits() + d.scale()).max(1), d.scale())
+                }
+                Value::Timestamp(_) => DataType::Timestamp,
+                Value::Date(_) => DataType::Date,
+                Value::Interval(_) => DataType::Interval,
+                Value::Json(_) => DataType::Json,
+                Value::Null => unreachable!("Null has no definite type, it is always cast."),
             },
             AnalyzedExpression::Column(_, column_type) => *column_type,
-            AnalyzedExpression::BinaryExpr { return_type, .. } => *return_type,
+            AnalyzedExpression::BinaryExpr { return_type, .. }
+            | AnalyzedExpression::ScalarFunction { return_type, .. }
+            | AnalyzedExpression::Case { return_type, .. } => *return_type,
+            AnalyzedExpression::Cast { data_type, .. }
+            | AnalyzedExpression::Parameter { data_type, .. } => *data_type,
             AnalyzedExpression::IsPredicate { .. } => DataType::Bool,
         }
     }
 
+    /// The relation of the column the expression reads, if it is a column reference.
+    pub(crate) fn relation(&self, input_schema: &OutputSchema) -> Option<String> {
+        match self {
+            AnalyzedExpression::Column(column, _) => input_schema
+                .fields
+                .get(column.index)
+                .and_then(|field| field.relation.clone()),
+            _ => None,
+        }
+    }
+
     /// Determines whether this expression can produce NULL given the input schema.
     pub fn is_nullable(&self, input_schema: &OutputSchema) -> bool {
         match self {
-            // Literals are never null (null literals are rejected during analysis)
-            AnalyzedExpression::Literal(_) => false,
+            // Literals are only null when they are cast to a type
+            AnalyzedExpression::Literal(value) => matches!(value, Value::Null),
             // Column nullability comes from the source field
             AnalyzedExpression::Column(col_ref, _) => input_schema
                 .fields
@@ -93,17 +196,168 @@ impl AnalyzedExpression {
             }
             // IS TRUE / IS NULL / etc. always returns a definite bool, never null
             AnalyzedExpression::IsPredicate { .. } => false,
+            // Functions may return NULL for any input, assume they do
+            AnalyzedExpression::ScalarFunction { .. } => true,
+            // A CASE is nullable if any result is, or there is no ELSE to fall back to
+            AnalyzedExpression::Case {
+                branches,
+                else_result,
+                ..
+            } => {
+                branches
+                    .iter()
+                    .any(|(_, result)| result.is_nullable(input_schema))
+                    || else_result
+                        .as_ref()
+                        .is_none_or(|result| result.is_nullable(input_schema))
+            }
+            AnalyzedExpression::Cast { expr, .. } => expr.is_nullable(input_schema),
+            // Any parameter can be given NULL
+            AnalyzedExpression::Parameter { .. } => true,
+        }
+    }
+
+    /// Renders the expression like an [`Expression`], naming columns after the fields of
+    /// `input_schema`.
+    pub fn display<'a>(&'a self, input_schema: &'a OutputSchema) -> DisplayExpression<'a> {
+        DisplayExpression {
+            expr: self,
+            input_schema,
+        }
+    }
+}
+
+/// An [`AnalyzedExpression`] together with the schema its columns refer to.
+pub struct DisplayExpression<'a> {
+    expr: &'a AnalyzedExpression,
+    input_schema: &'a OutputSchema,
+}
+
+impl fmt::Display for DisplayExpression<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let schema = self.input_schema;
+
+        match self.expr {
+            AnalyzedExpression::Literal(value) => {
+                write!(f, "{}", Expression::Literal(value.clone()))
+            }
+            AnalyzedExpression::Column(column, _) => match schema.fields.get(column.index) {
+                Some(Field {
+                    name,
+                    relation: Some(relation),
+                    ..
+                }) if schema.has_several_relations() => write!(f, "{relation}.{name}"),
+                Some(field) => write!(f, "{}", field.name),
+                None => write!(f, "${}", column.index),
+            },
+            AnalyzedExpression::BinaryExpr {
+                left, op, right, ..
+            } => write!(
+                f,
+                "({} {op} {})",
+                left.display(schema),
+                right.display(schema)
+            ),
+            AnalyzedExpression::IsPredicate {
+                expr,
+                predicate,
+                negated,
+            } => write!(
+                f,
+                "{} {} {predicate}",
+                expr.display(schema),
+                if *negated { "IS NOT" } else { "IS" }
+            ),
+            AnalyzedExpression::ScalarFunction { function, args, .. } => {
+                write!(f, "{}(", function.name)?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", arg.display(schema))?;
+                }
+                write!(f, ")")
+            }
+            AnalyzedExpression::Case {
+                operand,
+                branches,
+                else_result,
+                ..
+            } => {
+                write!(f, "CASE")?;
+                if let Some(operand) = operand {
+                    write!(f, " {}", operand.display(schema))?;
+                }
+                for (condition, result) in branches {
+                    write!(
+                        f,
+                        " WHEN {} THEN {}",
+                        condition.display(schema),
+                        result.display(schema)
+                    )?;
+                }
+                if let Some(else_result) = else_result {
+                    write!(f, " ELSE {}", else_result.display(schema))?;
+                }
+                write!(f, " END")
+            }
+            AnalyzedExpression::Cast { expr, data_type } => {
+                write!(f, "CAST({} AS {data_type})", expr.display(schema))
+            }
+            AnalyzedExpression::Parameter { number, .. } => write!(f, "${number}"),
         }
     }
 }
 
 pub struct Analyzer<'a, 'db> {
     context: &'a CatalogContext<'db>,
+
+    /// Types of the parameters `$1`, `$2`, ..., `None` until given or inferred.
+    parameter_types: RefCell<Vec<Option<DataType>>>,
 }
 
 impl<'a, 'db> Analyzer<'a, 'db> {
     pub fn new(context: &'a CatalogContext<'db>) -> Self {
-        Self { context }
+        Self::with_parameter_types(context, &[])
+    }
+
+    /// Creates an analyzer where the first parameters have the given types, the types of
+    /// the others are inferred from where they are used.
+    pub(crate) fn with_parameter_types(
+        context: &'a CatalogContext<'db>,
+        parameter_types: &[DataType],
+    ) -> Self {
+        Self {
+            context,
+            parameter_types: RefCell::new(parameter_types.iter().copied().map(Some).collect()),
+        }
+    }
+
+    /// Number of parameters given or used so far.
+    pub(crate) fn parameter_count(&self) -> usize {
+        self.parameter_types.borrow().len()
+    }
+
+    /// The types of all parameters, `$1` first.
+    ///
+    /// Fails if a parameter is skipped, e.g. only `$2` is used, without a type given.
+    pub(crate) fn parameter_types(&self) -> Result<Vec<DataType>> {
+        self.parameter_types
+            .borrow()
+            .iter()
+            .enumerate()
+            .map(|(index, data_type)| {
+                data_type.ok_or_else(|| {
+                    miette!("Could not determine the type of parameter ${}", index + 1)
+                })
+            })
+            .collect()
+    }
+
+    /// Binds an expression that references no columns, e.g. an `EXECUTE` argument.
+    pub(crate) fn bind_constant(&self, expr: &Expression) -> Result<AnalyzedExpression> {
+        let schema = OutputSchema { fields: Vec::new() };
+        self.bind(expr, &BindScope::Input(&schema))
     }
 
     pub fn analyze(&self, statement: Statement) -> Result<LogicalPlan> {
@@ -112,6 +366,7 @@ impl<'a, 'db> Analyzer<'a, 'db> {
                 select_list,
                 from_clause,
                 where_clause,
+                group_by,
             }) => {
                 let mut plan = self.analyze_from(from_clause)?;
 
@@ -119,6 +374,10 @@ impl<'a, 'db> Analyzer<'a, 'db> {
                     plan = self.analyze_where(plan, &expr)?;
                 }
 
+                if !group_by.is_empty() || self.has_aggregates(&select_list) {
+                    return self.analyze_aggregate(plan, &group_by, &select_list);
+                }
+
                 plan = self.analyze_projection(plan, &select_list)?;
 
                 Ok(plan)
@@ -127,8 +386,48 @@ impl<'a, 'db> Analyzer<'a, 'db> {
         }
     }
 
+    /// Builds the scans of the FROM clause, joined left to right.
     fn analyze_from(&self, from_clause: FromClause) -> Result<LogicalPlan> {
-        let physical_schema = self.context.get_table(&from_clause.table_name)?.schema();
+        let mut qualifiers = vec![from_clause.table.qualifier().to_string()];
+        let mut plan = self.analyze_table(&from_clause.table)?;
+
+        for join in from_clause.joins {
+            let qualifier = join.table.qualifier().to_string();
+            if qualifiers.contains(&qualifier) {
+                return Err(miette!(
+                    "Table name {qualifier} is specified more than once"
+                ));
+            }
+            qualifiers.push(qualifier);
+
+            let right = self.analyze_table(&join.table)?;
+            let schema = OutputSchema {
+                fields: plan
+                    .schema()
+                    .fields
+                    .iter()
+                    .chain(&right.schema().fields)
+                    .cloned()
+                    .collect(),
+            };
+            let conditions = match &join.condition {
+                Some(condition) => vec![self.bind_expression(condition, &schema)?],
+                None => Vec::new(),
+            };
+
+            plan = LogicalPlan::Join {
+                left: Box::new(plan),
+                right: Box::new(right),
+                conditions,
+                schema,
+            };
+        }
+
+        Ok(plan)
+    }
+
+    fn analyze_table(&self, table: &TableReference) -> Result<LogicalPlan> {
+        let physical_schema = self.context.get_table(&table.table_name)?.schema();
 
         let virtual_fields = physical_schema
             .columns
@@ -136,6 +435,7 @@ impl<'a, 'db> Analyzer<'a, 'db> {
             .map(|col| Field {
                 name: col.name.clone(),
                 alias: None,
+                relation: Some(table.qualifier().to_string()),
                 data_type: col.data_type,
                 is_nullable: col.nullable,
             })
@@ -146,8 +446,10 @@ impl<'a, 'db> Analyzer<'a, 'db> {
         };
 
         Ok(LogicalPlan::Scan {
-            table_name: from_clause.table_name.to_string(),
+            table_name: table.table_name.to_string(),
             schema: resolved_schema,
+            projection: None,
+            filters: Vec::new(),
         })
     }
 
@@ -182,6 +484,7 @@ impl<'a, 'db> Analyzer<'a, 'db> {
                     let field = Field {
                         name: expr.to_column_name().to_string(),
                         alias: alias.as_ref().map(|a| a.to_string()),
+                        relation: analyzed_expr.relation(input_schema),
                         data_type: analyzed_expr.get_type(),
                         is_nullable: analyzed_expr.is_nullable(input_schema),
                     };
@@ -201,6 +504,189 @@ impl<'a, 'db> Analyzer<'a, 'db> {
         })
     }
 
+    /// Whether any target of the select list calls an aggregate function.
+    fn has_aggregates(&self, select_list: &SelectList) -> bool {
+        select_list.iter().any(|item| match item {
+            SelectTarget::Star => false,
+            SelectTarget::Expression { expr, .. } => self.contains_aggregate(expr),
+        })
+    }
+
+    fn contains_aggregate(&self, expr: &Expression) -> bool {
+        match expr {
+            Expression::Function { name, .. }
+                if self.context.aggregate_function(name).is_some() =>
+            {
+                true
+            }
+            _ => expr
+                .children()
+                .into_iter()
+                .any(|child| self.contains_aggregate(child)),
+        }
+    }
+
+    /// Builds an [`LogicalPlan::Aggregate`] node, followed by a projection over its output.
+    ///
+    /// The aggregate outputs the GROUP BY expressions followed by one column per distinct
+    /// aggregate call. Select targets are then bound against that output.
+    fn analyze_aggregate(
+        &self,
+        input_plan: LogicalPlan,
+        group_by: &[Expression],
+        select_list: &SelectList,
+    ) -> Result<LogicalPlan> {
+        let input_schema = input_plan.schema();
+
+        let mut group_exprs = Vec::new();
+        let mut fields = Vec::new();
+        for expr in group_by {
+            let analyzed_expr = self.bind_expression(expr, input_schema)?;
+            fields.push(Field {
+                name: expr.to_column_name().to_string(),
+                alias: None,
+                relation: analyzed_expr.relation(input_schema),
+                data_type: analyzed_expr.get_type(),
+                is_nullable: analyzed_expr.is_nullable(input_schema),
+            });
+            group_exprs.push(analyzed_expr);
+        }
+
+        let mut aggregate_calls = Vec::new();
+        for item in select_list.iter() {
+            let SelectTarget::Expression { expr, .. } = item else {
+                return Err(miette!(
+                    "SELECT * cannot be used with aggregates or GROUP BY"
+                ));
+            };
+            self.collect_aggregates(expr, &mut aggregate_calls);
+        }
+
+        let mut aggregates = Vec::new();
+        for call in &aggregate_calls {
+            let aggregate = self.bind_aggregate(call, input_schema)?;
+            fields.push(Field {
+                name: call.to_column_name().to_string(),
+                alias: None,
+                relation: None,
+                data_type: aggregate.return_type,
+                is_nullable: true,
+            });
+            aggregates.push(aggregate);
+        }
+
+        let aggregate_schema = OutputSchema { fields };
+
+        let mut analyzed_exprs = Vec::new();
+        let mut output_fields = Vec::new();
+        for item in select_list.iter() {
+            let SelectTarget::Expression { expr, alias } = item else {
+                unreachable!("SELECT * was rejected above");
+            };
+
+            let scope = BindScope::Grouped {
+                group_by,
+                aggregate_calls: &aggregate_calls,
+                schema: &aggregate_schema,
+            };
+            let analyzed_expr = self.bind(expr, &scope)?;
+
+            output_fields.push(Field {
+                name: expr.to_column_name().to_string(),
+                alias: alias.clone(),
+                relation: None,
+                data_type: analyzed_expr.get_type(),
+                is_nullable: analyzed_expr.is_nullable(&aggregate_schema),
+            });
+            analyzed_exprs.push(analyzed_expr);
+        }
+
+        let aggregate_plan = LogicalPlan::Aggregate {
+            input: Box::new(input_plan),
+            group_by: group_exprs,
+            aggregates,
+            schema: aggregate_schema,
+        };
+
+        Ok(LogicalPlan::Projection {
+            input: Box::new(aggregate_plan),
+            expressions: analyzed_exprs,
+            schema: OutputSchema {
+                fields: output_fields,
+            },
+        })
+    }
+
+    /// Collects every distinct aggregate call in `expr`.
+    fn collect_aggregates(&self, expr: &Expression, calls: &mut Vec<Expression>) {
+        match expr {
+            Expression::Function { name, .. }
+                if self.context.aggregate_function(name).is_some() =>
+            {
+                if !calls.contains(expr) {
+                    calls.push(expr.clone());
+                }
+            }
+            _ => {
+                for child in expr.children() {
+                    self.collect_aggregates(child, calls);
+                }
+            }
+        }
+    }
+
+    fn bind_aggregate(
+        &self,
+        call: &Expression,
+        input_schema: &OutputSchema,
+    ) -> Result<AggregateExpr> {
+        let Expression::Function { name, args } = call else {
+            unreachable!("only function calls are collected as aggregates");
+        };
+        let function = self
+            .context
+            .aggregate_function(name)
+            .ok_or_else(|| miette!("Aggregate function {name} does not exist"))?;
+
+        // `count(*)` aggregates over the rows themselves, without any argument
+        if let [Expression::Identifier(star)] = args.as_slice()
+            && star == "*"
+        {
+            let return_type = match function.signature.return_type {
+                ReturnType::Fixed(data_type) => data_type,
+                ReturnType::SameAsArgument(_)
+                | ReturnType::WidenedArgument(_)
+                | ReturnType::Quotient(_) => {
+                    return Err(miette!("{name}(*) is not supported"));
+                }
+            };
+
+            return Ok(AggregateExpr {
+                function,
+                args: Vec::new(),
+                return_type,
+            });
+        }
+
+        let args = args
+            .iter()
+            .map(|arg| {
+                if self.contains_aggregate(arg) {
+                    return Err(miette!("Aggregate function calls cannot be nested"));
+                }
+                self.bind_expression(arg, input_schema)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let (args, return_type) = Self::coerce_arguments(name, &function.signature, args)?;
+
+        Ok(AggregateExpr {
+            function,
+            args,
+            return_type,
+        })
+    }
+
     fn analyze_where(
         &self,
         input_plan: LogicalPlan,
@@ -221,10 +707,42 @@ impl<'a, 'db> Analyzer<'a, 'db> {
         expr: &Expression,
         input_schema: &OutputSchema,
     ) -> Result<AnalyzedExpression> {
+        self.bind(expr, &BindScope::Input(input_schema))
+    }
+
+    fn bind(&self, expr: &Expression, scope: &BindScope) -> Result<AnalyzedExpression> {
+        if let Some(index) = scope.grouped_position(expr) {
+            return Ok(AnalyzedExpression::Column(
+                ColumnRef {
+                    index,
+                    relation: None,
+                },
+                scope.schema().fields[index].data_type,
+            ));
+        }
+
         match expr {
             Expression::BinaryOp { left, op, right } => {
-                let left = self.bind_expression(left, input_schema)?;
-                let right = self.bind_expression(right, input_schema)?;
+                // A parameter takes the type of the other operand
+                let (left, right) = if matches!(**left, Expression::Parameter(_))
+                    && !matches!(**right, Expression::Parameter(_))
+                {
+                    let right = self.bind(right, scope)?;
+                    let left = self.bind_expecting(left, scope, right.get_type())?;
+                    (left, right)
+                } else {
+                    let left = self.bind(left, scope)?;
+                    let expected = match op {
+                        // JSON keys and paths are given as text
+                        Operator::JsonGet
+                        | Operator::JsonGetText
+                        | Operator::JsonPath
+                        | Operator::JsonPathText => DataType::Text,
+                        _ => left.get_type(),
+                    };
+                    let right = self.bind_expecting(right, scope, expected)?;
+                    (left, right)
+                };
 
                 let return_type = self.resolve_binary_op(left.get_type(), *op, right.get_type())?;
 
@@ -236,9 +754,13 @@ impl<'a, 'db> Analyzer<'a, 'db> {
                 })
             }
             Expression::Identifier(name) => {
-                let index = input_schema
-                    .find_column(name)
-                    .ok_or_else(|| miette!("Column {name} could not be found"))?;
+                let BindScope::Input(input_schema) = scope else {
+                    return Err(miette!(
+                        "Column {name} must appear in the GROUP BY clause or be used in an aggregate function"
+                    ));
+                };
+
+                let index = input_schema.find_column(name)?;
                 let field = &input_schema.fields[index];
 
                 Ok(AnalyzedExpression::Column(
@@ -249,6 +771,7 @@ impl<'a, 'db> Analyzer<'a, 'db> {
                     field.data_type,
                 ))
             }
+            Expression::Parameter(number) => self.bind_parameter(*number, None),
             Expression::Literal(scalar_value) => match scalar_value {
                 Value::Null => Err(miette!(
                     "NULL literal cannot be used in this context. Use 'IS NULL' or 'IS NOT NULL' instead"
@@ -260,29 +783,278 @@ impl<'a, 'db> Analyzer<'a, 'db> {
                 predicate,
                 is_negated,
             } => {
-                let inner_analyzed = self.bind_expression(expr, input_schema)?;
+                let inner_analyzed = self.bind(expr, scope)?;
+                self.bind_is_predicate(inner_analyzed, predicate, *is_negated)
+            }
+            Expression::Function { name, args } => {
+                if self.context.aggregate_function(name).is_some() {
+                    return Err(miette!(
+                        "Aggregate function {name} is not allowed in this context"
+                    ));
+                }
 
-                let inner_type = inner_analyzed.get_type();
-                match predicate {
-                    IsPredicate::True | IsPredicate::False => {
-                        if inner_type != DataType::Bool {
-                            return Err(miette!("IS TRUE/FALSE requires boolean input"));
+                let function = self.context.scalar_function(name);
+                let args = args
+                    .iter()
+                    .enumerate()
+                    .map(|(position, arg)| {
+                        match function
+                            .as_ref()
+                            .and_then(|function| function.signature.argument_type(position))
+                        {
+                            Some(expected) => self.bind_expecting(arg, scope, expected),
+                            None => self.bind(arg, scope),
+                        }
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                self.bind_scalar_function(name, args)
+            }
+            Expression::Case {
+                operand,
+                branches,
+                else_result,
+            } => self.bind_case(operand.as_deref(), branches, else_result.as_deref(), scope),
+            Expression::Cast { expr, data_type } => {
+                let inner_analyzed = match &**expr {
+                    // A cast gives NULL a type, e.g. `CAST(NULL AS INT)`
+                    Expression::Literal(Value::Null) => AnalyzedExpression::Literal(Value::Null),
+                    expr => {
+                        let inner_analyzed = self.bind_expecting(expr, scope, *data_type)?;
+                        let from = inner_analyzed.get_type();
+                        if !DataType::can_cast(from, *data_type) {
+                            return Err(DatabaseError::TypeMismatch(format!(
+                                "Cannot cast {from} to {data_type}"
+                            ))
+                            .into());
                         }
+                        inner_analyzed
                     }
-                    IsPredicate::Null => {
-                        // Just works
+                };
+
+                Ok(AnalyzedExpression::Cast {
+                    expr: Box::new(inner_analyzed),
+                    data_type: *data_type,
+                })
+            }
+        }
+    }
+
+    /// Binds an expression, giving it the `expected` type if it is a parameter whose type
+    /// is not known yet.
+    fn bind_expecting(
+        &self,
+        expr: &Expression,
+        scope: &BindScope,
+        expected: DataType,
+    ) -> Result<AnalyzedExpression> {
+        if let Expression::Parameter(number) = expr {
+            self.bind_parameter(*number, Some(expected))
+        } else {
+            self.bind(expr, scope)
+        }
+    }
+
+    fn bind_parameter(
+        &self,
+        number: usize,
+        expected: Option<DataType>,
+    ) -> Result<AnalyzedExpression> {
+        let mut parameter_types = self.parameter_types.borrow_mut();
+        if parameter_types.len() < number {
+            parameter_types.resize(number, None);
+        }
+
+        let slot = &mut parameter_types[number - 1];
+        let data_type = match (*slot, expected) {
+            (Some(data_type), _) => data_type,
+            (None, Some(expected)) => *slot.insert(expected),
+            (None, None) => {
+                return Err(miette!(
+                    "Could not determine the type of parameter ${number}, add a cast"
+                ));
+            }
+        };
+
+        Ok(AnalyzedExpression::Parameter { number, data_type })
+    }
+
+    /// Binds a CASE expression.
+    ///
+    /// All results must share a common type, results of another type are implicitly
+    /// cast to it. A missing ELSE evaluates to NULL.
+    fn bind_case(
+        &self,
+        operand: Option<&Expression>,
+        branches: &[(Expression, Expression)],
+        else_result: Option<&Expression>,
+        scope: &BindScope,
+    ) -> Result<AnalyzedExpression> {
+        let operand = operand
+            .map(|operand| self.bind(operand, scope))
+            .transpose()?;
+
+        let mut conditions = Vec::new();
+        for (condition, _) in branches {
+            let condition = self.bind(condition, scope)?;
+            let condition_type = condition.get_type();
+
+            match &operand {
+                Some(operand) => {
+                    let operand_type = operand.get_type();
+                    if DataType::common_type(operand_type, condition_type).is_none() {
+                        return Err(DatabaseError::TypeMismatch(format!(
+                            "CASE operand of type {operand_type} cannot be compared to WHEN value of type {condition_type}"
+                        ))
+                        .into());
                     }
                 }
+                None if condition_type != DataType::Bool => {
+                    return Err(DatabaseError::TypeMismatch(format!(
+                        "CASE WHEN condition must be Boolean, got {condition_type}"
+                    ))
+                    .into());
+                }
+                None => {}
+            }
 
-                let predicate: IsPredicateTarget = predicate.into();
+            conditions.push(condition);
+        }
 
-                Ok(AnalyzedExpression::IsPredicate {
-                    expr: Box::new(inner_analyzed),
-                    predicate,
-                    negated: *is_negated,
-                })
+        // NULL results take whatever type the other results have
+        let bind_result = |expr: &Expression| match expr {
+            Expression::Literal(Value::Null) => Ok(None),
+            expr => self.bind(expr, scope).map(Some),
+        };
+
+        let mut results = branches
+            .iter()
+            .map(|(_, result)| bind_result(result))
+            .collect::<Result<Vec<_>>>()?;
+        let else_result = else_result.map(bind_result).transpose()?.flatten();
+
+        let mut return_type: Option<DataType> = None;
+        for result in results.iter().flatten().chain(&else_result) {
+            let result_type = result.get_type();
+            return_type = match return_type {
+                None => Some(result_type),
+                Some(current) => {
+                    Some(DataType::common_type(current, result_type).ok_or_else(|| {
+                        DatabaseError::TypeMismatch(format!(
+                            "CASE results of type {current} and {result_type} cannot be matched"
+                        ))
+                    })?)
+                }
+            };
+        }
+        let return_type =
+            return_type.ok_or_else(|| miette!("CASE must have at least one non-NULL result"))?;
+
+        let unify = |result: Option<AnalyzedExpression>| match result {
+            Some(result) if result.get_type() == return_type => result,
+            Some(result) => AnalyzedExpression::Cast {
+                expr: Box::new(result),
+                data_type: return_type,
+            },
+            None => AnalyzedExpression::Cast {
+                expr: Box::new(AnalyzedExpression::Literal(Value::Null)),
+                data_type: return_type,
+            },
+        };
+
+        let branches = conditions
+            .into_iter()
+            .zip(results.drain(..))
+            .map(|(condition, result)| (condition, unify(result)))
+            .collect();
+
+        Ok(AnalyzedExpression::Case {
+            operand: operand.map(Box::new),
+            branches,
+            else_result: else_result.map(|result| Box::new(unify(Some(result)))),
+            return_type,
+        })
+    }
+
+    fn bind_is_predicate(
+        &self,
+        inner_analyzed: AnalyzedExpression,
+        predicate: &IsPredicate,
+        is_negated: bool,
+    ) -> Result<AnalyzedExpression> {
+        let inner_type = inner_analyzed.get_type();
+        match predicate {
+            IsPredicate::True | IsPredicate::False => {
+                if inner_type != DataType::Bool {
+                    return Err(miette!("IS TRUE/FALSE requires boolean input"));
+                }
+            }
+            IsPredicate::Null => {
+                // Just works
             }
         }
+
+        let predicate: IsPredicateTarget = predicate.into();
+
+        Ok(AnalyzedExpression::IsPredicate {
+            expr: Box::new(inner_analyzed),
+            predicate,
+            negated: is_negated,
+        })
+    }
+
+    fn bind_scalar_function(
+        &self,
+        name: &str,
+        args: Vec<AnalyzedExpression>,
+    ) -> Result<AnalyzedExpression> {
+        let function = self
+            .context
+            .scalar_function(name)
+            .ok_or_else(|| miette!("Function {name} does not exist"))?;
+
+        let (args, return_type) = Self::coerce_arguments(name, &function.signature, args)?;
+
+        Ok(AnalyzedExpression::ScalarFunction {
+            function,
+            args,
+            return_type,
+        })
+    }
+
+    /// Checks the arguments of a call against `signature` and casts them to the types it
+    /// declares, returning them with the type of the result.
+    fn coerce_arguments(
+        name: &str,
+        signature: &Signature,
+        args: Vec<AnalyzedExpression>,
+    ) -> Result<(Vec<AnalyzedExpression>, DataType)> {
+        let arg_types = args
+            .iter()
+            .map(AnalyzedExpression::get_type)
+            .collect::<Vec<_>>();
+        signature.resolve(name, &arg_types)?;
+
+        let args = args
+            .into_iter()
+            .enumerate()
+            .map(
+                |(position, arg)| match signature.argument_cast(position, &arg_types) {
+                    Some(data_type) => AnalyzedExpression::Cast {
+                        expr: Box::new(arg),
+                        data_type,
+                    },
+                    None => arg,
+                },
+            )
+            .collect::<Vec<_>>();
+
+        // The result may take its type from a cast argument
+        let arg_types = args
+            .iter()
+            .map(AnalyzedExpression::get_type)
+            .collect::<Vec<_>>();
+        let return_type = signature.resolve(name, &arg_types)?;
+        Ok((args, return_type))
     }
 
     fn resolve_binary_op(&self, left: DataType, op: Operator, right: DataType) -> Result<DataType> {
@@ -293,38 +1065,126 @@ impl<'a, 'db> Analyzer<'a, 'db> {
             | Operator::GreaterThanEqual
             | Operator::LessThanEqual
             | Operator::NotEqual
-                if DataType::can_coerce(left, right) =>
+                if DataType::common_type(left, right).is_some() =>
             {
                 Ok(DataType::Bool)
             }
             Operator::Add | Operator::Subtract | Operator::Multiply | Operator::Divide => {
-                Self::resolve_arithmetic_type(left, right)
+                match Self::resolve_temporal_arithmetic_type(left, op, right)
+                    .or_else(|| Self::resolve_decimal_arithmetic_type(left, op, right))
+                {
+                    Some(data_type) => Ok(data_type),
+                    None => Self::resolve_arithmetic_type(left, right),
+                }
             }
             Operator::And | Operator::Or if left == DataType::Bool && right == DataType::Bool => {
                 Ok(DataType::Bool)
             }
+            Operator::JsonGet | Operator::JsonGetText
+                if left == DataType::Json
+                    && (right.is_integer()
+                        || matches!(right, DataType::Text | DataType::VarChar(_))) =>
+            {
+                Ok(Self::resolve_json_access_type(op))
+            }
+            Operator::JsonPath | Operator::JsonPathText
+                if left == DataType::Json
+                    && matches!(right, DataType::Text | DataType::VarChar(_)) =>
+            {
+                Ok(Self::resolve_json_access_type(op))
+            }
             _ => Err(miette!("Type mismatch between {left:?} {op} {right:?}")),
         }
     }
 
-    fn resolve_arithmetic_type(left: DataType, right: DataType) -> Result<DataType> {
-        if left == right {
-            return Ok(left);
+    /// The `->>` and `#>>` operators return text, the others the JSON value itself.
+    fn resolve_json_access_type(op: Operator) -> DataType {
+        match op {
+            Operator::JsonGetText | Operator::JsonPathText => DataType::Text,
+            _ => DataType::Json,
+        }
+    }
+
+    /// Result type of arithmetic involving dates, timestamps and intervals.
+    fn resolve_temporal_arithmetic_type(
+        left: DataType,
+        op: Operator,
+        right: DataType,
+    ) -> Option<DataType> {
+        use DataType::{Date, Float64, Int64, Interval, Timestamp};
+
+        match (left, op, right) {
+            (Timestamp | Date, Operator::Add | Operator::Subtract, Interval)
+            | (Interval, Operator::Add, Timestamp | Date) => Some(Timestamp),
+            (Date, Operator::Add | Operator::Subtract, Int64) | (Int64, Operator::Add, Date) => {
+                Some(Date)
+            }
+            (Date, Operator::Subtract, Date) => Some(Int64),
+            (Timestamp | Date, Operator::Subtract, Timestamp | Date)
+            | (Interval, Operator::Add | Operator::Subtract, Interval)
+            | (Interval, Operator::Multiply | Operator::Divide, Int64 | Float64)
+            | (Int64 | Float64, Operator::Multiply, Interval) => Some(Interval),
+            _ => None,
+        }
+    }
+
+    /// Result type of exact arithmetic on decimals and integers, following the SQL rules
+    /// for precision and scale. Floats make the result a float.
+    fn resolve_decimal_arithmetic_type(
+        left: DataType,
+        op: Operator,
+        right: DataType,
+    ) -> Option<DataType> {
+        if !matches!(left, DataType::Decimal(_, _)) && !matches!(right, DataType::Decimal(_, _)) {
+            return None;
+        }
+        if left == DataType::Float64 || right == DataType::Float64 {
+            return Some(DataType::Float64);
         }
 
-        match (left, right) {
-            (_, DataType::Float64) | (DataType::Float64, _) => Ok(DataType::Float64),
-            (_, DataType::Int64) | (DataType::Int64, _) => Ok(DataType::Int64),
-            _ => Err(miette!(
+        let (p1, s1) = left.decimal_precision()?;
+        let (p2, s2) = right.decimal_precision()?;
+
+        let (precision, scale) = match op {
+            Operator::Add | Operator::Subtract => {
+                let scale = s1.max(s2);
+                ((p1 - s1).max(p2 - s2) + scale + 1, scale)
+            }
+            Operator::Multiply => (p1 + p2, s1 + s2),
+            _ => (MAX_PRECISION, Decimal::division_scale(s1, s2)),
+        };
+
+        Some(DataType::Decimal(
+            precision.min(MAX_PRECISION),
+            scale.min(MAX_PRECISION),
+        ))
+    }
+
+    fn resolve_arithmetic_type(left: DataType, right: DataType) -> Result<DataType> {
+        let is_numeric = |t: DataType| t.is_integer() || t == DataType::Float64;
+        if !is_numeric(left) || !is_numeric(right) {
+            return Err(miette!(
                 "Cannot perform arithmetic between {left:?} and {right:?}"
-            )),
+            ));
         }
+
+        // Integers widen to the larger of the two, anything with a float is a float
+        DataType::common_type(left, right)
+            .ok_or_else(|| miette!("Cannot perform arithmetic between {left:?} and {right:?}"))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{ColumnDef, Schema, core::types::DataType};
+    use std::{
+        ops::{Deref, DerefMut},
+        path::PathBuf,
+    };
+
+    use super::*;
+    use crate::{
+        ColumnDef, Database, Schema, Signature, core::types::DataType, sql::parser::SqlParser,
+    };
 
     /// Creates a test schema with common columns
     fn create_test_schema() -> Schema {
@@ -335,4 +1195,281 @@ mod tests {
             ColumnDef::new("age", DataType::Int64, true),
         ])
     }
+
+    /// A database of a single test, its directory is removed when the test ends.
+    struct TestDatabase {
+        db: Database,
+        directory: PathBuf,
+    }
+
+    impl Deref for TestDatabase {
+        type Target = Database;
+
+        fn deref(&self) -> &Database {
+            &self.db
+        }
+    }
+
+    impl DerefMut for TestDatabase {
+        fn deref_mut(&mut self) -> &mut Database {
+            &mut self.db
+        }
+    }
+
+    impl Drop for TestDatabase {
+        fn drop(&mut self) {
+            std::fs::remove_dir_all(&self.directory).ok();
+        }
+    }
+
+    /// Creates a database holding a `users` table, in a directory named after the test
+    fn create_test_database(name: &str) -> TestDatabase {
+        let directory = std::env::temp_dir()
+            .join("scuttle_analyzer_tests")
+            .join(name);
+        std::fs::remove_dir_all(&directory).ok();
+
+        let mut db = Database::new(&directory);
+        db.create_table("users", create_test_schema()).unwrap();
+        TestDatabase { db, directory }
+    }
+
+    fn analyze(db: &mut Database, query: &str) -> Result<LogicalPlan> {
+        let statement = SqlParser::new(query).parse()?;
+        let context = CatalogContext::new(db);
+        Analyzer::new(&context).analyze(statement)
+    }
+
+    #[test]
+    fn test_analyze_scalar_function() {
+        let mut db = create_test_database("analyze_scalar_function");
+        db.register_scalar_function(
+            "tenant_hash",
+            Signature::exact(vec![DataType::Text], DataType::Int64),
+            |_| Ok(Value::Int64(0)),
+        )
+        .unwrap();
+
+        let plan = analyze(&mut db, "SELECT tenant_hash(name) FROM users").unwrap();
+        let field = &plan.schema().fields[0];
+        assert_eq!(field.name, "tenant_hash");
+        assert_eq!(field.data_type, DataType::Int64);
+
+        let err = analyze(&mut db, "SELECT tenant_hash(age) FROM users");
+        assert!(err.is_err());
+
+        let err = analyze(&mut db, "SELECT missing(age) FROM users");
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn test_analyze_aggregate() {
+        let mut db = create_test_database("analyze_aggregate");
+
+        let plan = analyze(
+            &mut db,
+            "SELECT name, count(*), sum(age) + 1 FROM users GROUP BY name",
+        )
+        .unwrap();
+
+        let LogicalPlan::Projection { input, schema, .. } = plan else {
+            panic!("Expected a projection over the aggregate");
+        };
+        let types = schema
+            .fields
+            .iter()
+            .map(|f| f.data_type)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![DataType::Text, DataType::Int64, DataType::Int64]
+        );
+
+        let LogicalPlan::Aggregate {
+            group_by,
+            aggregates,
+            ..
+        } = *input
+        else {
+            panic!("Expected an aggregate node");
+        };
+        assert_eq!(group_by.len(), 1);
+        assert_eq!(aggregates.len(), 2);
+        assert!(aggregates[0].args.is_empty());
+    }
+
+    #[test]
+    fn test_analyze_aggregate_errors() {
+        let mut db = create_test_database("analyze_aggregate_errors");
+
+        // Ungrouped column next to an aggregate
+        assert!(analyze(&mut db, "SELECT name, count(*) FROM users").is_err());
+        // Aggregates are not allowed in WHERE
+        assert!(analyze(&mut db, "SELECT id FROM users WHERE count(*) > 1").is_err());
+        // Nested aggregates
+        assert!(analyze(&mut db, "SELECT sum(count(id)) FROM users").is_err());
+        // Aggregate over text
+        assert!(analyze(&mut db, "SELECT sum(name) FROM users").is_err());
+    }
+
+    #[test]
+    fn test_analyze_case_unifies_result_types() {
+        let mut db = create_test_database("analyze_case_unifies_result_types");
+
+        let plan = analyze(
+            &mut db,
+            "SELECT CASE WHEN age > 30 THEN 1 WHEN age IS NULL THEN NULL ELSE 2.5 END FROM users",
+        )
+        .unwrap();
+        let field = &plan.schema().fields[0];
+        assert_eq!(field.data_type, DataType::Float64);
+        assert!(field.is_nullable);
+
+        // Results without a common type
+        assert!(
+            analyze(
+                &mut db,
+                "SELECT CASE WHEN age > 30 THEN 1 ELSE 'x' END FROM users"
+            )
+            .is_err()
+        );
+        // Searched CASE requires boolean conditions
+        assert!(analyze(&mut db, "SELECT CASE WHEN age THEN 1 END FROM users").is_err());
+        // Simple CASE compares the operand to each WHEN value
+        assert!(analyze(&mut db, "SELECT CASE age WHEN 'x' THEN 1 END FROM users").is_err());
+    }
+
+    #[test]
+    fn test_analyze_cast() {
+        let mut db = create_test_database("analyze_cast");
+
+        let plan = analyze(&mut db, "SELECT age::TEXT, CAST(name AS INT) FROM users").unwrap();
+        let types = plan
+            .schema()
+            .fields
+            .iter()
+            .map(|f| f.data_type)
+            .collect::<Vec<_>>();
+        assert_eq!(types, vec![DataType::Text, DataType::Int64]);
+
+        let err = analyze(&mut db, "SELECT CAST(name = 'x' AS FLOAT) FROM users").unwrap_err();
+        assert!(err.to_string().contains("Cannot cast Boolean to Float"));
+    }
+
+    #[test]
+    fn test_analyze_temporal_arithmetic() {
+        let mut db = create_test_database("analyze_temporal_arithmetic");
+
+        let plan = analyze(
+            &mut db,
+            "SELECT now() - INTERVAL '1 day', now() - TIMESTAMP '2024-01-01', \
+             DATE '2024-01-02' - DATE '2024-01-01', extract(year FROM now()) FROM users \
+             WHERE now() > DATE '2024-01-01'",
+        )
+        .unwrap();
+        let types = plan
+            .schema()
+            .fields
+            .iter()
+            .map(|f| f.data_type)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![
+                DataType::Timestamp,
+                DataType::Interval,
+                DataType::Int64,
+                DataType::Float64
+            ]
+        );
+
+        let err = analyze(&mut db, "SELECT now() + now() FROM users");
+        assert!(err.is_err());
+
+        let err = analyze(&mut db, "SELECT now() * 2 FROM users");
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn test_analyze_decimal_arithmetic() {
+        let mut db = create_test_database("analyze_decimal_arithmetic");
+
+        let plan = analyze(
+            &mut db,
+            "SELECT age::NUMERIC(5, 2) + 1, age::NUMERIC(5, 2) * 0.5::NUMERIC(3, 1), \
+             age::NUMERIC(5, 2) / 3, age::NUMERIC(5, 2) + 1.5 FROM users \
+             WHERE age::NUMERIC(5, 2) > 19.99",
+        )
+        .unwrap();
+        let types = plan
+            .schema()
+            .fields
+            .iter()
+            .map(|f| f.data_type)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![
+                DataType::Decimal(22, 2),
+                DataType::Decimal(8, 3),
+                DataType::Decimal(38, 6),
+                DataType::Float64,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_analyze_integer_widths() {
+        let mut db = create_test_database("analyze_integer_widths");
+
+        let plan = analyze(
+            &mut db,
+            "SELECT age::SMALLINT + age::INT4, age::SMALLINT * 2 FROM users",
+        )
+        .unwrap();
+        let types = plan
+            .schema()
+            .fields
+            .iter()
+            .map(|f| f.data_type)
+            .collect::<Vec<_>>();
+        assert_eq!(types, vec![DataType::Int32, DataType::Int64]);
+
+        let plan = analyze(&mut db, "SELECT sum(age::SMALLINT) FROM users").unwrap();
+        assert_eq!(plan.schema().fields[0].data_type, DataType::Int64);
+
+        let err = analyze(&mut db, "SELECT X'00' + X'01' FROM users");
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn test_analyze_json_operators() {
+        let mut db = create_test_database("analyze_json_operators");
+
+        let plan = analyze(
+            &mut db,
+            "SELECT name::JSON -> 'a', name::JSON ->> 0, name::JSON #> '{a,b}', \
+             name::JSON #>> '{a}', json_typeof(name::JSON) FROM users",
+        )
+        .unwrap();
+        let types = plan
+            .schema()
+            .fields
+            .iter()
+            .map(|f| f.data_type)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![
+                DataType::Json,
+                DataType::Text,
+                DataType::Json,
+                DataType::Text,
+                DataType::Text
+            ]
+        );
+
+        assert!(analyze(&mut db, "SELECT name -> 'a' FROM users").is_err());
+        assert!(analyze(&mut db, "SELECT name::JSON #> 1 FROM users").is_err());
+    }
 }
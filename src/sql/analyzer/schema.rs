@@ -1,4 +1,4 @@
-use crate::DataType;
+use crate::{DataType, DatabaseError};
 
 #[derive(Debug, Clone)]
 pub struct OutputSchema {
@@ -6,8 +6,36 @@ pub struct OutputSchema {
 }
 
 impl OutputSchema {
-    pub fn find_column(&self, name: &str) -> Option<usize> {
-        self.fields.iter().position(|field| field.name == name)
+    /// Finds a column by its name, qualified by its relation (`u.name`) or not (`name`).
+    ///
+    /// Fails if no column has the name, or if an unqualified name is shared by the
+    /// columns of several relations.
+    pub fn find_column(&self, name: &str) -> Result<usize, DatabaseError> {
+        let qualified = name.split_once('.').and_then(|(relation, column)| {
+            self.fields.iter().position(|field| {
+                field.relation.as_deref() == Some(relation) && field.name == column
+            })
+        });
+        if let Some(index) = qualified {
+            return Ok(index);
+        }
+
+        let mut matches = (0..self.fields.len()).filter(|&index| self.fields[index].name == name);
+        match (matches.next(), matches.next()) {
+            (Some(index), None) => Ok(index),
+            (Some(_), Some(_)) => Err(DatabaseError::InvalidQuery(format!(
+                "Column reference {name} is ambiguous"
+            ))),
+            (None, _) => Err(DatabaseError::ColumnNotFound(name.to_string())),
+        }
+    }
+
+    /// Whether the columns come from more than one relation, their names are then
+    /// qualified when shown.
+    pub(crate) fn has_several_relations(&self) -> bool {
+        self.fields
+            .iter()
+            .any(|field| field.relation != self.fields[0].relation)
     }
 }
 
@@ -15,6 +43,16 @@ impl OutputSchema {
 pub struct Field {
     pub name: String,
     pub alias: Option<String>,
+
+    /// The table the column is read from, by the name that qualifies it in the query.
+    pub relation: Option<String>,
     pub data_type: DataType,
     pub is_nullable: bool,
 }
+
+impl Field {
+    /// The name of the column in a result, its alias if it has one.
+    pub fn output_name(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.name)
+    }
+}
@@ -1,7 +1,10 @@
 use std::fmt;
 
 use crate::{
-    core::types::Value,
+    core::{
+        binary,
+        types::{DataType, Value},
+    },
     sql::ast::{operator::Operator, predicate::IsPredicate},
 };
 
@@ -27,11 +30,33 @@ pub enum Expression {
     /// Literal value (e.g., `25`, `'Alice'`)
     Literal(Value),
 
+    /// Parameter placeholder of a prepared statement (e.g., `$1`), numbered from 1
+    Parameter(usize),
+
     Is {
         expr: Box<Expression>,
         predicate: IsPredicate,
         is_negated: bool,
     },
+
+    /// Function call (e.g., `lower(name)`, `count(*)`)
+    Function { name: String, args: Vec<Expression> },
+
+    /// `CASE [operand] WHEN ... THEN ... [ELSE ...] END`
+    ///
+    /// Without an operand every WHEN is a boolean condition, with an operand
+    /// each WHEN is a value compared against it.
+    Case {
+        operand: Option<Box<Expression>>,
+        branches: Vec<(Expression, Expression)>,
+        else_result: Option<Box<Expression>>,
+    },
+
+    /// Type conversion (e.g., `CAST(age AS TEXT)`, `age::TEXT`)
+    Cast {
+        expr: Box<Expression>,
+        data_type: DataType,
+    },
 }
 
 impl fmt::Display for Expression {
@@ -41,13 +66,23 @@ impl fmt::Display for Expression {
                 write!(f, "({left} {op:?} {right})")
             }
             Expression::Identifier(name) => write!(f, "{name}"),
+            Expression::Parameter(number) => write!(f, "${number}"),
             Expression::Literal(value) => match value {
                 Value::Float64(num) => write!(f, "{num}"),
+                Value::Int16(num) => write!(f, "{num}"),
+                Value::Int32(num) => write!(f, "{num}"),
                 Value::Int64(num) => write!(f, "{num}"),
+                Value::Bytea(bytes) => write!(f, "X'{}'", binary::encode_hex(bytes)),
+                Value::Uuid(_) => write!(f, "'{value}'::UUID"),
                 Value::Text(s) => write!(f, "\"{s}\""),
                 Value::Bool(bool) => {
                     write!(f, "{}", bool.to_string().to_uppercase())
                 }
+                Value::Decimal(d) => write!(f, "{d}"),
+                Value::Timestamp(_) => write!(f, "TIMESTAMP '{value}'"),
+                Value::Date(_) => write!(f, "DATE '{value}'"),
+                Value::Interval(_) => write!(f, "INTERVAL '{value}'"),
+                Value::Json(_) => write!(f, "'{value}'::JSON"),
                 Value::Null => write!(f, "NULL"),
             },
             Expression::Is {
@@ -59,6 +94,34 @@ impl fmt::Display for Expression {
                 "{expr} {} {predicate}",
                 if *is_negated { "IS NOT" } else { "IS" }
             ),
+            Expression::Function { name, args } => {
+                write!(f, "{name}(")?;
+                for (i, arg) in args.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{arg}")?;
+                }
+                write!(f, ")")
+            }
+            Expression::Case {
+                operand,
+                branches,
+                else_result,
+            } => {
+                write!(f, "CASE")?;
+                if let Some(operand) = operand {
+                    write!(f, " {operand}")?;
+                }
+                for (condition, result) in branches {
+                    write!(f, " WHEN {condition} THEN {result}")?;
+                }
+                if let Some(else_result) = else_result {
+                    write!(f, " ELSE {else_result}")?;
+                }
+                write!(f, " END")
+            }
+            Expression::Cast { expr, data_type } => write!(f, "CAST({expr} AS {data_type})"),
         }
     }
 }
@@ -66,8 +129,36 @@ impl fmt::Display for Expression {
 impl Expression {
     pub fn to_column_name(&self) -> &str {
         match self {
-            Expression::Identifier(name) => name,
+            // `u.name` is named after the column, without its relation
+            Expression::Identifier(name) => {
+                name.rsplit_once('.').map_or(name, |(_, column)| column)
+            }
+            Expression::Function { name, .. } => name,
+            Expression::Case { .. } => "case",
+            Expression::Cast { expr, .. } => expr.to_column_name(),
             _ => "?column?",
         }
     }
+
+    /// Returns the direct sub-expressions of this expression.
+    pub fn children(&self) -> Vec<&Expression> {
+        match self {
+            Expression::BinaryOp { left, right, .. } => vec![left, right],
+            Expression::Identifier(_) | Expression::Literal(_) | Expression::Parameter(_) => {
+                Vec::new()
+            }
+            Expression::Is { expr, .. } | Expression::Cast { expr, .. } => vec![expr],
+            Expression::Function { args, .. } => args.iter().collect(),
+            Expression::Case {
+                operand,
+                branches,
+                else_result,
+            } => operand
+                .iter()
+                .map(|operand| &**operand)
+                .chain(branches.iter().flat_map(|(when, then)| [when, then]))
+                .chain(else_result.iter().map(|else_result| &**else_result))
+                .collect(),
+        }
+    }
 }
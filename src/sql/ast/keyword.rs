@@ -27,22 +27,49 @@ pub enum Keyword {
     Into,
     Values,
     As,
+    Group,
+    By,
+
+    Vacuum,
+    Analyze,
+    Explain,
+    Prepare,
+    Execute,
+    Deallocate,
+    All,
 
     Join,
     Inner,
     Left,
     Right,
+    Full,
+    Outer,
+    Cross,
     On,
 
     // Data Types
+    #[strum(serialize = "Smallint", serialize = "Int2", serialize = "Int16")]
+    Smallint,
+    #[strum(serialize = "Int4", serialize = "Int32")]
+    Int32,
     #[strum(serialize = "Int", serialize = "Integer")]
     Integer,
+    #[strum(serialize = "Bigint", serialize = "Int8", serialize = "Int64")]
+    Bigint,
     Float,
+    #[strum(serialize = "Decimal", serialize = "Numeric")]
+    Decimal,
     Varchar,
     #[strum(serialize = "Text", serialize = "String")]
     Text,
+    #[strum(serialize = "Bytea", serialize = "Blob")]
+    Bytea,
+    Uuid,
     Timestamp,
     Date,
+    Interval,
+    #[strum(serialize = "Json", serialize = "Jsonb")]
+    Json,
     #[strum(serialize = "Bool", serialize = "Boolean")]
     Boolean,
 
@@ -59,6 +86,14 @@ pub enum Keyword {
     Null,
 
     Distinct,
+
+    Case,
+    When,
+    Then,
+    Else,
+    End,
+    Cast,
+    Extract,
 }
 
 impl Keyword {
@@ -69,7 +104,20 @@ impl Keyword {
     pub fn is_type(self) -> bool {
         matches!(
             self,
-            Self::Integer | Self::Float | Self::Text | Self::Timestamp | Self::Date | Self::Boolean
+            Self::Smallint
+                | Self::Int32
+                | Self::Integer
+                | Self::Bigint
+                | Self::Float
+                | Self::Decimal
+                | Self::Text
+                | Self::Bytea
+                | Self::Uuid
+                | Self::Timestamp
+                | Self::Date
+                | Self::Interval
+                | Self::Json
+                | Self::Boolean
         )
     }
 }
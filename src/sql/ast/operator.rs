@@ -24,6 +24,15 @@ pub enum Operator {
     Multiply,
     Divide,
     Subtract,
+
+    /// JSON field or element (->)
+    JsonGet,
+    /// JSON field or element as text (->>)
+    JsonGetText,
+    /// JSON value at a path (#>)
+    JsonPath,
+    /// JSON value at a path as text (#>>)
+    JsonPathText,
 }
 
 impl fmt::Display for Operator {
@@ -47,6 +56,10 @@ impl Operator {
             Operator::Subtract => "-",
             Operator::Multiply => "*",
             Operator::Divide => "/",
+            Operator::JsonGet => "->",
+            Operator::JsonGetText => "->>",
+            Operator::JsonPath => "#>",
+            Operator::JsonPathText => "#>>",
         }
     }
 
@@ -64,9 +77,13 @@ impl Operator {
             | Operator::LessThanEqual
             | Operator::GreaterThan
             | Operator::GreaterThanEqual => 5,
+            // Like other non-arithmetic operators in PostgreSQL, looser than `+` and `-`
+            Operator::JsonGet
+            | Operator::JsonGetText
+            | Operator::JsonPath
+            | Operator::JsonPathText => 6,
             Operator::Add | Operator::Subtract => 7,
             Operator::Multiply | Operator::Divide => 10,
         }
     }
 }
-
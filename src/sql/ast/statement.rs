@@ -13,6 +13,12 @@ pub enum Statement {
     Update,
     Insert,
     Delete,
+    Vacuum(VacuumStatement),
+    Analyze(AnalyzeStatement),
+    Explain(ExplainStatement),
+    Prepare(PrepareStatement),
+    Execute(ExecuteStatement),
+    Deallocate(DeallocateStatement),
 }
 
 #[derive(Debug, Clone)]
@@ -20,11 +26,94 @@ pub struct SelectStatement {
     pub select_list: SelectList,
     pub from_clause: FromClause,
     pub where_clause: Option<Expression>,
+    pub group_by: Vec<Expression>,
 }
 
+/// `FROM table [, table | [INNER] JOIN table ON condition | CROSS JOIN table ...]`.
 #[derive(Debug, Clone, PartialEq)]
 pub struct FromClause {
+    pub table: TableReference,
+
+    /// The tables joined to the ones before them, in order.
+    pub joins: Vec<Join>,
+}
+
+impl FromClause {
+    /// The names of the tables read, in the order they appear.
+    pub fn table_names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(&self.table)
+            .chain(self.joins.iter().map(|join| &join.table))
+            .map(|table| table.table_name.as_str())
+    }
+}
+
+/// `table [[AS] alias]`, a table read by a query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableReference {
     pub table_name: String,
+    pub alias: Option<String>,
+}
+
+impl TableReference {
+    /// The name that qualifies the columns of the table in the query.
+    pub fn qualifier(&self) -> &str {
+        self.alias.as_deref().unwrap_or(&self.table_name)
+    }
+}
+
+/// An inner join of `table` to the tables before it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Join {
+    pub table: TableReference,
+
+    /// The condition that pairs of rows are kept on, every pair for a cross join.
+    pub condition: Option<Expression>,
+}
+
+/// `VACUUM [table]`, reclaims the space of deleted rows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VacuumStatement {
+    /// The table to vacuum, every table if `None`.
+    pub table_name: Option<String>,
+}
+
+/// `EXPLAIN [ANALYZE] statement`, shows the plan of a statement.
+#[derive(Debug, Clone)]
+pub struct ExplainStatement {
+    /// Whether the statement is also run, to report what each operator did.
+    pub analyze: bool,
+    pub statement: Box<Statement>,
+}
+
+/// `PREPARE name [(type, ...)] AS statement`, plans a statement to run it with `EXECUTE`.
+#[derive(Debug, Clone)]
+pub struct PrepareStatement {
+    pub name: String,
+
+    /// Types of the first parameters, the types of the others are inferred.
+    pub parameter_types: Vec<DataType>,
+    pub statement: Box<Statement>,
+}
+
+/// `EXECUTE name [(value, ...)]`, runs a prepared statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecuteStatement {
+    pub name: String,
+    pub parameters: Vec<Expression>,
+}
+
+/// `DEALLOCATE [PREPARE] name | ALL`, removes prepared statements.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeallocateStatement {
+    /// The statement to remove, every statement if `None`.
+    pub name: Option<String>,
+}
+
+/// `ANALYZE [table]`, gathers the statistics of a table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnalyzeStatement {
+    /// The table to analyze, every table if `None`.
+    pub table_name: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -1,17 +1,40 @@
-use miette::{IntoDiagnostic, Result};
+use std::sync::Arc;
 
-use crate::db::{database::Database, table::table_def::TableDef};
+use miette::Result;
 
+use crate::{
+    core::temporal,
+    db::{database::Database, table::table_def::TableDef},
+    sql::functions::{AggregateFunction, ScalarFunction},
+};
+
+/// What planning and running one statement needs from the database.
+#[derive(Debug)]
 pub struct CatalogContext<'db> {
-    pub database: &'db mut Database,
+    pub database: &'db Database,
+
+    /// When the statement started, the value of `now()` for all of its rows.
+    pub statement_time: i64,
 }
 
 impl<'db> CatalogContext<'db> {
-    pub(crate) fn new(database: &'db mut Database) -> Self {
-        Self { database }
+    pub(crate) fn new(database: &'db Database) -> Self {
+        Self {
+            database,
+            statement_time: temporal::now(),
+        }
     }
 
     pub fn get_table(&self, table_name: &str) -> Result<&TableDef> {
-        self.database.get_table(table_name).into_diagnostic()
+        // Kept a DatabaseError, so callers can tell what failed
+        Ok(self.database.get_table(table_name)?)
+    }
+
+    pub fn scalar_function(&self, name: &str) -> Option<Arc<ScalarFunction>> {
+        self.database.functions.scalar(name)
+    }
+
+    pub fn aggregate_function(&self, name: &str) -> Option<Arc<AggregateFunction>> {
+        self.database.functions.aggregate(name)
     }
 }
@@ -6,10 +6,7 @@ use crate::{
     sql::{
         analyzer::{AnalyzedExpression, IsPredicateTarget},
         ast::operator::Operator,
-        evaluator::{
-            Evaluator, values_add, values_divide, values_equal, values_greater_than,
-            values_less_than, values_multiply, values_subtract,
-        },
+        evaluator::{Evaluator, binary_operation, cast_value, values_equal},
     },
 };
 
@@ -19,6 +16,9 @@ impl Evaluator<Value> for ExpressionEvaluator {
     fn evaluate(&self, analyzed_expr: &AnalyzedExpression, row: &Row) -> Result<Value> {
         match analyzed_expr {
             AnalyzedExpression::Literal(value) => Ok(value.clone()),
+            AnalyzedExpression::Parameter { number, .. } => {
+                Err(miette!("No value was given for parameter ${number}"))
+            }
             AnalyzedExpression::Column(column_reference, _) => {
                 let row_val = row
                     .get_value(column_reference.index)
@@ -39,48 +39,7 @@ impl Evaluator<Value> for ExpressionEvaluator {
                 let left_val = self.evaluate(left, row)?;
                 let right_val = self.evaluate(right, row)?;
 
-                match op {
-                    // Math
-                    Operator::Add => values_add(&left_val, &right_val),
-                    Operator::Subtract => values_subtract(&left_val, &right_val),
-                    Operator::Multiply => values_multiply(&left_val, &right_val),
-                    Operator::Divide => values_divide(&left_val, &right_val),
-
-                    // Comparisons (These now return Value::Boolean)
-                    Operator::Equal => Ok(values_equal(&left_val, &right_val)),
-                    Operator::NotEqual => {
-                        let eq = match values_equal(&left_val, &right_val) {
-                            Value::Bool(b) => Value::Bool(!b),
-                            Value::Null => Value::Null,
-                            _ => unreachable!("values_equal should only return Boolean or Null"),
-                        };
-
-                        Ok(eq)
-                    }
-                    Operator::GreaterThan => Ok(values_greater_than(&left_val, &right_val)),
-                    Operator::LessThan => Ok(values_less_than(&left_val, &right_val)),
-                    Operator::GreaterThanEqual => {
-                        let less = values_less_than(&left_val, &right_val);
-                        match less {
-                            Value::Bool(b) => Ok(Value::Bool(!b)),
-                            Value::Null => Ok(Value::Null),
-                            _ => unreachable!("Comparison should either return a NULL or BOOL"),
-                        }
-                    }
-                    Operator::LessThanEqual => {
-                        let greater = values_greater_than(&left_val, &right_val);
-                        match greater {
-                            Value::Bool(b) => Ok(Value::Bool(!b)),
-                            Value::Null => Ok(Value::Null),
-                            _ => unreachable!("Comparison should either return a NULL or BOOL"),
-                        }
-                    }
-
-                    _ => Err(miette!(
-                        "Operator {:?} not implemented in ExpressionEvaluator",
-                        op
-                    )),
-                }
+                binary_operation(&left_val, *op, &right_val)
             }
             AnalyzedExpression::IsPredicate {
                 expr,
@@ -103,6 +62,47 @@ impl Evaluator<Value> for ExpressionEvaluator {
 
                 Ok(Value::Bool(bool))
             }
+            AnalyzedExpression::ScalarFunction { function, args, .. } => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.evaluate(arg, row))
+                    .collect::<Result<Vec<_>>>()?;
+
+                Ok(function.invoke(&args)?)
+            }
+            AnalyzedExpression::Case {
+                operand,
+                branches,
+                else_result,
+                ..
+            } => {
+                let operand = operand
+                    .as_ref()
+                    .map(|operand| self.evaluate(operand, row))
+                    .transpose()?;
+
+                for (condition, result) in branches {
+                    let condition = self.evaluate(condition, row)?;
+
+                    let matched = match &operand {
+                        Some(operand) => values_equal(operand, &condition) == Value::Bool(true),
+                        None => Self::is_truthy(&condition),
+                    };
+
+                    if matched {
+                        return self.evaluate(result, row);
+                    }
+                }
+
+                match else_result {
+                    Some(else_result) => self.evaluate(else_result, row),
+                    None => Ok(Value::Null),
+                }
+            }
+            AnalyzedExpression::Cast { expr, data_type } => {
+                let value = self.evaluate(expr, row)?;
+                Ok(cast_value(&value, *data_type)?)
+            }
         }
     }
 }
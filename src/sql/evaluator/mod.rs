@@ -1,59 +1,135 @@
+use std::{borrow::Cow, cmp::Ordering};
+
 use miette::{Result, miette};
 
-use crate::{Row, core::types::Value, sql::analyzer::AnalyzedExpression};
+use crate::{
+    DataType, DatabaseError, Decimal, Interval, Row,
+    core::{
+        binary,
+        json::{self, Json, PathStep},
+        temporal,
+        types::Value,
+    },
+    sql::{analyzer::AnalyzedExpression, ast::operator::Operator},
+};
 
 pub mod expression;
-pub mod predicate;
+pub mod vectorized;
 
-/// The core trait that both evaluators must implement.
+/// Evaluates an expression against a single row.
 ///
 /// - `T = Value` for `ExpressionEvaluator` (math, strings)
-/// - `T = bool` for `PredicateEvaluator` (WHERE clauses)
+///
+/// Execution nodes evaluate whole batches with
+/// [`VectorizedEvaluator`](vectorized::VectorizedEvaluator) instead.
 pub trait Evaluator<T> {
     fn evaluate(&self, analyzed_expr: &AnalyzedExpression, row: &Row) -> Result<T>;
 }
 
 pub fn values_add(left: &Value, right: &Value) -> Result<Value> {
+    if let Some(result) = integer_arithmetic(left, right, i64::checked_add) {
+        return result;
+    }
+    let (left, right) = (&*widen_integer(left), &*widen_integer(right));
+
     match (left, right) {
-        (Value::Int64(a), Value::Int64(b)) => Ok(Value::Int64(a + b)),
         (Value::Float64(a), Value::Float64(b)) => Ok(Value::Float64(a + b)),
         (Value::Int64(a), Value::Float64(b)) => Ok(Value::Float64(*a as f64 + *b)),
         (Value::Float64(a), Value::Int64(b)) => Ok(Value::Float64(*a + *b as f64)),
+        (Value::Timestamp(_) | Value::Date(_), Value::Interval(interval)) => {
+            add_interval(left, interval)
+        }
+        (Value::Interval(interval), Value::Timestamp(_) | Value::Date(_)) => {
+            add_interval(right, interval)
+        }
+        (Value::Date(date), Value::Int64(days)) | (Value::Int64(days), Value::Date(date)) => {
+            add_days(*date, *days)
+        }
+        (Value::Interval(a), Value::Interval(b)) => a
+            .checked_add(b)
+            .map(Value::Interval)
+            .ok_or_else(|| miette!("Interval out of range")),
+        (Value::Decimal(_), _) | (_, Value::Decimal(_)) => {
+            decimal_arithmetic(left, right, Decimal::checked_add, |a, b| a + b)
+        }
         (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
         _ => Err(miette!("Cannot add {:?} and {:?}", left, right)),
     }
 }
 
 pub fn values_subtract(left: &Value, right: &Value) -> Result<Value> {
+    if let Some(result) = integer_arithmetic(left, right, i64::checked_sub) {
+        return result;
+    }
+    let (left, right) = (&*widen_integer(left), &*widen_integer(right));
+
     match (left, right) {
-        (Value::Int64(a), Value::Int64(b)) => Ok(Value::Int64(a - b)),
         (Value::Float64(a), Value::Float64(b)) => Ok(Value::Float64(a - b)),
         (Value::Int64(a), Value::Float64(b)) => Ok(Value::Float64(*a as f64 - *b)),
         (Value::Float64(a), Value::Int64(b)) => Ok(Value::Float64(*a - *b as f64)),
+        (Value::Timestamp(_) | Value::Date(_), Value::Interval(interval)) => {
+            let negated = interval
+                .checked_neg()
+                .ok_or_else(|| miette!("Interval out of range"))?;
+            add_interval(left, &negated)
+        }
+        (Value::Date(a), Value::Date(b)) => Ok(Value::Int64(*a as i64 - *b as i64)),
+        (Value::Date(date), Value::Int64(days)) => add_days(
+            *date,
+            days.checked_neg()
+                .ok_or_else(|| miette!("Date out of range"))?,
+        ),
+        (Value::Timestamp(_) | Value::Date(_), Value::Timestamp(_) | Value::Date(_)) => {
+            let (Some(a), Some(b)) = (as_timestamp(left), as_timestamp(right)) else {
+                unreachable!("both sides are timestamps or dates");
+            };
+            Ok(Value::Interval(temporal::timestamp_difference(a, b)?))
+        }
+        (Value::Interval(a), Value::Interval(b)) => b
+            .checked_neg()
+            .and_then(|b| a.checked_add(&b))
+            .map(Value::Interval)
+            .ok_or_else(|| miette!("Interval out of range")),
+        (Value::Decimal(_), _) | (_, Value::Decimal(_)) => {
+            decimal_arithmetic(left, right, Decimal::checked_sub, |a, b| a - b)
+        }
         (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
         _ => Err(miette!("Cannot subtract {:?} and {:?}", left, right)),
     }
 }
 
 pub fn values_multiply(left: &Value, right: &Value) -> Result<Value> {
+    if let Some(result) = integer_arithmetic(left, right, i64::checked_mul) {
+        return result;
+    }
+    let (left, right) = (&*widen_integer(left), &*widen_integer(right));
+
     match (left, right) {
-        (Value::Int64(a), Value::Int64(b)) => Ok(Value::Int64(a * b)),
         (Value::Float64(a), Value::Float64(b)) => Ok(Value::Float64(a * b)),
         (Value::Int64(a), Value::Float64(b)) => Ok(Value::Float64(*a as f64 * *b)),
         (Value::Float64(a), Value::Int64(b)) => Ok(Value::Float64(*a * *b as f64)),
+        (Value::Interval(interval), factor @ (Value::Int64(_) | Value::Float64(_)))
+        | (factor @ (Value::Int64(_) | Value::Float64(_)), Value::Interval(interval)) => {
+            scale_interval(interval, as_f64(factor))
+        }
+        (Value::Decimal(_), _) | (_, Value::Decimal(_)) => {
+            decimal_arithmetic(left, right, Decimal::checked_mul, |a, b| a * b)
+        }
         (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
         _ => Err(miette!("Cannot multiply {:?} and {:?}", left, right)),
     }
 }
 
 pub fn values_divide(left: &Value, right: &Value) -> Result<Value> {
+    if let (Some(_), Some((0, _))) = (as_integer(left), as_integer(right)) {
+        return Err(miette!("Division by zero"));
+    }
+    if let Some(result) = integer_arithmetic(left, right, i64::checked_div) {
+        return result;
+    }
+    let (left, right) = (&*widen_integer(left), &*widen_integer(right));
+
     match (left, right) {
-        (Value::Int64(a), Value::Int64(b)) => {
-            if *b == 0 {
-                return Err(miette!("Division by zero"));
-            }
-            Ok(Value::Int64(a / b))
-        }
         (Value::Float64(a), Value::Float64(b)) => {
             if *b == 0.0 {
                 return Err(miette!("Division by zero"));
@@ -72,6 +148,21 @@ pub fn values_divide(left: &Value, right: &Value) -> Result<Value> {
             }
             Ok(Value::Float64(*a / *b as f64))
         }
+        (Value::Interval(interval), divisor @ (Value::Int64(_) | Value::Float64(_))) => {
+            let divisor = as_f64(divisor);
+            if divisor == 0.0 {
+                return Err(miette!("Division by zero"));
+            }
+            scale_interval(interval, 1.0 / divisor)
+        }
+        (Value::Decimal(_), _) | (_, Value::Decimal(_)) => {
+            if matches!(right, Value::Decimal(d) if d.mantissa() == 0)
+                || matches!(right, Value::Int64(0))
+            {
+                return Err(miette!("Division by zero"));
+            }
+            decimal_arithmetic(left, right, Decimal::checked_div, |a, b| a / b)
+        }
         (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
         _ => Err(miette!("Cannot divide {:?} and {:?}", left, right)),
     }
@@ -81,6 +172,7 @@ pub fn values_equal(left: &Value, right: &Value) -> Value {
     if matches!(left, Value::Null) || matches!(right, Value::Null) {
         return Value::Null;
     }
+    let (left, right) = (&*widen_integer(left), &*widen_integer(right));
 
     let result = match (left, right) {
         (Value::Int64(a), Value::Int64(b)) => a == b,
@@ -89,7 +181,7 @@ pub fn values_equal(left: &Value, right: &Value) -> Value {
         (Value::Float64(a), Value::Int64(b)) => (a - *b as f64).abs() < f64::EPSILON,
         (Value::Text(a), Value::Text(b)) => a == b,
         (Value::Bool(a), Value::Bool(b)) => a == b,
-        _ => false,
+        _ => compare_values(left, right) == Some(Ordering::Equal),
     };
     Value::Bool(result)
 }
@@ -98,13 +190,14 @@ pub fn values_greater_than(left: &Value, right: &Value) -> Value {
     if matches!(left, Value::Null) || matches!(right, Value::Null) {
         return Value::Null;
     }
+    let (left, right) = (&*widen_integer(left), &*widen_integer(right));
 
     let result = match (left, right) {
         (Value::Int64(a), Value::Int64(b)) => a > b,
         (Value::Float64(a), Value::Float64(b)) => a > b,
         (Value::Int64(a), Value::Float64(b)) => (*a as f64) > *b,
         (Value::Float64(a), Value::Int64(b)) => *a > (*b as f64),
-        _ => false,
+        _ => compare_values(left, right) == Some(Ordering::Greater),
     };
     Value::Bool(result)
 }
@@ -113,17 +206,402 @@ pub fn values_less_than(left: &Value, right: &Value) -> Value {
     if matches!(left, Value::Null) || matches!(right, Value::Null) {
         return Value::Null;
     }
+    let (left, right) = (&*widen_integer(left), &*widen_integer(right));
 
     let result = match (left, right) {
         (Value::Int64(a), Value::Int64(b)) => a < b,
         (Value::Float64(a), Value::Float64(b)) => a < b,
         (Value::Int64(a), Value::Float64(b)) => (*a as f64) < *b,
         (Value::Float64(a), Value::Int64(b)) => *a < (*b as f64),
-        _ => false,
+        _ => compare_values(left, right) == Some(Ordering::Less),
     };
     Value::Bool(result)
 }
 
+/// Applies a binary operator other than AND and OR to two values.
+pub fn binary_operation(left: &Value, op: Operator, right: &Value) -> Result<Value> {
+    match op {
+        // Math
+        Operator::Add => values_add(left, right),
+        Operator::Subtract => values_subtract(left, right),
+        Operator::Multiply => values_multiply(left, right),
+        Operator::Divide => values_divide(left, right),
+
+        // JSON access
+        Operator::JsonGet | Operator::JsonGetText | Operator::JsonPath | Operator::JsonPathText => {
+            json_access(left, op, right)
+        }
+
+        // Comparisons (These now return Value::Boolean)
+        Operator::Equal => Ok(values_equal(left, right)),
+        Operator::NotEqual => Ok(negate(values_equal(left, right))),
+        Operator::GreaterThan => Ok(values_greater_than(left, right)),
+        Operator::LessThan => Ok(values_less_than(left, right)),
+        Operator::GreaterThanEqual => Ok(negate(values_less_than(left, right))),
+        Operator::LessThanEqual => Ok(negate(values_greater_than(left, right))),
+
+        _ => Err(miette!(
+            "Operator {:?} not implemented in ExpressionEvaluator",
+            op
+        )),
+    }
+}
+
+/// Negates the result of a comparison, NULL stays NULL.
+fn negate(comparison: Value) -> Value {
+    match comparison {
+        Value::Bool(b) => Value::Bool(!b),
+        Value::Null => Value::Null,
+        _ => unreachable!("Comparison should either return a NULL or BOOL"),
+    }
+}
+
+/// Applies one of the JSON access operators `->`, `->>`, `#>` and `#>>`.
+///
+/// Missing keys, out of range indexes and, for the text variants, JSON `null` give
+/// SQL `NULL`, so path lookups can be filtered with `IS NULL`.
+pub fn json_access(left: &Value, op: Operator, right: &Value) -> Result<Value> {
+    let Value::Json(document) = left else {
+        return match left {
+            Value::Null => Ok(Value::Null),
+            _ => Err(miette!("Operator {op} expects a JSON value, got {left:?}")),
+        };
+    };
+
+    let path = match (op, &*widen_integer(right)) {
+        (_, Value::Null) => return Ok(Value::Null),
+        (Operator::JsonGet | Operator::JsonGetText, Value::Text(key)) => {
+            vec![PathStep::Key(key.clone())]
+        }
+        (Operator::JsonGet | Operator::JsonGetText, Value::Int64(index)) => {
+            vec![PathStep::Index(*index)]
+        }
+        (Operator::JsonPath | Operator::JsonPathText, Value::Text(path)) => {
+            json::parse_text_path(path)?
+        }
+        (_, other) => return Err(miette!("Invalid operand for {op}: {other:?}")),
+    };
+
+    Ok(json_result(
+        document.get_path(&path),
+        op == Operator::JsonGet || op == Operator::JsonPath,
+    ))
+}
+
+/// Converts the result of a JSON lookup to a SQL value, as JSON or as text.
+pub(crate) fn json_result(found: Option<&Json>, as_json: bool) -> Value {
+    match found {
+        Some(json) if as_json => Value::Json(json.clone()),
+        Some(json) => json.to_text().map_or(Value::Null, Value::Text),
+        None => Value::Null,
+    }
+}
+
+/// Orders values of types without a dedicated comparison arm.
+fn compare_values(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Bytea(a), Value::Bytea(b)) => Some(a.cmp(b)),
+        (Value::Uuid(a), Value::Uuid(b)) => Some(a.cmp(b)),
+        (Value::Json(a), Value::Json(b)) => a.partial_cmp(b),
+        _ => decimal_ordering(left, right).or_else(|| temporal_ordering(left, right)),
+    }
+}
+
+/// The value and type of an integer of any width.
+fn as_integer(value: &Value) -> Option<(i64, DataType)> {
+    match value {
+        Value::Int16(n) => Some((*n as i64, DataType::Int16)),
+        Value::Int32(n) => Some((*n as i64, DataType::Int32)),
+        Value::Int64(n) => Some((*n, DataType::Int64)),
+        _ => None,
+    }
+}
+
+/// Converts smaller integers to `Int64`, so only one integer width needs handling.
+fn widen_integer(value: &Value) -> Cow<'_, Value> {
+    match value {
+        Value::Int16(n) => Cow::Owned(Value::Int64(*n as i64)),
+        Value::Int32(n) => Cow::Owned(Value::Int64(*n as i64)),
+        _ => Cow::Borrowed(value),
+    }
+}
+
+/// Converts an integer to the given integer type, failing if it is out of range.
+fn narrow_integer(n: i64, data_type: DataType) -> Result<Value, DatabaseError> {
+    let out_of_range = || DatabaseError::TypeMismatch(format!("{data_type} out of range: {n}"));
+
+    match data_type {
+        DataType::Int16 => i16::try_from(n)
+            .map(Value::Int16)
+            .map_err(|_| out_of_range()),
+        DataType::Int32 => i32::try_from(n)
+            .map(Value::Int32)
+            .map_err(|_| out_of_range()),
+        _ => Ok(Value::Int64(n)),
+    }
+}
+
+/// Checked arithmetic on two integers, the result has the wider of the two types.
+///
+/// `None` if either side is not an integer.
+fn integer_arithmetic(
+    left: &Value,
+    right: &Value,
+    op: fn(i64, i64) -> Option<i64>,
+) -> Option<Result<Value>> {
+    let (a, left_type) = as_integer(left)?;
+    let (b, right_type) = as_integer(right)?;
+    let data_type = DataType::common_type(left_type, right_type)?;
+
+    let result = match op(a, b) {
+        Some(n) => narrow_integer(n, data_type).map_err(Into::into),
+        None => Err(miette!("{data_type} out of range")),
+    };
+    Some(result)
+}
+
+/// Orders a decimal against another decimal, an integer or a float.
+///
+/// Floats are converted through their shortest representation, so a `19.99` literal
+/// is exactly equal to a stored `19.99`.
+fn decimal_ordering(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Decimal(_), _) | (_, Value::Decimal(_)) => {
+            match (as_decimal(left), as_decimal(right)) {
+                (Some(a), Some(b)) => Some(a.cmp(&b)),
+                // Floats beyond the range of a decimal
+                _ => as_float(left)?.partial_cmp(&as_float(right)?),
+            }
+        }
+        _ => None,
+    }
+}
+
+fn as_decimal(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::Decimal(d) => Some(*d),
+        Value::Int64(n) => Some(Decimal::from(*n)),
+        Value::Float64(n) => Decimal::from_f64(*n),
+        _ => None,
+    }
+}
+
+fn as_float(value: &Value) -> Option<f64> {
+    match value {
+        Value::Decimal(d) => Some(d.to_f64()),
+        Value::Int64(n) => Some(*n as f64),
+        Value::Float64(n) => Some(*n),
+        _ => None,
+    }
+}
+
+/// Applies an exact operation to decimals and integers; with a float involved the
+/// result is a float, as decided by the analyzer.
+fn decimal_arithmetic(
+    left: &Value,
+    right: &Value,
+    decimal_op: fn(&Decimal, &Decimal) -> Option<Decimal>,
+    float_op: fn(f64, f64) -> f64,
+) -> Result<Value> {
+    match (left, right) {
+        (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+        (Value::Decimal(_) | Value::Int64(_), Value::Decimal(_) | Value::Int64(_)) => {
+            let (Some(a), Some(b)) = (as_decimal(left), as_decimal(right)) else {
+                unreachable!("integers and decimals are always decimals");
+            };
+            decimal_op(&a, &b)
+                .map(Value::Decimal)
+                .ok_or_else(|| miette!("Numeric value out of range"))
+        }
+        (Value::Decimal(_) | Value::Float64(_), Value::Decimal(_) | Value::Float64(_)) => {
+            let (Some(a), Some(b)) = (as_float(left), as_float(right)) else {
+                unreachable!("floats and decimals are always floats");
+            };
+            Ok(Value::Float64(float_op(a, b)))
+        }
+        _ => Err(miette!(
+            "Unsupported numeric operation on {:?} and {:?}",
+            left,
+            right
+        )),
+    }
+}
+
+/// Orders two date/time values, comparing a date as midnight of that day.
+///
+/// Intervals are compared by their length, counting a month as 30 days.
+fn temporal_ordering(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Date(a), Value::Date(b)) => Some(a.cmp(b)),
+        (Value::Interval(a), Value::Interval(b)) => Some(a.total_micros().cmp(&b.total_micros())),
+        _ => Some(as_timestamp(left)?.cmp(&as_timestamp(right)?)),
+    }
+}
+
+fn as_timestamp(value: &Value) -> Option<i64> {
+    match value {
+        Value::Timestamp(ts) => Some(*ts),
+        Value::Date(days) => Some(*days as i64 * temporal::MICROS_PER_DAY),
+        _ => None,
+    }
+}
+
+fn as_f64(value: &Value) -> f64 {
+    match value {
+        Value::Int64(n) => *n as f64,
+        Value::Float64(n) => *n,
+        _ => unreachable!("only called with numbers"),
+    }
+}
+
+/// Adds an interval to a timestamp or date, the result is always a timestamp.
+fn add_interval(value: &Value, interval: &Interval) -> Result<Value> {
+    let timestamp =
+        as_timestamp(value).ok_or_else(|| miette!("Cannot add interval to {value:?}"))?;
+    Ok(Value::Timestamp(temporal::add_interval(
+        timestamp, interval,
+    )?))
+}
+
+fn add_days(date: i32, days: i64) -> Result<Value> {
+    i32::try_from(date as i64 + days)
+        .map(Value::Date)
+        .map_err(|_| miette!("Date out of range"))
+}
+
+fn scale_interval(interval: &Interval, factor: f64) -> Result<Value> {
+    interval
+        .mul_f64(factor)
+        .map(Value::Interval)
+        .ok_or_else(|| miette!("Interval out of range"))
+}
+
+/// Converts `value` to `data_type`, as done by `CAST(value AS data_type)`.
+///
+/// NULL converts to NULL of any type. Fails with [`DatabaseError::TypeMismatch`] if the
+/// conversion is not supported or the value has no representation in the target type.
+pub fn cast_value(value: &Value, data_type: DataType) -> Result<Value, DatabaseError> {
+    let invalid = || DatabaseError::TypeMismatch(format!("Cannot cast {value:?} to {data_type}"));
+
+    // Smaller integers convert like Int64, with a range check when narrowing
+    if let Value::Int16(_) | Value::Int32(_) = value {
+        return cast_value(&widen_integer(value), data_type);
+    }
+    if let DataType::Int16 | DataType::Int32 = data_type {
+        return match cast_value(value, DataType::Int64)? {
+            Value::Int64(n) => narrow_integer(n, data_type),
+            other => Ok(other),
+        };
+    }
+
+    let cast = match (value, data_type) {
+        (Value::Null, _) => Value::Null,
+
+        (Value::Int64(_), DataType::Int64)
+        | (Value::Float64(_), DataType::Float64)
+        | (Value::Bool(_), DataType::Bool)
+        | (Value::Text(_), DataType::Text)
+        | (Value::Timestamp(_), DataType::Timestamp)
+        | (Value::Date(_), DataType::Date)
+        | (Value::Interval(_), DataType::Interval)
+        | (Value::Bytea(_), DataType::Bytea)
+        | (Value::Uuid(_), DataType::Uuid)
+        | (Value::Json(_), DataType::Json) => value.clone(),
+
+        (Value::Int64(n), DataType::Float64) => Value::Float64(*n as f64),
+        (Value::Int64(n), DataType::Bool) => Value::Bool(*n != 0),
+        (Value::Float64(n), DataType::Int64) => {
+            let rounded = n.round_ties_even();
+            // i64::MAX as f64 rounds up to 2^63, which is already out of range
+            if !rounded.is_finite() || rounded < i64::MIN as f64 || rounded >= i64::MAX as f64 {
+                return Err(DatabaseError::TypeMismatch(format!(
+                    "Float {n} is out of range for {data_type}"
+                )));
+            }
+            Value::Int64(rounded as i64)
+        }
+        (Value::Bool(b), DataType::Int64) => Value::Int64(i64::from(*b)),
+
+        (Value::Text(s), DataType::Int64) => {
+            Value::Int64(s.trim().parse::<i64>().map_err(|_| {
+                DatabaseError::TypeMismatch(format!(
+                    "Invalid input syntax for type {data_type}: \"{s}\""
+                ))
+            })?)
+        }
+        (Value::Text(s), DataType::Float64) => {
+            Value::Float64(s.trim().parse::<f64>().map_err(|_| {
+                DatabaseError::TypeMismatch(format!(
+                    "Invalid input syntax for type {data_type}: \"{s}\""
+                ))
+            })?)
+        }
+        (Value::Text(s), DataType::Bool) => match s.trim().to_lowercase().as_str() {
+            "t" | "true" | "yes" | "on" | "1" => Value::Bool(true),
+            "f" | "false" | "no" | "off" | "0" => Value::Bool(false),
+            _ => {
+                return Err(DatabaseError::TypeMismatch(format!(
+                    "Invalid input syntax for type {data_type}: \"{s}\""
+                )));
+            }
+        },
+
+        (Value::Decimal(d), DataType::Decimal(precision, scale)) => {
+            Value::Decimal(d.round_to(precision, scale)?)
+        }
+        (Value::Int64(n), DataType::Decimal(precision, scale)) => {
+            Value::Decimal(Decimal::from(*n).round_to(precision, scale)?)
+        }
+        (Value::Float64(n), DataType::Decimal(precision, scale)) => {
+            let decimal = Decimal::from_f64(*n).ok_or_else(|| {
+                DatabaseError::TypeMismatch(format!("Float {n} is out of range for {data_type}"))
+            })?;
+            Value::Decimal(decimal.round_to(precision, scale)?)
+        }
+        (Value::Text(s), DataType::Decimal(precision, scale)) => {
+            Value::Decimal(s.parse::<Decimal>()?.round_to(precision, scale)?)
+        }
+        (Value::Decimal(d), DataType::Float64) => Value::Float64(d.to_f64()),
+        (Value::Decimal(d), DataType::Int64) => Value::Int64(d.to_i64().ok_or_else(|| {
+            DatabaseError::TypeMismatch(format!("Numeric {d} is out of range for {data_type}"))
+        })?),
+        (Value::Text(s), DataType::Bytea) => Value::Bytea(binary::parse_bytea(s)?),
+        (Value::Text(s), DataType::Uuid) => Value::Uuid(binary::parse_uuid(s)?),
+        (Value::Text(s), DataType::Json) => Value::Json(Json::parse(s)?),
+        (Value::Text(s), DataType::Timestamp) => Value::Timestamp(temporal::parse_timestamp(s)?),
+        (Value::Text(s), DataType::Date) => Value::Date(temporal::parse_date(s)?),
+        (Value::Text(s), DataType::Interval) => Value::Interval(temporal::parse_interval(s)?),
+        (Value::Date(days), DataType::Timestamp) => {
+            Value::Timestamp(*days as i64 * temporal::MICROS_PER_DAY)
+        }
+        (Value::Timestamp(ts), DataType::Date) => {
+            let days = ts.div_euclid(temporal::MICROS_PER_DAY);
+            Value::Date(i32::try_from(days).map_err(|_| invalid())?)
+        }
+
+        (value, DataType::Text) => Value::Text(value.to_string()),
+        (value, DataType::VarChar(max_len)) => {
+            let Value::Text(mut text) = cast_value(value, DataType::Text)? else {
+                return Err(invalid());
+            };
+
+            // An explicit cast truncates to the maximum length instead of failing
+            if text.len() > max_len {
+                let mut end = max_len;
+                while !text.is_char_boundary(end) {
+                    end -= 1;
+                }
+                text.truncate(end);
+            }
+            Value::Text(text)
+        }
+
+        _ => return Err(invalid()),
+    };
+
+    Ok(cast)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -306,9 +784,243 @@ mod tests {
         assert_eq!(result, Value::Bool(false));
     }
 
+    #[test]
+    fn test_cast_numbers() {
+        assert_eq!(
+            cast_value(&Value::Int64(3), DataType::Float64).unwrap(),
+            Value::Float64(3.0)
+        );
+        assert_eq!(
+            cast_value(&Value::Float64(2.5), DataType::Int64).unwrap(),
+            Value::Int64(2)
+        );
+        assert_eq!(
+            cast_value(&Value::Float64(-3.7), DataType::Int64).unwrap(),
+            Value::Int64(-4)
+        );
+        assert!(cast_value(&Value::Float64(1e20), DataType::Int64).is_err());
+        assert!(cast_value(&Value::Float64(f64::NAN), DataType::Int64).is_err());
+    }
+
+    #[test]
+    fn test_cast_text() {
+        assert_eq!(
+            cast_value(&Value::Text(" 42 ".to_string()), DataType::Int64).unwrap(),
+            Value::Int64(42)
+        );
+        assert_eq!(
+            cast_value(&Value::Text("yes".to_string()), DataType::Bool).unwrap(),
+            Value::Bool(true)
+        );
+        assert_eq!(
+            cast_value(&Value::Float64(1.5), DataType::Text).unwrap(),
+            Value::Text("1.5".to_string())
+        );
+        assert_eq!(
+            cast_value(&Value::Text("héllo".to_string()), DataType::VarChar(2)).unwrap(),
+            Value::Text("h".to_string())
+        );
+
+        let err = cast_value(&Value::Text("abc".to_string()), DataType::Int64).unwrap_err();
+        assert!(matches!(err, DatabaseError::TypeMismatch(_)));
+    }
+
+    #[test]
+    fn test_cast_null_and_unsupported() {
+        assert_eq!(
+            cast_value(&Value::Null, DataType::Bool).unwrap(),
+            Value::Null
+        );
+        assert!(cast_value(&Value::Float64(1.0), DataType::Bool).is_err());
+    }
+
     #[test]
     fn test_values_less_than_with_null() {
         let result = values_less_than(&Value::Null, &Value::Int64(10));
         assert_eq!(result, Value::Null);
     }
+
+    fn decimal(s: &str) -> Value {
+        Value::Decimal(s.parse().unwrap())
+    }
+
+    #[test]
+    fn test_decimal_arithmetic_is_exact() {
+        let sum = values_add(&decimal("0.1"), &decimal("0.2")).unwrap();
+        assert_eq!(values_equal(&sum, &decimal("0.3")), Value::Bool(true));
+        assert_eq!(sum.to_string(), "0.3");
+
+        let total = values_multiply(&decimal("19.99"), &Value::Int64(3)).unwrap();
+        assert_eq!(total.to_string(), "59.97");
+
+        let share = values_divide(&decimal("10.00"), &Value::Int64(3)).unwrap();
+        assert_eq!(share.to_string(), "3.333333");
+
+        assert!(values_divide(&decimal("1"), &decimal("0.0")).is_err());
+
+        // A float operand makes the result a float
+        let mixed = values_add(&decimal("1.5"), &Value::Float64(0.25)).unwrap();
+        assert_eq!(mixed, Value::Float64(1.75));
+    }
+
+    #[test]
+    fn test_decimal_comparisons() {
+        assert_eq!(
+            values_equal(&decimal("19.99"), &Value::Float64(19.99)),
+            Value::Bool(true)
+        );
+        assert_eq!(
+            values_equal(&decimal("2.00"), &Value::Int64(2)),
+            Value::Bool(true)
+        );
+        assert_eq!(
+            values_greater_than(&decimal("0.30"), &decimal("0.3")),
+            Value::Bool(false)
+        );
+        assert_eq!(
+            values_less_than(&Value::Int64(-1), &decimal("-0.5")),
+            Value::Bool(true)
+        );
+    }
+
+    #[test]
+    fn test_cast_decimal() {
+        assert_eq!(
+            cast_value(&Value::Float64(2.345), DataType::Decimal(5, 2))
+                .unwrap()
+                .to_string(),
+            "2.35"
+        );
+        assert_eq!(
+            cast_value(&Value::Text("-2.5".to_string()), DataType::Decimal(3, 0)).unwrap(),
+            decimal("-3")
+        );
+        assert_eq!(
+            cast_value(&decimal("7.5"), DataType::Int64).unwrap(),
+            Value::Int64(8)
+        );
+        assert!(cast_value(&Value::Int64(1000), DataType::Decimal(5, 2)).is_err());
+    }
+
+    #[test]
+    fn test_small_integer_overflow() {
+        assert_eq!(
+            values_add(&Value::Int16(100), &Value::Int16(27)).unwrap(),
+            Value::Int16(127)
+        );
+        assert!(values_add(&Value::Int16(i16::MAX), &Value::Int16(1)).is_err());
+        assert!(values_multiply(&Value::Int32(i32::MAX), &Value::Int16(2)).is_err());
+        assert!(values_add(&Value::Int64(i64::MAX), &Value::Int64(1)).is_err());
+
+        // The result takes the wider type
+        assert_eq!(
+            values_multiply(&Value::Int16(i16::MAX), &Value::Int32(2)).unwrap(),
+            Value::Int32(i16::MAX as i32 * 2)
+        );
+        assert_eq!(
+            values_add(&Value::Int32(1), &Value::Float64(0.5)).unwrap(),
+            Value::Float64(1.5)
+        );
+        assert!(values_divide(&Value::Int16(1), &Value::Int32(0)).is_err());
+        assert_eq!(
+            values_equal(&Value::Int16(3), &Value::Int64(3)),
+            Value::Bool(true)
+        );
+    }
+
+    #[test]
+    fn test_binary_values() {
+        let uuid = cast_value(
+            &Value::Text("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11".to_string()),
+            DataType::Uuid,
+        )
+        .unwrap();
+        assert_eq!(
+            cast_value(&uuid, DataType::Text).unwrap(),
+            Value::Text("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11".to_string())
+        );
+        assert_eq!(values_equal(&uuid, &uuid), Value::Bool(true));
+        assert!(cast_value(&Value::Text("nope".to_string()), DataType::Uuid).is_err());
+
+        let bytes = cast_value(&Value::Text("\\x0aff".to_string()), DataType::Bytea).unwrap();
+        assert_eq!(bytes, Value::Bytea(vec![0x0A, 0xFF]));
+        assert_eq!(
+            values_less_than(&bytes, &Value::Bytea(vec![0x0B])),
+            Value::Bool(true)
+        );
+    }
+
+    #[test]
+    fn test_cast_small_integers() {
+        assert_eq!(
+            cast_value(&Value::Int64(300), DataType::Int16).unwrap(),
+            Value::Int16(300)
+        );
+        assert!(cast_value(&Value::Int64(40_000), DataType::Int16).is_err());
+        assert_eq!(
+            cast_value(&Value::Text("-5".to_string()), DataType::Int32).unwrap(),
+            Value::Int32(-5)
+        );
+        assert_eq!(
+            cast_value(&Value::Int16(7), DataType::Float64).unwrap(),
+            Value::Float64(7.0)
+        );
+        assert_eq!(
+            cast_value(&Value::Null, DataType::Int16).unwrap(),
+            Value::Null
+        );
+    }
+
+    #[test]
+    fn test_json_access() {
+        let document = cast_value(
+            &Value::Text(r#"{"kind": "click", "tags": ["a", "b"], "meta": {"x": null}}"#.into()),
+            DataType::Json,
+        )
+        .unwrap();
+        let text = |s: &str| Value::Text(s.to_string());
+
+        assert_eq!(
+            json_access(&document, Operator::JsonGetText, &text("kind")).unwrap(),
+            text("click")
+        );
+        assert_eq!(
+            json_access(&document, Operator::JsonGet, &text("kind")).unwrap(),
+            Value::Json(Json::String("click".to_string()))
+        );
+        assert_eq!(
+            json_access(&document, Operator::JsonPathText, &text("{tags,1}")).unwrap(),
+            text("b")
+        );
+
+        let tags = json_access(&document, Operator::JsonGet, &text("tags")).unwrap();
+        assert_eq!(
+            json_access(&tags, Operator::JsonGetText, &Value::Int32(-1)).unwrap(),
+            text("b")
+        );
+        assert_eq!(
+            json_access(&tags, Operator::JsonGetText, &Value::Int64(5)).unwrap(),
+            Value::Null
+        );
+
+        // JSON null is SQL NULL as text, but stays a JSON value otherwise
+        assert_eq!(
+            json_access(&document, Operator::JsonPathText, &text("{meta,x}")).unwrap(),
+            Value::Null
+        );
+        assert_eq!(
+            json_access(&document, Operator::JsonPath, &text("{meta,x}")).unwrap(),
+            Value::Json(Json::Null)
+        );
+        assert_eq!(
+            json_access(&Value::Null, Operator::JsonGet, &text("kind")).unwrap(),
+            Value::Null
+        );
+
+        assert!(cast_value(&text("{oops"), DataType::Json).is_err());
+        assert_eq!(
+            values_equal(&document, &document.clone()),
+            Value::Bool(true)
+        );
+    }
 }
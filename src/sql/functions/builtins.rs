@@ -0,0 +1,481 @@
+use std::cmp::Ordering;
+
+use crate::{
+    DataType, DatabaseError, Value,
+    core::{
+        binary,
+        decimal::{Decimal, MAX_PRECISION},
+    },
+    sql::functions::{Accumulator, FunctionRegistry, ReturnType, Signature},
+};
+
+type BuiltinScalar = fn(&[Value]) -> Result<Value, DatabaseError>;
+type BuiltinAggregate = fn() -> Box<dyn Accumulator>;
+
+/// Registers the built-in scalar and aggregate functions.
+pub(crate) fn register(registry: &mut FunctionRegistry) {
+    let numeric = vec![
+        DataType::Int64,
+        DataType::Float64,
+        DataType::Decimal(MAX_PRECISION, 0),
+    ];
+
+    let scalars: [(&str, Signature, BuiltinScalar); 6] = [
+        (
+            "abs",
+            Signature::uniform(1, numeric.clone(), ReturnType::SameAsArgument(0)),
+            abs,
+        ),
+        (
+            "lower",
+            Signature::exact(vec![DataType::Text], DataType::Text),
+            lower,
+        ),
+        (
+            "upper",
+            Signature::exact(vec![DataType::Text], DataType::Text),
+            upper,
+        ),
+        (
+            "length",
+            Signature::exact(vec![DataType::Text], DataType::Int64),
+            length,
+        ),
+        (
+            "coalesce",
+            Signature::common(ReturnType::SameAsArgument(0)),
+            coalesce,
+        ),
+        (
+            "gen_random_uuid",
+            Signature::exact(vec![], DataType::Uuid),
+            gen_random_uuid,
+        ),
+    ];
+
+    for (name, signature, func) in scalars {
+        registry
+            .register_scalar(name, signature, Box::new(func))
+            .expect("built-in function names are unique");
+    }
+
+    let aggregates: [(&str, Signature, BuiltinAggregate); 5] = [
+        (
+            "count",
+            Signature::any(1, ReturnType::Fixed(DataType::Int64)),
+            || Box::new(CountAccumulator::default()),
+        ),
+        (
+            "sum",
+            Signature::uniform(1, numeric.clone(), ReturnType::WidenedArgument(0)),
+            || Box::new(SumAccumulator::default()),
+        ),
+        (
+            "avg",
+            Signature::uniform(1, numeric, ReturnType::Quotient(0)),
+            || Box::new(AvgAccumulator::default()),
+        ),
+        (
+            "min",
+            Signature::any(1, ReturnType::SameAsArgument(0)),
+            || Box::new(ExtremumAccumulator::new(Ordering::Less)),
+        ),
+        (
+            "max",
+            Signature::any(1, ReturnType::SameAsArgument(0)),
+            || Box::new(ExtremumAccumulator::new(Ordering::Greater)),
+        ),
+    ];
+
+    for (name, signature, init) in aggregates {
+        registry
+            .register_aggregate(name, signature, Box::new(init))
+            .expect("built-in function names are unique");
+    }
+}
+
+fn abs(args: &[Value]) -> Result<Value, DatabaseError> {
+    let out_of_range = || DatabaseError::TypeMismatch("Integer out of range in abs".to_string());
+
+    match &args[0] {
+        Value::Int16(n) => n.checked_abs().map(Value::Int16).ok_or_else(out_of_range),
+        Value::Int32(n) => n.checked_abs().map(Value::Int32).ok_or_else(out_of_range),
+        Value::Int64(n) => n.checked_abs().map(Value::Int64).ok_or_else(out_of_range),
+        Value::Float64(n) => Ok(Value::Float64(n.abs())),
+        Value::Decimal(d) => Ok(Value::Decimal(d.abs())),
+        Value::Null => Ok(Value::Null),
+        other => Err(DatabaseError::TypeMismatch(format!(
+            "abs expects a number, got {other:?}"
+        ))),
+    }
+}
+
+fn lower(args: &[Value]) -> Result<Value, DatabaseError> {
+    match &args[0] {
+        Value::Text(s) => Ok(Value::Text(s.to_lowercase())),
+        Value::Null => Ok(Value::Null),
+        other => Err(DatabaseError::TypeMismatch(format!(
+            "lower expects text, got {other:?}"
+        ))),
+    }
+}
+
+fn upper(args: &[Value]) -> Result<Value, DatabaseError> {
+    match &args[0] {
+        Value::Text(s) => Ok(Value::Text(s.to_uppercase())),
+        Value::Null => Ok(Value::Null),
+        other => Err(DatabaseError::TypeMismatch(format!(
+            "upper expects text, got {other:?}"
+        ))),
+    }
+}
+
+fn length(args: &[Value]) -> Result<Value, DatabaseError> {
+    match &args[0] {
+        Value::Text(s) => Ok(Value::Int64(s.chars().count() as i64)),
+        Value::Null => Ok(Value::Null),
+        other => Err(DatabaseError::TypeMismatch(format!(
+            "length expects text, got {other:?}"
+        ))),
+    }
+}
+
+fn gen_random_uuid(_args: &[Value]) -> Result<Value, DatabaseError> {
+    Ok(Value::Uuid(binary::random_uuid()))
+}
+
+fn coalesce(args: &[Value]) -> Result<Value, DatabaseError> {
+    Ok(args
+        .iter()
+        .find(|value| !matches!(value, Value::Null))
+        .cloned()
+        .unwrap_or(Value::Null))
+}
+
+/// `count(*)` counts every row, `count(expr)` only the non-null ones.
+#[derive(Debug, Default)]
+struct CountAccumulator {
+    count: i64,
+}
+
+impl Accumulator for CountAccumulator {
+    fn update(&mut self, args: &[Value]) -> Result<(), DatabaseError> {
+        if !matches!(args.first(), Some(Value::Null)) {
+            self.count += 1;
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, state: &[Value]) -> Result<(), DatabaseError> {
+        match state {
+            [Value::Int64(count)] => {
+                self.count += count;
+                Ok(())
+            }
+            _ => Err(invalid_state("count", state)),
+        }
+    }
+
+    fn state(&self) -> Vec<Value> {
+        vec![Value::Int64(self.count)]
+    }
+
+    fn finalize(&self) -> Result<Value, DatabaseError> {
+        Ok(Value::Int64(self.count))
+    }
+}
+
+#[derive(Debug)]
+struct SumAccumulator {
+    sum: Value,
+}
+
+impl Default for SumAccumulator {
+    fn default() -> Self {
+        Self { sum: Value::Null }
+    }
+}
+
+impl Accumulator for SumAccumulator {
+    fn update(&mut self, args: &[Value]) -> Result<(), DatabaseError> {
+        // Integers of every width are summed as Int64
+        let value = match &args[0] {
+            Value::Int16(n) => &Value::Int64(*n as i64),
+            Value::Int32(n) => &Value::Int64(*n as i64),
+            value => value,
+        };
+
+        self.sum = match (&self.sum, value) {
+            (_, Value::Null) => return Ok(()),
+            (Value::Null, value) => value.clone(),
+            (Value::Int64(a), Value::Int64(b)) => {
+                Value::Int64(a.checked_add(*b).ok_or_else(|| {
+                    DatabaseError::TypeMismatch("Integer out of range in sum".to_string())
+                })?)
+            }
+            (Value::Float64(a), Value::Float64(b)) => Value::Float64(a + b),
+            (Value::Decimal(a), Value::Decimal(b)) => {
+                Value::Decimal(a.checked_add(b).ok_or_else(|| {
+                    DatabaseError::TypeMismatch("Numeric value out of range in sum".to_string())
+                })?)
+            }
+            (sum, value) => {
+                return Err(DatabaseError::TypeMismatch(format!(
+                    "Cannot add {value:?} to sum {sum:?}"
+                )));
+            }
+        };
+        Ok(())
+    }
+
+    fn merge(&mut self, state: &[Value]) -> Result<(), DatabaseError> {
+        match state {
+            [partial] => self.update(std::slice::from_ref(partial)),
+            _ => Err(invalid_state("sum", state)),
+        }
+    }
+
+    fn state(&self) -> Vec<Value> {
+        vec![self.sum.clone()]
+    }
+
+    fn finalize(&self) -> Result<Value, DatabaseError> {
+        Ok(self.sum.clone())
+    }
+}
+
+/// Sums integers and floats as Float64 and decimals exactly, the average has the type
+/// of the sum.
+#[derive(Debug, Default)]
+struct AvgAccumulator {
+    sum: SumAccumulator,
+    count: i64,
+}
+
+impl Accumulator for AvgAccumulator {
+    fn update(&mut self, args: &[Value]) -> Result<(), DatabaseError> {
+        let value = match &args[0] {
+            Value::Null => return Ok(()),
+            Value::Int16(n) => Value::Float64(*n as f64),
+            Value::Int32(n) => Value::Float64(*n as f64),
+            Value::Int64(n) => Value::Float64(*n as f64),
+            value @ (Value::Float64(_) | Value::Decimal(_)) => value.clone(),
+            other => {
+                return Err(DatabaseError::TypeMismatch(format!(
+                    "avg expects a number, got {other:?}"
+                )));
+            }
+        };
+        self.sum.update(&[value])?;
+        self.count += 1;
+        Ok(())
+    }
+
+    fn merge(&mut self, state: &[Value]) -> Result<(), DatabaseError> {
+        match state {
+            [sum, Value::Int64(count)] => {
+                self.sum.update(std::slice::from_ref(sum))?;
+                self.count += count;
+                Ok(())
+            }
+            _ => Err(invalid_state("avg", state)),
+        }
+    }
+
+    fn state(&self) -> Vec<Value> {
+        vec![self.sum.sum.clone(), Value::Int64(self.count)]
+    }
+
+    fn finalize(&self) -> Result<Value, DatabaseError> {
+        match &self.sum.sum {
+            _ if self.count == 0 => Ok(Value::Null),
+            Value::Float64(sum) => Ok(Value::Float64(sum / self.count as f64)),
+            Value::Decimal(sum) => sum
+                .checked_div(&Decimal::from(self.count))
+                .map(Value::Decimal)
+                .ok_or_else(|| {
+                    DatabaseError::TypeMismatch("Numeric value out of range in avg".to_string())
+                }),
+            sum => Err(invalid_state("avg", std::slice::from_ref(sum))),
+        }
+    }
+}
+
+/// Shared implementation of `min` and `max`, keeps the value ordered `keep` relative to the rest.
+#[derive(Debug)]
+struct ExtremumAccumulator {
+    keep: Ordering,
+    current: Value,
+}
+
+impl ExtremumAccumulator {
+    fn new(keep: Ordering) -> Self {
+        Self {
+            keep,
+            current: Value::Null,
+        }
+    }
+}
+
+impl Accumulator for ExtremumAccumulator {
+    fn update(&mut self, args: &[Value]) -> Result<(), DatabaseError> {
+        let value = &args[0];
+        if matches!(value, Value::Null) {
+            return Ok(());
+        }
+
+        if matches!(self.current, Value::Null)
+            || value.partial_cmp(&self.current) == Some(self.keep)
+        {
+            self.current = value.clone();
+        }
+        Ok(())
+    }
+
+    fn merge(&mut self, state: &[Value]) -> Result<(), DatabaseError> {
+        match state {
+            [partial] => self.update(std::slice::from_ref(partial)),
+            _ => Err(invalid_state("min/max", state)),
+        }
+    }
+
+    fn state(&self) -> Vec<Value> {
+        vec![self.current.clone()]
+    }
+
+    fn finalize(&self) -> Result<Value, DatabaseError> {
+        Ok(self.current.clone())
+    }
+}
+
+fn invalid_state(name: &str, state: &[Value]) -> DatabaseError {
+    DatabaseError::SerializationError(format!("Invalid {name} state: {state:?}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(accumulator: &mut dyn Accumulator, values: &[Value]) {
+        for value in values {
+            accumulator
+                .update(std::slice::from_ref(value))
+                .expect("update to succeed");
+        }
+    }
+
+    #[test]
+    fn test_count_skips_nulls() {
+        let mut count = CountAccumulator::default();
+        run(&mut count, &[Value::Int64(1), Value::Null, Value::Int64(3)]);
+        assert_eq!(count.finalize().unwrap(), Value::Int64(2));
+
+        let mut count_star = CountAccumulator::default();
+        count_star.update(&[]).unwrap();
+        count_star.update(&[]).unwrap();
+        assert_eq!(count_star.finalize().unwrap(), Value::Int64(2));
+    }
+
+    #[test]
+    fn test_sum_and_merge() {
+        let mut left = SumAccumulator::default();
+        run(&mut left, &[Value::Int64(1), Value::Int64(2)]);
+
+        let mut right = SumAccumulator::default();
+        run(&mut right, &[Value::Null, Value::Int64(4)]);
+
+        left.merge(&right.state()).unwrap();
+        assert_eq!(left.finalize().unwrap(), Value::Int64(7));
+
+        let mut decimals = SumAccumulator::default();
+        run(
+            &mut decimals,
+            &[
+                Value::Decimal("0.10".parse().unwrap()),
+                Value::Decimal("0.20".parse().unwrap()),
+            ],
+        );
+        assert_eq!(decimals.finalize().unwrap().to_string(), "0.30".to_string());
+
+        let empty = SumAccumulator::default();
+        assert_eq!(empty.finalize().unwrap(), Value::Null);
+
+        let mut small = SumAccumulator::default();
+        run(&mut small, &[Value::Int16(i16::MAX), Value::Int16(1)]);
+        assert_eq!(small.finalize().unwrap(), Value::Int64(i16::MAX as i64 + 1));
+    }
+
+    #[test]
+    fn test_sum_overflow() {
+        let mut sum = SumAccumulator::default();
+        run(&mut sum, &[Value::Int64(i64::MAX)]);
+        assert!(sum.update(&[Value::Int64(1)]).is_err());
+    }
+
+    #[test]
+    fn test_avg() {
+        let mut avg = AvgAccumulator::default();
+        run(
+            &mut avg,
+            &[Value::Int64(1), Value::Float64(2.0), Value::Null],
+        );
+        assert_eq!(avg.finalize().unwrap(), Value::Float64(1.5));
+
+        let mut left = AvgAccumulator::default();
+        run(
+            &mut left,
+            &[
+                Value::Decimal("0.10".parse().unwrap()),
+                Value::Decimal("0.20".parse().unwrap()),
+            ],
+        );
+        let mut right = AvgAccumulator::default();
+        run(&mut right, &[Value::Decimal("0.20".parse().unwrap())]);
+        left.merge(&right.state()).unwrap();
+        assert_eq!(left.finalize().unwrap().to_string(), "0.166667");
+
+        // Decimals beyond the precision of a float stay exact
+        let mut large = AvgAccumulator::default();
+        let value = Value::Decimal("12345678901234567890.12".parse().unwrap());
+        run(&mut large, &[value.clone(), value.clone()]);
+        assert_eq!(large.finalize().unwrap(), value);
+
+        let empty = AvgAccumulator::default();
+        assert_eq!(empty.finalize().unwrap(), Value::Null);
+    }
+
+    #[test]
+    fn test_min_max() {
+        let values = [
+            Value::Text("b".to_string()),
+            Value::Null,
+            Value::Text("a".to_string()),
+            Value::Text("c".to_string()),
+        ];
+
+        let mut min = ExtremumAccumulator::new(Ordering::Less);
+        run(&mut min, &values);
+        assert_eq!(min.finalize().unwrap(), Value::Text("a".to_string()));
+
+        let mut max = ExtremumAccumulator::new(Ordering::Greater);
+        run(&mut max, &values);
+        assert_eq!(max.finalize().unwrap(), Value::Text("c".to_string()));
+    }
+
+    #[test]
+    fn test_scalar_builtins() {
+        assert_eq!(abs(&[Value::Int64(-4)]).unwrap(), Value::Int64(4));
+        assert_eq!(
+            lower(&[Value::Text("ABC".to_string())]).unwrap(),
+            Value::Text("abc".to_string())
+        );
+        assert_eq!(
+            length(&[Value::Text("héllo".to_string())]).unwrap(),
+            Value::Int64(5)
+        );
+        assert_eq!(
+            coalesce(&[Value::Null, Value::Int64(2), Value::Int64(3)]).unwrap(),
+            Value::Int64(2)
+        );
+    }
+}
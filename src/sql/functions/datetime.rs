@@ -0,0 +1,142 @@
+use crate::{
+    DataType, DatabaseError, Value,
+    core::temporal,
+    sql::{
+        analyzer::AnalyzedExpression,
+        functions::{FunctionRegistry, Signature},
+        optimizer::{Transformed, map_expressions, transform_up},
+        planner::logical::LogicalPlan,
+    },
+};
+
+type BuiltinScalar = fn(&[Value]) -> Result<Value, DatabaseError>;
+
+/// Registers the built-in date and time functions.
+pub(crate) fn register(registry: &mut FunctionRegistry) {
+    let field_and_timestamp = vec![DataType::Text, DataType::Timestamp];
+
+    let scalars: [(&str, Signature, BuiltinScalar); 5] = [
+        ("now", Signature::exact(vec![], DataType::Timestamp), now),
+        (
+            "extract",
+            Signature::exact(field_and_timestamp.clone(), DataType::Float64),
+            extract,
+        ),
+        (
+            "date_part",
+            Signature::exact(field_and_timestamp.clone(), DataType::Float64),
+            extract,
+        ),
+        (
+            "date_trunc",
+            Signature::exact(field_and_timestamp, DataType::Timestamp),
+            date_trunc,
+        ),
+        (
+            "to_char",
+            Signature::exact(vec![DataType::Timestamp, DataType::Text], DataType::Text),
+            to_char,
+        ),
+    ];
+
+    for (name, signature, func) in scalars {
+        registry
+            .register_scalar(name, signature, Box::new(func))
+            .expect("built-in function names are unique");
+    }
+}
+
+/// Called only outside of statements, e.g. for an `EXECUTE` argument. In a statement,
+/// calls are replaced by [`bind_statement_time`].
+fn now(_args: &[Value]) -> Result<Value, DatabaseError> {
+    Ok(Value::Timestamp(temporal::now()))
+}
+
+/// Replaces every `now()` call of `plan` with `statement_time`, so all the rows of a
+/// statement get the same time, as in PostgreSQL.
+pub(crate) fn bind_statement_time(plan: LogicalPlan, statement_time: i64) -> LogicalPlan {
+    let substitute = |expr| match expr {
+        AnalyzedExpression::ScalarFunction { ref function, .. } if function.name == "now" => {
+            Transformed::yes(AnalyzedExpression::Literal(Value::Timestamp(
+                statement_time,
+            )))
+        }
+        expr => Transformed::no(expr),
+    };
+
+    transform_up(plan, &|node| map_expressions(node, &substitute)).value
+}
+
+fn extract(args: &[Value]) -> Result<Value, DatabaseError> {
+    match (&args[0], timestamp_arg(&args[1])) {
+        (Value::Null, _) | (_, None) => Ok(Value::Null),
+        (Value::Text(field), Some(ts)) => Ok(Value::Float64(temporal::extract(field, ts)?)),
+        (other, _) => Err(DatabaseError::TypeMismatch(format!(
+            "extract expects a field name, got {other:?}"
+        ))),
+    }
+}
+
+fn date_trunc(args: &[Value]) -> Result<Value, DatabaseError> {
+    match (&args[0], timestamp_arg(&args[1])) {
+        (Value::Null, _) | (_, None) => Ok(Value::Null),
+        (Value::Text(field), Some(ts)) => Ok(Value::Timestamp(temporal::date_trunc(field, ts)?)),
+        (other, _) => Err(DatabaseError::TypeMismatch(format!(
+            "date_trunc expects a field name, got {other:?}"
+        ))),
+    }
+}
+
+fn to_char(args: &[Value]) -> Result<Value, DatabaseError> {
+    match (timestamp_arg(&args[0]), &args[1]) {
+        (None, _) | (_, Value::Null) => Ok(Value::Null),
+        (Some(ts), Value::Text(template)) => Ok(Value::Text(temporal::to_char(ts, template))),
+        (_, other) => Err(DatabaseError::TypeMismatch(format!(
+            "to_char expects a template, got {other:?}"
+        ))),
+    }
+}
+
+/// The argument as a timestamp, dates are taken at midnight. `None` for NULL.
+fn timestamp_arg(value: &Value) -> Option<i64> {
+    match value {
+        Value::Timestamp(ts) => Some(*ts),
+        Value::Date(days) => Some(*days as i64 * temporal::MICROS_PER_DAY),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timestamp(s: &str) -> Value {
+        Value::Timestamp(temporal::parse_timestamp(s).unwrap())
+    }
+
+    #[test]
+    fn test_extract_from_date() {
+        let date = Value::Date(temporal::parse_date("2024-07-04").unwrap());
+        assert_eq!(
+            extract(&[Value::Text("month".to_string()), date]).unwrap(),
+            Value::Float64(7.0)
+        );
+        assert_eq!(
+            extract(&[Value::Text("day".to_string()), Value::Null]).unwrap(),
+            Value::Null
+        );
+    }
+
+    #[test]
+    fn test_date_trunc_and_to_char() {
+        let ts = timestamp("2024-07-04 18:30:15");
+        assert_eq!(
+            date_trunc(&[Value::Text("day".to_string()), ts.clone()]).unwrap(),
+            timestamp("2024-07-04")
+        );
+        assert_eq!(
+            to_char(&[ts, Value::Text("DD/MM/YYYY".to_string())]).unwrap(),
+            Value::Text("04/07/2024".to_string())
+        );
+    }
+}
@@ -0,0 +1,113 @@
+use crate::{
+    DataType, DatabaseError, Value,
+    core::json,
+    sql::{
+        evaluator::json_result,
+        functions::{FunctionRegistry, Signature},
+    },
+};
+
+type BuiltinScalar = fn(&[Value]) -> Result<Value, DatabaseError>;
+
+/// Registers the built-in JSON functions.
+pub(crate) fn register(registry: &mut FunctionRegistry) {
+    let scalars: [(&str, Signature, BuiltinScalar); 3] = [
+        (
+            "json_extract",
+            Signature::exact(vec![DataType::Json, DataType::Text], DataType::Json),
+            json_extract,
+        ),
+        (
+            "json_array_length",
+            Signature::exact(vec![DataType::Json], DataType::Int64),
+            json_array_length,
+        ),
+        (
+            "json_typeof",
+            Signature::exact(vec![DataType::Json], DataType::Text),
+            json_typeof,
+        ),
+    ];
+
+    for (name, signature, func) in scalars {
+        registry
+            .register_scalar(name, signature, Box::new(func))
+            .expect("built-in function names are unique");
+    }
+}
+
+/// The value at a JSONPath such as `$.items[0].name`, NULL if there is none.
+fn json_extract(args: &[Value]) -> Result<Value, DatabaseError> {
+    match (&args[0], &args[1]) {
+        (Value::Null, _) | (_, Value::Null) => Ok(Value::Null),
+        (Value::Json(document), Value::Text(path)) => {
+            let path = json::parse_json_path(path)?;
+            Ok(json_result(document.get_path(&path), true))
+        }
+        (document, path) => Err(DatabaseError::TypeMismatch(format!(
+            "json_extract expects a document and a path, got {document:?} and {path:?}"
+        ))),
+    }
+}
+
+fn json_array_length(args: &[Value]) -> Result<Value, DatabaseError> {
+    match &args[0] {
+        Value::Null => Ok(Value::Null),
+        Value::Json(json::Json::Array(elements)) => Ok(Value::Int64(elements.len() as i64)),
+        Value::Json(other) => Err(DatabaseError::TypeMismatch(format!(
+            "Cannot get array length of a {}",
+            other.type_name()
+        ))),
+        other => Err(DatabaseError::TypeMismatch(format!(
+            "json_array_length expects a JSON value, got {other:?}"
+        ))),
+    }
+}
+
+fn json_typeof(args: &[Value]) -> Result<Value, DatabaseError> {
+    match &args[0] {
+        Value::Null => Ok(Value::Null),
+        Value::Json(document) => Ok(Value::Text(document.type_name().to_string())),
+        other => Err(DatabaseError::TypeMismatch(format!(
+            "json_typeof expects a JSON value, got {other:?}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json(text: &str) -> Value {
+        Value::Json(json::Json::parse(text).unwrap())
+    }
+
+    #[test]
+    fn test_json_extract() {
+        let document = json(r#"{"items": [{"name": "pen"}, {"name": "ink"}]}"#);
+        assert_eq!(
+            json_extract(&[document.clone(), Value::Text("$.items[1].name".to_string())]).unwrap(),
+            json(r#""ink""#)
+        );
+        assert_eq!(
+            json_extract(&[document.clone(), Value::Text("$.missing".to_string())]).unwrap(),
+            Value::Null
+        );
+        assert!(json_extract(&[document, Value::Text("items".to_string())]).is_err());
+    }
+
+    #[test]
+    fn test_json_array_length_and_typeof() {
+        assert_eq!(
+            json_array_length(&[json("[1, [2, 3], {}]")]).unwrap(),
+            Value::Int64(3)
+        );
+        assert!(json_array_length(&[json(r#"{"a": 1}"#)]).is_err());
+
+        assert_eq!(
+            json_typeof(&[json("12.5")]).unwrap(),
+            Value::Text("number".to_string())
+        );
+        assert_eq!(json_typeof(&[Value::Null]).unwrap(), Value::Null);
+    }
+}
@@ -0,0 +1,514 @@
+use std::{collections::HashMap, fmt, sync::Arc};
+
+use crate::{
+    DataType, DatabaseError, Value,
+    core::decimal::{Decimal, MAX_PRECISION},
+};
+
+pub(crate) mod builtins;
+pub(crate) mod datetime;
+pub(crate) mod json;
+
+/// Implementation of a scalar function, called once per row.
+pub type ScalarImpl = dyn Fn(&[Value]) -> Result<Value, DatabaseError> + Send + Sync;
+
+/// Factory creating a fresh [`Accumulator`] for every group.
+pub type AccumulatorFactory = dyn Fn() -> Box<dyn Accumulator> + Send + Sync;
+
+/// Running state of an aggregate function.
+///
+/// An accumulator is created per group, fed every input row with [`Accumulator::update`]
+/// and turned into the aggregate value with [`Accumulator::finalize`]. Partial aggregates
+/// (e.g. computed by different workers) are combined through [`Accumulator::state`] and
+/// [`Accumulator::merge`].
+pub trait Accumulator: Send {
+    /// Folds the arguments of one input row into the state.
+    ///
+    /// `args` is empty for `count(*)`.
+    fn update(&mut self, args: &[Value]) -> Result<(), DatabaseError>;
+
+    /// Combines the state of another accumulator, as returned by [`Accumulator::state`].
+    fn merge(&mut self, state: &[Value]) -> Result<(), DatabaseError>;
+
+    /// Returns the intermediate state of this accumulator.
+    ///
+    /// Every accumulator of a function returns the same number of values, partial
+    /// states are passed between execution nodes as that many columns.
+    fn state(&self) -> Vec<Value>;
+
+    /// Produces the final value of the aggregate.
+    fn finalize(&self) -> Result<Value, DatabaseError>;
+}
+
+/// The argument types a function accepts.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ArgumentTypes {
+    /// Exactly these argument types, in order.
+    Exact(Vec<DataType>),
+
+    /// A fixed number of arguments, each one of the listed types.
+    Uniform(usize, Vec<DataType>),
+
+    /// One or more arguments, each one of the listed types.
+    Variadic(Vec<DataType>),
+
+    /// One or more arguments of any types with a common type, which they are cast to.
+    Common,
+
+    /// A fixed number of arguments of any type.
+    Any(usize),
+}
+
+/// How the return type of a function is determined.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReturnType {
+    /// Always returns the given type.
+    Fixed(DataType),
+
+    /// Returns the type of the argument at the given position.
+    SameAsArgument(usize),
+
+    /// Returns the type of the argument at the given position, with integers widened
+    /// to Int64 and decimals to the largest precision, e.g. for sums.
+    WidenedArgument(usize),
+
+    /// Returns Float64 for an integer or float argument at the given position and a
+    /// decimal of the largest precision for a decimal one, e.g. for averages.
+    Quotient(usize),
+}
+
+/// The type signature of a function, used by the analyzer for type checking.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signature {
+    pub arguments: ArgumentTypes,
+    pub return_type: ReturnType,
+}
+
+impl Signature {
+    /// Creates a signature accepting exactly the given argument types.
+    pub fn exact(arguments: Vec<DataType>, return_type: DataType) -> Self {
+        Self {
+            arguments: ArgumentTypes::Exact(arguments),
+            return_type: ReturnType::Fixed(return_type),
+        }
+    }
+
+    /// Creates a signature accepting `count` arguments, each one of `valid_types`.
+    pub fn uniform(count: usize, valid_types: Vec<DataType>, return_type: ReturnType) -> Self {
+        Self {
+            arguments: ArgumentTypes::Uniform(count, valid_types),
+            return_type,
+        }
+    }
+
+    /// Creates a signature accepting one or more arguments, each one of `valid_types`.
+    pub fn variadic(valid_types: Vec<DataType>, return_type: ReturnType) -> Self {
+        Self {
+            arguments: ArgumentTypes::Variadic(valid_types),
+            return_type,
+        }
+    }
+
+    /// Creates a signature accepting one or more arguments with a common type, see
+    /// [`DataType::common_type`].
+    pub fn common(return_type: ReturnType) -> Self {
+        Self {
+            arguments: ArgumentTypes::Common,
+            return_type,
+        }
+    }
+
+    /// Creates a signature accepting `count` arguments of any type.
+    pub fn any(count: usize, return_type: ReturnType) -> Self {
+        Self {
+            arguments: ArgumentTypes::Any(count),
+            return_type,
+        }
+    }
+
+    /// The type the argument at `position` must have, if only one type is accepted.
+    pub fn argument_type(&self, position: usize) -> Option<DataType> {
+        match &self.arguments {
+            ArgumentTypes::Exact(expected) => expected.get(position).copied(),
+            ArgumentTypes::Uniform(_, valid) | ArgumentTypes::Variadic(valid) => match valid[..] {
+                [data_type] => Some(data_type),
+                _ => None,
+            },
+            ArgumentTypes::Common | ArgumentTypes::Any(_) => None,
+        }
+    }
+
+    /// The type the argument at `position` of the arguments of types `arg_types` is cast
+    /// to before the call, so the function gets the values it declared. `None` if the
+    /// values already have that form, or if several types are accepted.
+    pub(crate) fn argument_cast(
+        &self,
+        position: usize,
+        arg_types: &[DataType],
+    ) -> Option<DataType> {
+        let actual = *arg_types.get(position)?;
+        if self.arguments == ArgumentTypes::Common {
+            // Every argument gets exactly the common type, decimals included
+            let expected = common_argument_type(arg_types)?;
+            return (actual != expected).then_some(expected);
+        }
+
+        let expected = self.argument_type(position)?;
+        let same_values = match (actual, expected) {
+            // Both are held as text, and decimals keep their own scale
+            (DataType::Text | DataType::VarChar(_), DataType::Text | DataType::VarChar(_))
+            | (DataType::Decimal(_, _), DataType::Decimal(_, _)) => true,
+            _ => actual == expected,
+        };
+        (!same_values).then_some(expected)
+    }
+
+    /// Checks the argument types against this signature and returns the result type.
+    pub fn resolve(&self, name: &str, arg_types: &[DataType]) -> Result<DataType, DatabaseError> {
+        let accepts = |actual: DataType, valid: &[DataType]| {
+            valid
+                .iter()
+                .any(|expected| DataType::can_coerce(actual, *expected))
+        };
+
+        let valid = match &self.arguments {
+            ArgumentTypes::Exact(expected) => {
+                expected.len() == arg_types.len()
+                    && arg_types
+                        .iter()
+                        .zip(expected)
+                        .all(|(actual, expected)| DataType::can_coerce(*actual, *expected))
+            }
+            ArgumentTypes::Uniform(count, valid) => {
+                arg_types.len() == *count && arg_types.iter().all(|t| accepts(*t, valid))
+            }
+            ArgumentTypes::Variadic(valid) => {
+                !arg_types.is_empty() && arg_types.iter().all(|t| accepts(*t, valid))
+            }
+            ArgumentTypes::Common => common_argument_type(arg_types).is_some(),
+            ArgumentTypes::Any(count) => arg_types.len() == *count,
+        };
+
+        if !valid {
+            let args = arg_types
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(DatabaseError::TypeMismatch(format!(
+                "No function matches {name}({args})"
+            )));
+        }
+
+        match self.return_type {
+            ReturnType::Fixed(data_type) => Ok(data_type),
+            ReturnType::SameAsArgument(index)
+            | ReturnType::WidenedArgument(index)
+            | ReturnType::Quotient(index) => {
+                let data_type = arg_types.get(index).copied().ok_or_else(|| {
+                    DatabaseError::TypeMismatch(format!(
+                        "Function {name} has no argument {index} to take its return type from"
+                    ))
+                })?;
+
+                match (self.return_type, data_type) {
+                    (ReturnType::WidenedArgument(_), _) if data_type.is_integer() => {
+                        Ok(DataType::Int64)
+                    }
+                    (ReturnType::WidenedArgument(_), DataType::Decimal(_, scale)) => {
+                        Ok(DataType::Decimal(MAX_PRECISION, scale))
+                    }
+                    (ReturnType::Quotient(_), DataType::Decimal(_, scale)) => Ok(
+                        DataType::Decimal(MAX_PRECISION, Decimal::division_scale(scale, 0)),
+                    ),
+                    (ReturnType::Quotient(_), _) => Ok(DataType::Float64),
+                    _ => Ok(data_type),
+                }
+            }
+        }
+    }
+}
+
+/// The type all of `arg_types` can be coerced to, `None` if there is none or no argument.
+fn common_argument_type(arg_types: &[DataType]) -> Option<DataType> {
+    let (first, rest) = arg_types.split_first()?;
+    rest.iter().try_fold(*first, |common, data_type| {
+        DataType::common_type(common, *data_type)
+    })
+}
+
+/// A registered scalar function.
+pub struct ScalarFunction {
+    pub name: String,
+    pub signature: Signature,
+    pub(crate) func: Box<ScalarImpl>,
+}
+
+impl ScalarFunction {
+    /// Calls the function with already evaluated arguments.
+    pub fn invoke(&self, args: &[Value]) -> Result<Value, DatabaseError> {
+        (self.func)(args)
+    }
+}
+
+impl fmt::Debug for ScalarFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ScalarFunction")
+            .field("name", &self.name)
+            .field("signature", &self.signature)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A registered aggregate function.
+pub struct AggregateFunction {
+    pub name: String,
+    pub signature: Signature,
+    pub(crate) init: Box<AccumulatorFactory>,
+}
+
+impl AggregateFunction {
+    /// Creates a fresh accumulator for a new group.
+    pub fn accumulator(&self) -> Box<dyn Accumulator> {
+        (self.init)()
+    }
+}
+
+impl fmt::Debug for AggregateFunction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AggregateFunction")
+            .field("name", &self.name)
+            .field("signature", &self.signature)
+            .finish_non_exhaustive()
+    }
+}
+
+/// All functions callable from SQL, both built-in and user-defined.
+///
+/// Function names are case-insensitive.
+#[derive(Debug)]
+pub struct FunctionRegistry {
+    scalars: HashMap<String, Arc<ScalarFunction>>,
+    aggregates: HashMap<String, Arc<AggregateFunction>>,
+}
+
+impl Default for FunctionRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FunctionRegistry {
+    /// Creates a registry holding the built-in functions.
+    pub fn new() -> Self {
+        let mut registry = Self {
+            scalars: HashMap::new(),
+            aggregates: HashMap::new(),
+        };
+        builtins::register(&mut registry);
+        datetime::register(&mut registry);
+        json::register(&mut registry);
+        registry
+    }
+
+    pub fn register_scalar(
+        &mut self,
+        name: &str,
+        signature: Signature,
+        func: Box<ScalarImpl>,
+    ) -> Result<(), DatabaseError> {
+        let name = self.check_available(name)?;
+        let function = ScalarFunction {
+            name: name.clone(),
+            signature,
+            func,
+        };
+        self.scalars.insert(name, Arc::new(function));
+        Ok(())
+    }
+
+    pub fn register_aggregate(
+        &mut self,
+        name: &str,
+        signature: Signature,
+        init: Box<AccumulatorFactory>,
+    ) -> Result<(), DatabaseError> {
+        let name = self.check_available(name)?;
+        let function = AggregateFunction {
+            name: name.clone(),
+            signature,
+            init,
+        };
+        self.aggregates.insert(name, Arc::new(function));
+        Ok(())
+    }
+
+    pub fn scalar(&self, name: &str) -> Option<Arc<ScalarFunction>> {
+        self.scalars.get(&name.to_lowercase()).cloned()
+    }
+
+    pub fn aggregate(&self, name: &str) -> Option<Arc<AggregateFunction>> {
+        self.aggregates.get(&name.to_lowercase()).cloned()
+    }
+
+    /// Returns the normalized name if no function is registered under it yet.
+    fn check_available(&self, name: &str) -> Result<String, DatabaseError> {
+        let name = name.to_lowercase();
+        if self.scalars.contains_key(&name) || self.aggregates.contains_key(&name) {
+            return Err(DatabaseError::InvalidQuery(format!(
+                "Function {name} already exists"
+            )));
+        }
+        Ok(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_exact_signature() {
+        let signature =
+            Signature::exact(vec![DataType::Float64, DataType::Float64], DataType::Bool);
+
+        assert_eq!(
+            signature
+                .resolve("f", &[DataType::Float64, DataType::Int64])
+                .unwrap(),
+            DataType::Bool
+        );
+        assert!(signature.resolve("f", &[DataType::Float64]).is_err());
+        assert!(
+            signature
+                .resolve("f", &[DataType::Text, DataType::Float64])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_resolve_same_as_argument() {
+        let signature = Signature::uniform(
+            1,
+            vec![DataType::Int64, DataType::Float64],
+            ReturnType::SameAsArgument(0),
+        );
+
+        assert_eq!(
+            signature.resolve("abs", &[DataType::Int64]).unwrap(),
+            DataType::Int64
+        );
+        assert_eq!(
+            signature.resolve("abs", &[DataType::Float64]).unwrap(),
+            DataType::Float64
+        );
+        assert!(signature.resolve("abs", &[DataType::Bool]).is_err());
+    }
+
+    #[test]
+    fn test_resolve_sum_and_average_types() {
+        let numeric = vec![
+            DataType::Int64,
+            DataType::Float64,
+            DataType::Decimal(MAX_PRECISION, 0),
+        ];
+        let sum = Signature::uniform(1, numeric.clone(), ReturnType::WidenedArgument(0));
+        let avg = Signature::uniform(1, numeric, ReturnType::Quotient(0));
+
+        assert_eq!(
+            sum.resolve("sum", &[DataType::Int32]).unwrap(),
+            DataType::Int64
+        );
+        assert_eq!(
+            sum.resolve("sum", &[DataType::Decimal(5, 2)]).unwrap(),
+            DataType::Decimal(MAX_PRECISION, 2)
+        );
+        assert_eq!(
+            avg.resolve("avg", &[DataType::Int64]).unwrap(),
+            DataType::Float64
+        );
+        assert_eq!(
+            avg.resolve("avg", &[DataType::Decimal(5, 2)]).unwrap(),
+            DataType::Decimal(MAX_PRECISION, 6)
+        );
+    }
+
+    #[test]
+    fn test_arguments_are_cast_to_declared_types() {
+        let signature = Signature::exact(
+            vec![DataType::Float64, DataType::Text, DataType::Decimal(10, 2)],
+            DataType::Bool,
+        );
+        let arg_types = [
+            DataType::Int64,
+            DataType::VarChar(20),
+            DataType::Decimal(5, 1),
+        ];
+        assert_eq!(
+            signature.argument_cast(0, &arg_types),
+            Some(DataType::Float64)
+        );
+        assert_eq!(signature.argument_cast(0, &[DataType::Float64]), None);
+        assert_eq!(signature.argument_cast(1, &arg_types), None);
+        assert_eq!(signature.argument_cast(2, &arg_types), None);
+        assert_eq!(
+            signature.argument_cast(2, &[DataType::Text, DataType::Text, DataType::Int32]),
+            Some(DataType::Decimal(10, 2))
+        );
+
+        // Built-ins accepting several types get their arguments as they are
+        let signature = Signature::uniform(
+            1,
+            vec![DataType::Int64, DataType::Float64],
+            ReturnType::SameAsArgument(0),
+        );
+        assert_eq!(signature.argument_cast(0, &[DataType::Int32]), None);
+    }
+
+    #[test]
+    fn test_arguments_are_cast_to_common_type() {
+        let signature = Signature::common(ReturnType::SameAsArgument(0));
+
+        let arg_types = [DataType::Int32, DataType::Int64, DataType::Float64];
+        assert!(signature.resolve("coalesce", &arg_types).is_ok());
+        assert_eq!(
+            signature.argument_cast(0, &arg_types),
+            Some(DataType::Float64)
+        );
+        assert_eq!(signature.argument_cast(2, &arg_types), None);
+
+        let arg_types = [DataType::Decimal(5, 2), DataType::Decimal(10, 0)];
+        assert_eq!(
+            signature.argument_cast(1, &arg_types),
+            Some(DataType::Decimal(12, 2))
+        );
+
+        let error = signature
+            .resolve("coalesce", &[DataType::Int64, DataType::Bool])
+            .unwrap_err();
+        assert!(matches!(error, DatabaseError::TypeMismatch(_)));
+        assert!(signature.resolve("coalesce", &[]).is_err());
+    }
+
+    #[test]
+    fn test_register_duplicate_function() {
+        let mut registry = FunctionRegistry::new();
+
+        let result = registry.register_scalar(
+            "LOWER",
+            Signature::exact(vec![DataType::Text], DataType::Text),
+            Box::new(|args| Ok(args[0].clone())),
+        );
+        assert!(result.is_err());
+
+        registry
+            .register_scalar(
+                "Tenant_Hash",
+                Signature::exact(vec![DataType::Text], DataType::Int64),
+                Box::new(|_| Ok(Value::Int64(7))),
+            )
+            .unwrap();
+        let function = registry.scalar("tenant_hash").expect("function to exist");
+        assert_eq!(function.invoke(&[]).unwrap(), Value::Int64(7));
+    }
+}
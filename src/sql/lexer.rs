@@ -15,9 +15,14 @@ pub enum Token<'a> {
     Integer(i64),
     Float(f64),
     String(Cow<'a, str>),
+    /// Hex string literal, `X'DEADBEEF'`, holding the hex digits
+    HexString(Cow<'a, str>),
+    /// Parameter placeholder, `$1` or `?`, holding its number counting from 1
+    Parameter(usize),
 
     Comma,
     SemiColon,
+    DoubleColon,
     Equal,
     NotEqual,
     GreaterThan,
@@ -30,6 +35,14 @@ pub enum Token<'a> {
     Plus,
     Minus,
     Slash,
+    /// JSON field or element access, `->`
+    Arrow,
+    /// JSON field or element access as text, `->>`
+    LongArrow,
+    /// JSON path access, `#>`
+    HashArrow,
+    /// JSON path access as text, `#>>`
+    HashLongArrow,
 }
 
 /// SQL lexer that tokenizes a query string.
@@ -44,6 +57,9 @@ pub struct Lexer<'a> {
 
     /// Current byte position
     position: usize,
+
+    /// Number of `?` placeholders read so far, they are numbered in order
+    positional_parameters: usize,
 }
 
 impl<'src> Lexer<'src> {
@@ -53,6 +69,7 @@ impl<'src> Lexer<'src> {
             whole: input,
             rest: input,
             position: 0,
+            positional_parameters: 0,
         }
     }
 
@@ -91,7 +108,19 @@ impl<'src> Lexer<'src> {
                 c.is_whitespace()
                     || matches!(
                         c,
-                        ',' | ';' | '=' | '*' | '/' | '+' | '-' | '(' | ')' | '<' | '>' | '!'
+                        ',' | ';'
+                            | ':'
+                            | '='
+                            | '*'
+                            | '/'
+                            | '+'
+                            | '-'
+                            | '('
+                            | ')'
+                            | '<'
+                            | '>'
+                            | '!'
+                            | '#'
                     )
             })
             .unwrap_or(self.rest.len());
@@ -123,6 +152,23 @@ impl<'src> Lexer<'src> {
         Cow::from(string_value)
     }
 
+    /// Consumes a `$n` parameter placeholder from the input.
+    fn consume_parameter(&mut self) -> Result<Token<'src>> {
+        let digits_end = self.rest[1..]
+            .find(|c: char| !c.is_ascii_digit())
+            .map_or(self.rest.len(), |end| end + 1);
+
+        let number = self.rest[1..digits_end]
+            .parse::<usize>()
+            .ok()
+            .filter(|number| *number > 0)
+            .ok_or_else(|| miette!("Invalid parameter at position {}", self.position))?;
+        self.position += digits_end;
+        self.rest = &self.rest[digits_end..];
+
+        Ok(Token::Parameter(number))
+    }
+
     /// Consumes a numeric literal from the input.
     ///
     /// Reads digits and optional decimal point.
@@ -173,8 +219,42 @@ impl<'a> Iterator for Lexer<'a> {
             ')' => Ok(self.consume_symbol(Token::RightParen)),
             ',' => Ok(self.consume_symbol(Token::Comma)),
             '+' => Ok(self.consume_symbol(Token::Plus)),
-            '-' => Ok(self.consume_symbol(Token::Minus)),
+            '-' => {
+                if self.rest.starts_with("->>") {
+                    self.rest = &self.rest[3..];
+                    self.position += 3;
+                    Ok(Token::LongArrow)
+                } else if self.rest.starts_with("->") {
+                    self.rest = &self.rest[2..];
+                    self.position += 2;
+                    Ok(Token::Arrow)
+                } else {
+                    Ok(self.consume_symbol(Token::Minus))
+                }
+            }
+            '#' => {
+                if self.rest.starts_with("#>>") {
+                    self.rest = &self.rest[3..];
+                    self.position += 3;
+                    Ok(Token::HashLongArrow)
+                } else if self.rest.starts_with("#>") {
+                    self.rest = &self.rest[2..];
+                    self.position += 2;
+                    Ok(Token::HashArrow)
+                } else {
+                    Err(miette!(
+                        "Unexpected character '{}' at position {}",
+                        char,
+                        self.position
+                    ))
+                }
+            }
             '*' => Ok(self.consume_symbol(Token::Asterisk)),
+            '$' => self.consume_parameter(),
+            '?' => {
+                self.positional_parameters += 1;
+                Ok(self.consume_symbol(Token::Parameter(self.positional_parameters)))
+            }
             '/' => Ok(self.consume_symbol(Token::Slash)),
             ';' => Ok(self.consume_symbol(Token::SemiColon)),
             '=' => Ok(self.consume_symbol(Token::Equal)),
@@ -209,6 +289,19 @@ impl<'a> Iterator for Lexer<'a> {
                     ))
                 }
             }
+            ':' => {
+                if self.rest.len() > 1 && self.rest.chars().nth(1) == Some(':') {
+                    self.rest = &self.rest[2..];
+                    self.position += 2;
+                    Ok(Token::DoubleColon)
+                } else {
+                    Err(miette!(
+                        "Unexpected character '{}' at position {}",
+                        char,
+                        self.position
+                    ))
+                }
+            }
             '\'' => {
                 let string_value = self.consume_string('\'');
                 Ok(Token::String(string_value))
@@ -224,6 +317,12 @@ impl<'a> Iterator for Lexer<'a> {
                     Err(err) => Err(err),
                 }
             }
+            'x' | 'X' if self.rest[1..].starts_with('\'') => {
+                self.rest = &self.rest[1..];
+                self.position += 1;
+                let hex_digits = self.consume_string('\'');
+                Ok(Token::HexString(hex_digits))
+            }
             _ if char.is_alphabetic() => {
                 let word = self.consume_word()?;
 
@@ -345,6 +444,68 @@ mod tests {
         assert!(lexer.next().is_none());
     }
 
+    #[test]
+    fn test_lexer_with_cast() {
+        let mut lexer = Lexer::new("SELECT age::TEXT FROM users");
+
+        assert_token_eq(lexer.next(), Token::Keyword(Keyword::Select));
+        assert_token_eq(lexer.next(), Token::Identifier(Cow::from("age")));
+        assert_token_eq(lexer.next(), Token::DoubleColon);
+        assert_token_eq(lexer.next(), Token::Keyword(Keyword::Text));
+        assert_token_eq(lexer.next(), Token::Keyword(Keyword::From));
+        assert_token_eq(lexer.next(), Token::Identifier(Cow::from("users")));
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn test_lexer_with_hex_string() {
+        let mut lexer = Lexer::new("X'DEADBEEF' x'00' xy");
+
+        assert_token_eq(lexer.next(), Token::HexString(Cow::from("DEADBEEF")));
+        assert_token_eq(lexer.next(), Token::HexString(Cow::from("00")));
+        assert_token_eq(lexer.next(), Token::Identifier(Cow::from("xy")));
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn test_lexer_with_json_operators() {
+        let mut lexer = Lexer::new("payload->'a'->>'b' #> '{c}' #>>'{d}' - 1");
+
+        assert_token_eq(lexer.next(), Token::Identifier(Cow::from("payload")));
+        assert_token_eq(lexer.next(), Token::Arrow);
+        assert_token_eq(lexer.next(), Token::String(Cow::from("a")));
+        assert_token_eq(lexer.next(), Token::LongArrow);
+        assert_token_eq(lexer.next(), Token::String(Cow::from("b")));
+        assert_token_eq(lexer.next(), Token::HashArrow);
+        assert_token_eq(lexer.next(), Token::String(Cow::from("{c}")));
+        assert_token_eq(lexer.next(), Token::HashLongArrow);
+        assert_token_eq(lexer.next(), Token::String(Cow::from("{d}")));
+        assert_token_eq(lexer.next(), Token::Minus);
+        assert_token_eq(lexer.next(), Token::Integer(1));
+        assert!(lexer.next().is_none());
+    }
+
+    #[test]
+    fn test_lexer_with_parameters() {
+        let mut lexer = Lexer::new("age > $2 AND name = $10 OR ? < ?");
+
+        assert_token_eq(lexer.next(), Token::Identifier(Cow::from("age")));
+        assert_token_eq(lexer.next(), Token::GreaterThan);
+        assert_token_eq(lexer.next(), Token::Parameter(2));
+        assert_token_eq(lexer.next(), Token::Keyword(Keyword::And));
+        assert_token_eq(lexer.next(), Token::Identifier(Cow::from("name")));
+        assert_token_eq(lexer.next(), Token::Equal);
+        assert_token_eq(lexer.next(), Token::Parameter(10));
+        assert_token_eq(lexer.next(), Token::Keyword(Keyword::Or));
+        assert_token_eq(lexer.next(), Token::Parameter(1));
+        assert_token_eq(lexer.next(), Token::LessThan);
+        assert_token_eq(lexer.next(), Token::Parameter(2));
+        assert!(lexer.next().is_none());
+
+        assert!(Lexer::new("$0").next().unwrap().is_err());
+        assert!(Lexer::new("$").next().unwrap().is_err());
+    }
+
     #[test]
     fn test_number() {
         let mut lexer = Lexer::new("5.0 5");
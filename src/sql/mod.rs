@@ -2,6 +2,8 @@ pub(crate) mod analyzer;
 pub(crate) mod ast;
 pub(crate) mod catalog_context;
 pub(crate) mod evaluator;
+pub(crate) mod functions;
 pub(crate) mod lexer;
+pub(crate) mod optimizer;
 pub(crate) mod parser;
 pub(crate) mod planner;
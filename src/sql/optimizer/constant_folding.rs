@@ -0,0 +1,202 @@
+use crate::{
+    Row, Value,
+    sql::{
+        analyzer::AnalyzedExpression,
+        evaluator::{Evaluator, cast_value, expression::ExpressionEvaluator},
+        optimizer::{OptimizerRule, Transformed, constant_value, map_expressions},
+        planner::logical::LogicalPlan,
+    },
+};
+
+/// Evaluates operations on constants once at planning time, e.g. `age > 10 + 5`
+/// becomes `age > 15`.
+///
+/// Expressions failing to evaluate, like a division by zero, are kept so the error is
+/// raised if they are ever executed.
+#[derive(Debug)]
+pub(crate) struct ConstantFolding;
+
+impl OptimizerRule for ConstantFolding {
+    fn rewrite(&self, plan: LogicalPlan) -> Transformed<LogicalPlan> {
+        map_expressions(plan, &fold)
+    }
+}
+
+fn fold(expr: AnalyzedExpression) -> Transformed<AnalyzedExpression> {
+    let value = match &expr {
+        AnalyzedExpression::BinaryExpr { left, right, .. }
+            if constant_value(left).is_some() && constant_value(right).is_some() =>
+        {
+            ExpressionEvaluator
+                .evaluate(&expr, &Row::new(Vec::new()))
+                .ok()
+        }
+        AnalyzedExpression::Cast {
+            expr: inner,
+            data_type,
+        } => match inner.as_ref() {
+            AnalyzedExpression::Literal(value) if *value != Value::Null => {
+                cast_value(value, *data_type).ok()
+            }
+            _ => None,
+        },
+        _ => None,
+    };
+
+    let Some(value) = value else {
+        return Transformed::no(expr);
+    };
+
+    let data_type = expr.get_type();
+    if value == Value::Null {
+        return Transformed::yes(AnalyzedExpression::Cast {
+            expr: Box::new(AnalyzedExpression::Literal(Value::Null)),
+            data_type,
+        });
+    }
+
+    // The literal has to keep the type the expression was analyzed with
+    let folded = AnalyzedExpression::Literal(value);
+    if folded.get_type() != data_type {
+        return Transformed::no(expr);
+    }
+
+    Transformed::yes(folded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        DataType,
+        sql::{
+            ast::operator::Operator,
+            optimizer::tests::{binary, column, filter, literal, scan},
+        },
+    };
+
+    fn fold_condition(condition: AnalyzedExpression) -> (AnalyzedExpression, bool) {
+        let rewritten = ConstantFolding.rewrite(filter(scan(), condition));
+        let LogicalPlan::Filter { condition, .. } = rewritten.value else {
+            panic!("Expected a filter");
+        };
+        (condition, rewritten.changed)
+    }
+
+    #[test]
+    fn test_fold_nested_arithmetic() {
+        // age > (10 + 5) * 2
+        let sum = binary(
+            literal(Value::Int64(10)),
+            Operator::Add,
+            literal(Value::Int64(5)),
+            DataType::Int64,
+        );
+        let product = binary(
+            sum,
+            Operator::Multiply,
+            literal(Value::Int64(2)),
+            DataType::Int64,
+        );
+        let (condition, changed) = fold_condition(binary(
+            column(1, DataType::Int64),
+            Operator::GreaterThan,
+            product,
+            DataType::Bool,
+        ));
+
+        assert!(changed);
+        let AnalyzedExpression::BinaryExpr { left, right, .. } = condition else {
+            panic!("Expected the comparison to remain");
+        };
+        assert!(matches!(*left, AnalyzedExpression::Column(..)));
+        assert!(matches!(
+            *right,
+            AnalyzedExpression::Literal(Value::Int64(30))
+        ));
+    }
+
+    #[test]
+    fn test_fold_comparison_and_null() {
+        let (condition, _) = fold_condition(binary(
+            literal(Value::Int64(1)),
+            Operator::LessThan,
+            literal(Value::Float64(1.5)),
+            DataType::Bool,
+        ));
+        assert!(matches!(
+            condition,
+            AnalyzedExpression::Literal(Value::Bool(true))
+        ));
+
+        let null = AnalyzedExpression::Cast {
+            expr: Box::new(literal(Value::Null)),
+            data_type: DataType::Int64,
+        };
+        let (condition, changed) = fold_condition(binary(
+            null,
+            Operator::Equal,
+            literal(Value::Int64(1)),
+            DataType::Bool,
+        ));
+        assert!(changed);
+        assert!(matches!(
+            condition,
+            AnalyzedExpression::Cast {
+                data_type: DataType::Bool,
+                ..
+            }
+        ));
+        assert_eq!(constant_value(&condition), Some(&Value::Null));
+    }
+
+    #[test]
+    fn test_keep_failing_and_column_expressions() {
+        let division = binary(
+            literal(Value::Int64(1)),
+            Operator::Divide,
+            literal(Value::Int64(0)),
+            DataType::Int64,
+        );
+        let (condition, changed) = fold_condition(binary(
+            column(1, DataType::Int64),
+            Operator::Equal,
+            division,
+            DataType::Bool,
+        ));
+
+        assert!(!changed);
+        let AnalyzedExpression::BinaryExpr { right, .. } = condition else {
+            panic!("Expected the comparison to remain");
+        };
+        assert!(matches!(
+            *right,
+            AnalyzedExpression::BinaryExpr {
+                op: Operator::Divide,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_fold_cast_of_literal() {
+        let (condition, changed) = fold_condition(binary(
+            column(1, DataType::Int64),
+            Operator::Equal,
+            AnalyzedExpression::Cast {
+                expr: Box::new(literal(Value::Text("42".to_string()))),
+                data_type: DataType::Int64,
+            },
+            DataType::Bool,
+        ));
+
+        assert!(changed);
+        let AnalyzedExpression::BinaryExpr { right, .. } = condition else {
+            panic!("Expected the comparison to remain");
+        };
+        assert!(matches!(
+            *right,
+            AnalyzedExpression::Literal(Value::Int64(42))
+        ));
+    }
+}
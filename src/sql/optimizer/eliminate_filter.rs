@@ -0,0 +1,161 @@
+use crate::{
+    Value,
+    sql::{
+        analyzer::AnalyzedExpression,
+        optimizer::{OptimizerRule, Transformed, constant_value},
+        planner::logical::LogicalPlan,
+    },
+};
+
+/// Drops filters that are always true and replaces plans whose filter is always false
+/// (or NULL) with an empty relation, as well as joins with an empty input.
+#[derive(Debug)]
+pub(crate) struct EliminateFilter;
+
+impl OptimizerRule for EliminateFilter {
+    fn rewrite(&self, plan: LogicalPlan) -> Transformed<LogicalPlan> {
+        match plan {
+            LogicalPlan::Filter { input, condition } => match constant_value(&condition) {
+                Some(Value::Bool(true)) => Transformed::yes(*input),
+                Some(_) => Transformed::yes(LogicalPlan::Empty {
+                    schema: input.schema().clone(),
+                }),
+                None if matches!(*input, LogicalPlan::Empty { .. }) => Transformed::yes(*input),
+                None => Transformed::no(LogicalPlan::Filter { input, condition }),
+            },
+            LogicalPlan::Scan {
+                table_name,
+                schema,
+                projection,
+                filters,
+            } => {
+                if filters
+                    .iter()
+                    .any(|filter| never_passes(constant_value(filter)))
+                {
+                    return Transformed::yes(LogicalPlan::Empty { schema });
+                }
+
+                let count = filters.len();
+                let filters = filters
+                    .into_iter()
+                    .filter(|filter| constant_value(filter) != Some(&Value::Bool(true)))
+                    .collect::<Vec<AnalyzedExpression>>();
+                let changed = filters.len() != count;
+
+                Transformed {
+                    value: LogicalPlan::Scan {
+                        table_name,
+                        schema,
+                        projection,
+                        filters,
+                    },
+                    changed,
+                }
+            }
+            LogicalPlan::Projection { input, schema, .. }
+                if matches!(*input, LogicalPlan::Empty { .. }) =>
+            {
+                Transformed::yes(LogicalPlan::Empty { schema })
+            }
+            LogicalPlan::Join {
+                left,
+                right,
+                conditions,
+                schema,
+            } => {
+                if matches!(*left, LogicalPlan::Empty { .. })
+                    || matches!(*right, LogicalPlan::Empty { .. })
+                    || conditions
+                        .iter()
+                        .any(|condition| never_passes(constant_value(condition)))
+                {
+                    return Transformed::yes(LogicalPlan::Empty { schema });
+                }
+
+                let count = conditions.len();
+                let conditions = conditions
+                    .into_iter()
+                    .filter(|condition| constant_value(condition) != Some(&Value::Bool(true)))
+                    .collect::<Vec<AnalyzedExpression>>();
+                let changed = conditions.len() != count;
+
+                Transformed {
+                    value: LogicalPlan::Join {
+                        left,
+                        right,
+                        conditions,
+                        schema,
+                    },
+                    changed,
+                }
+            }
+            // An aggregate without GROUP BY still produces a row for an empty input
+            plan => Transformed::no(plan),
+        }
+    }
+}
+
+fn never_passes(value: Option<&Value>) -> bool {
+    matches!(value, Some(value) if *value != Value::Bool(true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sql::optimizer::tests::{age_above, filter, join, literal, scan};
+
+    #[test]
+    fn test_remove_always_true_filter() {
+        let rewritten = EliminateFilter.rewrite(filter(scan(), literal(Value::Bool(true))));
+        assert!(rewritten.changed);
+        assert!(matches!(rewritten.value, LogicalPlan::Scan { .. }));
+    }
+
+    #[test]
+    fn test_always_false_filter_is_empty() {
+        for condition in [literal(Value::Bool(false)), literal(Value::Null)] {
+            let rewritten = EliminateFilter.rewrite(filter(scan(), condition));
+            let LogicalPlan::Empty { schema } = rewritten.value else {
+                panic!("Expected an empty relation");
+            };
+            assert_eq!(schema.fields.len(), 2);
+        }
+
+        let mut plan = scan();
+        if let LogicalPlan::Scan { filters, .. } = &mut plan {
+            *filters = vec![age_above(18), literal(Value::Bool(false))];
+        }
+        let rewritten = EliminateFilter.rewrite(plan);
+        assert!(matches!(rewritten.value, LogicalPlan::Empty { .. }));
+    }
+
+    #[test]
+    fn test_keep_row_dependent_filter() {
+        let rewritten = EliminateFilter.rewrite(filter(scan(), age_above(18)));
+        assert!(!rewritten.changed);
+        assert!(matches!(rewritten.value, LogicalPlan::Filter { .. }));
+    }
+
+    #[test]
+    fn test_join_with_empty_input_is_empty() {
+        let empty = LogicalPlan::Empty {
+            schema: scan().schema().clone(),
+        };
+        let rewritten = EliminateFilter.rewrite(join(scan(), empty, Vec::new()));
+        let LogicalPlan::Empty { schema } = rewritten.value else {
+            panic!("Expected an empty relation");
+        };
+        assert_eq!(schema.fields.len(), 4);
+
+        let rewritten = EliminateFilter.rewrite(join(
+            scan(),
+            scan(),
+            vec![literal(Value::Bool(true)), age_above(18)],
+        ));
+        let LogicalPlan::Join { conditions, .. } = rewritten.value else {
+            panic!("Expected the join to be kept");
+        };
+        assert_eq!(conditions.len(), 1);
+    }
+}
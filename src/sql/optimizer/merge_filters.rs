@@ -0,0 +1,76 @@
+use crate::{
+    DataType,
+    sql::{
+        analyzer::AnalyzedExpression,
+        ast::operator::Operator,
+        optimizer::{OptimizerRule, Transformed},
+        planner::logical::LogicalPlan,
+    },
+};
+
+/// Combines a filter directly above another one into a single filter on their `AND`.
+#[derive(Debug)]
+pub(crate) struct MergeFilters;
+
+impl OptimizerRule for MergeFilters {
+    fn rewrite(&self, plan: LogicalPlan) -> Transformed<LogicalPlan> {
+        match plan {
+            LogicalPlan::Filter { input, condition } => match *input {
+                LogicalPlan::Filter {
+                    input: inner_input,
+                    condition: inner_condition,
+                } => Transformed::yes(LogicalPlan::Filter {
+                    input: inner_input,
+                    condition: AnalyzedExpression::BinaryExpr {
+                        left: Box::new(inner_condition),
+                        op: Operator::And,
+                        right: Box::new(condition),
+                        return_type: DataType::Bool,
+                    },
+                }),
+                input => Transformed::no(LogicalPlan::Filter {
+                    input: Box::new(input),
+                    condition,
+                }),
+            },
+            plan => Transformed::no(plan),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sql::optimizer::tests::{age_above, filter, scan};
+
+    #[test]
+    fn test_merge_adjacent_filters() {
+        let plan = filter(filter(scan(), age_above(18)), age_above(65));
+
+        let rewritten = MergeFilters.rewrite(plan);
+        assert!(rewritten.changed);
+
+        let LogicalPlan::Filter { input, condition } = rewritten.value else {
+            panic!("Expected a single filter");
+        };
+        assert!(matches!(*input, LogicalPlan::Scan { .. }));
+        assert_eq!(
+            format!("{condition:?}"),
+            format!(
+                "{:?}",
+                AnalyzedExpression::BinaryExpr {
+                    left: Box::new(age_above(18)),
+                    op: Operator::And,
+                    right: Box::new(age_above(65)),
+                    return_type: DataType::Bool,
+                }
+            )
+        );
+    }
+
+    #[test]
+    fn test_single_filter_unchanged() {
+        let rewritten = MergeFilters.rewrite(filter(scan(), age_above(18)));
+        assert!(!rewritten.changed);
+    }
+}
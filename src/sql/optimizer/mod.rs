@@ -0,0 +1,542 @@
+//! Rule-based rewrites of the [`LogicalPlan`] produced by the analyzer.
+//!
+//! Every [`OptimizerRule`] rewrites a single plan node. The [`Optimizer`] applies its
+//! rules bottom-up to every node, over and over until none of them changes the plan.
+
+use crate::{
+    Value,
+    sql::{
+        analyzer::{AnalyzedExpression, ColumnRef},
+        planner::logical::LogicalPlan,
+    },
+};
+
+pub(crate) mod constant_folding;
+pub(crate) mod eliminate_filter;
+pub(crate) mod merge_filters;
+pub(crate) mod push_down_filter;
+pub(crate) mod push_down_projection;
+pub(crate) mod simplify_booleans;
+
+use constant_folding::ConstantFolding;
+use eliminate_filter::EliminateFilter;
+use merge_filters::MergeFilters;
+use push_down_filter::PushDownFilter;
+use push_down_projection::PushDownProjection;
+use simplify_booleans::SimplifyBooleans;
+
+/// The result of a rewrite, along with whether it changed anything.
+#[derive(Debug)]
+pub(crate) struct Transformed<T> {
+    pub(crate) value: T,
+    pub(crate) changed: bool,
+}
+
+impl<T> Transformed<T> {
+    pub(crate) fn yes(value: T) -> Self {
+        Self {
+            value,
+            changed: true,
+        }
+    }
+
+    pub(crate) fn no(value: T) -> Self {
+        Self {
+            value,
+            changed: false,
+        }
+    }
+}
+
+/// A rewrite of one plan node.
+pub(crate) trait OptimizerRule: std::fmt::Debug {
+    /// Rewrites `plan`, whose inputs were already rewritten.
+    fn rewrite(&self, plan: LogicalPlan) -> Transformed<LogicalPlan>;
+}
+
+/// Applies rewrite rules to a logical plan until it does not change anymore.
+#[derive(Debug)]
+pub struct Optimizer {
+    rules: Vec<Box<dyn OptimizerRule>>,
+}
+
+impl Default for Optimizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Optimizer {
+    /// Upper bound of passes over the plan, in case rules keep undoing each other.
+    const MAX_PASSES: usize = 16;
+
+    /// Creates an optimizer with the built-in rules.
+    pub fn new() -> Self {
+        Self {
+            rules: vec![
+                Box::new(ConstantFolding),
+                Box::new(SimplifyBooleans),
+                Box::new(EliminateFilter),
+                Box::new(MergeFilters),
+                Box::new(PushDownFilter),
+                Box::new(PushDownProjection),
+            ],
+        }
+    }
+
+    pub fn optimize(&self, mut plan: LogicalPlan) -> LogicalPlan {
+        for _ in 0..Self::MAX_PASSES {
+            let mut changed = false;
+
+            for rule in &self.rules {
+                let rewritten = transform_up(plan, &|node| rule.rewrite(node));
+                changed |= rewritten.changed;
+                plan = rewritten.value;
+            }
+
+            if !changed {
+                break;
+            }
+        }
+
+        plan
+    }
+}
+
+/// Rewrites every node of `plan` with `f`, inputs before the nodes consuming them.
+pub(crate) fn transform_up(
+    plan: LogicalPlan,
+    f: &dyn Fn(LogicalPlan) -> Transformed<LogicalPlan>,
+) -> Transformed<LogicalPlan> {
+    let mut children_changed = false;
+    let mut visit = |input: Box<LogicalPlan>| {
+        let rewritten = transform_up(*input, f);
+        children_changed |= rewritten.changed;
+        Box::new(rewritten.value)
+    };
+
+    let plan = match plan {
+        LogicalPlan::Filter { input, condition } => LogicalPlan::Filter {
+            input: visit(input),
+            condition,
+        },
+        LogicalPlan::Projection {
+            input,
+            expressions,
+            schema,
+        } => LogicalPlan::Projection {
+            input: visit(input),
+            expressions,
+            schema,
+        },
+        LogicalPlan::Aggregate {
+            input,
+            group_by,
+            aggregates,
+            schema,
+        } => LogicalPlan::Aggregate {
+            input: visit(input),
+            group_by,
+            aggregates,
+            schema,
+        },
+        LogicalPlan::Join {
+            left,
+            right,
+            conditions,
+            schema,
+        } => LogicalPlan::Join {
+            left: visit(left),
+            right: visit(right),
+            conditions,
+            schema,
+        },
+        leaf @ (LogicalPlan::Scan { .. } | LogicalPlan::Empty { .. }) => leaf,
+    };
+
+    let mut rewritten = f(plan);
+    rewritten.changed |= children_changed;
+    rewritten
+}
+
+/// Rewrites every expression held directly by a plan node, see [`transform_expression_up`].
+pub(crate) fn map_expressions(
+    plan: LogicalPlan,
+    f: &dyn Fn(AnalyzedExpression) -> Transformed<AnalyzedExpression>,
+) -> Transformed<LogicalPlan> {
+    let mut changed = false;
+    let mut visit = |expr: AnalyzedExpression| {
+        let rewritten = transform_expression_up(expr, f);
+        changed |= rewritten.changed;
+        rewritten.value
+    };
+
+    let plan = match plan {
+        LogicalPlan::Scan {
+            table_name,
+            schema,
+            projection,
+            filters,
+        } => LogicalPlan::Scan {
+            table_name,
+            schema,
+            projection,
+            filters: filters.into_iter().map(&mut visit).collect(),
+        },
+        LogicalPlan::Filter { input, condition } => LogicalPlan::Filter {
+            input,
+            condition: visit(condition),
+        },
+        LogicalPlan::Projection {
+            input,
+            expressions,
+            schema,
+        } => LogicalPlan::Projection {
+            input,
+            expressions: expressions.into_iter().map(&mut visit).collect(),
+            schema,
+        },
+        LogicalPlan::Aggregate {
+            input,
+            group_by,
+            mut aggregates,
+            schema,
+        } => {
+            let group_by = group_by.into_iter().map(&mut visit).collect();
+            for aggregate in &mut aggregates {
+                aggregate.args = std::mem::take(&mut aggregate.args)
+                    .into_iter()
+                    .map(&mut visit)
+                    .collect();
+            }
+
+            LogicalPlan::Aggregate {
+                input,
+                group_by,
+                aggregates,
+                schema,
+            }
+        }
+        LogicalPlan::Join {
+            left,
+            right,
+            conditions,
+            schema,
+        } => LogicalPlan::Join {
+            left,
+            right,
+            conditions: conditions.into_iter().map(&mut visit).collect(),
+            schema,
+        },
+        empty @ LogicalPlan::Empty { .. } => empty,
+    };
+
+    Transformed {
+        value: plan,
+        changed,
+    }
+}
+
+/// Rewrites every node of `expr` with `f`, operands before the expressions using them.
+pub(crate) fn transform_expression_up(
+    expr: AnalyzedExpression,
+    f: &dyn Fn(AnalyzedExpression) -> Transformed<AnalyzedExpression>,
+) -> Transformed<AnalyzedExpression> {
+    let mut children_changed = false;
+    let mut visit = |expr: AnalyzedExpression| {
+        let rewritten = transform_expression_up(expr, f);
+        children_changed |= rewritten.changed;
+        rewritten.value
+    };
+
+    let expr = match expr {
+        AnalyzedExpression::BinaryExpr {
+            left,
+            op,
+            right,
+            return_type,
+        } => AnalyzedExpression::BinaryExpr {
+            left: Box::new(visit(*left)),
+            op,
+            right: Box::new(visit(*right)),
+            return_type,
+        },
+        AnalyzedExpression::IsPredicate {
+            expr,
+            predicate,
+            negated,
+        } => AnalyzedExpression::IsPredicate {
+            expr: Box::new(visit(*expr)),
+            predicate,
+            negated,
+        },
+        AnalyzedExpression::ScalarFunction {
+            function,
+            args,
+            return_type,
+        } => AnalyzedExpression::ScalarFunction {
+            function,
+            args: args.into_iter().map(&mut visit).collect(),
+            return_type,
+        },
+        AnalyzedExpression::Case {
+            operand,
+            branches,
+            else_result,
+            return_type,
+        } => AnalyzedExpression::Case {
+            operand: operand.map(|operand| Box::new(visit(*operand))),
+            branches: branches
+                .into_iter()
+                .map(|(condition, result)| (visit(condition), visit(result)))
+                .collect(),
+            else_result: else_result.map(|result| Box::new(visit(*result))),
+            return_type,
+        },
+        AnalyzedExpression::Cast { expr, data_type } => AnalyzedExpression::Cast {
+            expr: Box::new(visit(*expr)),
+            data_type,
+        },
+        leaf @ (AnalyzedExpression::Literal(_)
+        | AnalyzedExpression::Column(..)
+        | AnalyzedExpression::Parameter { .. }) => leaf,
+    };
+
+    let mut rewritten = f(expr);
+    rewritten.changed |= children_changed;
+    rewritten
+}
+
+/// The value of an expression that does not depend on the row, if it is one.
+///
+/// Typed NULLs are represented as a cast of a NULL literal.
+pub(crate) fn constant_value(expr: &AnalyzedExpression) -> Option<&Value> {
+    match expr {
+        AnalyzedExpression::Literal(value) => Some(value),
+        AnalyzedExpression::Cast { expr, .. } => match expr.as_ref() {
+            AnalyzedExpression::Literal(Value::Null) => Some(&Value::Null),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Rewrites every column reference of `expr` to the column at `position(index)`.
+pub(crate) fn renumber_columns(
+    expr: AnalyzedExpression,
+    position: &dyn Fn(usize) -> usize,
+) -> AnalyzedExpression {
+    transform_expression_up(expr, &|expr| match expr {
+        AnalyzedExpression::Column(column, data_type) => {
+            Transformed::yes(AnalyzedExpression::Column(
+                ColumnRef {
+                    index: position(column.index),
+                    relation: column.relation,
+                },
+                data_type,
+            ))
+        }
+        expr => Transformed::no(expr),
+    })
+    .value
+}
+
+/// Adds the indices of the columns `expr` reads to `columns`.
+pub(crate) fn referenced_columns(expr: &AnalyzedExpression, columns: &mut Vec<usize>) {
+    match expr {
+        AnalyzedExpression::Column(column, _) => columns.push(column.index),
+        AnalyzedExpression::Literal(_) | AnalyzedExpression::Parameter { .. } => {}
+        AnalyzedExpression::BinaryExpr { left, right, .. } => {
+            referenced_columns(left, columns);
+            referenced_columns(right, columns);
+        }
+        AnalyzedExpression::IsPredicate { expr, .. } | AnalyzedExpression::Cast { expr, .. } => {
+            referenced_columns(expr, columns);
+        }
+        AnalyzedExpression::ScalarFunction { args, .. } => {
+            for arg in args {
+                referenced_columns(arg, columns);
+            }
+        }
+        AnalyzedExpression::Case {
+            operand,
+            branches,
+            else_result,
+            ..
+        } => {
+            for expr in operand.iter().chain(else_result) {
+                referenced_columns(expr, columns);
+            }
+            for (condition, result) in branches {
+                referenced_columns(condition, columns);
+                referenced_columns(result, columns);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        DataType,
+        sql::{
+            analyzer::schema::{Field, OutputSchema},
+            ast::operator::Operator,
+        },
+    };
+
+    pub(super) fn column(index: usize, data_type: DataType) -> AnalyzedExpression {
+        AnalyzedExpression::Column(
+            ColumnRef {
+                index,
+                relation: None,
+            },
+            data_type,
+        )
+    }
+
+    pub(super) fn literal(value: Value) -> AnalyzedExpression {
+        AnalyzedExpression::Literal(value)
+    }
+
+    pub(super) fn binary(
+        left: AnalyzedExpression,
+        op: Operator,
+        right: AnalyzedExpression,
+        return_type: DataType,
+    ) -> AnalyzedExpression {
+        AnalyzedExpression::BinaryExpr {
+            left: Box::new(left),
+            op,
+            right: Box::new(right),
+            return_type,
+        }
+    }
+
+    /// `age > value`, with `age` the second column of [`scan`].
+    pub(super) fn age_above(value: i64) -> AnalyzedExpression {
+        binary(
+            column(1, DataType::Int64),
+            Operator::GreaterThan,
+            literal(Value::Int64(value)),
+            DataType::Bool,
+        )
+    }
+
+    /// Scan of a `users (name TEXT, age BIGINT)` table.
+    pub(super) fn scan() -> LogicalPlan {
+        let field = |name: &str, data_type| Field {
+            name: name.to_string(),
+            alias: None,
+            relation: None,
+            data_type,
+            is_nullable: true,
+        };
+
+        LogicalPlan::Scan {
+            table_name: "users".to_string(),
+            schema: OutputSchema {
+                fields: vec![field("name", DataType::Text), field("age", DataType::Int64)],
+            },
+            projection: None,
+            filters: Vec::new(),
+        }
+    }
+
+    /// Join of two plans, with the columns of `left` followed by those of `right`.
+    pub(super) fn join(
+        left: LogicalPlan,
+        right: LogicalPlan,
+        conditions: Vec<AnalyzedExpression>,
+    ) -> LogicalPlan {
+        let schema = OutputSchema {
+            fields: left
+                .schema()
+                .fields
+                .iter()
+                .chain(&right.schema().fields)
+                .cloned()
+                .collect(),
+        };
+        LogicalPlan::Join {
+            left: Box::new(left),
+            right: Box::new(right),
+            conditions,
+            schema,
+        }
+    }
+
+    pub(super) fn filter(input: LogicalPlan, condition: AnalyzedExpression) -> LogicalPlan {
+        LogicalPlan::Filter {
+            input: Box::new(input),
+            condition,
+        }
+    }
+
+    #[test]
+    fn test_optimize_to_fixpoint() {
+        // WHERE age > 10 + 5 AND TRUE, with the filter above a projection of both columns
+        let condition = binary(
+            binary(
+                column(1, DataType::Int64),
+                Operator::GreaterThan,
+                binary(
+                    literal(Value::Int64(10)),
+                    Operator::Add,
+                    literal(Value::Int64(5)),
+                    DataType::Int64,
+                ),
+                DataType::Bool,
+            ),
+            Operator::And,
+            literal(Value::Bool(true)),
+            DataType::Bool,
+        );
+        let input = scan();
+        let schema = input.schema().clone();
+        let projection = LogicalPlan::Projection {
+            input: Box::new(input),
+            expressions: vec![column(0, DataType::Text), column(1, DataType::Int64)],
+            schema,
+        };
+
+        let plan = Optimizer::new().optimize(filter(projection, condition));
+
+        let LogicalPlan::Projection { input, .. } = plan else {
+            panic!("Expected the projection on top, got {plan:?}");
+        };
+        let LogicalPlan::Scan { filters, .. } = *input else {
+            panic!("Expected the filter to be part of the scan, got {input:?}");
+        };
+        let [
+            AnalyzedExpression::BinaryExpr {
+                op: Operator::GreaterThan,
+                right,
+                ..
+            },
+        ] = filters.as_slice()
+        else {
+            panic!("Expected a single folded comparison, got {filters:?}");
+        };
+        assert!(matches!(
+            right.as_ref(),
+            AnalyzedExpression::Literal(Value::Int64(15))
+        ));
+    }
+
+    #[test]
+    fn test_optimize_always_false_where() {
+        let plan = Optimizer::new().optimize(filter(
+            scan(),
+            binary(
+                literal(Value::Int64(1)),
+                Operator::Equal,
+                literal(Value::Int64(2)),
+                DataType::Bool,
+            ),
+        ));
+
+        assert!(matches!(plan, LogicalPlan::Empty { .. }), "got {plan:?}");
+    }
+}
@@ -0,0 +1,331 @@
+use crate::sql::{
+    analyzer::{AnalyzedExpression, schema::OutputSchema},
+    ast::operator::Operator,
+    optimizer::{
+        OptimizerRule, Transformed, referenced_columns, renumber_columns, transform_expression_up,
+    },
+    planner::logical::LogicalPlan,
+};
+
+/// Moves filters closer to the data: below projections and joins and into scans, so rows
+/// are dropped before anything else is computed for them.
+///
+/// A filter only moves below a projection if the columns it uses are not computed by
+/// function calls, which would otherwise be evaluated twice. A filter above a join
+/// becomes conditions of the join, conditions reading a single input of a join are then
+/// moved to that input.
+#[derive(Debug)]
+pub(crate) struct PushDownFilter;
+
+impl OptimizerRule for PushDownFilter {
+    fn rewrite(&self, plan: LogicalPlan) -> Transformed<LogicalPlan> {
+        let (input, condition) = match plan {
+            LogicalPlan::Filter { input, condition } => (input, condition),
+            LogicalPlan::Join {
+                left,
+                right,
+                conditions,
+                schema,
+            } => return push_down_join_conditions(*left, *right, conditions, schema),
+            plan => return Transformed::no(plan),
+        };
+
+        match *input {
+            LogicalPlan::Scan {
+                table_name,
+                schema,
+                projection,
+                mut filters,
+            } => {
+                split_conjunction(condition, &mut filters);
+                Transformed::yes(LogicalPlan::Scan {
+                    table_name,
+                    schema,
+                    projection,
+                    filters,
+                })
+            }
+            LogicalPlan::Projection {
+                input,
+                expressions,
+                schema,
+            } if can_inline(&condition, &expressions) => {
+                let condition = transform_expression_up(condition, &|expr| match expr {
+                    AnalyzedExpression::Column(column, _) => {
+                        Transformed::yes(expressions[column.index].clone())
+                    }
+                    expr => Transformed::no(expr),
+                })
+                .value;
+
+                Transformed::yes(LogicalPlan::Projection {
+                    input: Box::new(LogicalPlan::Filter { input, condition }),
+                    expressions,
+                    schema,
+                })
+            }
+            LogicalPlan::Join {
+                left,
+                right,
+                mut conditions,
+                schema,
+            } => {
+                split_conjunction(condition, &mut conditions);
+                Transformed::yes(LogicalPlan::Join {
+                    left,
+                    right,
+                    conditions,
+                    schema,
+                })
+            }
+            input => Transformed::no(LogicalPlan::Filter {
+                input: Box::new(input),
+                condition,
+            }),
+        }
+    }
+}
+
+/// Moves the conditions of a join that only read one of its inputs into filters on that
+/// input, the other conditions are kept by the join.
+fn push_down_join_conditions(
+    mut left: LogicalPlan,
+    mut right: LogicalPlan,
+    conditions: Vec<AnalyzedExpression>,
+    schema: OutputSchema,
+) -> Transformed<LogicalPlan> {
+    let count = conditions.len();
+    let mut conjuncts = Vec::new();
+    for condition in conditions {
+        split_conjunction(condition, &mut conjuncts);
+    }
+    let mut changed = conjuncts.len() != count;
+
+    let left_width = left.schema().fields.len();
+    let mut kept = Vec::new();
+    for condition in conjuncts {
+        let mut columns = Vec::new();
+        referenced_columns(&condition, &mut columns);
+
+        if columns.is_empty() {
+            kept.push(condition);
+        } else if columns.iter().all(|index| *index < left_width) {
+            left = LogicalPlan::Filter {
+                input: Box::new(left),
+                condition,
+            };
+            changed = true;
+        } else if columns.iter().all(|index| *index >= left_width) {
+            right = LogicalPlan::Filter {
+                input: Box::new(right),
+                condition: renumber_columns(condition, &|index| index - left_width),
+            };
+            changed = true;
+        } else {
+            kept.push(condition);
+        }
+    }
+
+    Transformed {
+        value: LogicalPlan::Join {
+            left: Box::new(left),
+            right: Box::new(right),
+            conditions: kept,
+            schema,
+        },
+        changed,
+    }
+}
+
+/// Adds the operands of a chain of `AND`s to `conjuncts`.
+fn split_conjunction(expr: AnalyzedExpression, conjuncts: &mut Vec<AnalyzedExpression>) {
+    match expr {
+        AnalyzedExpression::BinaryExpr {
+            left,
+            op: Operator::And,
+            right,
+            ..
+        } => {
+            split_conjunction(*left, conjuncts);
+            split_conjunction(*right, conjuncts);
+        }
+        expr => conjuncts.push(expr),
+    }
+}
+
+/// Whether the projected expressions used by `condition` can be copied into it.
+fn can_inline(condition: &AnalyzedExpression, expressions: &[AnalyzedExpression]) -> bool {
+    let mut columns = Vec::new();
+    referenced_columns(condition, &mut columns);
+
+    columns.into_iter().all(|index| {
+        expressions
+            .get(index)
+            .is_some_and(|expr| !calls_function(expr))
+    })
+}
+
+fn calls_function(expr: &AnalyzedExpression) -> bool {
+    match expr {
+        AnalyzedExpression::ScalarFunction { .. } => true,
+        AnalyzedExpression::Column(..)
+        | AnalyzedExpression::Literal(_)
+        | AnalyzedExpression::Parameter { .. } => false,
+        AnalyzedExpression::BinaryExpr { left, right, .. } => {
+            calls_function(left) || calls_function(right)
+        }
+        AnalyzedExpression::IsPredicate { expr, .. } | AnalyzedExpression::Cast { expr, .. } => {
+            calls_function(expr)
+        }
+        AnalyzedExpression::Case {
+            operand,
+            branches,
+            else_result,
+            ..
+        } => {
+            operand
+                .iter()
+                .chain(else_result)
+                .any(|expr| calls_function(expr))
+                || branches
+                    .iter()
+                    .any(|(condition, result)| calls_function(condition) || calls_function(result))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        DataType, Value,
+        sql::{
+            functions::FunctionRegistry,
+            optimizer::tests::{age_above, binary, column, filter, join, literal, scan},
+        },
+    };
+
+    fn projection(expressions: Vec<AnalyzedExpression>) -> LogicalPlan {
+        let input = scan();
+        let schema = input.schema().clone();
+        LogicalPlan::Projection {
+            input: Box::new(input),
+            expressions,
+            schema,
+        }
+    }
+
+    #[test]
+    fn test_push_filter_into_scan() {
+        let condition = binary(age_above(18), Operator::And, age_above(65), DataType::Bool);
+
+        let rewritten = PushDownFilter.rewrite(filter(scan(), condition));
+        assert!(rewritten.changed);
+
+        let LogicalPlan::Scan { filters, .. } = rewritten.value else {
+            panic!("Expected the filter in the scan");
+        };
+        assert_eq!(
+            format!("{filters:?}"),
+            format!("{:?}", vec![age_above(18), age_above(65)])
+        );
+    }
+
+    #[test]
+    fn test_push_filter_below_projection() {
+        // SELECT age + 1 AS next_age, name ... WHERE next_age > 18
+        let next_age = binary(
+            column(1, DataType::Int64),
+            Operator::Add,
+            literal(Value::Int64(1)),
+            DataType::Int64,
+        );
+        let plan = filter(
+            projection(vec![next_age.clone(), column(0, DataType::Text)]),
+            binary(
+                column(0, DataType::Int64),
+                Operator::GreaterThan,
+                literal(Value::Int64(18)),
+                DataType::Bool,
+            ),
+        );
+
+        let rewritten = PushDownFilter.rewrite(plan);
+        assert!(rewritten.changed);
+
+        let LogicalPlan::Projection { input, .. } = rewritten.value else {
+            panic!("Expected the projection on top");
+        };
+        let LogicalPlan::Filter { condition, .. } = *input else {
+            panic!("Expected the filter below the projection");
+        };
+        let AnalyzedExpression::BinaryExpr { left, .. } = condition else {
+            panic!("Expected a comparison");
+        };
+        assert_eq!(format!("{left:?}"), format!("{next_age:?}"));
+    }
+
+    #[test]
+    fn test_keep_filter_on_function_result() {
+        let lower = FunctionRegistry::new().scalar("lower").unwrap();
+        let plan = filter(
+            projection(vec![AnalyzedExpression::ScalarFunction {
+                function: lower,
+                args: vec![column(0, DataType::Text)],
+                return_type: DataType::Text,
+            }]),
+            binary(
+                column(0, DataType::Text),
+                Operator::Equal,
+                literal(Value::Text("bob".to_string())),
+                DataType::Bool,
+            ),
+        );
+
+        let rewritten = PushDownFilter.rewrite(plan);
+        assert!(!rewritten.changed);
+        assert!(matches!(rewritten.value, LogicalPlan::Filter { .. }));
+    }
+
+    #[test]
+    fn test_push_filter_below_join() {
+        // users a JOIN users b WHERE b.age > 18 AND a.age = b.age
+        let same_age = binary(
+            column(1, DataType::Int64),
+            Operator::Equal,
+            column(3, DataType::Int64),
+            DataType::Bool,
+        );
+        let b_age_above = binary(
+            column(3, DataType::Int64),
+            Operator::GreaterThan,
+            literal(Value::Int64(18)),
+            DataType::Bool,
+        );
+        let plan = filter(
+            join(scan(), scan(), Vec::new()),
+            binary(b_age_above, Operator::And, same_age.clone(), DataType::Bool),
+        );
+
+        let rewritten = PushDownFilter.rewrite(plan);
+        assert!(rewritten.changed);
+        let rewritten = PushDownFilter.rewrite(rewritten.value);
+        assert!(rewritten.changed);
+
+        let LogicalPlan::Join {
+            left,
+            right,
+            conditions,
+            ..
+        } = rewritten.value
+        else {
+            panic!("Expected the join on top");
+        };
+        assert!(matches!(*left, LogicalPlan::Scan { .. }));
+        let LogicalPlan::Filter { condition, .. } = *right else {
+            panic!("Expected the filter on the right input");
+        };
+        assert_eq!(format!("{condition:?}"), format!("{:?}", age_above(18)));
+        assert_eq!(format!("{conditions:?}"), format!("{:?}", vec![same_age]));
+    }
+}
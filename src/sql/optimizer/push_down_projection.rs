@@ -0,0 +1,435 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::sql::{
+    analyzer::{AnalyzedExpression, ColumnRef, schema::OutputSchema},
+    optimizer::{
+        OptimizerRule, Transformed, map_expressions, referenced_columns, renumber_columns,
+    },
+    planner::logical::LogicalPlan,
+};
+
+/// Makes scans only read the columns used by the plan above them, e.g.
+/// `SELECT id FROM users` only decodes the `id` column.
+///
+/// Applies to a projection or aggregate reading scans, possibly through filters and
+/// joins. Each input of a join keeps the columns used above the join and by its
+/// conditions. Column references of all these nodes are renumbered to the pruned
+/// schemas.
+#[derive(Debug)]
+pub(crate) struct PushDownProjection;
+
+impl OptimizerRule for PushDownProjection {
+    fn rewrite(&self, mut plan: LogicalPlan) -> Transformed<LogicalPlan> {
+        if !matches!(
+            plan,
+            LogicalPlan::Projection { .. } | LogicalPlan::Aggregate { .. }
+        ) {
+            return Transformed::no(plan);
+        }
+        let required = required_columns(plan.expressions());
+        let (LogicalPlan::Projection { input, .. } | LogicalPlan::Aggregate { input, .. }) =
+            &mut plan
+        else {
+            unreachable!("only projections and aggregates are pruned below");
+        };
+
+        let empty = LogicalPlan::Empty {
+            schema: OutputSchema { fields: Vec::new() },
+        };
+        let pruned = prune(std::mem::replace(input.as_mut(), empty), &required);
+        let (pruned_input, positions) = pruned.value;
+        **input = pruned_input;
+        if !pruned.changed {
+            return Transformed::no(plan);
+        }
+
+        let renumber = |expr| match expr {
+            AnalyzedExpression::Column(column, data_type) => {
+                Transformed::yes(AnalyzedExpression::Column(
+                    ColumnRef {
+                        index: positions[&column.index],
+                        relation: column.relation,
+                    },
+                    data_type,
+                ))
+            }
+            expr => Transformed::no(expr),
+        };
+        Transformed::yes(map_expressions(plan, &renumber).value)
+    }
+}
+
+/// The columns read by `expressions`.
+fn required_columns<'e>(
+    expressions: impl IntoIterator<Item = &'e AnalyzedExpression>,
+) -> BTreeSet<usize> {
+    let mut columns = Vec::new();
+    for expr in expressions {
+        referenced_columns(expr, &mut columns);
+    }
+    columns.into_iter().collect()
+}
+
+/// Rewrites `plan` to return as few columns besides `required` as its inputs allow,
+/// with the new position of every required column.
+///
+/// Projections and aggregates compute their output and are left as they are, they
+/// prune their own inputs.
+fn prune(
+    plan: LogicalPlan,
+    required: &BTreeSet<usize>,
+) -> Transformed<(LogicalPlan, HashMap<usize, usize>)> {
+    match plan {
+        LogicalPlan::Scan {
+            table_name,
+            schema,
+            projection,
+            filters,
+        } => {
+            let mut required = required.clone();
+            required.extend(required_columns(&filters));
+            if required.len() == schema.fields.len() {
+                let scan = LogicalPlan::Scan {
+                    table_name,
+                    schema,
+                    projection,
+                    filters,
+                };
+                return Transformed::no((scan, unchanged(&required)));
+            }
+
+            let positions = required
+                .iter()
+                .enumerate()
+                .map(|(position, index)| (*index, position))
+                .collect::<HashMap<_, _>>();
+            let fields = required
+                .iter()
+                .map(|index| schema.fields[*index].clone())
+                .collect();
+            // A scan that was pruned before maps into its already reduced columns
+            let projection = required
+                .iter()
+                .map(|index| {
+                    projection
+                        .as_ref()
+                        .map_or(*index, |columns| columns[*index])
+                })
+                .collect();
+            let filters = filters
+                .into_iter()
+                .map(|filter| renumber_columns(filter, &|index| positions[&index]))
+                .collect();
+
+            let scan = LogicalPlan::Scan {
+                table_name,
+                schema: OutputSchema { fields },
+                projection: Some(projection),
+                filters,
+            };
+            Transformed::yes((scan, positions))
+        }
+        LogicalPlan::Filter { input, condition } => {
+            let mut required = required.clone();
+            required.extend(required_columns([&condition]));
+
+            let pruned = prune(*input, &required);
+            let (input, positions) = pruned.value;
+            let filter = LogicalPlan::Filter {
+                input: Box::new(input),
+                condition: renumber_columns(condition, &|index| positions[&index]),
+            };
+            Transformed {
+                value: (filter, positions),
+                changed: pruned.changed,
+            }
+        }
+        LogicalPlan::Join {
+            left,
+            right,
+            conditions,
+            schema,
+        } => {
+            let mut required = required.clone();
+            required.extend(required_columns(&conditions));
+
+            let left_width = left.schema().fields.len();
+            let (left_required, right_required) = required
+                .iter()
+                .copied()
+                .partition::<BTreeSet<_>, _>(|index| *index < left_width);
+            let right_required = right_required
+                .into_iter()
+                .map(|index| index - left_width)
+                .collect();
+
+            let left = prune(*left, &left_required);
+            let right = prune(*right, &right_required);
+            let changed = left.changed || right.changed;
+            let (left, left_positions) = left.value;
+            let (right, right_positions) = right.value;
+            if !changed {
+                let join = LogicalPlan::Join {
+                    left: Box::new(left),
+                    right: Box::new(right),
+                    conditions,
+                    schema,
+                };
+                return Transformed::no((join, unchanged(&required)));
+            }
+
+            let pruned_left_width = left.schema().fields.len();
+            let positions = required
+                .iter()
+                .map(|index| {
+                    let position = match index.checked_sub(left_width) {
+                        Some(index) => pruned_left_width + right_positions[&index],
+                        None => left_positions[index],
+                    };
+                    (*index, position)
+                })
+                .collect::<HashMap<_, _>>();
+            let conditions = conditions
+                .into_iter()
+                .map(|condition| renumber_columns(condition, &|index| positions[&index]))
+                .collect();
+            let schema = OutputSchema {
+                fields: left
+                    .schema()
+                    .fields
+                    .iter()
+                    .chain(&right.schema().fields)
+                    .cloned()
+                    .collect(),
+            };
+
+            let join = LogicalPlan::Join {
+                left: Box::new(left),
+                right: Box::new(right),
+                conditions,
+                schema,
+            };
+            Transformed::yes((join, positions))
+        }
+        plan @ (LogicalPlan::Projection { .. }
+        | LogicalPlan::Aggregate { .. }
+        | LogicalPlan::Empty { .. }) => Transformed::no((plan, unchanged(required))),
+    }
+}
+
+/// Positions of columns that keep their place.
+fn unchanged(columns: &BTreeSet<usize>) -> HashMap<usize, usize> {
+    columns.iter().map(|index| (*index, *index)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        DataType, Value,
+        sql::{
+            analyzer::schema::Field,
+            ast::operator::Operator,
+            functions::FunctionRegistry,
+            optimizer::tests::{age_above, binary, column, filter, join, literal, scan},
+            planner::logical::AggregateExpr,
+        },
+    };
+
+    fn projection(input: LogicalPlan, expressions: Vec<AnalyzedExpression>) -> LogicalPlan {
+        let schema = OutputSchema {
+            fields: expressions
+                .iter()
+                .map(|expr| Field {
+                    name: "expr".to_string(),
+                    alias: None,
+                    relation: None,
+                    data_type: expr.get_type(),
+                    is_nullable: true,
+                })
+                .collect(),
+        };
+        LogicalPlan::Projection {
+            input: Box::new(input),
+            expressions,
+            schema,
+        }
+    }
+
+    fn column_index(expr: &AnalyzedExpression) -> usize {
+        match expr {
+            AnalyzedExpression::Column(column, _) => column.index,
+            other => panic!("Expected a column, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_prune_scan_columns() {
+        // SELECT age + 1 FROM users
+        let plan = projection(
+            scan(),
+            vec![binary(
+                column(1, DataType::Int64),
+                Operator::Add,
+                literal(Value::Int64(1)),
+                DataType::Int64,
+            )],
+        );
+
+        let rewritten = PushDownProjection.rewrite(plan);
+        assert!(rewritten.changed);
+
+        let LogicalPlan::Projection {
+            input, expressions, ..
+        } = rewritten.value
+        else {
+            panic!("Expected the projection to remain");
+        };
+        let AnalyzedExpression::BinaryExpr { left, .. } = &expressions[0] else {
+            panic!("Expected the addition to remain");
+        };
+        assert_eq!(column_index(left), 0);
+
+        let LogicalPlan::Scan {
+            schema, projection, ..
+        } = *input
+        else {
+            panic!("Expected a scan");
+        };
+        assert_eq!(projection, Some(vec![1]));
+        assert_eq!(schema.fields.len(), 1);
+        assert_eq!(schema.fields[0].name, "age");
+
+        // Applying the rule again changes nothing
+        let plan = projection_over(schema, projection);
+        assert!(!PushDownProjection.rewrite(plan).changed);
+    }
+
+    fn projection_over(schema: OutputSchema, columns: Option<Vec<usize>>) -> LogicalPlan {
+        let scan = LogicalPlan::Scan {
+            table_name: "users".to_string(),
+            schema,
+            projection: columns,
+            filters: Vec::new(),
+        };
+        projection(scan, vec![column(0, DataType::Int64)])
+    }
+
+    #[test]
+    fn test_keep_columns_used_by_filters() {
+        // SELECT name FROM users WHERE age > 18, with the filter in the scan
+        let mut input = scan();
+        if let LogicalPlan::Scan { filters, .. } = &mut input {
+            filters.push(age_above(18));
+        }
+        let plan = projection(input, vec![column(0, DataType::Text)]);
+
+        let rewritten = PushDownProjection.rewrite(plan);
+        assert!(!rewritten.changed);
+
+        // SELECT age FROM users WHERE age > 18, with a separate filter
+        let plan = projection(
+            filter(scan(), age_above(18)),
+            vec![column(1, DataType::Int64)],
+        );
+        let rewritten = PushDownProjection.rewrite(plan);
+        assert!(rewritten.changed);
+
+        let LogicalPlan::Projection { input, .. } = rewritten.value else {
+            panic!("Expected the projection to remain");
+        };
+        let LogicalPlan::Filter { input, condition } = *input else {
+            panic!("Expected the filter to remain");
+        };
+        let AnalyzedExpression::BinaryExpr { left, .. } = condition else {
+            panic!("Expected a comparison");
+        };
+        assert_eq!(column_index(&left), 0);
+        assert!(matches!(
+            *input,
+            LogicalPlan::Scan {
+                projection: Some(ref columns),
+                ..
+            } if columns == &[1]
+        ));
+    }
+
+    #[test]
+    fn test_count_star_reads_no_column() {
+        let count = FunctionRegistry::new().aggregate("count").unwrap();
+        let plan = LogicalPlan::Aggregate {
+            input: Box::new(scan()),
+            group_by: Vec::new(),
+            aggregates: vec![AggregateExpr {
+                function: count,
+                args: Vec::new(),
+                return_type: DataType::Int64,
+            }],
+            schema: OutputSchema { fields: Vec::new() },
+        };
+
+        let rewritten = PushDownProjection.rewrite(plan);
+        let LogicalPlan::Aggregate { input, .. } = rewritten.value else {
+            panic!("Expected the aggregate to remain");
+        };
+        assert!(matches!(
+            *input,
+            LogicalPlan::Scan {
+                projection: Some(ref columns),
+                ..
+            } if columns.is_empty()
+        ));
+    }
+
+    #[test]
+    fn test_prune_join_inputs() {
+        // SELECT b.age FROM users a JOIN users b ON a.name = b.name
+        let condition = binary(
+            column(0, DataType::Text),
+            Operator::Equal,
+            column(2, DataType::Text),
+            DataType::Bool,
+        );
+        let plan = projection(
+            join(scan(), scan(), vec![condition]),
+            vec![column(3, DataType::Int64)],
+        );
+
+        let rewritten = PushDownProjection.rewrite(plan);
+        assert!(rewritten.changed);
+
+        let LogicalPlan::Projection {
+            input, expressions, ..
+        } = rewritten.value
+        else {
+            panic!("Expected the projection to remain");
+        };
+        assert_eq!(column_index(&expressions[0]), 2);
+
+        let LogicalPlan::Join {
+            left,
+            right,
+            conditions,
+            schema,
+        } = *input
+        else {
+            panic!("Expected the join to remain");
+        };
+        let AnalyzedExpression::BinaryExpr {
+            left: a, right: b, ..
+        } = &conditions[0]
+        else {
+            panic!("Expected a comparison");
+        };
+        assert_eq!((column_index(a), column_index(b)), (0, 1));
+        assert_eq!(schema.fields.len(), 3);
+
+        // The left input only keeps the join key, the right one needs both its columns
+        let scan_columns = |plan: &LogicalPlan| match plan {
+            LogicalPlan::Scan { projection, .. } => projection.clone(),
+            other => panic!("Expected a scan, got {other:?}"),
+        };
+        assert_eq!(scan_columns(&left), Some(vec![0]));
+        assert_eq!(scan_columns(&right), None);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{
+    Value,
+    sql::{
+        analyzer::AnalyzedExpression,
+        ast::operator::Operator,
+        optimizer::{OptimizerRule, Transformed, map_expressions},
+        planner::logical::LogicalPlan,
+    },
+};
+
+/// Removes boolean identities, e.g. `x AND TRUE` becomes `x` and `x OR TRUE` becomes
+/// `TRUE`.
+///
+/// These hold under three-valued logic as well: `NULL AND FALSE` is `FALSE`.
+#[derive(Debug)]
+pub(crate) struct SimplifyBooleans;
+
+impl OptimizerRule for SimplifyBooleans {
+    fn rewrite(&self, plan: LogicalPlan) -> Transformed<LogicalPlan> {
+        map_expressions(plan, &simplify)
+    }
+}
+
+fn simplify(expr: AnalyzedExpression) -> Transformed<AnalyzedExpression> {
+    let AnalyzedExpression::BinaryExpr {
+        left,
+        op: op @ (Operator::And | Operator::Or),
+        right,
+        return_type,
+    } = expr
+    else {
+        return Transformed::no(expr);
+    };
+
+    let as_bool = |expr: &AnalyzedExpression| match expr {
+        AnalyzedExpression::Literal(Value::Bool(b)) => Some(*b),
+        _ => None,
+    };
+
+    // The value deciding the result on its own, TRUE for OR and FALSE for AND
+    let absorbing = op == Operator::Or;
+    match (as_bool(&left), as_bool(&right)) {
+        (Some(b), _) | (_, Some(b)) if b == absorbing => {
+            Transformed::yes(AnalyzedExpression::Literal(Value::Bool(absorbing)))
+        }
+        (Some(_), _) => Transformed::yes(*right),
+        (_, Some(_)) => Transformed::yes(*left),
+        (None, None) => Transformed::no(AnalyzedExpression::BinaryExpr {
+            left,
+            op,
+            right,
+            return_type,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        DataType,
+        sql::optimizer::tests::{age_above, binary, literal},
+    };
+
+    fn simplified(left: AnalyzedExpression, op: Operator, right: AnalyzedExpression) -> String {
+        let rewritten = simplify(binary(left, op, right, DataType::Bool));
+        format!("{:?}", rewritten.value)
+    }
+
+    #[test]
+    fn test_and_identities() {
+        let age = format!("{:?}", age_above(18));
+        let bool = |b| literal(Value::Bool(b));
+
+        assert_eq!(simplified(age_above(18), Operator::And, bool(true)), age);
+        assert_eq!(simplified(bool(true), Operator::And, age_above(18)), age);
+        assert_eq!(
+            simplified(age_above(18), Operator::And, bool(false)),
+            format!("{:?}", bool(false))
+        );
+    }
+
+    #[test]
+    fn test_or_identities() {
+        let age = format!("{:?}", age_above(18));
+        let bool = |b| literal(Value::Bool(b));
+
+        assert_eq!(simplified(age_above(18), Operator::Or, bool(false)), age);
+        assert_eq!(
+            simplified(bool(true), Operator::Or, age_above(18)),
+            format!("{:?}", bool(true))
+        );
+    }
+
+    #[test]
+    fn test_keep_other_expressions() {
+        let rewritten = simplify(binary(
+            age_above(18),
+            Operator::And,
+            age_above(65),
+            DataType::Bool,
+        ));
+        assert!(!rewritten.changed);
+
+        let rewritten = simplify(age_above(18));
+        assert!(!rewritten.changed);
+    }
+}
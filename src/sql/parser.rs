@@ -4,6 +4,7 @@ use miette::{Result, miette};
 
 use crate::{
     DataType, Value,
+    core::{binary, decimal::MAX_PRECISION},
     sql::{
         ast::{
             expression::Expression,
@@ -11,11 +12,13 @@ use crate::{
             operator::Operator,
             predicate::IsPredicate,
             statement::{
-                ColumnConstraint, ColumnDefinition, CreateStatement, FromClause, SelectStatement,
-                Statement,
+                AnalyzeStatement, ColumnConstraint, ColumnDefinition, CreateStatement,
+                DeallocateStatement, ExecuteStatement, ExplainStatement, FromClause, Join,
+                PrepareStatement, SelectStatement, Statement, TableReference, VacuumStatement,
             },
             target::{SelectList, SelectTarget},
         },
+        evaluator::cast_value,
         lexer::{Lexer, Token},
     },
 };
@@ -44,6 +47,12 @@ impl<'src> SqlParser<'src> {
             Token::Keyword(keyword) => match keyword {
                 Keyword::Select => self.parse_select_statement()?,
                 Keyword::Create => self.parse_create_statement()?,
+                Keyword::Vacuum => self.parse_vacuum_statement()?,
+                Keyword::Analyze => self.parse_analyze_statement()?,
+                Keyword::Explain => self.parse_explain_statement()?,
+                Keyword::Prepare => self.parse_prepare_statement()?,
+                Keyword::Execute => self.parse_execute_statement()?,
+                Keyword::Deallocate => self.parse_deallocate_statement()?,
                 _ => return Err(miette!("Unsupported keyword: {:?}", keyword)),
             },
             _ => return Err(miette!("Unexpected token: {:?}", token)),
@@ -59,23 +68,80 @@ impl<'src> SqlParser<'src> {
 
         self.expect_keyword(Keyword::From)?;
 
-        let table_name = self.expect_identifier()?;
+        let from_clause = self.parse_from_clause()?;
 
         let where_clause = self
-            .expect_keyword(Keyword::Where)
-            .ok()
-            .map(|_| self.parse_expression(0))
+            .consume_if(Token::Keyword(Keyword::Where))
+            .then(|| self.parse_expression(0))
             .transpose()?;
 
+        let group_by = if self.consume_if(Token::Keyword(Keyword::Group)) {
+            self.expect_keyword(Keyword::By)?;
+            self.parse_expression_list()?
+        } else {
+            Vec::new()
+        };
+
         Ok(Statement::Select(SelectStatement {
             select_list,
-            from_clause: FromClause {
-                table_name: table_name.to_string(),
-            },
+            from_clause,
             where_clause,
+            group_by,
         }))
     }
 
+    fn parse_from_clause(&mut self) -> Result<FromClause> {
+        let table = self.parse_table_reference()?;
+
+        let mut joins = Vec::new();
+        loop {
+            let condition = if self.consume_if(Token::Comma) {
+                false
+            } else if self.consume_if(Token::Keyword(Keyword::Cross)) {
+                self.expect_keyword(Keyword::Join)?;
+                false
+            } else if self.consume_if(Token::Keyword(Keyword::Join)) {
+                true
+            } else if self.consume_if(Token::Keyword(Keyword::Inner)) {
+                self.expect_keyword(Keyword::Join)?;
+                true
+            } else if let Some(Ok(Token::Keyword(
+                kind @ (Keyword::Left | Keyword::Right | Keyword::Full),
+            ))) = self.lexer.peek()
+            {
+                return Err(miette!("{:?} joins are not supported", kind));
+            } else {
+                break;
+            };
+
+            let table = self.parse_table_reference()?;
+            let condition = condition
+                .then(|| {
+                    self.expect_keyword(Keyword::On)?;
+                    self.parse_expression(0)
+                })
+                .transpose()?;
+
+            joins.push(Join { table, condition });
+        }
+
+        Ok(FromClause { table, joins })
+    }
+
+    fn parse_table_reference(&mut self) -> Result<TableReference> {
+        let table_name = self.expect_identifier()?.to_string();
+
+        let alias = if self.consume_if(Token::Keyword(Keyword::As))
+            || matches!(self.lexer.peek(), Some(Ok(Token::Identifier(_))))
+        {
+            Some(self.expect_identifier()?.to_string())
+        } else {
+            None
+        };
+
+        Ok(TableReference { table_name, alias })
+    }
+
     fn parse_create_statement(&mut self) -> Result<Statement> {
         self.expect_keyword(Keyword::Create)?;
         self.expect_keyword(Keyword::Table)?;
@@ -99,6 +165,90 @@ impl<'src> SqlParser<'src> {
         }))
     }
 
+    fn parse_vacuum_statement(&mut self) -> Result<Statement> {
+        self.expect_keyword(Keyword::Vacuum)?;
+
+        let table_name = matches!(self.lexer.peek(), Some(Ok(Token::Identifier(_))))
+            .then(|| self.expect_identifier())
+            .transpose()?
+            .map(|table_name| table_name.to_string());
+
+        Ok(Statement::Vacuum(VacuumStatement { table_name }))
+    }
+
+    fn parse_analyze_statement(&mut self) -> Result<Statement> {
+        self.expect_keyword(Keyword::Analyze)?;
+
+        let table_name = matches!(self.lexer.peek(), Some(Ok(Token::Identifier(_))))
+            .then(|| self.expect_identifier())
+            .transpose()?
+            .map(|table_name| table_name.to_string());
+
+        Ok(Statement::Analyze(AnalyzeStatement { table_name }))
+    }
+
+    fn parse_explain_statement(&mut self) -> Result<Statement> {
+        self.expect_keyword(Keyword::Explain)?;
+
+        let analyze = self.consume_if(Token::Keyword(Keyword::Analyze));
+        let statement = Box::new(self.parse()?);
+
+        Ok(Statement::Explain(ExplainStatement { analyze, statement }))
+    }
+
+    fn parse_prepare_statement(&mut self) -> Result<Statement> {
+        self.expect_keyword(Keyword::Prepare)?;
+
+        let name = self.expect_identifier()?.to_string();
+
+        let mut parameter_types = Vec::new();
+        if self.consume_if(Token::LeftParen) {
+            parameter_types.push(self.parse_data_type()?);
+            while self.consume_if(Token::Comma) {
+                parameter_types.push(self.parse_data_type()?);
+            }
+            self.expect_token(Token::RightParen)?;
+        }
+
+        self.expect_keyword(Keyword::As)?;
+        let statement = Box::new(self.parse()?);
+
+        Ok(Statement::Prepare(PrepareStatement {
+            name,
+            parameter_types,
+            statement,
+        }))
+    }
+
+    fn parse_execute_statement(&mut self) -> Result<Statement> {
+        self.expect_keyword(Keyword::Execute)?;
+
+        let name = self.expect_identifier()?.to_string();
+
+        let parameters = if self.consume_if(Token::LeftParen) {
+            let parameters = self.parse_expression_list()?;
+            self.expect_token(Token::RightParen)?;
+            parameters
+        } else {
+            Vec::new()
+        };
+
+        Ok(Statement::Execute(ExecuteStatement { name, parameters }))
+    }
+
+    fn parse_deallocate_statement(&mut self) -> Result<Statement> {
+        self.expect_keyword(Keyword::Deallocate)?;
+        self.consume_if(Token::Keyword(Keyword::Prepare));
+
+        let name = if self.consume_if(Token::Keyword(Keyword::All)) {
+            None
+        } else {
+            Some(self.expect_identifier()?.to_string())
+        };
+
+        Ok(Statement::Deallocate(DeallocateStatement { name }))
+    }
+
     fn parse_targets(&mut self) -> Result<SelectList> {
         let mut columns = Vec::new();
 
@@ -160,9 +310,15 @@ impl<'src> SqlParser<'src> {
             Token::Keyword(kw) if kw.is_bool_literal() => {
                 Expression::Literal(Value::Bool(matches!(kw, Keyword::True)))
             }
+            Token::Keyword(Keyword::Null) => Expression::Literal(Value::Null),
             Token::Integer(i) => Expression::Literal(Value::Int64(i)),
             Token::Float(f) => Expression::Literal(Value::Float64(f)),
             Token::String(s) => Expression::Literal(Value::Text(s.to_string())),
+            Token::HexString(hex) => Expression::Literal(Value::Bytea(binary::decode_hex(&hex)?)),
+            Token::Parameter(number) => Expression::Parameter(number),
+            Token::Identifier(i) if self.peek_is(Token::LeftParen) => {
+                self.parse_function_call(i.to_string())?
+            }
             Token::Identifier(i) => Expression::Identifier(i.to_string()),
             Token::Asterisk => Expression::Identifier("*".to_string()),
             Token::LeftParen => {
@@ -172,14 +328,142 @@ impl<'src> SqlParser<'src> {
 
                 expr
             }
+            Token::Keyword(kw @ (Keyword::Timestamp | Keyword::Date | Keyword::Interval)) => {
+                self.parse_typed_literal(kw)?
+            }
+            Token::Keyword(Keyword::Extract) => self.parse_extract()?,
+            Token::Keyword(Keyword::Case) => self.parse_case()?,
+            Token::Keyword(Keyword::Cast) => {
+                self.expect_token(Token::LeftParen)?;
+                let expr = self.parse_expression(0)?;
+                self.expect_keyword(Keyword::As)?;
+                let data_type = self.parse_data_type()?;
+                self.expect_token(Token::RightParen)?;
+
+                Expression::Cast {
+                    expr: Box::new(expr),
+                    data_type,
+                }
+            }
             t => {
                 return Err(miette!("Expected a column or value, but found {:?}", t));
             }
         };
 
+        let expr = self.parse_cast_postfix(expr)?;
+
         self.parse_is_postfix(expr)
     }
 
+    /// Parses a literal such as `TIMESTAMP '2024-01-01 12:00:00'`, the type keyword has
+    /// already been consumed.
+    fn parse_typed_literal(&mut self, keyword: Keyword) -> Result<Expression> {
+        let Token::String(text) = self.next_token()? else {
+            return Err(miette!("Expected a string after {keyword:?}"));
+        };
+
+        let data_type = match keyword {
+            Keyword::Timestamp => DataType::Timestamp,
+            Keyword::Date => DataType::Date,
+            _ => DataType::Interval,
+        };
+        let value = cast_value(&Value::Text(text.to_string()), data_type)?;
+
+        Ok(Expression::Literal(value))
+    }
+
+    /// Parses `EXTRACT(field FROM expr)` into a call of the `extract` function, the
+    /// `EXTRACT` keyword has already been consumed.
+    fn parse_extract(&mut self) -> Result<Expression> {
+        self.expect_token(Token::LeftParen)?;
+        let field = match self.next_token()? {
+            Token::Identifier(field) | Token::String(field) => field.to_string(),
+            t => return Err(miette!("Expected a field to extract, but found {:?}", t)),
+        };
+        self.expect_keyword(Keyword::From)?;
+        let expr = self.parse_expression(0)?;
+        self.expect_token(Token::RightParen)?;
+
+        Ok(Expression::Function {
+            name: "extract".to_string(),
+            args: vec![Expression::Literal(Value::Text(field)), expr],
+        })
+    }
+
+    /// Parses a `CASE` expression, the `CASE` keyword has already been consumed.
+    fn parse_case(&mut self) -> Result<Expression> {
+        let operand = if self.peek_keyword(Keyword::When) {
+            None
+        } else {
+            Some(Box::new(self.parse_expression(0)?))
+        };
+
+        let mut branches = Vec::new();
+        while self.consume_if(Token::Keyword(Keyword::When)) {
+            let condition = self.parse_expression(0)?;
+            self.expect_keyword(Keyword::Then)?;
+            let result = self.parse_expression(0)?;
+            branches.push((condition, result));
+        }
+
+        if branches.is_empty() {
+            return Err(miette!("CASE requires at least one WHEN clause"));
+        }
+
+        let else_result = self
+            .consume_if(Token::Keyword(Keyword::Else))
+            .then(|| self.parse_expression(0))
+            .transpose()?
+            .map(Box::new);
+
+        self.expect_keyword(Keyword::End)?;
+
+        Ok(Expression::Case {
+            operand,
+            branches,
+            else_result,
+        })
+    }
+
+    // Potentially parse one or more "::type" postfixes
+    fn parse_cast_postfix(&mut self, mut expr: Expression) -> Result<Expression> {
+        while self.consume_if(Token::DoubleColon) {
+            let data_type = self.parse_data_type()?;
+            expr = Expression::Cast {
+                expr: Box::new(expr),
+                data_type,
+            };
+        }
+
+        Ok(expr)
+    }
+
+    /// Parses the argument list of a function call, the name has already been consumed.
+    fn parse_function_call(&mut self, name: String) -> Result<Expression> {
+        self.expect_token(Token::LeftParen)?;
+
+        let args = if self.consume_if(Token::RightParen) {
+            Vec::new()
+        } else {
+            let args = self.parse_expression_list()?;
+            self.expect_token(Token::RightParen)?;
+            args
+        };
+
+        Ok(Expression::Function { name, args })
+    }
+
+    /// Parses a comma separated list of expressions.
+    fn parse_expression_list(&mut self) -> Result<Vec<Expression>> {
+        let mut exprs = vec![self.parse_expression(0)?];
+
+        while self.consume_if(Token::Comma) {
+            exprs.push(self.parse_expression(0)?);
+        }
+
+        Ok(exprs)
+    }
+
     // Potentially parse "IS" postfix
     fn parse_is_postfix(&mut self, expr: Expression) -> Result<Expression> {
         if !self.consume_if(Token::Keyword(Keyword::Is)) {
@@ -204,28 +488,7 @@ impl<'src> SqlParser<'src> {
     fn parse_column_definition(&mut self) -> Result<ColumnDefinition> {
         let name = self.expect_identifier()?;
 
-        let Token::Keyword(data_type) = self.next_token()? else {
-            return Err(miette!("Expected a column type."));
-        };
-
-        let data_type = match data_type {
-            Keyword::Integer => DataType::Int64,
-            Keyword::Float => DataType::Float64,
-            Keyword::Varchar => {
-                self.expect_token(Token::LeftParen)?;
-                let size = self.expect_integer()?;
-                let size: usize = size
-                    .try_into()
-                    .map_err(|_| miette!("VARCHAR size must be positive, got {}", size))?;
-                self.expect_token(Token::RightParen)?;
-
-                DataType::VarChar(size)
-            }
-            Keyword::Text => DataType::Text,
-            Keyword::Timestamp => DataType::Timestamp,
-            Keyword::Boolean => DataType::Bool,
-            _ => return Err(miette!("Expected a column type.")),
-        };
+        let data_type = self.parse_data_type()?;
 
         let mut constraints = vec![];
 
@@ -267,6 +530,72 @@ impl<'src> SqlParser<'src> {
         })
     }
 
+    fn parse_data_type(&mut self) -> Result<DataType> {
+        let Token::Keyword(data_type) = self.next_token()? else {
+            return Err(miette!("Expected a column type."));
+        };
+
+        let data_type = match data_type {
+            Keyword::Smallint => DataType::Int16,
+            Keyword::Int32 => DataType::Int32,
+            Keyword::Integer | Keyword::Bigint => DataType::Int64,
+            Keyword::Float => DataType::Float64,
+            Keyword::Varchar => {
+                self.expect_token(Token::LeftParen)?;
+                let size = self.expect_integer()?;
+                let size: usize = size
+                    .try_into()
+                    .map_err(|_| miette!("VARCHAR size must be positive, got {}", size))?;
+                self.expect_token(Token::RightParen)?;
+
+                DataType::VarChar(size)
+            }
+            Keyword::Decimal => self.parse_decimal_type()?,
+            Keyword::Text => DataType::Text,
+            Keyword::Bytea => DataType::Bytea,
+            Keyword::Uuid => DataType::Uuid,
+            Keyword::Timestamp => DataType::Timestamp,
+            Keyword::Date => DataType::Date,
+            Keyword::Interval => DataType::Interval,
+            Keyword::Json => DataType::Json,
+            Keyword::Boolean => DataType::Bool,
+            _ => return Err(miette!("Expected a column type.")),
+        };
+
+        Ok(data_type)
+    }
+
+    /// Parses the optional `(precision[, scale])` of a `DECIMAL`/`NUMERIC` type.
+    ///
+    /// As in the SQL standard the scale defaults to 0, a bare `NUMERIC` has the
+    /// maximum precision.
+    fn parse_decimal_type(&mut self) -> Result<DataType> {
+        if !self.consume_if(Token::LeftParen) {
+            return Ok(DataType::Decimal(MAX_PRECISION, 0));
+        }
+
+        let precision = self.expect_integer()?;
+        let scale = if self.consume_if(Token::Comma) {
+            self.expect_integer()?
+        } else {
+            0
+        };
+        self.expect_token(Token::RightParen)?;
+
+        if !(1..=MAX_PRECISION as i64).contains(&precision) {
+            return Err(miette!(
+                "NUMERIC precision must be between 1 and {MAX_PRECISION}, got {precision}"
+            ));
+        }
+        if !(0..=precision).contains(&scale) {
+            return Err(miette!(
+                "NUMERIC scale must be between 0 and the precision {precision}, got {scale}"
+            ));
+        }
+
+        Ok(DataType::Decimal(precision as u8, scale as u8))
+    }
+
     fn peek_binary_op(&mut self) -> Result<Operator> {
         match self.peek_token()? {
             Token::Equal => Ok(Operator::Equal),
@@ -281,6 +610,11 @@ impl<'src> SqlParser<'src> {
             Token::Asterisk => Ok(Operator::Multiply),
             Token::Slash => Ok(Operator::Divide),
 
+            Token::Arrow => Ok(Operator::JsonGet),
+            Token::LongArrow => Ok(Operator::JsonGetText),
+            Token::HashArrow => Ok(Operator::JsonPath),
+            Token::HashLongArrow => Ok(Operator::JsonPathText),
+
             Token::Keyword(Keyword::And) => Ok(Operator::And),
             Token::Keyword(Keyword::Or) => Ok(Operator::Or),
 
@@ -361,6 +695,7 @@ impl<'src> SqlParser<'src> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Interval;
 
     /// Helper to parse a query and extract components
     fn parse(query: &str) -> Statement {
@@ -386,15 +721,21 @@ mod tests {
                 select_list,
                 from_clause,
                 where_clause,
+                group_by,
             }) => {
                 assert_eq!(select_list.0, vec![SelectTarget::Star]);
                 assert_eq!(
                     from_clause,
                     FromClause {
-                        table_name: "users".to_string(),
+                        table: TableReference {
+                            table_name: "users".to_string(),
+                            alias: None,
+                        },
+                        joins: Vec::new(),
                     }
                 );
                 assert!(where_clause.is_none());
+                assert!(group_by.is_empty());
             }
             _ => panic!("Expected Select statement"),
         }
@@ -460,6 +801,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_joins() {
+        let query = "SELECT u.name FROM users u JOIN orders AS o ON u.id = o.user_id, items CROSS JOIN tags";
+        match parse(query) {
+            Statement::Select(SelectStatement { from_clause, .. }) => {
+                assert_eq!(from_clause.table.qualifier(), "u");
+                assert_eq!(
+                    from_clause.table_names().collect::<Vec<_>>(),
+                    vec!["users", "orders", "items", "tags"]
+                );
+                assert_eq!(from_clause.joins[0].table.qualifier(), "o");
+                assert_eq!(
+                    from_clause.joins[0].condition,
+                    Some(Expression::BinaryOp {
+                        left: Box::new(Expression::Identifier("u.id".to_string())),
+                        op: Operator::Equal,
+                        right: Box::new(Expression::Identifier("o.user_id".to_string())),
+                    })
+                );
+                assert_eq!(from_clause.joins[1].condition, None);
+                assert_eq!(from_clause.joins[2].condition, None);
+            }
+            other => panic!("Expected Select statement, got {other:?}"),
+        }
+
+        let mut parser = SqlParser::new("SELECT * FROM users INNER JOIN orders");
+        assert!(parser.parse().is_err());
+
+        let mut parser = SqlParser::new("SELECT * FROM users LEFT JOIN orders ON true");
+        assert!(parser.parse().is_err());
+    }
+
     #[test]
     fn test_parse_where_simple_comparison() {
         let expr = parse_where("SELECT * FROM users WHERE id = 1");
@@ -635,6 +1008,428 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_parse_function_call() {
+        match parse("SELECT lower(name), count(*) FROM users GROUP BY name") {
+            Statement::Select(SelectStatement {
+                select_list,
+                group_by,
+                ..
+            }) => {
+                assert_eq!(
+                    select_list.0,
+                    vec![
+                        SelectTarget::Expression {
+                            expr: Expression::Function {
+                                name: "lower".to_string(),
+                                args: vec![Expression::Identifier("name".to_string())],
+                            },
+                            alias: None,
+                        },
+                        SelectTarget::Expression {
+                            expr: Expression::Function {
+                                name: "count".to_string(),
+                                args: vec![Expression::Identifier("*".to_string())],
+                            },
+                            alias: None,
+                        },
+                    ]
+                );
+                assert_eq!(group_by, vec![Expression::Identifier("name".to_string())]);
+            }
+            _ => panic!("Expected Select statement"),
+        }
+    }
+
+    #[test]
+    fn test_parse_function_call_in_where() {
+        let expr = parse_where("SELECT * FROM users WHERE distance(lat, lon, 1.5) < 10");
+        if let Expression::BinaryOp { left, .. } = expr {
+            assert_eq!(
+                *left,
+                Expression::Function {
+                    name: "distance".to_string(),
+                    args: vec![
+                        Expression::Identifier("lat".to_string()),
+                        Expression::Identifier("lon".to_string()),
+                        Expression::Literal(Value::Float64(1.5)),
+                    ],
+                }
+            );
+        } else {
+            panic!("Expected comparison with a function call");
+        }
+    }
+
+    #[test]
+    fn test_parse_case() {
+        let expr = parse_where(
+            "SELECT * FROM users WHERE CASE WHEN age > 30 THEN 'old' ELSE 'young' END = 'old'",
+        );
+        let Expression::BinaryOp { left, .. } = expr else {
+            panic!("Expected comparison with a CASE expression");
+        };
+        assert_eq!(
+            *left,
+            Expression::Case {
+                operand: None,
+                branches: vec![(
+                    Expression::BinaryOp {
+                        left: Box::new(Expression::Identifier("age".to_string())),
+                        op: Operator::GreaterThan,
+                        right: Box::new(Expression::Literal(Value::Int64(30))),
+                    },
+                    Expression::Literal(Value::Text("old".to_string())),
+                )],
+                else_result: Some(Box::new(Expression::Literal(Value::Text(
+                    "young".to_string()
+                )))),
+            }
+        );
+
+        let expr =
+            parse_where("SELECT * FROM t WHERE CASE x WHEN 1 THEN TRUE WHEN 2 THEN FALSE END");
+        let Expression::Case {
+            operand,
+            branches,
+            else_result,
+        } = expr
+        else {
+            panic!("Expected a CASE expression");
+        };
+        assert_eq!(
+            operand,
+            Some(Box::new(Expression::Identifier("x".to_string())))
+        );
+        assert_eq!(branches.len(), 2);
+        assert!(else_result.is_none());
+    }
+
+    #[test]
+    fn test_parse_cast() {
+        let expected = Expression::Cast {
+            expr: Box::new(Expression::Identifier("age".to_string())),
+            data_type: DataType::Text,
+        };
+
+        let expr = parse_where("SELECT * FROM users WHERE CAST(age AS TEXT) = '30'");
+        let Expression::BinaryOp { left, .. } = expr else {
+            panic!("Expected comparison with a cast");
+        };
+        assert_eq!(*left, expected);
+
+        // `::` binds tighter than any binary operator
+        let expr = parse_where("SELECT * FROM users WHERE age::TEXT = '30'");
+        let Expression::BinaryOp { left, .. } = expr else {
+            panic!("Expected comparison with a cast");
+        };
+        assert_eq!(*left, expected);
+
+        let expr = parse_where("SELECT * FROM users WHERE '1'::INT::BOOL");
+        assert_eq!(
+            expr,
+            Expression::Cast {
+                expr: Box::new(Expression::Cast {
+                    expr: Box::new(Expression::Literal(Value::Text("1".to_string()))),
+                    data_type: DataType::Int64,
+                }),
+                data_type: DataType::Bool,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_temporal_literals() {
+        let expr = parse_where("SELECT * FROM t WHERE TIMESTAMP '2024-01-01 12:00:00'");
+        assert_eq!(
+            expr,
+            Expression::Literal(Value::Timestamp(1_704_110_400_000_000))
+        );
+
+        let expr = parse_where("SELECT * FROM t WHERE DATE '1970-01-02'");
+        assert_eq!(expr, Expression::Literal(Value::Date(1)));
+
+        let expr = parse_where("SELECT * FROM t WHERE INTERVAL '1 month 2 days'");
+        assert_eq!(
+            expr,
+            Expression::Literal(Value::Interval(Interval::new(1, 2, 0)))
+        );
+
+        let result = SqlParser::new("SELECT * FROM t WHERE DATE '2024-02-30'").parse();
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_decimal_types() {
+        let expr = parse_where("SELECT * FROM t WHERE price::NUMERIC(10, 2) = 1");
+        let Expression::BinaryOp { left, .. } = expr else {
+            panic!("Expected comparison with a cast");
+        };
+        assert_eq!(
+            *left,
+            Expression::Cast {
+                expr: Box::new(Expression::Identifier("price".to_string())),
+                data_type: DataType::Decimal(10, 2),
+            }
+        );
+
+        let expr = parse_where("SELECT * FROM t WHERE CAST(price AS DECIMAL)");
+        assert!(matches!(
+            expr,
+            Expression::Cast {
+                data_type: DataType::Decimal(38, 0),
+                ..
+            }
+        ));
+
+        assert!(
+            SqlParser::new("SELECT * FROM t WHERE x::NUMERIC(2, 3)")
+                .parse()
+                .is_err()
+        );
+        assert!(
+            SqlParser::new("SELECT * FROM t WHERE x::NUMERIC(39)")
+                .parse()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_binary_types() {
+        let expr = parse_where("SELECT * FROM t WHERE data = X'DEADBEEF'");
+        let Expression::BinaryOp { right, .. } = expr else {
+            panic!("Expected comparison with a hex literal");
+        };
+        assert_eq!(
+            *right,
+            Expression::Literal(Value::Bytea(vec![0xDE, 0xAD, 0xBE, 0xEF]))
+        );
+
+        let expr = parse_where("SELECT * FROM t WHERE id::UUID::BLOB::SMALLINT::INT4");
+        let mut types = Vec::new();
+        let mut current = &expr;
+        while let Expression::Cast { expr, data_type } = current {
+            types.push(*data_type);
+            current = expr;
+        }
+        assert_eq!(
+            types,
+            vec![
+                DataType::Int32,
+                DataType::Int16,
+                DataType::Bytea,
+                DataType::Uuid
+            ]
+        );
+
+        assert!(
+            SqlParser::new("SELECT * FROM t WHERE X'ABC'")
+                .parse()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_json_operators() {
+        // Path operators bind tighter than comparisons and associate to the left
+        let expr = parse_where("SELECT * FROM t WHERE payload->'a'->>'kind' = 'click'");
+        let Expression::BinaryOp { left, op, .. } = expr else {
+            panic!("Expected comparison with a JSON path");
+        };
+        assert_eq!(op, Operator::Equal);
+        let Expression::BinaryOp { left, op, right } = *left else {
+            panic!("Expected ->> access");
+        };
+        assert_eq!(op, Operator::JsonGetText);
+        assert_eq!(*right, Expression::Literal(Value::Text("kind".to_string())));
+        assert!(matches!(
+            *left,
+            Expression::BinaryOp {
+                op: Operator::JsonGet,
+                ..
+            }
+        ));
+
+        let expr = parse_where("SELECT * FROM t WHERE payload #>> '{a,b}' = '1'");
+        let Expression::BinaryOp { left, .. } = expr else {
+            panic!("Expected comparison with a JSON path");
+        };
+        assert!(matches!(
+            *left,
+            Expression::BinaryOp {
+                op: Operator::JsonPathText,
+                ..
+            }
+        ));
+
+        let expr = parse_where("SELECT * FROM t WHERE doc::JSONB");
+        assert!(matches!(
+            expr,
+            Expression::Cast {
+                data_type: DataType::Json,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_parse_extract() {
+        let expr = parse_where("SELECT * FROM t WHERE EXTRACT(year FROM created_at) = 2024");
+        let Expression::BinaryOp { left, .. } = expr else {
+            panic!("Expected comparison with extract");
+        };
+        assert_eq!(
+            *left,
+            Expression::Function {
+                name: "extract".to_string(),
+                args: vec![
+                    Expression::Literal(Value::Text("year".to_string())),
+                    Expression::Identifier("created_at".to_string()),
+                ],
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_vacuum() {
+        match parse("VACUUM users") {
+            Statement::Vacuum(statement) => assert_eq!(
+                statement,
+                VacuumStatement {
+                    table_name: Some("users".to_string())
+                }
+            ),
+            other => panic!("Expected VACUUM statement, got {other:?}"),
+        }
+
+        match parse("vacuum;") {
+            Statement::Vacuum(statement) => assert_eq!(statement.table_name, None),
+            other => panic!("Expected VACUUM statement, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_analyze() {
+        match parse("ANALYZE users") {
+            Statement::Analyze(statement) => assert_eq!(
+                statement,
+                AnalyzeStatement {
+                    table_name: Some("users".to_string())
+                }
+            ),
+            other => panic!("Expected ANALYZE statement, got {other:?}"),
+        }
+
+        match parse("analyze") {
+            Statement::Analyze(statement) => assert_eq!(statement.table_name, None),
+            other => panic!("Expected ANALYZE statement, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_explain() {
+        match parse("EXPLAIN ANALYZE SELECT name FROM users") {
+            Statement::Explain(ExplainStatement { analyze, statement }) => {
+                assert!(analyze);
+                assert!(matches!(*statement, Statement::Select(_)));
+            }
+            other => panic!("Expected EXPLAIN statement, got {other:?}"),
+        }
+
+        match parse("explain select name from users") {
+            Statement::Explain(ExplainStatement { analyze, statement }) => {
+                assert!(!analyze);
+                assert!(matches!(*statement, Statement::Select(_)));
+            }
+            other => panic!("Expected EXPLAIN statement, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_prepare() {
+        match parse("PREPARE by_age (INT, TEXT) AS SELECT name FROM users WHERE age > $1") {
+            Statement::Prepare(PrepareStatement {
+                name,
+                parameter_types,
+                statement,
+            }) => {
+                assert_eq!(name, "by_age");
+                assert_eq!(parameter_types, vec![DataType::Int64, DataType::Text]);
+                let Statement::Select(select) = *statement else {
+                    panic!("Expected SELECT statement, got {statement:?}");
+                };
+                assert_eq!(
+                    select.where_clause,
+                    Some(Expression::BinaryOp {
+                        left: Box::new(Expression::Identifier("age".to_string())),
+                        op: Operator::GreaterThan,
+                        right: Box::new(Expression::Parameter(1)),
+                    })
+                );
+            }
+            other => panic!("Expected PREPARE statement, got {other:?}"),
+        }
+
+        match parse("prepare q as select name from users where age > ? and name = ?") {
+            Statement::Prepare(PrepareStatement {
+                parameter_types,
+                statement,
+                ..
+            }) => {
+                assert!(parameter_types.is_empty());
+                let Statement::Select(select) = *statement else {
+                    panic!("Expected SELECT statement, got {statement:?}");
+                };
+                let Some(Expression::BinaryOp { left, right, .. }) = select.where_clause else {
+                    panic!("Expected a conjunction");
+                };
+                assert!(matches!(
+                    *left,
+                    Expression::BinaryOp { ref right, .. } if **right == Expression::Parameter(1)
+                ));
+                assert!(matches!(
+                    *right,
+                    Expression::BinaryOp { ref right, .. } if **right == Expression::Parameter(2)
+                ));
+            }
+            other => panic!("Expected PREPARE statement, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_parse_execute_and_deallocate() {
+        match parse("EXECUTE by_age (30, 'x')") {
+            Statement::Execute(statement) => assert_eq!(
+                statement,
+                ExecuteStatement {
+                    name: "by_age".to_string(),
+                    parameters: vec![
+                        Expression::Literal(Value::Int64(30)),
+                        Expression::Literal(Value::Text("x".to_string())),
+                    ],
+                }
+            ),
+            other => panic!("Expected EXECUTE statement, got {other:?}"),
+        }
+
+        match parse("EXECUTE all_users") {
+            Statement::Execute(statement) => assert!(statement.parameters.is_empty()),
+            other => panic!("Expected EXECUTE statement, got {other:?}"),
+        }
+
+        match parse("DEALLOCATE PREPARE by_age") {
+            Statement::Deallocate(statement) => {
+                assert_eq!(statement.name, Some("by_age".to_string()))
+            }
+            other => panic!("Expected DEALLOCATE statement, got {other:?}"),
+        }
+
+        match parse("deallocate all") {
+            Statement::Deallocate(statement) => assert_eq!(statement.name, None),
+            other => panic!("Expected DEALLOCATE statement, got {other:?}"),
+        }
+    }
+
     #[test]
     fn test_parse_create_table() {
         match parse(
@@ -0,0 +1,288 @@
+use crate::{Row, Value, db::null_bitmap::NullBitmap};
+
+/// A set of rows passed between execution nodes, stored column by column.
+///
+/// Expressions are evaluated over whole columns, see
+/// [`VectorizedEvaluator`](crate::sql::evaluator::vectorized::VectorizedEvaluator).
+#[derive(Debug, Clone)]
+pub struct RecordBatch {
+    columns: Vec<Column>,
+
+    /// Kept apart from the columns, a batch can have rows but no columns, e.g. the
+    /// input of `count(*)`.
+    num_rows: usize,
+}
+
+impl RecordBatch {
+    pub(crate) fn new(columns: Vec<Column>, num_rows: usize) -> Self {
+        debug_assert!(columns.iter().all(|column| column.len() == num_rows));
+        Self { columns, num_rows }
+    }
+
+    /// Transposes rows of `width` values into columns.
+    pub fn from_rows(rows: Vec<Row>, width: usize) -> Self {
+        let num_rows = rows.len();
+        let mut columns = vec![Vec::with_capacity(num_rows); width];
+        for row in rows {
+            let mut values = row.values.into_iter();
+            for column in &mut columns {
+                column.push(values.next().unwrap_or(Value::Null));
+            }
+        }
+
+        Self {
+            columns: columns.into_iter().map(Column::from_values).collect(),
+            num_rows,
+        }
+    }
+
+    pub fn num_rows(&self) -> usize {
+        self.num_rows
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.num_rows == 0
+    }
+
+    pub fn columns(&self) -> &[Column] {
+        &self.columns
+    }
+
+    pub fn column(&self, index: usize) -> &Column {
+        &self.columns[index]
+    }
+
+    /// Copies the values of one row out of the columns.
+    pub fn row(&self, index: usize) -> Row {
+        Row::new(
+            self.columns
+                .iter()
+                .map(|column| column.value(index))
+                .collect(),
+        )
+    }
+
+    pub fn into_rows(self) -> Vec<Row> {
+        (0..self.num_rows).map(|index| self.row(index)).collect()
+    }
+
+    /// Keeps the rows at `selection`, in that order.
+    pub(crate) fn take(&self, selection: &[usize]) -> Self {
+        Self {
+            columns: self
+                .columns
+                .iter()
+                .map(|column| column.take(selection))
+                .collect(),
+            num_rows: selection.len(),
+        }
+    }
+}
+
+/// The values of one column, in a typed vector where the type allows it.
+///
+/// Slots of NULL values hold an arbitrary value, the validity is kept by [`Column`].
+#[derive(Debug, Clone)]
+pub enum ColumnValues {
+    Int64(Vec<i64>),
+    Float64(Vec<f64>),
+    Bool(Vec<bool>),
+    Text(Vec<String>),
+
+    /// Values of other types, or of mixed types.
+    Values(Vec<Value>),
+}
+
+/// A column of a [`RecordBatch`] with a bitmap of the rows holding NULL.
+#[derive(Debug, Clone)]
+pub struct Column {
+    values: ColumnValues,
+    nulls: NullBitmap,
+    len: usize,
+}
+
+impl Column {
+    pub(crate) fn new(values: ColumnValues, nulls: NullBitmap) -> Self {
+        let len = match &values {
+            ColumnValues::Int64(values) => values.len(),
+            ColumnValues::Float64(values) => values.len(),
+            ColumnValues::Bool(values) => values.len(),
+            ColumnValues::Text(values) => values.len(),
+            ColumnValues::Values(values) => values.len(),
+        };
+        Self { values, nulls, len }
+    }
+
+    /// Stores values in the typed vector all non NULL values fit into.
+    pub(crate) fn from_values(values: Vec<Value>) -> Self {
+        let len = values.len();
+        let mut nulls = NullBitmap::new(len);
+        for (index, value) in values.iter().enumerate() {
+            if matches!(value, Value::Null) {
+                nulls.set_null(index);
+            }
+        }
+
+        // The first non NULL value decides the vector, NULL slots take its default
+        let kind = values.iter().find(|value| !matches!(value, Value::Null));
+        let same_kind = |value: &Value| {
+            matches!(value, Value::Null)
+                || std::mem::discriminant(value) == std::mem::discriminant(kind.unwrap())
+        };
+        if kind.is_none() || !values.iter().all(same_kind) {
+            return Self::new(ColumnValues::Values(values), nulls);
+        }
+
+        let values = match kind {
+            Some(Value::Int64(_)) => ColumnValues::Int64(
+                values
+                    .into_iter()
+                    .map(|value| match value {
+                        Value::Int64(n) => n,
+                        _ => 0,
+                    })
+                    .collect(),
+            ),
+            Some(Value::Float64(_)) => ColumnValues::Float64(
+                values
+                    .into_iter()
+                    .map(|value| match value {
+                        Value::Float64(n) => n,
+                        _ => 0.0,
+                    })
+                    .collect(),
+            ),
+            Some(Value::Bool(_)) => ColumnValues::Bool(
+                values
+                    .into_iter()
+                    .map(|value| matches!(value, Value::Bool(true)))
+                    .collect(),
+            ),
+            Some(Value::Text(_)) => ColumnValues::Text(
+                values
+                    .into_iter()
+                    .map(|value| match value {
+                        Value::Text(text) => text,
+                        _ => String::new(),
+                    })
+                    .collect(),
+            ),
+            _ => ColumnValues::Values(values),
+        };
+        Self::new(values, nulls)
+    }
+
+    /// A column holding `value` in each of `len` rows.
+    pub(crate) fn repeat(value: &Value, len: usize) -> Self {
+        Self::from_values(vec![value.clone(); len])
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn values(&self) -> &ColumnValues {
+        &self.values
+    }
+
+    pub fn is_null(&self, index: usize) -> bool {
+        self.nulls.is_null(index)
+    }
+
+    /// The value of a row, as a [`Value`].
+    pub fn value(&self, index: usize) -> Value {
+        if self.is_null(index) {
+            return Value::Null;
+        }
+
+        match &self.values {
+            ColumnValues::Int64(values) => Value::Int64(values[index]),
+            ColumnValues::Float64(values) => Value::Float64(values[index]),
+            ColumnValues::Bool(values) => Value::Bool(values[index]),
+            ColumnValues::Text(values) => Value::Text(values[index].clone()),
+            ColumnValues::Values(values) => values[index].clone(),
+        }
+    }
+
+    /// Keeps the rows at `selection`, in that order.
+    pub(crate) fn take(&self, selection: &[usize]) -> Self {
+        fn pick<T: Clone>(values: &[T], selection: &[usize]) -> Vec<T> {
+            selection
+                .iter()
+                .map(|&index| values[index].clone())
+                .collect()
+        }
+
+        let values = match &self.values {
+            ColumnValues::Int64(values) => ColumnValues::Int64(pick(values, selection)),
+            ColumnValues::Float64(values) => ColumnValues::Float64(pick(values, selection)),
+            ColumnValues::Bool(values) => ColumnValues::Bool(pick(values, selection)),
+            ColumnValues::Text(values) => ColumnValues::Text(pick(values, selection)),
+            ColumnValues::Values(values) => ColumnValues::Values(pick(values, selection)),
+        };
+
+        let mut nulls = NullBitmap::new(selection.len());
+        for (position, &index) in selection.iter().enumerate() {
+            if self.is_null(index) {
+                nulls.set_null(position);
+            }
+        }
+
+        Self::new(values, nulls)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rows_round_trip_through_columns() {
+        let rows = vec![
+            Row::new(vec![Value::Int64(1), Value::Text("Alice".to_string())]),
+            Row::new(vec![Value::Null, Value::Text("Bob".to_string())]),
+            Row::new(vec![Value::Int64(3), Value::Int32(7)]),
+        ];
+        let batch = RecordBatch::from_rows(rows, 2);
+
+        assert_eq!(batch.num_rows(), 3);
+        assert!(matches!(batch.column(0).values(), ColumnValues::Int64(_)));
+        assert!(batch.column(0).is_null(1));
+        // Mixed types fall back to a vector of values
+        assert!(matches!(batch.column(1).values(), ColumnValues::Values(_)));
+
+        let rows = batch.into_rows();
+        assert_eq!(
+            rows[1].values,
+            vec![Value::Null, Value::Text("Bob".to_string())]
+        );
+        assert_eq!(rows[2].values, vec![Value::Int64(3), Value::Int32(7)]);
+    }
+
+    #[test]
+    fn test_take_keeps_selected_rows() {
+        let rows = (0..5)
+            .map(|n| {
+                Row::new(vec![if n == 3 {
+                    Value::Null
+                } else {
+                    Value::Float64(n as f64)
+                }])
+            })
+            .collect();
+        let batch = RecordBatch::from_rows(rows, 1).take(&[4, 3, 0]);
+
+        assert_eq!(batch.num_rows(), 3);
+        assert_eq!(batch.row(0).values, vec![Value::Float64(4.0)]);
+        assert!(batch.column(0).is_null(1));
+        assert_eq!(batch.row(2).values, vec![Value::Float64(0.0)]);
+
+        // Batches without columns still count their rows
+        let batch = RecordBatch::from_rows(vec![Row::new(Vec::new()); 4], 0);
+        assert_eq!(batch.take(&[0, 2]).num_rows(), 2);
+    }
+}
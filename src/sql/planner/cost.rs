@@ -0,0 +1,757 @@
+//! Cost and cardinality estimates of the nodes of a plan, used by the physical planner
+//! to choose access paths, join orders and join methods.
+//!
+//! Costs are in arbitrary units: reading one page sequentially costs [`SEQ_PAGE_COST`],
+//! processing a row or evaluating an operator costs a fraction of that. Row counts and
+//! selectivities come from the statistics gathered by `ANALYZE`, tables that were never
+//! analyzed fall back to fixed defaults.
+
+use std::{cmp::Ordering, collections::HashMap, sync::Arc};
+
+use miette::Result;
+
+use crate::{
+    ColumnStatistics, DataType, TableStatistics, Value,
+    db::{catalog::statistics::compare, database::Database, table::Table},
+    sql::{
+        analyzer::{AnalyzedExpression, IsPredicateTarget},
+        ast::operator::Operator,
+        evaluator::values_equal,
+        optimizer::constant_value,
+        planner::logical::LogicalPlan,
+    },
+    storage::page::{ItemPointer, Page, PageHeader},
+};
+
+/// Cost of reading one page of a table.
+pub(crate) const SEQ_PAGE_COST: f64 = 1.0;
+
+/// Cost of handing one row to the next operator.
+pub(crate) const CPU_TUPLE_COST: f64 = 0.01;
+
+/// Cost of evaluating one expression on a row.
+pub(crate) const CPU_OPERATOR_COST: f64 = 0.0025;
+
+/// Selectivity of `column = constant` without statistics.
+const DEFAULT_EQUALITY_SELECTIVITY: f64 = 0.005;
+
+/// Selectivity of `column < constant` and the like without statistics.
+const DEFAULT_RANGE_SELECTIVITY: f64 = 1.0 / 3.0;
+
+/// Selectivity of conditions nothing is known about.
+const DEFAULT_SELECTIVITY: f64 = 0.5;
+
+/// Assumed width of variable-length values in tables that were never analyzed.
+const DEFAULT_VARIABLE_WIDTH: usize = 32;
+
+/// Fraction of the input rows assumed to form distinct groups without statistics.
+const DEFAULT_GROUP_FRACTION: f64 = 0.1;
+
+/// Cost of reading one page of a table out of order, e.g. for an index scan.
+pub(crate) const RANDOM_PAGE_COST: f64 = 4.0;
+
+/// Cost of looking up one entry of an index.
+pub(crate) const CPU_INDEX_TUPLE_COST: f64 = 0.005;
+
+/// Estimated output and cost of a plan.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlanEstimate {
+    /// Estimated number of rows produced.
+    pub rows: f64,
+
+    /// Estimated cost of producing all rows, including the cost of the inputs.
+    pub cost: f64,
+}
+
+/// Estimates the nodes of a plan using the statistics of a database.
+///
+/// The planner estimates every node it considers once, from the estimates of its inputs.
+/// Statistics and row counts of tables are looked up once per model.
+pub(crate) struct CostModel<'db> {
+    database: &'db Database,
+    statistics: HashMap<String, Option<Arc<TableStatistics>>>,
+    table_rows: HashMap<String, f64>,
+}
+
+impl<'db> CostModel<'db> {
+    pub(crate) fn new(database: &'db Database) -> Self {
+        Self {
+            database,
+            statistics: HashMap::new(),
+            table_rows: HashMap::new(),
+        }
+    }
+
+    /// Estimate of reading every page of the table of `scan`, applying its filters.
+    pub(crate) fn seq_scan(&mut self, scan: &LogicalPlan) -> Result<PlanEstimate> {
+        let LogicalPlan::Scan {
+            table_name,
+            filters,
+            ..
+        } = scan
+        else {
+            unreachable!("only scans read tables");
+        };
+        let pages = self.database.buffer_manager.page_count(table_name)? as f64;
+        let table_rows = self.table_rows(table_name)?;
+        let selectivity = self.conjunction_selectivity(filters, scan)?;
+
+        Ok(PlanEstimate {
+            rows: table_rows * selectivity,
+            cost: pages * SEQ_PAGE_COST
+                + table_rows * (CPU_TUPLE_COST + filters.len() as f64 * CPU_OPERATOR_COST),
+        })
+    }
+
+    /// Estimate of reading the rows of the table of `scan` satisfying `index_conditions`,
+    /// some of its filters, through an index. The other filters are applied to the rows
+    /// read.
+    ///
+    /// Every row read costs a random page read, up to the number of pages of the table.
+    pub(crate) fn index_scan(
+        &mut self,
+        scan: &LogicalPlan,
+        index_conditions: &[AnalyzedExpression],
+    ) -> Result<PlanEstimate> {
+        let LogicalPlan::Scan {
+            table_name,
+            filters,
+            ..
+        } = scan
+        else {
+            unreachable!("only scans read tables");
+        };
+        let pages = self.database.buffer_manager.page_count(table_name)? as f64;
+        let table_rows = self.table_rows(table_name)?;
+        let fetched = table_rows * self.conjunction_selectivity(index_conditions, scan)?;
+        let residual_filters = filters.len() - index_conditions.len();
+
+        Ok(PlanEstimate {
+            rows: table_rows * self.conjunction_selectivity(filters, scan)?,
+            cost: (table_rows + 1.0).log2() * CPU_OPERATOR_COST
+                + fetched * CPU_INDEX_TUPLE_COST
+                + fetched.min(pages) * RANDOM_PAGE_COST
+                + fetched * (CPU_TUPLE_COST + residual_filters as f64 * CPU_OPERATOR_COST),
+        })
+    }
+
+    /// Estimate of keeping the rows of `input` satisfying `condition`.
+    pub(crate) fn filter(
+        &mut self,
+        input: PlanEstimate,
+        condition: &AnalyzedExpression,
+        input_plan: &LogicalPlan,
+    ) -> Result<PlanEstimate> {
+        Ok(PlanEstimate {
+            rows: input.rows * self.selectivity(condition, input_plan)?,
+            cost: input.cost + input.rows * CPU_OPERATOR_COST,
+        })
+    }
+
+    /// Estimate of computing `expressions` expressions for every row of `input`.
+    pub(crate) fn projection(&self, input: PlanEstimate, expressions: usize) -> PlanEstimate {
+        PlanEstimate {
+            rows: input.rows,
+            cost: input.cost + input.rows * expressions as f64 * CPU_OPERATOR_COST,
+        }
+    }
+
+    /// Estimate of `aggregate`, a [`LogicalPlan::Aggregate`] over rows estimated by `input`.
+    pub(crate) fn aggregate(
+        &mut self,
+        input: PlanEstimate,
+        aggregate: &LogicalPlan,
+    ) -> Result<PlanEstimate> {
+        let LogicalPlan::Aggregate {
+            input: input_plan,
+            group_by,
+            ..
+        } = aggregate
+        else {
+            unreachable!("only aggregates form groups");
+        };
+
+        // Without GROUP BY there is exactly one group
+        let mut groups = 1.0;
+        for expr in group_by {
+            groups *= self.distinct_values(expr, input_plan, input.rows)?;
+        }
+        let groups = groups.min(input.rows).max(1.0);
+
+        Ok(PlanEstimate {
+            rows: groups,
+            cost: input.cost
+                + input.rows * aggregate.expressions().len() as f64 * CPU_OPERATOR_COST
+                + groups * CPU_TUPLE_COST,
+        })
+    }
+
+    /// Estimate of sorting the rows of `input`.
+    pub(crate) fn sort(&self, input: PlanEstimate) -> PlanEstimate {
+        PlanEstimate {
+            rows: input.rows,
+            cost: input.cost + 2.0 * input.rows * input.rows.max(2.0).log2() * CPU_OPERATOR_COST,
+        }
+    }
+
+    /// Estimate of a nested loop join returning `rows` rows, which compares every row of
+    /// `outer` with every row of `inner` on `conditions` conditions.
+    pub(crate) fn nested_loop_join(
+        &self,
+        outer: PlanEstimate,
+        inner: PlanEstimate,
+        rows: f64,
+        conditions: usize,
+    ) -> PlanEstimate {
+        PlanEstimate {
+            rows,
+            cost: outer.cost
+                + inner.cost
+                + outer.rows * inner.rows * conditions.max(1) as f64 * CPU_OPERATOR_COST
+                + rows * CPU_TUPLE_COST,
+        }
+    }
+
+    /// Estimate of a hash join returning `rows` rows, which builds a hash table of the
+    /// rows of `inner` on `keys` keys and looks up the rows of `outer` in it. The pairs
+    /// found are then checked against `conditions` other conditions.
+    pub(crate) fn hash_join(
+        &self,
+        outer: PlanEstimate,
+        inner: PlanEstimate,
+        rows: f64,
+        keys: usize,
+        conditions: usize,
+    ) -> PlanEstimate {
+        PlanEstimate {
+            rows,
+            cost: outer.cost
+                + inner.cost
+                + inner.rows * (CPU_TUPLE_COST + keys as f64 * CPU_OPERATOR_COST)
+                + outer.rows * keys as f64 * CPU_OPERATOR_COST
+                + rows * (CPU_TUPLE_COST + conditions as f64 * CPU_OPERATOR_COST),
+        }
+    }
+
+    /// Estimate of a merge join returning `rows` rows, which reads both inputs once in the
+    /// order of their keys. The pairs found are then checked against `conditions` other
+    /// conditions.
+    pub(crate) fn merge_join(
+        &self,
+        outer: PlanEstimate,
+        inner: PlanEstimate,
+        rows: f64,
+        conditions: usize,
+    ) -> PlanEstimate {
+        PlanEstimate {
+            rows,
+            cost: outer.cost
+                + inner.cost
+                + (outer.rows + inner.rows) * CPU_OPERATOR_COST
+                + rows * (CPU_TUPLE_COST + conditions as f64 * CPU_OPERATOR_COST),
+        }
+    }
+
+    /// Estimated fraction of the pairs of rows of a join for which `left = right` holds,
+    /// with `left` and `right` columns of `join` read from inputs of `left_rows` and
+    /// `right_rows` rows.
+    ///
+    /// Every value of the side with fewer distinct values is assumed to have matches on
+    /// the other side.
+    pub(crate) fn equi_join_selectivity(
+        &mut self,
+        join: &LogicalPlan,
+        (left, left_rows): (usize, f64),
+        (right, right_rows): (usize, f64),
+    ) -> Result<f64> {
+        let mut selectivity = 1.0;
+        let mut distinct_values = 1.0_f64;
+        for (column, rows) in [(left, left_rows), (right, right_rows)] {
+            let (distinct, null_fraction) = match self.column_statistics(join, column)? {
+                Some(statistics) => (statistics.distinct_count, statistics.null_fraction),
+                None => (1.0 / DEFAULT_EQUALITY_SELECTIVITY, 0.0),
+            };
+            selectivity *= 1.0 - null_fraction;
+            distinct_values = distinct_values.max(distinct.min(rows).max(1.0));
+        }
+
+        Ok(selectivity / distinct_values)
+    }
+
+    /// Estimated number of rows of a table.
+    ///
+    /// Statistics may be out of date, the number of rows per page they recorded is
+    /// assumed to still hold. Without them the rows are assumed to fill every page.
+    fn table_rows(&mut self, table_name: &str) -> Result<f64> {
+        if let Some(rows) = self.table_rows.get(table_name) {
+            return Ok(*rows);
+        }
+
+        let pages = self.database.buffer_manager.page_count(table_name)? as f64;
+        let rows = match self.table_statistics(table_name)? {
+            Some(statistics) if statistics.page_count > 0 => {
+                statistics.row_count as f64 * pages / statistics.page_count as f64
+            }
+            _ => {
+                let row_width = self
+                    .database
+                    .get_table(table_name)?
+                    .schema()
+                    .columns
+                    .iter()
+                    .map(|column| type_width(column.data_type))
+                    .sum::<usize>();
+                let rows_per_page =
+                    (Page::SIZE - PageHeader::SIZE) / (row_width + ItemPointer::SIZE);
+                pages * rows_per_page as f64
+            }
+        };
+
+        self.table_rows.insert(table_name.to_string(), rows);
+        Ok(rows)
+    }
+
+    fn table_statistics(&mut self, table_name: &str) -> Result<Option<Arc<TableStatistics>>> {
+        if let Some(statistics) = self.statistics.get(table_name) {
+            return Ok(statistics.clone());
+        }
+
+        let statistics = self.database.statistics(table_name)?;
+        self.statistics
+            .insert(table_name.to_string(), statistics.clone());
+        Ok(statistics)
+    }
+
+    /// The statistics of the values in column `index` of the output of `plan`, if it
+    /// comes straight from an analyzed table.
+    fn column_statistics(
+        &mut self,
+        plan: &LogicalPlan,
+        index: usize,
+    ) -> Result<Option<ColumnStatistics>> {
+        let source = match plan {
+            LogicalPlan::Scan {
+                table_name, schema, ..
+            } => {
+                let Some(field) = schema.fields.get(index) else {
+                    return Ok(None);
+                };
+                return Ok(self
+                    .table_statistics(table_name)?
+                    .and_then(|statistics| statistics.column(&field.name).cloned()));
+            }
+            LogicalPlan::Empty { .. } => return Ok(None),
+            LogicalPlan::Filter { input, .. } => return self.column_statistics(input, index),
+            LogicalPlan::Join { left, right, .. } => {
+                let left_width = left.schema().fields.len();
+                return match index.checked_sub(left_width) {
+                    Some(index) => self.column_statistics(right, index),
+                    None => self.column_statistics(left, index),
+                };
+            }
+            LogicalPlan::Projection { expressions, .. } => expressions.get(index),
+            LogicalPlan::Aggregate { group_by, .. } => group_by.get(index),
+        };
+
+        match (source, plan) {
+            (
+                Some(AnalyzedExpression::Column(column, _)),
+                LogicalPlan::Projection { input, .. } | LogicalPlan::Aggregate { input, .. },
+            ) => self.column_statistics(input, column.index),
+            _ => Ok(None),
+        }
+    }
+
+    /// Estimated fraction of the rows of `input` for which all of `conditions` are true.
+    fn conjunction_selectivity(
+        &mut self,
+        conditions: &[AnalyzedExpression],
+        input: &LogicalPlan,
+    ) -> Result<f64> {
+        let mut selectivity = 1.0;
+        for condition in conditions {
+            selectivity *= self.selectivity(condition, input)?;
+        }
+        Ok(selectivity)
+    }
+
+    /// Estimated fraction of the rows of `input` for which `expr` is true.
+    pub(crate) fn selectivity(
+        &mut self,
+        expr: &AnalyzedExpression,
+        input: &LogicalPlan,
+    ) -> Result<f64> {
+        if let Some(value) = constant_value(expr) {
+            return Ok(if *value == Value::Bool(true) {
+                1.0
+            } else {
+                0.0
+            });
+        }
+
+        let selectivity = match expr {
+            AnalyzedExpression::BinaryExpr {
+                left,
+                op: Operator::And,
+                right,
+                ..
+            } => self.selectivity(left, input)? * self.selectivity(right, input)?,
+            AnalyzedExpression::BinaryExpr {
+                left,
+                op: Operator::Or,
+                right,
+                ..
+            } => {
+                let left = self.selectivity(left, input)?;
+                let right = self.selectivity(right, input)?;
+                left + right - left * right
+            }
+            AnalyzedExpression::BinaryExpr {
+                left, op, right, ..
+            } => match (left.as_ref(), constant_value(left), constant_value(right)) {
+                (AnalyzedExpression::Column(column, _), None, Some(value)) => {
+                    self.comparison_selectivity(input, column.index, *op, value)?
+                }
+                (_, Some(value), None) => match right.as_ref() {
+                    AnalyzedExpression::Column(column, _) => {
+                        self.comparison_selectivity(input, column.index, flip(*op), value)?
+                    }
+                    _ => default_selectivity(*op),
+                },
+                _ => default_selectivity(*op),
+            },
+            AnalyzedExpression::IsPredicate {
+                expr,
+                predicate,
+                negated,
+            } => {
+                let selectivity = match (expr.as_ref(), predicate) {
+                    (AnalyzedExpression::Column(column, _), IsPredicateTarget::Null) => self
+                        .column_statistics(input, column.index)?
+                        .map_or(DEFAULT_EQUALITY_SELECTIVITY, |statistics| {
+                            statistics.null_fraction
+                        }),
+                    (_, IsPredicateTarget::True) => self.selectivity(expr, input)?,
+                    _ => DEFAULT_SELECTIVITY,
+                };
+                if *negated {
+                    1.0 - selectivity
+                } else {
+                    selectivity
+                }
+            }
+            AnalyzedExpression::Column(column, DataType::Bool) => self.comparison_selectivity(
+                input,
+                column.index,
+                Operator::Equal,
+                &Value::Bool(true),
+            )?,
+            _ => DEFAULT_SELECTIVITY,
+        };
+
+        Ok(selectivity.clamp(0.0, 1.0))
+    }
+
+    /// Estimated fraction of the rows of `input` where `column op value` is true.
+    fn comparison_selectivity(
+        &mut self,
+        input: &LogicalPlan,
+        column: usize,
+        op: Operator,
+        value: &Value,
+    ) -> Result<f64> {
+        if matches!(value, Value::Null) {
+            return Ok(0.0);
+        }
+        let Some(statistics) = self.column_statistics(input, column)? else {
+            return Ok(default_selectivity(op));
+        };
+
+        // Rows that are neither NULL nor one of the most common values
+        let common_fraction = statistics.most_common_frequencies.iter().sum::<f64>();
+        let other_fraction = (1.0 - statistics.null_fraction - common_fraction).max(0.0);
+
+        let equal_fraction = || {
+            let common = statistics
+                .most_common_values
+                .iter()
+                .position(|common| values_equal(common, value) == Value::Bool(true));
+            match common {
+                Some(position) => statistics.most_common_frequencies[position],
+                None => {
+                    let other_values =
+                        statistics.distinct_count - statistics.most_common_values.len() as f64;
+                    if other_values >= 1.0 {
+                        other_fraction / other_values
+                    } else {
+                        0.0
+                    }
+                }
+            }
+        };
+
+        let selectivity = match op {
+            Operator::Equal => equal_fraction(),
+            Operator::NotEqual => 1.0 - equal_fraction() - statistics.null_fraction,
+            Operator::LessThan
+            | Operator::LessThanEqual
+            | Operator::GreaterThan
+            | Operator::GreaterThanEqual => {
+                let common = statistics
+                    .most_common_values
+                    .iter()
+                    .zip(&statistics.most_common_frequencies)
+                    .filter(|(common, _)| satisfies(compare(common, value), op))
+                    .map(|(_, frequency)| frequency)
+                    .sum::<f64>();
+                let below = histogram_fraction_below(&statistics.histogram_bounds, value);
+                let other = match (op, below) {
+                    (_, None) => DEFAULT_RANGE_SELECTIVITY,
+                    (Operator::LessThan | Operator::LessThanEqual, Some(below)) => below,
+                    (_, Some(below)) => 1.0 - below,
+                };
+                common + other * other_fraction
+            }
+            _ => default_selectivity(op),
+        };
+
+        Ok(selectivity)
+    }
+
+    /// Estimated number of distinct values of `expr` over the rows of `input`.
+    fn distinct_values(
+        &mut self,
+        expr: &AnalyzedExpression,
+        input: &LogicalPlan,
+        input_rows: f64,
+    ) -> Result<f64> {
+        let statistics = match expr {
+            AnalyzedExpression::Column(column, _) => self.column_statistics(input, column.index)?,
+            _ => None,
+        };
+
+        Ok(match statistics {
+            // NULLs form a group of their own
+            Some(statistics) if statistics.null_fraction > 0.0 => statistics.distinct_count + 1.0,
+            Some(statistics) => statistics.distinct_count,
+            None => input_rows * DEFAULT_GROUP_FRACTION,
+        })
+    }
+}
+
+/// Fraction of the histogram below `value`, interpolating inside the bucket holding it.
+fn histogram_fraction_below(bounds: &[Value], value: &Value) -> Option<f64> {
+    if bounds.len() < 2 {
+        return None;
+    }
+    if compare(value, &bounds[0]) != Ordering::Greater {
+        return Some(0.0);
+    }
+    if compare(value, &bounds[bounds.len() - 1]) != Ordering::Less {
+        return Some(1.0);
+    }
+
+    let bucket = bounds
+        .windows(2)
+        .position(|bucket| compare(value, &bucket[1]) == Ordering::Less)?;
+    let within = match (
+        as_number(&bounds[bucket]),
+        as_number(&bounds[bucket + 1]),
+        as_number(value),
+    ) {
+        (Some(low), Some(high), Some(value)) if high > low => (value - low) / (high - low),
+        // Values that are not numbers are assumed to sit in the middle of their bucket
+        _ => 0.5,
+    };
+
+    Some((bucket as f64 + within) / (bounds.len() - 1) as f64)
+}
+
+/// A value as a number, for interpolating between histogram bounds.
+fn as_number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Int16(n) => Some(*n as f64),
+        Value::Int32(n) | Value::Date(n) => Some(*n as f64),
+        Value::Int64(n) | Value::Timestamp(n) => Some(*n as f64),
+        Value::Float64(n) => Some(*n),
+        _ => None,
+    }
+}
+
+/// Whether an ordering between a value and a constant satisfies a comparison.
+fn satisfies(ordering: Ordering, op: Operator) -> bool {
+    match op {
+        Operator::LessThan => ordering == Ordering::Less,
+        Operator::LessThanEqual => ordering != Ordering::Greater,
+        Operator::GreaterThan => ordering == Ordering::Greater,
+        Operator::GreaterThanEqual => ordering != Ordering::Less,
+        _ => ordering == Ordering::Equal,
+    }
+}
+
+/// The comparison with its operands swapped, `a < b` is `b > a`.
+pub(crate) fn flip(op: Operator) -> Operator {
+    match op {
+        Operator::LessThan => Operator::GreaterThan,
+        Operator::LessThanEqual => Operator::GreaterThanEqual,
+        Operator::GreaterThan => Operator::LessThan,
+        Operator::GreaterThanEqual => Operator::LessThanEqual,
+        op => op,
+    }
+}
+
+fn default_selectivity(op: Operator) -> f64 {
+    match op {
+        Operator::Equal => DEFAULT_EQUALITY_SELECTIVITY,
+        Operator::NotEqual => 1.0 - DEFAULT_EQUALITY_SELECTIVITY,
+        Operator::LessThan
+        | Operator::LessThanEqual
+        | Operator::GreaterThan
+        | Operator::GreaterThanEqual => DEFAULT_RANGE_SELECTIVITY,
+        _ => DEFAULT_SELECTIVITY,
+    }
+}
+
+/// Size of a value of the given type in a row, a guess for variable-length types.
+fn type_width(data_type: DataType) -> usize {
+    match data_type {
+        DataType::Bool => 1,
+        DataType::Int16 => 2,
+        DataType::Int32 | DataType::Date => 4,
+        DataType::Int64 | DataType::Float64 | DataType::Timestamp => 8,
+        DataType::Decimal(_, _) | DataType::Uuid | DataType::Interval => 16,
+        DataType::Text | DataType::VarChar(_) | DataType::Bytea | DataType::Json => {
+            DEFAULT_VARIABLE_WIDTH
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ColumnDef, Row, Schema,
+        sql::{
+            analyzer::Analyzer, catalog_context::CatalogContext, optimizer::Optimizer,
+            parser::SqlParser, planner::physical::PhysicalPlanner,
+        },
+    };
+
+    /// 1000 users with unique names, ages 0 to 49 except those ending in 9, which are
+    /// NULL instead.
+    fn database(name: &str) -> Database {
+        let directory = std::env::temp_dir().join(format!("scuttle_cost_{name}_tests"));
+        std::fs::remove_dir_all(&directory).ok();
+
+        let mut db = Database::new(&directory);
+        let schema = Schema::new(vec![
+            ColumnDef::new("name", DataType::Text, false),
+            ColumnDef::new("age", DataType::Int64, true),
+        ]);
+        db.create_table("users", schema).unwrap();
+        for id in 0..1000 {
+            let age = match id % 10 {
+                9 => Value::Null,
+                _ => Value::Int64(id % 50),
+            };
+            db.insert_row(
+                "users",
+                Row::new(vec![Value::Text(format!("user {id}")), age]),
+            )
+            .unwrap();
+        }
+        db
+    }
+
+    fn estimate(db: &mut Database, query: &str) -> PlanEstimate {
+        let statement = SqlParser::new(query).parse().unwrap();
+        let context = CatalogContext::new(db);
+        let plan = Analyzer::new(&context).analyze(statement).unwrap();
+        let plan = Optimizer::new().optimize(plan);
+        let node = PhysicalPlanner::new(&context)
+            .profiled()
+            .create_physical_plan(plan)
+            .unwrap();
+        node.profile().unwrap().estimate
+    }
+
+    fn assert_rows(estimate: PlanEstimate, rows: f64) {
+        assert!(
+            (estimate.rows - rows).abs() < 1e-6,
+            "expected {rows} rows, estimated {}",
+            estimate.rows
+        );
+    }
+
+    #[test]
+    fn test_estimates_without_statistics() {
+        let mut db = database("defaults");
+        let pages = db.buffer_manager.page_count("users").unwrap() as f64;
+
+        let scan = estimate(&mut db, "SELECT name FROM users");
+        let rows_per_page = ((Page::SIZE - PageHeader::SIZE) / (40 + ItemPointer::SIZE)) as f64;
+        assert_rows(scan, pages * rows_per_page);
+        assert!(scan.cost > pages * SEQ_PAGE_COST);
+
+        let filtered = estimate(&mut db, "SELECT name FROM users WHERE age = 7");
+        assert_rows(filtered, scan.rows * DEFAULT_EQUALITY_SELECTIVITY);
+        assert!(filtered.cost > scan.cost);
+    }
+
+    #[test]
+    fn test_estimates_from_statistics() {
+        let mut db = database("statistics");
+        db.analyze("users").unwrap();
+
+        assert_rows(estimate(&mut db, "SELECT name FROM users"), 1000.0);
+        // Every age is one of the most common values
+        assert_rows(
+            estimate(&mut db, "SELECT name FROM users WHERE age = 7"),
+            20.0,
+        );
+        assert_rows(
+            estimate(&mut db, "SELECT name FROM users WHERE age < 10"),
+            180.0,
+        );
+        assert_rows(
+            estimate(&mut db, "SELECT name FROM users WHERE 10 > age"),
+            180.0,
+        );
+        assert_rows(
+            estimate(&mut db, "SELECT name FROM users WHERE age = 9"),
+            0.0,
+        );
+        assert_rows(
+            estimate(&mut db, "SELECT name FROM users WHERE age IS NULL"),
+            100.0,
+        );
+        assert_rows(
+            estimate(&mut db, "SELECT name FROM users WHERE age = 7 OR age = 8"),
+            40.0 - 0.4,
+        );
+
+        // Names are unique, so the histogram decides, 555 of them sort after 'user 5'
+        let names = estimate(&mut db, "SELECT name FROM users WHERE name > 'user 5'");
+        assert!((500.0..600.0).contains(&names.rows), "{names:?}");
+    }
+
+    #[test]
+    fn test_estimated_groups() {
+        let mut db = database("groups");
+        db.analyze("users").unwrap();
+
+        assert_rows(estimate(&mut db, "SELECT count(*) FROM users"), 1.0);
+        // 45 ages and NULL
+        assert_rows(
+            estimate(&mut db, "SELECT age, count(*) FROM users GROUP BY age"),
+            46.0,
+        );
+        assert_rows(
+            estimate(
+                &mut db,
+                "SELECT age, count(*) FROM users WHERE age = 7 GROUP BY age",
+            ),
+            20.0,
+        );
+    }
+}
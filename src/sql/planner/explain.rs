@@ -0,0 +1,56 @@
+//! Rendering of execution plans for `EXPLAIN`.
+
+use crate::sql::planner::physical::ExecutionNode;
+
+/// Renders a plan as indented lines, each operator followed by its details.
+///
+/// Profiled nodes show the estimated cost and rows, and with `analyze` what was
+/// measured while the plan ran.
+pub(crate) fn explain_plan(root: &dyn ExecutionNode, analyze: bool) -> Vec<String> {
+    let mut lines = Vec::new();
+    explain_node(root, 0, analyze, &mut lines);
+    lines
+}
+
+fn explain_node(node: &dyn ExecutionNode, depth: usize, analyze: bool, lines: &mut Vec<String>) {
+    // Inputs are drawn as arrows under their parent, details line up with the name
+    let (prefix, detail_indent) = match depth {
+        0 => (String::new(), "  ".to_string()),
+        _ => {
+            let indent = " ".repeat(6 * depth - 4);
+            (format!("{indent}->  "), format!("{indent}      "))
+        }
+    };
+
+    let mut line = format!("{prefix}{}", node.name());
+    if let Some(profile) = node.profile() {
+        line.push_str(&format!(
+            "  (cost={:.2} rows={:.0})",
+            profile.estimate.cost, profile.estimate.rows
+        ));
+        if analyze {
+            line.push_str(&format!(
+                " (actual rows={} batches={} time={:.3} ms)",
+                profile.rows,
+                profile.batches,
+                profile.elapsed.as_secs_f64() * 1000.0
+            ));
+        }
+    }
+    lines.push(line);
+
+    for detail in node.details() {
+        lines.push(format!("{detail_indent}{detail}"));
+    }
+    let output = node
+        .schema()
+        .fields
+        .iter()
+        .map(|field| field.output_name())
+        .collect::<Vec<_>>();
+    lines.push(format!("{detail_indent}Output: {}", output.join(", ")));
+
+    for child in node.children() {
+        explain_node(child, depth + 1, analyze, lines);
+    }
+}
@@ -0,0 +1,516 @@
+//! Execution nodes joining the rows of two inputs, and the sort merge joins read their
+//! inputs through.
+//!
+//! Joined rows hold the columns of the outer input followed by those of the inner one.
+//! Join keys are never NULL in a joined row, `NULL = NULL` is not true.
+
+use std::{cmp::Ordering, collections::HashMap};
+
+use miette::Result;
+
+use crate::{
+    DataType, Value,
+    db::catalog::statistics::compare,
+    sql::{
+        analyzer::{AnalyzedExpression, ColumnRef, schema::OutputSchema},
+        ast::operator::Operator,
+        catalog_context::CatalogContext,
+        evaluator::vectorized::VectorizedEvaluator,
+        planner::{
+            batch::{Column, RecordBatch},
+            physical::{BATCH_SIZE, ExecutionNode, GroupKey, display_list},
+        },
+    },
+};
+
+/// Reads every row of `node` into a single batch.
+fn collect_rows(node: &mut dyn ExecutionNode, context: &CatalogContext) -> Result<RecordBatch> {
+    let width = node.schema().fields.len();
+    let mut rows = Vec::new();
+    while let Some(batch) = node.next(context)? {
+        rows.extend(batch.into_rows());
+    }
+    Ok(RecordBatch::from_rows(rows, width))
+}
+
+/// Joins the pairs of rows of `outer` and `inner` at the same positions of
+/// `outer_rows` and `inner_rows`, keeping those satisfying every condition.
+fn join_rows(
+    outer: &RecordBatch,
+    outer_rows: &[usize],
+    inner: &RecordBatch,
+    inner_rows: &[usize],
+    conditions: &[AnalyzedExpression],
+) -> Result<RecordBatch> {
+    let columns = outer
+        .columns()
+        .iter()
+        .map(|column| column.take(outer_rows))
+        .chain(inner.columns().iter().map(|column| column.take(inner_rows)))
+        .collect();
+    let mut batch = RecordBatch::new(columns, outer_rows.len());
+
+    let evaluator = VectorizedEvaluator;
+    for condition in conditions {
+        batch = batch.take(&evaluator.select(condition, &batch)?);
+    }
+    Ok(batch)
+}
+
+/// The conditions of a join as EXPLAIN shows them, under `label`.
+fn display_conditions(
+    label: &str,
+    conditions: &[AnalyzedExpression],
+    schema: &OutputSchema,
+) -> Option<String> {
+    (!conditions.is_empty())
+        .then(|| format!("{label}: {}", display_list(conditions, schema, " AND ")))
+}
+
+/// The equalities of the key columns of a join, as conditions on the joined rows.
+fn key_conditions(
+    keys: &[(usize, usize)],
+    outer_width: usize,
+    schema: &OutputSchema,
+) -> Vec<AnalyzedExpression> {
+    let column = |index: usize| {
+        let field = &schema.fields[index];
+        Box::new(AnalyzedExpression::Column(
+            ColumnRef {
+                index,
+                relation: field.relation.clone(),
+            },
+            field.data_type,
+        ))
+    };
+
+    keys.iter()
+        .map(|(outer, inner)| AnalyzedExpression::BinaryExpr {
+            left: column(*outer),
+            op: Operator::Equal,
+            right: column(outer_width + inner),
+            return_type: DataType::Bool,
+        })
+        .collect()
+}
+
+/// Joins every outer row with every inner row satisfying the conditions, the inner
+/// rows are read once and kept in memory.
+#[derive(Debug)]
+pub struct NestedLoopJoinExec {
+    outer: Box<dyn ExecutionNode>,
+    inner: Box<dyn ExecutionNode>,
+    conditions: Vec<AnalyzedExpression>,
+    schema: OutputSchema,
+
+    /// The inner rows, read on the first call.
+    inner_rows: Option<RecordBatch>,
+
+    /// The outer batch being joined, and the position of its next row.
+    outer_batch: Option<(RecordBatch, usize)>,
+}
+
+impl NestedLoopJoinExec {
+    pub(crate) fn new(
+        outer: Box<dyn ExecutionNode>,
+        inner: Box<dyn ExecutionNode>,
+        conditions: Vec<AnalyzedExpression>,
+        schema: OutputSchema,
+    ) -> Self {
+        Self {
+            outer,
+            inner,
+            conditions,
+            schema,
+            inner_rows: None,
+            outer_batch: None,
+        }
+    }
+}
+
+impl ExecutionNode for NestedLoopJoinExec {
+    fn schema(&self) -> &OutputSchema {
+        &self.schema
+    }
+
+    fn name(&self) -> String {
+        "Nested Loop".to_string()
+    }
+
+    fn details(&self) -> Vec<String> {
+        display_conditions("Join Filter", &self.conditions, &self.schema)
+            .into_iter()
+            .collect()
+    }
+
+    fn children(&self) -> Vec<&dyn ExecutionNode> {
+        vec![self.outer.as_ref(), self.inner.as_ref()]
+    }
+
+    fn next(&mut self, context: &CatalogContext) -> Result<Option<RecordBatch>> {
+        if self.inner_rows.is_none() {
+            self.inner_rows = Some(collect_rows(self.inner.as_mut(), context)?);
+        }
+        let inner = self.inner_rows.as_ref().expect("inner rows were just read");
+        if inner.is_empty() {
+            return Ok(None);
+        }
+
+        // Outer rows are joined a few at a time, so batches stay around BATCH_SIZE pairs
+        let outer_step = (BATCH_SIZE / inner.num_rows()).max(1);
+        loop {
+            let (outer, next_row) = match &mut self.outer_batch {
+                Some((outer, next_row)) if *next_row < outer.num_rows() => (outer, next_row),
+                _ => match self.outer.next(context)? {
+                    Some(batch) => {
+                        self.outer_batch = Some((batch, 0));
+                        continue;
+                    }
+                    None => return Ok(None),
+                },
+            };
+
+            let end = outer.num_rows().min(*next_row + outer_step);
+            let mut outer_rows = Vec::new();
+            let mut inner_rows = Vec::new();
+            for outer_row in *next_row..end {
+                outer_rows.extend(std::iter::repeat_n(outer_row, inner.num_rows()));
+                inner_rows.extend(0..inner.num_rows());
+            }
+            *next_row = end;
+
+            let batch = join_rows(outer, &outer_rows, inner, &inner_rows, &self.conditions)?;
+            if !batch.is_empty() {
+                return Ok(Some(batch));
+            }
+        }
+    }
+}
+
+/// Joins rows with equal keys by looking up every outer row in a hash table of the
+/// inner rows, built on the first call.
+#[derive(Debug)]
+pub struct HashJoinExec {
+    outer: Box<dyn ExecutionNode>,
+    inner: Box<dyn ExecutionNode>,
+
+    /// The key columns, of an outer row and of an inner row.
+    keys: Vec<(usize, usize)>,
+
+    /// Conditions checked on the pairs with equal keys.
+    conditions: Vec<AnalyzedExpression>,
+    schema: OutputSchema,
+
+    /// The inner rows and the positions of the rows of every key.
+    table: Option<(RecordBatch, HashMap<GroupKey, Vec<usize>>)>,
+}
+
+impl HashJoinExec {
+    pub(crate) fn new(
+        outer: Box<dyn ExecutionNode>,
+        inner: Box<dyn ExecutionNode>,
+        keys: Vec<(usize, usize)>,
+        conditions: Vec<AnalyzedExpression>,
+        schema: OutputSchema,
+    ) -> Self {
+        Self {
+            outer,
+            inner,
+            keys,
+            conditions,
+            schema,
+            table: None,
+        }
+    }
+
+    /// The key of a row, `None` if a key column is NULL as it matches no row.
+    fn key(
+        batch: &RecordBatch,
+        row: usize,
+        columns: impl Iterator<Item = usize>,
+    ) -> Option<GroupKey> {
+        columns
+            .map(|column| {
+                let column = batch.column(column);
+                (!column.is_null(row)).then(|| column.value(row))
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(GroupKey)
+    }
+}
+
+impl ExecutionNode for HashJoinExec {
+    fn schema(&self) -> &OutputSchema {
+        &self.schema
+    }
+
+    fn name(&self) -> String {
+        "Hash Join".to_string()
+    }
+
+    fn details(&self) -> Vec<String> {
+        let outer_width = self.outer.schema().fields.len();
+        let keys = key_conditions(&self.keys, outer_width, &self.schema);
+        display_conditions("Hash Cond", &keys, &self.schema)
+            .into_iter()
+            .chain(display_conditions(
+                "Join Filter",
+                &self.conditions,
+                &self.schema,
+            ))
+            .collect()
+    }
+
+    fn children(&self) -> Vec<&dyn ExecutionNode> {
+        vec![self.outer.as_ref(), self.inner.as_ref()]
+    }
+
+    fn next(&mut self, context: &CatalogContext) -> Result<Option<RecordBatch>> {
+        if self.table.is_none() {
+            let inner = collect_rows(self.inner.as_mut(), context)?;
+            let mut rows = HashMap::<GroupKey, Vec<usize>>::new();
+            for row in 0..inner.num_rows() {
+                if let Some(key) = Self::key(&inner, row, self.keys.iter().map(|key| key.1)) {
+                    rows.entry(key).or_default().push(row);
+                }
+            }
+            self.table = Some((inner, rows));
+        }
+        let (inner, rows) = self.table.as_ref().expect("the hash table was just built");
+        if inner.is_empty() {
+            return Ok(None);
+        }
+
+        while let Some(outer) = self.outer.next(context)? {
+            let mut outer_rows = Vec::new();
+            let mut inner_rows = Vec::new();
+            for outer_row in 0..outer.num_rows() {
+                let key = Self::key(&outer, outer_row, self.keys.iter().map(|key| key.0));
+                if let Some(matches) = key.and_then(|key| rows.get(&key)) {
+                    outer_rows.extend(std::iter::repeat_n(outer_row, matches.len()));
+                    inner_rows.extend(matches);
+                }
+            }
+
+            let batch = join_rows(&outer, &outer_rows, inner, &inner_rows, &self.conditions)?;
+            if !batch.is_empty() {
+                return Ok(Some(batch));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Joins rows with equal keys from inputs sorted on them, reading both in step.
+#[derive(Debug)]
+pub struct MergeJoinExec {
+    outer: Box<dyn ExecutionNode>,
+    inner: Box<dyn ExecutionNode>,
+
+    /// The key columns, of an outer row and of an inner row.
+    key: (usize, usize),
+
+    /// Conditions checked on the pairs with equal keys.
+    conditions: Vec<AnalyzedExpression>,
+    schema: OutputSchema,
+
+    /// Both inputs, read on the first call.
+    inputs: Option<(RecordBatch, RecordBatch)>,
+
+    /// The positions of the pairs of rows with equal keys, and of the next one to return.
+    pairs: Vec<(usize, usize)>,
+    next_pair: usize,
+}
+
+impl MergeJoinExec {
+    pub(crate) fn new(
+        outer: Box<dyn ExecutionNode>,
+        inner: Box<dyn ExecutionNode>,
+        key: (usize, usize),
+        conditions: Vec<AnalyzedExpression>,
+        schema: OutputSchema,
+    ) -> Self {
+        Self {
+            outer,
+            inner,
+            key,
+            conditions,
+            schema,
+            inputs: None,
+            pairs: Vec::new(),
+            next_pair: 0,
+        }
+    }
+
+    /// The pairs of rows of the sorted inputs with equal keys, in order.
+    fn merge(&self, outer: &RecordBatch, inner: &RecordBatch) -> Vec<(usize, usize)> {
+        let outer_keys = outer.column(self.key.0);
+        let inner_keys = inner.column(self.key.1);
+
+        let mut pairs = Vec::new();
+        let (mut outer_row, mut inner_row) = (0, 0);
+        while outer_row < outer.num_rows() && inner_row < inner.num_rows() {
+            if outer_keys.is_null(outer_row) {
+                outer_row += 1;
+                continue;
+            }
+            if inner_keys.is_null(inner_row) {
+                inner_row += 1;
+                continue;
+            }
+
+            let key = outer_keys.value(outer_row);
+            match compare(&key, &inner_keys.value(inner_row)) {
+                Ordering::Less => outer_row += 1,
+                Ordering::Greater => inner_row += 1,
+                Ordering::Equal => {
+                    // Every outer row of the key is joined with every inner row of it
+                    let same_key = |column: &Column, row: usize| {
+                        !column.is_null(row) && compare(&column.value(row), &key) == Ordering::Equal
+                    };
+                    let outer_end = (outer_row..outer.num_rows())
+                        .find(|row| !same_key(outer_keys, *row))
+                        .unwrap_or(outer.num_rows());
+                    let inner_end = (inner_row..inner.num_rows())
+                        .find(|row| !same_key(inner_keys, *row))
+                        .unwrap_or(inner.num_rows());
+
+                    for outer_match in outer_row..outer_end {
+                        pairs.extend(
+                            (inner_row..inner_end).map(|inner_match| (outer_match, inner_match)),
+                        );
+                    }
+                    outer_row = outer_end;
+                    inner_row = inner_end;
+                }
+            }
+        }
+        pairs
+    }
+}
+
+impl ExecutionNode for MergeJoinExec {
+    fn schema(&self) -> &OutputSchema {
+        &self.schema
+    }
+
+    fn name(&self) -> String {
+        "Merge Join".to_string()
+    }
+
+    fn details(&self) -> Vec<String> {
+        let outer_width = self.outer.schema().fields.len();
+        let key = key_conditions(&[self.key], outer_width, &self.schema);
+        display_conditions("Merge Cond", &key, &self.schema)
+            .into_iter()
+            .chain(display_conditions(
+                "Join Filter",
+                &self.conditions,
+                &self.schema,
+            ))
+            .collect()
+    }
+
+    fn children(&self) -> Vec<&dyn ExecutionNode> {
+        vec![self.outer.as_ref(), self.inner.as_ref()]
+    }
+
+    fn next(&mut self, context: &CatalogContext) -> Result<Option<RecordBatch>> {
+        if self.inputs.is_none() {
+            let outer = collect_rows(self.outer.as_mut(), context)?;
+            let inner = collect_rows(self.inner.as_mut(), context)?;
+            self.pairs = self.merge(&outer, &inner);
+            self.inputs = Some((outer, inner));
+        }
+        let (outer, inner) = self.inputs.as_ref().expect("the inputs were just read");
+        let pairs = &self.pairs;
+
+        while self.next_pair < pairs.len() {
+            let end = pairs.len().min(self.next_pair + BATCH_SIZE);
+            let (outer_rows, inner_rows): (Vec<_>, Vec<_>) =
+                pairs[self.next_pair..end].iter().copied().unzip();
+            self.next_pair = end;
+
+            let batch = join_rows(outer, &outer_rows, inner, &inner_rows, &self.conditions)?;
+            if !batch.is_empty() {
+                return Ok(Some(batch));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Sorts the rows of its input on a column, NULLs last. Reads the whole input on the
+/// first call.
+#[derive(Debug)]
+pub struct SortExec {
+    child: Box<dyn ExecutionNode>,
+    column: usize,
+
+    /// The sorted rows and the position of the next row to return.
+    sorted: Option<(RecordBatch, usize)>,
+}
+
+impl SortExec {
+    pub(crate) fn new(child: Box<dyn ExecutionNode>, column: usize) -> Self {
+        Self {
+            child,
+            column,
+            sorted: None,
+        }
+    }
+}
+
+impl ExecutionNode for SortExec {
+    fn schema(&self) -> &OutputSchema {
+        self.child.schema()
+    }
+
+    fn name(&self) -> String {
+        "Sort".to_string()
+    }
+
+    fn details(&self) -> Vec<String> {
+        let schema = self.child.schema();
+        let field = &schema.fields[self.column];
+        let key = AnalyzedExpression::Column(
+            ColumnRef {
+                index: self.column,
+                relation: field.relation.clone(),
+            },
+            field.data_type,
+        );
+        vec![format!("Sort Key: {}", key.display(schema))]
+    }
+
+    fn children(&self) -> Vec<&dyn ExecutionNode> {
+        vec![self.child.as_ref()]
+    }
+
+    fn next(&mut self, context: &CatalogContext) -> Result<Option<RecordBatch>> {
+        if self.sorted.is_none() {
+            let width = self.child.schema().fields.len();
+            let mut rows = collect_rows(self.child.as_mut(), context)?.into_rows();
+            rows.sort_by(
+                |a, b| match (&a.values[self.column], &b.values[self.column]) {
+                    (Value::Null, Value::Null) => Ordering::Equal,
+                    (Value::Null, _) => Ordering::Greater,
+                    (_, Value::Null) => Ordering::Less,
+                    (a, b) => compare(a, b),
+                },
+            );
+            self.sorted = Some((RecordBatch::from_rows(rows, width), 0));
+        }
+        let (sorted, next_row) = self.sorted.as_mut().expect("the rows were just sorted");
+
+        if *next_row >= sorted.num_rows() {
+            return Ok(None);
+        }
+        let end = sorted.num_rows().min(*next_row + BATCH_SIZE);
+        let batch = sorted.take(&(*next_row..end).collect::<Vec<_>>());
+        *next_row = end;
+        Ok(Some(batch))
+    }
+}
@@ -0,0 +1,588 @@
+//! Choice of the order tables are joined in and of the method joining them.
+//!
+//! The plans of every set of relations are built from the plans of its subsets, cheapest
+//! first, by dynamic programming. A set keeps its cheapest plan, and the cheapest plan
+//! for every order of its rows a merge join could use. Sets are only joined on a
+//! condition between them, unless the relations cannot be joined without a cross product.
+//!
+//! Joins of more than [`MAX_ENUMERATED_RELATIONS`] relations are too costly to enumerate,
+//! their relations are joined in the order of the FROM clause instead, each with its
+//! cheapest method.
+
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+    rc::Rc,
+};
+
+use crate::sql::planner::cost::{CostModel, PlanEstimate};
+
+/// Largest number of relations whose join orders are all considered.
+pub(crate) const MAX_ENUMERATED_RELATIONS: usize = 8;
+
+/// A set of relations, relation `i` being in it if bit `i` is set.
+pub(crate) type RelationSet = u64;
+
+/// An input of the join, e.g. a scan of a table.
+#[derive(Debug)]
+pub(crate) struct JoinRelation {
+    /// The positions of its columns in the output of the join.
+    pub(crate) columns: Range<usize>,
+
+    /// The ways to read it, all producing the same rows.
+    pub(crate) paths: Vec<RelationPath>,
+}
+
+/// A way to read a relation.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct RelationPath {
+    pub(crate) estimate: PlanEstimate,
+
+    /// The column the rows are read in the order of, by position in the output of the join.
+    pub(crate) ordering: Option<usize>,
+}
+
+/// A condition of the join, on the columns of two or more relations.
+#[derive(Debug)]
+pub(crate) struct JoinCondition {
+    /// The relations whose columns it reads.
+    pub(crate) relations: RelationSet,
+
+    /// The columns it compares, if it is an equality of two columns a hash or merge join
+    /// can use.
+    pub(crate) keys: Option<(usize, usize)>,
+
+    /// Estimated fraction of the rows it keeps.
+    pub(crate) selectivity: f64,
+}
+
+/// How two inputs are joined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum JoinMethod {
+    /// Compares every outer row with every inner row.
+    NestedLoop,
+
+    /// Looks up every outer row in a hash table of the inner rows.
+    Hash,
+
+    /// Reads both inputs in the order of their keys.
+    Merge,
+}
+
+/// A plan of the join of a set of relations.
+#[derive(Debug)]
+pub(crate) struct JoinPlan {
+    pub(crate) node: JoinNode,
+    pub(crate) estimate: PlanEstimate,
+
+    /// The column the rows are returned in the order of, by position in the output of
+    /// the join.
+    pub(crate) ordering: Option<usize>,
+    relations: RelationSet,
+}
+
+#[derive(Debug)]
+pub(crate) enum JoinNode {
+    /// Reads a relation with one of its paths.
+    Relation { relation: usize, path: usize },
+
+    /// Sorts its input on a column.
+    Sort { input: Rc<JoinPlan>, column: usize },
+
+    /// Joins two plans, returning the columns of `outer` followed by those of `inner`.
+    Join {
+        method: JoinMethod,
+        outer: Rc<JoinPlan>,
+        inner: Rc<JoinPlan>,
+
+        /// The columns compared by a hash or merge join, an outer column with an inner
+        /// one. A merge join compares a single pair.
+        keys: Vec<(usize, usize)>,
+
+        /// The conditions checked on the pairs of rows besides the keys.
+        conditions: Vec<usize>,
+    },
+}
+
+impl JoinPlan {
+    /// The positions in the output of the join of the columns this plan returns, in order.
+    pub(crate) fn columns(&self, relations: &[JoinRelation]) -> Vec<usize> {
+        match &self.node {
+            JoinNode::Relation { relation, .. } => relations[*relation].columns.clone().collect(),
+            JoinNode::Sort { input, .. } => input.columns(relations),
+            JoinNode::Join { outer, inner, .. } => {
+                let mut columns = outer.columns(relations);
+                columns.extend(inner.columns(relations));
+                columns
+            }
+        }
+    }
+}
+
+/// Finds the cheapest plan joining all `relations` on all `conditions`.
+pub(crate) fn best_join_plan(
+    cost_model: &CostModel,
+    relations: &[JoinRelation],
+    conditions: &[JoinCondition],
+) -> Rc<JoinPlan> {
+    let mut enumerator = JoinEnumerator {
+        cost_model,
+        relations,
+        conditions,
+        rows: HashMap::new(),
+    };
+
+    let plans = if relations.len() <= MAX_ENUMERATED_RELATIONS {
+        enumerator.enumerate()
+    } else {
+        enumerator.join_in_order()
+    };
+
+    plans
+        .into_iter()
+        .min_by(|a, b| a.estimate.cost.total_cmp(&b.estimate.cost))
+        .expect("a join has at least one plan")
+}
+
+struct JoinEnumerator<'a, 'db> {
+    cost_model: &'a CostModel<'db>,
+    relations: &'a [JoinRelation],
+    conditions: &'a [JoinCondition],
+
+    /// Estimated rows of the join of every set of relations, whatever the plan.
+    rows: HashMap<RelationSet, f64>,
+}
+
+impl JoinEnumerator<'_, '_> {
+    /// The plans of the join of all relations, built from the plans of their subsets.
+    fn enumerate(&mut self) -> Vec<Rc<JoinPlan>> {
+        let count = self.relations.len();
+        let all: RelationSet = (1 << count) - 1;
+
+        let mut plans = HashMap::<RelationSet, Vec<Rc<JoinPlan>>>::new();
+        // Sets joined without a cross product
+        let mut connected = HashSet::<RelationSet>::new();
+        for relation in 0..count {
+            plans.insert(1 << relation, self.relation_plans(relation));
+            connected.insert(1 << relation);
+        }
+
+        // Sets in increasing size, so the plans of their subsets are known
+        let mut sets = (1..=all)
+            .filter(|set: &RelationSet| set.count_ones() > 1)
+            .collect::<Vec<_>>();
+        sets.sort_by_key(|set| set.count_ones());
+
+        for set in sets {
+            let mut set_plans = Vec::new();
+            for cross_products in [false, true] {
+                // Every split of the set in two, each side being the outer one in turn
+                let mut outer = (set - 1) & set;
+                while outer != 0 {
+                    let inner = set & !outer;
+                    if let (Some(outer_plans), Some(inner_plans)) =
+                        (plans.get(&outer), plans.get(&inner))
+                        && (cross_products
+                            || connected.contains(&outer)
+                                && connected.contains(&inner)
+                                && self.connects(outer, inner))
+                    {
+                        for outer_plan in outer_plans {
+                            for inner_plan in inner_plans {
+                                for plan in self.join_plans(outer_plan, inner_plan) {
+                                    keep_plan(&mut set_plans, plan);
+                                }
+                            }
+                        }
+                    }
+                    outer = (outer - 1) & set;
+                }
+
+                if !set_plans.is_empty() {
+                    if !cross_products {
+                        connected.insert(set);
+                    }
+                    break;
+                }
+            }
+            plans.insert(set, set_plans);
+        }
+
+        plans.remove(&all).unwrap_or_default()
+    }
+
+    /// The plans of the join of all relations, joined one by one in their order.
+    fn join_in_order(&mut self) -> Vec<Rc<JoinPlan>> {
+        let mut plans = self.relation_plans(0);
+        for relation in 1..self.relations.len() {
+            let relation_plans = self.relation_plans(relation);
+
+            let mut joined = Vec::new();
+            for plan in &plans {
+                for relation_plan in &relation_plans {
+                    for plan in self
+                        .join_plans(plan, relation_plan)
+                        .into_iter()
+                        .chain(self.join_plans(relation_plan, plan))
+                    {
+                        keep_plan(&mut joined, plan);
+                    }
+                }
+            }
+            plans = joined;
+        }
+        plans
+    }
+
+    fn relation_plans(&self, relation: usize) -> Vec<Rc<JoinPlan>> {
+        let mut plans = Vec::new();
+        for (index, path) in self.relations[relation].paths.iter().enumerate() {
+            keep_plan(
+                &mut plans,
+                JoinPlan {
+                    node: JoinNode::Relation {
+                        relation,
+                        path: index,
+                    },
+                    estimate: path.estimate,
+                    ordering: path.ordering,
+                    relations: 1 << relation,
+                },
+            );
+        }
+        plans
+    }
+
+    /// Whether a condition reads columns of both sets, and no other relation.
+    fn joins(&self, condition: &JoinCondition, outer: RelationSet, inner: RelationSet) -> bool {
+        condition.relations & !(outer | inner) == 0
+            && condition.relations & outer != 0
+            && condition.relations & inner != 0
+    }
+
+    fn connects(&self, outer: RelationSet, inner: RelationSet) -> bool {
+        self.conditions
+            .iter()
+            .any(|condition| self.joins(condition, outer, inner))
+    }
+
+    /// The plans joining `outer` with `inner`, one per method that applies.
+    fn join_plans(&mut self, outer: &Rc<JoinPlan>, inner: &Rc<JoinPlan>) -> Vec<JoinPlan> {
+        let relations = outer.relations | inner.relations;
+        let rows = self.rows(relations);
+        let conditions = (0..self.conditions.len())
+            .filter(|&index| self.joins(&self.conditions[index], outer.relations, inner.relations))
+            .collect::<Vec<_>>();
+        let join =
+            |method, outer: &Rc<JoinPlan>, inner: &Rc<JoinPlan>, keys, conditions| JoinNode::Join {
+                method,
+                outer: Rc::clone(outer),
+                inner: Rc::clone(inner),
+                keys,
+                conditions,
+            };
+
+        let mut plans = vec![JoinPlan {
+            estimate: self.cost_model.nested_loop_join(
+                outer.estimate,
+                inner.estimate,
+                rows,
+                conditions.len(),
+            ),
+            node: join(
+                JoinMethod::NestedLoop,
+                outer,
+                inner,
+                Vec::new(),
+                conditions.clone(),
+            ),
+            ordering: outer.ordering,
+            relations,
+        }];
+
+        // Equalities of an outer column with an inner one, with the condition stating them
+        let keys = conditions
+            .iter()
+            .filter_map(|&index| {
+                let (left, right) = self.conditions[index].keys?;
+                match self.relation_of(left) & outer.relations != 0 {
+                    true => Some((left, right, index)),
+                    false => Some((right, left, index)),
+                }
+            })
+            .collect::<Vec<_>>();
+        if keys.is_empty() {
+            return plans;
+        }
+
+        let others = |excluded: &dyn Fn(usize) -> bool| {
+            conditions
+                .iter()
+                .copied()
+                .filter(|index| !excluded(*index))
+                .collect::<Vec<_>>()
+        };
+
+        let hash_conditions = others(&|index| keys.iter().any(|key| key.2 == index));
+        plans.push(JoinPlan {
+            estimate: self.cost_model.hash_join(
+                outer.estimate,
+                inner.estimate,
+                rows,
+                keys.len(),
+                hash_conditions.len(),
+            ),
+            node: join(
+                JoinMethod::Hash,
+                outer,
+                inner,
+                keys.iter()
+                    .map(|(outer, inner, _)| (*outer, *inner))
+                    .collect(),
+                hash_conditions,
+            ),
+            ordering: outer.ordering,
+            relations,
+        });
+
+        for &(outer_key, inner_key, key_condition) in &keys {
+            let sorted_outer = self.sorted(outer, outer_key);
+            let sorted_inner = self.sorted(inner, inner_key);
+            let merge_conditions = others(&|index| index == key_condition);
+            plans.push(JoinPlan {
+                estimate: self.cost_model.merge_join(
+                    sorted_outer.estimate,
+                    sorted_inner.estimate,
+                    rows,
+                    merge_conditions.len(),
+                ),
+                node: join(
+                    JoinMethod::Merge,
+                    &sorted_outer,
+                    &sorted_inner,
+                    vec![(outer_key, inner_key)],
+                    merge_conditions,
+                ),
+                ordering: Some(outer_key),
+                relations,
+            });
+        }
+
+        plans
+    }
+
+    /// `plan` with its rows in the order of `column`, sorted unless they already are.
+    fn sorted(&self, plan: &Rc<JoinPlan>, column: usize) -> Rc<JoinPlan> {
+        if plan.ordering == Some(column) {
+            return Rc::clone(plan);
+        }
+
+        Rc::new(JoinPlan {
+            estimate: self.cost_model.sort(plan.estimate),
+            ordering: Some(column),
+            relations: plan.relations,
+            node: JoinNode::Sort {
+                input: Rc::clone(plan),
+                column,
+            },
+        })
+    }
+
+    /// The relation holding a column, as a set.
+    fn relation_of(&self, column: usize) -> RelationSet {
+        let relation = self
+            .relations
+            .iter()
+            .position(|relation| relation.columns.contains(&column))
+            .expect("join columns belong to a relation");
+        1 << relation
+    }
+
+    /// Estimated rows of the join of a set of relations.
+    ///
+    /// The same for every plan of the set: the rows of the relations multiplied by the
+    /// selectivity of every condition between them.
+    fn rows(&mut self, set: RelationSet) -> f64 {
+        if let Some(rows) = self.rows.get(&set) {
+            return *rows;
+        }
+
+        let mut rows = 1.0;
+        for (index, relation) in self.relations.iter().enumerate() {
+            if set & (1 << index) != 0 {
+                rows *= relation.paths[0].estimate.rows;
+            }
+        }
+        for condition in self.conditions {
+            if condition.relations & !set == 0 {
+                rows *= condition.selectivity;
+            }
+        }
+
+        self.rows.insert(set, rows);
+        rows
+    }
+}
+
+/// Adds `plan` to the plans of a set, unless one as cheap returns its rows in the same
+/// order, or in an order while it does not.
+fn keep_plan(plans: &mut Vec<Rc<JoinPlan>>, plan: JoinPlan) {
+    let dominates = |kept: &JoinPlan, plan: &JoinPlan| {
+        kept.estimate.cost <= plan.estimate.cost
+            && (kept.ordering == plan.ordering || plan.ordering.is_none())
+    };
+
+    if plans.iter().any(|kept| dominates(kept, &plan)) {
+        return;
+    }
+    plans.retain(|kept| !dominates(&plan, kept));
+    plans.push(Rc::new(plan));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Database;
+
+    fn relation(columns: Range<usize>, rows: f64) -> JoinRelation {
+        JoinRelation {
+            columns,
+            paths: vec![RelationPath {
+                estimate: PlanEstimate {
+                    rows,
+                    cost: rows * 0.01,
+                },
+                ordering: None,
+            }],
+        }
+    }
+
+    fn equality(left: usize, right: usize, relations: RelationSet, rows: f64) -> JoinCondition {
+        JoinCondition {
+            relations,
+            keys: Some((left, right)),
+            selectivity: 1.0 / rows,
+        }
+    }
+
+    fn method(plan: &JoinPlan) -> JoinMethod {
+        match &plan.node {
+            JoinNode::Join { method, .. } => *method,
+            node => panic!("Expected a join, got {node:?}"),
+        }
+    }
+
+    /// The relations joined first, below the top join.
+    fn first_joined(plan: &JoinPlan) -> RelationSet {
+        let JoinNode::Join { outer, inner, .. } = &plan.node else {
+            panic!("Expected a join");
+        };
+        match outer.relations.count_ones() > 1 {
+            true => outer.relations,
+            false => inner.relations,
+        }
+    }
+
+    #[test]
+    fn test_hash_join_builds_on_smaller_input() {
+        let directory = std::env::temp_dir().join("scuttle_join_order_hash_tests");
+        let db = Database::new(&directory);
+        let cost_model = CostModel::new(&db);
+
+        let relations = [relation(0..2, 10.0), relation(2..4, 100_000.0)];
+        let conditions = [equality(0, 2, 0b11, 100_000.0)];
+        let plan = best_join_plan(&cost_model, &relations, &conditions);
+
+        assert_eq!(method(&plan), JoinMethod::Hash);
+        let JoinNode::Join { inner, keys, .. } = &plan.node else {
+            unreachable!();
+        };
+        assert_eq!(inner.relations, 0b01);
+        assert_eq!(keys, &vec![(2, 0)]);
+        assert_eq!(plan.columns(&relations), vec![2, 3, 0, 1]);
+    }
+
+    #[test]
+    fn test_small_and_non_equi_joins_use_nested_loops() {
+        let directory = std::env::temp_dir().join("scuttle_join_order_loop_tests");
+        let db = Database::new(&directory);
+        let cost_model = CostModel::new(&db);
+
+        let relations = [relation(0..1, 3.0), relation(1..2, 3.0)];
+        let plan = best_join_plan(&cost_model, &relations, &[equality(0, 1, 0b11, 3.0)]);
+        assert_eq!(method(&plan), JoinMethod::NestedLoop);
+
+        let relations = [relation(0..1, 1000.0), relation(1..2, 1000.0)];
+        let less_than = JoinCondition {
+            relations: 0b11,
+            keys: None,
+            selectivity: 1.0 / 3.0,
+        };
+        let plan = best_join_plan(&cost_model, &relations, &[less_than]);
+        assert_eq!(method(&plan), JoinMethod::NestedLoop);
+    }
+
+    #[test]
+    fn test_merge_join_of_ordered_inputs() {
+        let directory = std::env::temp_dir().join("scuttle_join_order_merge_tests");
+        let db = Database::new(&directory);
+        let cost_model = CostModel::new(&db);
+
+        let mut relations = [relation(0..1, 1000.0), relation(1..2, 1000.0)];
+        relations[0].paths[0].ordering = Some(0);
+        relations[1].paths[0].ordering = Some(1);
+        let plan = best_join_plan(&cost_model, &relations, &[equality(0, 1, 0b11, 1000.0)]);
+
+        assert_eq!(method(&plan), JoinMethod::Merge);
+        let JoinNode::Join { outer, inner, .. } = &plan.node else {
+            unreachable!();
+        };
+        // Neither input needs sorting
+        assert!(matches!(outer.node, JoinNode::Relation { .. }));
+        assert!(matches!(inner.node, JoinNode::Relation { .. }));
+    }
+
+    #[test]
+    fn test_avoid_cross_products() {
+        let directory = std::env::temp_dir().join("scuttle_join_order_chain_tests");
+        let db = Database::new(&directory);
+        let cost_model = CostModel::new(&db);
+
+        // a - b - c, where joining the small a and c first would be a cross product
+        let relations = [
+            relation(0..1, 10.0),
+            relation(1..3, 10_000.0),
+            relation(3..4, 10.0),
+        ];
+        let conditions = [equality(0, 1, 0b011, 10.0), equality(2, 3, 0b110, 10.0)];
+        let plan = best_join_plan(&cost_model, &relations, &conditions);
+        assert_ne!(first_joined(&plan), 0b101);
+
+        // Without conditions there is nothing but cross products
+        let plan = best_join_plan(&cost_model, &relations, &[]);
+        assert_eq!(plan.relations, 0b111);
+        assert_eq!(plan.estimate.rows, 10.0 * 10_000.0 * 10.0);
+    }
+
+    #[test]
+    fn test_many_relations_are_joined_in_order() {
+        let directory = std::env::temp_dir().join("scuttle_join_order_many_tests");
+        let db = Database::new(&directory);
+        let cost_model = CostModel::new(&db);
+
+        let count = MAX_ENUMERATED_RELATIONS + 2;
+        let relations = (0..count)
+            .map(|index| relation(index..index + 1, 100.0))
+            .collect::<Vec<_>>();
+        let conditions = (1..count)
+            .map(|index| equality(index - 1, index, 0b11 << (index - 1), 100.0))
+            .collect::<Vec<_>>();
+        let plan = best_join_plan(&cost_model, &relations, &conditions);
+
+        assert_eq!(plan.relations, (1 << count) - 1);
+        let mut columns = plan.columns(&relations);
+        columns.sort_unstable();
+        assert_eq!(columns, (0..count).collect::<Vec<_>>());
+        assert!((plan.estimate.rows - 100.0).abs() < 1e-6);
+    }
+}
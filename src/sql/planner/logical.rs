@@ -1,11 +1,30 @@
-use crate::sql::analyzer::{AnalyzedExpression, schema::OutputSchema};
+use std::sync::Arc;
 
-#[derive(Debug)]
+use crate::{
+    DataType,
+    sql::{
+        analyzer::{AnalyzedExpression, schema::OutputSchema},
+        functions::AggregateFunction,
+    },
+};
+
+#[derive(Debug, Clone)]
 pub enum LogicalPlan {
     Scan {
         table_name: String,
         schema: OutputSchema,
+
+        /// Ascending indices of the table columns to read, all columns if `None`.
+        ///
+        /// `schema` only holds these columns, column references above the scan point
+        /// into it.
+        projection: Option<Vec<usize>>,
+
+        /// Conditions every returned row satisfies, pushed down by the optimizer.
+        filters: Vec<AnalyzedExpression>,
     },
+    /// Produces no rows, e.g. in place of a filter that is always false.
+    Empty { schema: OutputSchema },
     Filter {
         input: Box<LogicalPlan>,
         condition: AnalyzedExpression,
@@ -15,12 +34,78 @@ pub enum LogicalPlan {
         expressions: Vec<AnalyzedExpression>,
         schema: OutputSchema,
     },
+    /// Groups the input by `group_by` and computes `aggregates` per group.
+    ///
+    /// Outputs the group by values followed by the aggregate values.
+    Aggregate {
+        input: Box<LogicalPlan>,
+        group_by: Vec<AnalyzedExpression>,
+        aggregates: Vec<AggregateExpr>,
+        schema: OutputSchema,
+    },
+    /// Pairs every row of `left` with every row of `right` satisfying all `conditions`.
+    ///
+    /// Outputs the columns of `left` followed by those of `right`, the conditions refer
+    /// to both. The physical planner chooses the order the inputs are joined in.
+    Join {
+        left: Box<LogicalPlan>,
+        right: Box<LogicalPlan>,
+        conditions: Vec<AnalyzedExpression>,
+        schema: OutputSchema,
+    },
+}
+
+/// A call to an aggregate function, e.g. `sum(price * 2)`.
+#[derive(Debug, Clone)]
+pub struct AggregateExpr {
+    pub function: Arc<AggregateFunction>,
+    pub args: Vec<AnalyzedExpression>,
+    pub return_type: DataType,
 }
 
 impl LogicalPlan {
+    /// The expressions evaluated by this node itself, not by its input.
+    pub fn expressions(&self) -> Vec<&AnalyzedExpression> {
+        match self {
+            LogicalPlan::Scan { filters, .. } => filters.iter().collect(),
+            LogicalPlan::Empty { .. } => Vec::new(),
+            LogicalPlan::Filter { condition, .. } => vec![condition],
+            LogicalPlan::Projection { expressions, .. } => expressions.iter().collect(),
+            LogicalPlan::Aggregate {
+                group_by,
+                aggregates,
+                ..
+            } => group_by
+                .iter()
+                .chain(aggregates.iter().flat_map(|aggregate| &aggregate.args))
+                .collect(),
+            LogicalPlan::Join { conditions, .. } => conditions.iter().collect(),
+        }
+    }
+
+    /// The tables read by the plan.
+    pub fn table_names(&self) -> Vec<&str> {
+        match self {
+            LogicalPlan::Scan { table_name, .. } => vec![table_name],
+            LogicalPlan::Empty { .. } => Vec::new(),
+            LogicalPlan::Filter { input, .. }
+            | LogicalPlan::Projection { input, .. }
+            | LogicalPlan::Aggregate { input, .. } => input.table_names(),
+            LogicalPlan::Join { left, right, .. } => {
+                let mut table_names = left.table_names();
+                table_names.extend(right.table_names());
+                table_names
+            }
+        }
+    }
+
     pub fn schema(&self) -> &OutputSchema {
         match self {
-            LogicalPlan::Scan { schema, .. } | LogicalPlan::Projection { schema, .. } => schema,
+            LogicalPlan::Scan { schema, .. }
+            | LogicalPlan::Empty { schema }
+            | LogicalPlan::Projection { schema, .. }
+            | LogicalPlan::Aggregate { schema, .. }
+            | LogicalPlan::Join { schema, .. } => schema,
             LogicalPlan::Filter { input, .. } => input.schema(),
         }
     }
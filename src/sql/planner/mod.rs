@@ -1,2 +1,7 @@
+pub(crate) mod batch;
+pub(crate) mod cost;
+pub(crate) mod explain;
+pub(crate) mod join;
+pub(crate) mod join_order;
 pub(crate) mod logical;
 pub(crate) mod physical;
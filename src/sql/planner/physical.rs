@@ -1,94 +1,1091 @@
-use miette::Result;
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, VecDeque},
+    hash::{Hash, Hasher},
+    ops::{Bound, Range},
+    time::{Duration, Instant},
+};
+
+use miette::{Result, miette};
 
 use crate::{
-    Value,
-    db::table::row::Row,
+    DataType, Value,
+    db::{catalog::statistics::compare, index::RowId, table::row::Row},
     sql::{
-        analyzer::{AnalyzedExpression, schema::OutputSchema},
+        analyzer::{
+            AnalyzedExpression, ColumnRef,
+            schema::{Field, OutputSchema},
+        },
+        ast::operator::Operator,
         catalog_context::CatalogContext,
-        evaluator::{Evaluator, expression::ExpressionEvaluator, predicate::PredicateEvaluator},
-        planner::logical::LogicalPlan,
+        evaluator::{cast_value, vectorized::VectorizedEvaluator},
+        functions::{Accumulator, datetime::bind_statement_time},
+        optimizer::{constant_value, referenced_columns, renumber_columns},
+        planner::{
+            batch::RecordBatch,
+            cost::{CostModel, PlanEstimate, flip},
+            join::{HashJoinExec, MergeJoinExec, NestedLoopJoinExec, SortExec},
+            join_order::{
+                JoinCondition, JoinMethod, JoinNode, JoinPlan, JoinRelation, RelationPath,
+                RelationSet, best_join_plan,
+            },
+            logical::{AggregateExpr, LogicalPlan},
+        },
     },
+    storage::page::PageId,
 };
 
+/// Number of rows a scan collects before returning a batch, pages are never split.
+pub(crate) const BATCH_SIZE: usize = 1024;
+
+/// Upper bound on the number of groups an aggregate allocates room for up front.
+const MAX_PREALLOCATED_GROUPS: usize = 1 << 16;
+
+/// Pages each worker of a parallel scan reads before the gathered rows are returned.
+const PAGES_PER_WORKER_ROUND: PageId = 16;
+
 pub struct PhysicalPlanner<'a, 'db> {
-    context: &'a mut CatalogContext<'db>,
+    context: &'a CatalogContext<'db>,
+
+    /// Estimates the nodes the planner chooses between.
+    cost_model: CostModel<'db>,
+
+    /// Whether every node is wrapped in a [`ProfiledExec`], for EXPLAIN.
+    profile: bool,
+}
+
+/// A node with the estimate it was chosen on.
+struct PlannedNode {
+    node: Box<dyn ExecutionNode>,
+    estimate: PlanEstimate,
+}
+
+/// A way to read the rows of a scan.
+#[derive(Debug, Clone)]
+struct ScanPath {
+    /// The entries of an index to read, a sequential scan reads every page instead.
+    index: Option<IndexRange>,
+    estimate: PlanEstimate,
+}
+
+/// The entries of an index a scan reads.
+#[derive(Debug, Clone)]
+struct IndexRange {
+    index_name: String,
+
+    /// The column of the scan holding the indexed values, rows are read in its order.
+    column: usize,
+    lower: Bound<Value>,
+    upper: Bound<Value>,
+
+    /// The filters of the scan the range stands for, by position.
+    conditions: Vec<usize>,
+}
+
+impl IndexRange {
+    /// Narrows the range to the values for which `value op constant` holds.
+    fn restrict(&mut self, op: Operator, constant: Value) {
+        let (lower, upper) = match op {
+            Operator::Equal => (
+                Some(Bound::Included(constant.clone())),
+                Some(Bound::Included(constant)),
+            ),
+            Operator::GreaterThan => (Some(Bound::Excluded(constant)), None),
+            Operator::GreaterThanEqual => (Some(Bound::Included(constant)), None),
+            Operator::LessThan => (None, Some(Bound::Excluded(constant))),
+            Operator::LessThanEqual => (None, Some(Bound::Included(constant))),
+            _ => unreachable!("indexes only serve comparisons"),
+        };
+
+        if let Some(lower) = lower
+            && narrower(&lower, &self.lower, Ordering::Greater)
+        {
+            self.lower = lower;
+        }
+        if let Some(upper) = upper
+            && narrower(&upper, &self.upper, Ordering::Less)
+        {
+            self.upper = upper;
+        }
+    }
+}
+
+/// Whether `bound` leaves out more values than `current`, bounds further towards
+/// `inward` leaving out more.
+fn narrower(bound: &Bound<Value>, current: &Bound<Value>, inward: Ordering) -> bool {
+    match (bound, current) {
+        (_, Bound::Unbounded) => true,
+        (Bound::Unbounded, _) => false,
+        (
+            Bound::Included(value) | Bound::Excluded(value),
+            Bound::Included(current_value) | Bound::Excluded(current_value),
+        ) => match compare(value, current_value) {
+            Ordering::Equal => matches!(bound, Bound::Excluded(_)),
+            ordering => ordering == inward,
+        },
+    }
+}
+
+/// The comparison of column `column` with a constant `filter` makes, if an index of
+/// the column finds the rows satisfying it.
+fn index_comparison(
+    filter: &AnalyzedExpression,
+    column: usize,
+    data_type: DataType,
+) -> Option<(Operator, Value)> {
+    let AnalyzedExpression::BinaryExpr {
+        left, op, right, ..
+    } = filter
+    else {
+        return None;
+    };
+    let (op, constant) = match (left.as_ref(), right.as_ref()) {
+        (AnalyzedExpression::Column(left, _), right) if left.index == column => {
+            (*op, constant_value(right)?)
+        }
+        (left, AnalyzedExpression::Column(right, _)) if right.index == column => {
+            (flip(*op), constant_value(left)?)
+        }
+        _ => return None,
+    };
+    let comparison = matches!(
+        op,
+        Operator::Equal
+            | Operator::LessThan
+            | Operator::LessThanEqual
+            | Operator::GreaterThan
+            | Operator::GreaterThanEqual
+    );
+    if !comparison || matches!(constant, Value::Null) {
+        return None;
+    }
+
+    // The index holds values of the column, `age < 5.5` cannot be looked up as `age < 6`
+    let value = cast_value(constant, data_type).ok()?;
+    (compare(&value, constant) == Ordering::Equal).then_some((op, value))
+}
+
+/// The inputs of a tree of joins and their conditions on its output, as the planner
+/// chooses how to join them.
+struct JoinInputs<'p> {
+    plans: Vec<&'p LogicalPlan>,
+    relations: Vec<JoinRelation>,
+
+    /// How each relation is read, the paths of [`JoinRelation::paths`] for scans.
+    sources: Vec<JoinSource>,
+
+    /// The conditions described by the [`JoinCondition`]s of the enumerator.
+    conditions: Vec<AnalyzedExpression>,
+}
+
+enum JoinSource {
+    Scan(Vec<ScanPath>),
+
+    /// An input that is not a scan, planned up front and taken once joined.
+    Planned(Option<PlannedNode>),
+}
+
+/// Collects the inputs of a tree of joins, with the position of their first column in
+/// its output, and its conditions renumbered to that output.
+fn flatten_join<'p>(
+    plan: &'p LogicalPlan,
+    offset: usize,
+    inputs: &mut Vec<(&'p LogicalPlan, usize)>,
+    conditions: &mut Vec<AnalyzedExpression>,
+) {
+    match plan {
+        LogicalPlan::Join {
+            left,
+            right,
+            conditions: join_conditions,
+            ..
+        } => {
+            let left_width = left.schema().fields.len();
+            flatten_join(left, offset, inputs, conditions);
+            flatten_join(right, offset + left_width, inputs, conditions);
+            conditions.extend(
+                join_conditions
+                    .iter()
+                    .map(|condition| renumber_columns(condition.clone(), &|index| index + offset)),
+            );
+        }
+        input => inputs.push((input, offset)),
+    }
+}
+
+/// The columns an equality of two columns compares, if their values hash and sort
+/// alike so a hash or merge join can match them.
+fn join_keys(condition: &AnalyzedExpression) -> Option<(usize, usize)> {
+    let AnalyzedExpression::BinaryExpr {
+        left,
+        op: Operator::Equal,
+        right,
+        ..
+    } = condition
+    else {
+        return None;
+    };
+    let (
+        AnalyzedExpression::Column(left, left_type),
+        AnalyzedExpression::Column(right, right_type),
+    ) = (left.as_ref(), right.as_ref())
+    else {
+        return None;
+    };
+
+    let comparable = match (left_type, right_type) {
+        (DataType::Text | DataType::VarChar(_), DataType::Text | DataType::VarChar(_)) => true,
+        (left_type, right_type) => left_type == right_type && left_type.is_indexable(),
+    };
+    comparable.then_some((left.index, right.index))
+}
+
+/// The position of a column of the output of a join among the columns of a node.
+fn column_position(columns: &[usize], column: usize) -> usize {
+    columns
+        .iter()
+        .position(|candidate| *candidate == column)
+        .expect("nodes return every column of their relations")
 }
 
 impl<'a, 'db> PhysicalPlanner<'a, 'db> {
-    pub(crate) fn new(context: &'a mut CatalogContext<'db>) -> Self {
-        Self { context }
+    pub(crate) fn new(context: &'a CatalogContext<'db>) -> Self {
+        Self {
+            context,
+            cost_model: CostModel::new(context.database),
+            profile: false,
+        }
+    }
+
+    /// Makes the planner record estimates and measure execution of every node.
+    pub(crate) fn profiled(mut self) -> Self {
+        self.profile = true;
+        self
     }
 
     pub fn create_physical_plan(
         &mut self,
         analyzed_plan: LogicalPlan,
     ) -> Result<Box<dyn ExecutionNode>> {
-        match analyzed_plan {
-            LogicalPlan::Scan { table_name, schema } => {
-                let data = self.context.database.get_rows(&table_name)?;
+        let analyzed_plan = bind_statement_time(analyzed_plan, self.context.statement_time);
+        Ok(self.create_node(&analyzed_plan)?.node)
+    }
 
-                Ok(Box::new(ScanExec { schema, data }))
+    /// Wraps `node` in a [`ProfiledExec`] when profiling.
+    fn planned(&self, node: Box<dyn ExecutionNode>, estimate: PlanEstimate) -> PlannedNode {
+        let node: Box<dyn ExecutionNode> = match self.profile {
+            true => Box::new(ProfiledExec {
+                inner: node,
+                profile: NodeProfile {
+                    estimate,
+                    rows: 0,
+                    batches: 0,
+                    elapsed: Duration::ZERO,
+                },
+            }),
+            false => node,
+        };
+        PlannedNode { node, estimate }
+    }
+
+    fn create_node(&mut self, analyzed_plan: &LogicalPlan) -> Result<PlannedNode> {
+        match analyzed_plan {
+            LogicalPlan::Scan { .. } => {
+                let path = self.cheapest_scan_path(analyzed_plan)?;
+                self.create_scan(analyzed_plan, path)
+            }
+            LogicalPlan::Empty { schema } => {
+                let empty = ScanExec {
+                    table_name: None,
+                    schema: schema.clone(),
+                    projection: None,
+                    filters: Vec::new(),
+                    next_page: 0,
+                    page_count: 0,
+                    parallel: false,
+                };
+                let estimate = PlanEstimate {
+                    rows: 0.0,
+                    cost: 0.0,
+                };
+                Ok(self.planned(Box::new(empty), estimate))
             }
             LogicalPlan::Filter { input, condition } => {
-                let child_node = self.create_physical_plan(*input)?;
+                let child = self.create_node(input)?;
+                let estimate = self.cost_model.filter(child.estimate, condition, input)?;
 
-                Ok(Box::new(FilterExec {
-                    child: child_node,
-                    expr: condition,
-                }))
+                let filter = FilterExec {
+                    child: child.node,
+                    expr: condition.clone(),
+                };
+                Ok(self.planned(Box::new(filter), estimate))
             }
             LogicalPlan::Projection {
                 input,
                 expressions,
                 schema,
             } => {
-                let child_node = self.create_physical_plan(*input)?;
+                // Projections read the columns of a join in the order it returns them
+                let (child, exprs) = match input.as_ref() {
+                    LogicalPlan::Join { .. } => {
+                        let (child, columns) = self.create_join(input)?;
+                        let exprs = expressions
+                            .iter()
+                            .map(|expr| {
+                                renumber_columns(expr.clone(), &|column| {
+                                    column_position(&columns, column)
+                                })
+                            })
+                            .collect();
+                        (child, exprs)
+                    }
+                    _ => (self.create_node(input)?, expressions.clone()),
+                };
+                let estimate = self
+                    .cost_model
+                    .projection(child.estimate, expressions.len());
+
+                let projection = ProjectionExec {
+                    child: child.node,
+                    exprs,
+                    schema: schema.clone(),
+                };
+                Ok(self.planned(Box::new(projection), estimate))
+            }
+            LogicalPlan::Aggregate {
+                input,
+                group_by,
+                aggregates,
+                schema,
+            } => {
+                let child = match input.as_ref() {
+                    LogicalPlan::Scan { table_name, .. } => {
+                        let path = self.cheapest_scan_path(input)?;
+
+                        // Aggregates sequentially reading a large table are computed by
+                        // the workers
+                        let page_count = self
+                            .context
+                            .database
+                            .buffer_manager
+                            .page_count(table_name)?;
+                        if path.index.is_none()
+                            && let Some(workers) = self.parallel_workers(page_count)
+                        {
+                            let estimate =
+                                self.cost_model.aggregate(path.estimate, analyzed_plan)?;
+                            return self.create_parallel_aggregate(
+                                input, group_by, aggregates, schema, estimate, workers,
+                            );
+                        }
+                        self.create_scan(input, path)?
+                    }
+                    _ => self.create_node(input)?,
+                };
+                let estimate = self.cost_model.aggregate(child.estimate, analyzed_plan)?;
+
+                let aggregate = AggregateExec {
+                    input_schema: child.node.schema().clone(),
+                    child: child.node,
+                    mode: AggregateMode::Single,
+                    group_by: group_by.clone(),
+                    aggregates: aggregates.clone(),
+                    schema: schema.clone(),
+                    // Hash tables are sized for the estimated number of groups
+                    estimated_groups: (estimate.rows as usize).min(MAX_PREALLOCATED_GROUPS),
+                    done: false,
+                };
+                Ok(self.planned(Box::new(aggregate), estimate))
+            }
+            LogicalPlan::Join { schema, .. } => {
+                let (planned, columns) = self.create_join(analyzed_plan)?;
+                if columns
+                    .iter()
+                    .enumerate()
+                    .all(|(position, column)| position == *column)
+                {
+                    return Ok(planned);
+                }
 
-                Ok(Box::new(ProjectionExec {
-                    child: child_node,
-                    exprs: expressions,
-                    schema,
-                }))
+                // Joined rows hold the columns of the relations in the order they were
+                // joined
+                let exprs = schema
+                    .fields
+                    .iter()
+                    .enumerate()
+                    .map(|(column, field)| {
+                        AnalyzedExpression::Column(
+                            ColumnRef {
+                                index: column_position(&columns, column),
+                                relation: field.relation.clone(),
+                            },
+                            field.data_type,
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                let estimate = self.cost_model.projection(planned.estimate, exprs.len());
+
+                let projection = ProjectionExec {
+                    child: planned.node,
+                    exprs,
+                    schema: schema.clone(),
+                };
+                Ok(self.planned(Box::new(projection), estimate))
             }
         }
     }
-}
 
-#[derive(Debug)]
-pub struct RecordBatch {
-    pub rows: Vec<Row>,
+    /// The ways to read the rows of a scan: reading every page, or reading the entries
+    /// of an index of a column its filters compare with a constant.
+    fn scan_paths(&mut self, scan: &LogicalPlan) -> Result<Vec<ScanPath>> {
+        let LogicalPlan::Scan {
+            table_name,
+            schema,
+            projection,
+            filters,
+        } = scan
+        else {
+            unreachable!("only scans read tables");
+        };
+
+        let mut paths = vec![ScanPath {
+            index: None,
+            estimate: self.cost_model.seq_scan(scan)?,
+        }];
+        for (index_name, table_column) in self.context.database.table_indexes(table_name) {
+            let column = match projection {
+                Some(projection) => projection.iter().position(|read| *read == table_column),
+                None => Some(table_column),
+            };
+            let Some(column) = column else {
+                continue;
+            };
+
+            let mut range = IndexRange {
+                index_name,
+                column,
+                lower: Bound::Unbounded,
+                upper: Bound::Unbounded,
+                conditions: Vec::new(),
+            };
+            for (position, filter) in filters.iter().enumerate() {
+                if let Some((op, value)) =
+                    index_comparison(filter, column, schema.fields[column].data_type)
+                {
+                    range.restrict(op, value);
+                    range.conditions.push(position);
+                }
+            }
+            if range.conditions.is_empty() {
+                continue;
+            }
+
+            let index_conditions = range
+                .conditions
+                .iter()
+                .map(|position| filters[*position].clone())
+                .collect::<Vec<_>>();
+            paths.push(ScanPath {
+                estimate: self.cost_model.index_scan(scan, &index_conditions)?,
+                index: Some(range),
+            });
+        }
+
+        Ok(paths)
+    }
+
+    fn cheapest_scan_path(&mut self, scan: &LogicalPlan) -> Result<ScanPath> {
+        Ok(self
+            .scan_paths(scan)?
+            .into_iter()
+            .min_by(|a, b| a.estimate.cost.total_cmp(&b.estimate.cost))
+            .expect("a sequential scan is always possible"))
+    }
+
+    fn create_scan(&mut self, scan: &LogicalPlan, path: ScanPath) -> Result<PlannedNode> {
+        let LogicalPlan::Scan {
+            table_name,
+            schema,
+            projection,
+            filters,
+        } = scan
+        else {
+            unreachable!("only scans read tables");
+        };
+        let Some(range) = path.index else {
+            let page_count = self
+                .context
+                .database
+                .buffer_manager
+                .page_count(table_name)?;
+            let scan = ScanExec {
+                table_name: Some(table_name.clone()),
+                schema: schema.clone(),
+                projection: projection.clone(),
+                filters: filters.clone(),
+                next_page: 0,
+                page_count,
+                parallel: false,
+            };
+
+            let node: Box<dyn ExecutionNode> = match self.parallel_workers(page_count) {
+                Some(workers) => Box::new(GatherExec::new(
+                    WorkerPlan {
+                        scan,
+                        aggregate: None,
+                    },
+                    workers,
+                )),
+                None => Box::new(scan),
+            };
+            return Ok(self.planned(node, path.estimate));
+        };
+
+        let (index_conditions, filters) = filters
+            .iter()
+            .enumerate()
+            .partition::<Vec<_>, _>(|(position, _)| range.conditions.contains(position));
+        let scan = IndexScanExec {
+            table_name: table_name.clone(),
+            index_name: range.index_name,
+            schema: schema.clone(),
+            projection: projection.clone(),
+            lower: range.lower,
+            upper: range.upper,
+            index_conditions: index_conditions
+                .into_iter()
+                .map(|(_, c)| c.clone())
+                .collect(),
+            filters: filters.into_iter().map(|(_, c)| c.clone()).collect(),
+            row_ids: None,
+            next_row: 0,
+        };
+        Ok(self.planned(Box::new(scan), path.estimate))
+    }
+
+    /// Plans a tree of joins as a whole, joining its inputs in the order and with the
+    /// methods the cost model estimates cheapest. Returns the node with the positions in
+    /// the output of `join` of the columns it returns.
+    fn create_join(&mut self, join: &LogicalPlan) -> Result<(PlannedNode, Vec<usize>)> {
+        let mut inputs = Vec::new();
+        let mut conditions = Vec::new();
+        flatten_join(join, 0, &mut inputs, &mut conditions);
+        if inputs.len() > RelationSet::BITS as usize {
+            return Err(miette!(
+                "Joins of more than {} tables are not supported",
+                RelationSet::BITS
+            ));
+        }
+
+        let mut join_inputs = JoinInputs {
+            plans: Vec::new(),
+            relations: Vec::new(),
+            sources: Vec::new(),
+            conditions: Vec::new(),
+        };
+        for (input, offset) in inputs {
+            let (paths, source) = match input {
+                LogicalPlan::Scan { .. } => {
+                    let paths = self.scan_paths(input)?;
+                    let relation_paths = paths
+                        .iter()
+                        .map(|path| RelationPath {
+                            estimate: path.estimate,
+                            ordering: path.index.as_ref().map(|range| offset + range.column),
+                        })
+                        .collect();
+                    (relation_paths, JoinSource::Scan(paths))
+                }
+                _ => {
+                    let node = self.create_node(input)?;
+                    let path = RelationPath {
+                        estimate: node.estimate,
+                        ordering: None,
+                    };
+                    (vec![path], JoinSource::Planned(Some(node)))
+                }
+            };
+
+            join_inputs.relations.push(JoinRelation {
+                columns: offset..offset + input.schema().fields.len(),
+                paths,
+            });
+            join_inputs.plans.push(input);
+            join_inputs.sources.push(source);
+        }
+
+        let relation_of = |column: usize| {
+            join_inputs
+                .relations
+                .iter()
+                .position(|relation| relation.columns.contains(&column))
+                .expect("join columns belong to a relation")
+        };
+
+        // Conditions on a single relation are pushed into it by the optimizer, any left
+        // are checked on the joined rows
+        let mut join_conditions = Vec::new();
+        let mut remaining = Vec::new();
+        for condition in conditions {
+            let mut columns = Vec::new();
+            referenced_columns(&condition, &mut columns);
+            let relations = columns.iter().fold(0, |set: RelationSet, column| {
+                set | 1 << relation_of(*column)
+            });
+            if relations.count_ones() < 2 {
+                remaining.push(condition);
+                continue;
+            }
+
+            let keys = join_keys(&condition);
+            let selectivity = match keys {
+                Some((left, right)) => {
+                    let rows = |column| {
+                        join_inputs.relations[relation_of(column)].paths[0]
+                            .estimate
+                            .rows
+                    };
+                    self.cost_model.equi_join_selectivity(
+                        join,
+                        (left, rows(left)),
+                        (right, rows(right)),
+                    )?
+                }
+                None => self.cost_model.selectivity(&condition, join)?,
+            };
+            join_conditions.push(JoinCondition {
+                relations,
+                keys,
+                selectivity,
+            });
+            join_inputs.conditions.push(condition);
+        }
+
+        let plan = best_join_plan(&self.cost_model, &join_inputs.relations, &join_conditions);
+        let (mut planned, columns) = self.create_join_node(&plan, &mut join_inputs)?;
+
+        for condition in remaining {
+            let estimate = self.cost_model.filter(planned.estimate, &condition, join)?;
+            let filter = FilterExec {
+                child: planned.node,
+                expr: renumber_columns(condition, &|column| column_position(&columns, column)),
+            };
+            planned = self.planned(Box::new(filter), estimate);
+        }
+
+        Ok((planned, columns))
+    }
+
+    /// Builds the nodes of a join plan, returning them with the positions in the output
+    /// of the join of the columns they return.
+    fn create_join_node(
+        &mut self,
+        plan: &JoinPlan,
+        join: &mut JoinInputs,
+    ) -> Result<(PlannedNode, Vec<usize>)> {
+        let columns = plan.columns(&join.relations);
+        let node: Box<dyn ExecutionNode> = match &plan.node {
+            JoinNode::Relation { relation, path } => {
+                let planned = match &mut join.sources[*relation] {
+                    JoinSource::Scan(paths) => {
+                        let path = paths[*path].clone();
+                        self.create_scan(join.plans[*relation], path)?
+                    }
+                    JoinSource::Planned(node) => node.take().expect("relations are joined once"),
+                };
+                return Ok((planned, columns));
+            }
+            JoinNode::Sort { input, column } => {
+                let (child, input_columns) = self.create_join_node(input, join)?;
+                Box::new(SortExec::new(
+                    child.node,
+                    column_position(&input_columns, *column),
+                ))
+            }
+            JoinNode::Join {
+                method,
+                outer,
+                inner,
+                keys,
+                conditions,
+            } => {
+                let (outer, outer_columns) = self.create_join_node(outer, join)?;
+                let (inner, inner_columns) = self.create_join_node(inner, join)?;
+
+                let mut schema = outer.node.schema().clone();
+                schema
+                    .fields
+                    .extend(inner.node.schema().fields.iter().cloned());
+                let conditions = conditions
+                    .iter()
+                    .map(|condition| {
+                        renumber_columns(join.conditions[*condition].clone(), &|column| {
+                            column_position(&columns, column)
+                        })
+                    })
+                    .collect();
+                let keys = keys
+                    .iter()
+                    .map(|(outer_key, inner_key)| {
+                        (
+                            column_position(&outer_columns, *outer_key),
+                            column_position(&inner_columns, *inner_key),
+                        )
+                    })
+                    .collect::<Vec<_>>();
+
+                match method {
+                    JoinMethod::NestedLoop => Box::new(NestedLoopJoinExec::new(
+                        outer.node, inner.node, conditions, schema,
+                    )),
+                    JoinMethod::Hash => Box::new(HashJoinExec::new(
+                        outer.node, inner.node, keys, conditions, schema,
+                    )),
+                    JoinMethod::Merge => Box::new(MergeJoinExec::new(
+                        outer.node, inner.node, keys[0], conditions, schema,
+                    )),
+                }
+            }
+        };
+
+        Ok((self.planned(node, plan.estimate), columns))
+    }
+
+    /// The number of workers to scan a table of `page_count` pages with, `None` if the
+    /// scan is better run by a single thread.
+    fn parallel_workers(&self, page_count: PageId) -> Option<usize> {
+        let database = &self.context.database;
+        let workers = (page_count / database.parallel_scan_pages.max(1)) as usize;
+        let workers = workers.min(database.max_parallel_workers());
+        (workers > 1).then_some(workers)
+    }
+
+    /// Plans an aggregate whose workers each aggregate their part of the table, the
+    /// states they return are then merged into the final values.
+    fn create_parallel_aggregate(
+        &mut self,
+        input: &LogicalPlan,
+        group_by: &[AnalyzedExpression],
+        aggregates: &[AggregateExpr],
+        schema: &OutputSchema,
+        estimate: PlanEstimate,
+        workers: usize,
+    ) -> Result<PlannedNode> {
+        let LogicalPlan::Scan {
+            table_name,
+            schema: input_schema,
+            projection,
+            filters,
+        } = input
+        else {
+            unreachable!("parallel aggregates read a scan");
+        };
+
+        // Group keys followed by the state of every aggregate
+        let mut partial_schema = OutputSchema {
+            fields: schema.fields[..group_by.len()].to_vec(),
+        };
+        for aggregate in aggregates {
+            let width = aggregate.function.accumulator().state().len();
+            partial_schema.fields.extend((0..width).map(|index| Field {
+                name: format!("{}_state{index}", aggregate.function.name),
+                alias: None,
+                relation: None,
+                data_type: aggregate.return_type,
+                is_nullable: true,
+            }));
+        }
+
+        let page_count = self
+            .context
+            .database
+            .buffer_manager
+            .page_count(table_name)?;
+        let worker_plan = WorkerPlan {
+            scan: ScanExec {
+                table_name: Some(table_name.clone()),
+                schema: input_schema.clone(),
+                projection: projection.clone(),
+                filters: filters.clone(),
+                next_page: 0,
+                page_count,
+                parallel: true,
+            },
+            aggregate: Some(PartialAggregate {
+                group_by: group_by.to_vec(),
+                aggregates: aggregates.to_vec(),
+                schema: partial_schema,
+            }),
+        };
+
+        let aggregate = AggregateExec {
+            child: Box::new(GatherExec::new(worker_plan, workers)),
+            mode: AggregateMode::Final,
+            group_by: group_by.to_vec(),
+            aggregates: aggregates.to_vec(),
+            input_schema: input_schema.clone(),
+            schema: schema.clone(),
+            estimated_groups: (estimate.rows as usize).min(MAX_PREALLOCATED_GROUPS),
+            done: false,
+        };
+        Ok(self.planned(Box::new(aggregate), estimate))
+    }
 }
 
 pub trait ExecutionNode: std::fmt::Debug {
     fn schema(&self) -> &OutputSchema;
 
-    fn next(&mut self) -> Result<Option<RecordBatch>>;
+    /// Returns the next batch of rows, `None` once the node is exhausted.
+    ///
+    /// Nodes read pages through `context` only when asked for rows, so a plan can be
+    /// consumed a batch at a time. The context is shared, workers of a
+    /// [`GatherExec`] read through it at the same time.
+    fn next(&mut self, context: &CatalogContext) -> Result<Option<RecordBatch>>;
+
+    /// The operator name shown by EXPLAIN.
+    fn name(&self) -> String;
+
+    /// Lines shown below the operator by EXPLAIN, such as its filter.
+    fn details(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// The nodes this node reads its rows from.
+    fn children(&self) -> Vec<&dyn ExecutionNode>;
+
+    /// The estimate and measurements of the node, if it is profiled.
+    fn profile(&self) -> Option<&NodeProfile> {
+        None
+    }
+}
+
+/// What EXPLAIN reports about a node besides its operator.
+#[derive(Debug, Clone)]
+pub struct NodeProfile {
+    /// The estimate of the cost model.
+    pub estimate: PlanEstimate,
+
+    /// Rows returned so far.
+    pub rows: usize,
+
+    /// Batches returned so far.
+    pub batches: usize,
+
+    /// Time spent in `next`, including the time spent in the inputs.
+    pub elapsed: Duration,
+}
+
+/// Wraps a node to count the rows and batches it returns and time its `next` calls.
+#[derive(Debug)]
+pub struct ProfiledExec {
+    inner: Box<dyn ExecutionNode>,
+    profile: NodeProfile,
+}
+impl ExecutionNode for ProfiledExec {
+    fn schema(&self) -> &OutputSchema {
+        self.inner.schema()
+    }
+
+    fn next(&mut self, context: &CatalogContext) -> Result<Option<RecordBatch>> {
+        let start = Instant::now();
+        let batch = self.inner.next(context);
+        self.profile.elapsed += start.elapsed();
+
+        if let Ok(Some(batch)) = &batch {
+            self.profile.rows += batch.num_rows();
+            self.profile.batches += 1;
+        }
+        batch
+    }
+
+    fn name(&self) -> String {
+        self.inner.name()
+    }
+
+    fn details(&self) -> Vec<String> {
+        self.inner.details()
+    }
+
+    fn children(&self) -> Vec<&dyn ExecutionNode> {
+        self.inner.children()
+    }
+
+    fn profile(&self) -> Option<&NodeProfile> {
+        Some(&self.profile)
+    }
+}
+
+/// Renders the given expressions separated by `separator`.
+pub(crate) fn display_list(
+    expressions: &[AnalyzedExpression],
+    input_schema: &OutputSchema,
+    separator: &str,
+) -> String {
+    expressions
+        .iter()
+        .map(|expr| expr.display(input_schema).to_string())
+        .collect::<Vec<_>>()
+        .join(separator)
 }
 
 #[derive(Debug)]
 pub struct ScanExec {
+    /// The table read, `None` for a plan known to produce no rows.
+    table_name: Option<String>,
     schema: OutputSchema,
-    data: Vec<Row>,
+    projection: Option<Vec<usize>>,
+    filters: Vec<AnalyzedExpression>,
+
+    /// The next page to read, pages from `page_count` on were added after planning.
+    next_page: PageId,
+    page_count: PageId,
+
+    /// Whether this scan reads a part of the table for a [`GatherExec`].
+    parallel: bool,
 }
 impl ExecutionNode for ScanExec {
     fn schema(&self) -> &OutputSchema {
         &self.schema
     }
 
-    fn next(&mut self) -> Result<Option<RecordBatch>> {
-        let batch_size = 1024;
+    fn name(&self) -> String {
+        match &self.table_name {
+            Some(table_name) if self.parallel => format!("Parallel Seq Scan on {table_name}"),
+            Some(table_name) => format!("Seq Scan on {table_name}"),
+            None => "Empty Result".to_string(),
+        }
+    }
 
-        if self.data.is_empty() {
+    fn details(&self) -> Vec<String> {
+        if self.filters.is_empty() {
+            return Vec::new();
+        }
+        vec![format!(
+            "Filter: {}",
+            display_list(&self.filters, &self.schema, " AND ")
+        )]
+    }
+
+    fn children(&self) -> Vec<&dyn ExecutionNode> {
+        Vec::new()
+    }
+
+    fn next(&mut self, context: &CatalogContext) -> Result<Option<RecordBatch>> {
+        let Some(table_name) = &self.table_name else {
             return Ok(None);
+        };
+        let evaluator = VectorizedEvaluator;
+
+        while self.next_page < self.page_count {
+            let mut rows = Vec::new();
+            while rows.len() < BATCH_SIZE && self.next_page < self.page_count {
+                rows.extend(context.database.page_rows(
+                    table_name,
+                    self.next_page,
+                    self.projection.as_deref(),
+                )?);
+                self.next_page += 1;
+            }
+
+            let mut batch = RecordBatch::from_rows(rows, self.schema.fields.len());
+            for filter in &self.filters {
+                batch = batch.take(&evaluator.select(filter, &batch)?);
+            }
+
+            if !batch.is_empty() {
+                return Ok(Some(batch));
+            }
         }
 
-        let end = batch_size.min(self.data.len());
-        let chunk = self.data.drain(..end).collect();
+        Ok(None)
+    }
+}
+
+/// Reads the rows of a table whose values of a column fall in a range, found in an
+/// index of the column. Rows are returned in the order of the index.
+#[derive(Debug)]
+pub struct IndexScanExec {
+    table_name: String,
+    index_name: String,
+    schema: OutputSchema,
+    projection: Option<Vec<usize>>,
+    lower: Bound<Value>,
+    upper: Bound<Value>,
+
+    /// The filters the range stands for, only shown by EXPLAIN.
+    index_conditions: Vec<AnalyzedExpression>,
+
+    /// The filters applied to the rows read.
+    filters: Vec<AnalyzedExpression>,
+
+    /// The rows in the range, looked up on the first call, and the next one to read.
+    row_ids: Option<Vec<RowId>>,
+    next_row: usize,
+}
+impl ExecutionNode for IndexScanExec {
+    fn schema(&self) -> &OutputSchema {
+        &self.schema
+    }
 
-        Ok(Some(RecordBatch { rows: chunk }))
+    fn name(&self) -> String {
+        format!(
+            "Index Scan using {} on {}",
+            self.index_name, self.table_name
+        )
+    }
+
+    fn details(&self) -> Vec<String> {
+        let mut details = vec![format!(
+            "Index Cond: {}",
+            display_list(&self.index_conditions, &self.schema, " AND ")
+        )];
+        if !self.filters.is_empty() {
+            details.push(format!(
+                "Filter: {}",
+                display_list(&self.filters, &self.schema, " AND ")
+            ));
+        }
+        details
+    }
+
+    fn children(&self) -> Vec<&dyn ExecutionNode> {
+        Vec::new()
+    }
+
+    fn next(&mut self, context: &CatalogContext) -> Result<Option<RecordBatch>> {
+        if self.row_ids.is_none() {
+            self.row_ids = Some(context.database.index_rows(
+                &self.index_name,
+                self.lower.as_ref(),
+                self.upper.as_ref(),
+            )?);
+        }
+        let row_ids = self.row_ids.as_deref().unwrap_or_default();
+        let evaluator = VectorizedEvaluator;
+
+        while self.next_row < row_ids.len() {
+            let end = row_ids.len().min(self.next_row + BATCH_SIZE);
+            let rows = context.database.fetch_rows(
+                &self.table_name,
+                &row_ids[self.next_row..end],
+                self.projection.as_deref(),
+            )?;
+            self.next_row = end;
+
+            let mut batch = RecordBatch::from_rows(rows, self.schema.fields.len());
+            for filter in &self.filters {
+                batch = batch.take(&evaluator.select(filter, &batch)?);
+            }
+
+            if !batch.is_empty() {
+                return Ok(Some(batch));
+            }
+        }
+
+        Ok(None)
     }
 }
+
 #[derive(Debug)]
 pub struct ProjectionExec {
     child: Box<dyn ExecutionNode>,
@@ -100,32 +1097,34 @@ impl ExecutionNode for ProjectionExec {
         &self.schema
     }
 
-    fn next(&mut self) -> Result<Option<RecordBatch>> {
-        let Some(batch) = self.child.next()? else {
-            return Ok(None);
-        };
+    fn name(&self) -> String {
+        "Projection".to_string()
+    }
 
-        let evaluator = ExpressionEvaluator;
+    fn details(&self) -> Vec<String> {
+        vec![format!(
+            "Expressions: {}",
+            display_list(&self.exprs, self.child.schema(), ", ")
+        )]
+    }
 
-        let projected_rows: Result<Vec<Row>> = batch
-            .rows
-            .into_iter()
-            .map(|row| {
-                let new_values: Result<Vec<Value>> = self
-                    .exprs
-                    .iter()
-                    .map(|expr| evaluator.evaluate(expr, &row))
-                    .collect();
+    fn children(&self) -> Vec<&dyn ExecutionNode> {
+        vec![self.child.as_ref()]
+    }
 
-                Ok(Row {
-                    values: new_values?,
-                })
-            })
-            .collect();
+    fn next(&mut self, context: &CatalogContext) -> Result<Option<RecordBatch>> {
+        let Some(batch) = self.child.next(context)? else {
+            return Ok(None);
+        };
+
+        let evaluator = VectorizedEvaluator;
+        let columns = self
+            .exprs
+            .iter()
+            .map(|expr| evaluator.evaluate(expr, &batch))
+            .collect::<Result<Vec<_>>>()?;
 
-        Ok(Some(RecordBatch {
-            rows: projected_rows?,
-        }))
+        Ok(Some(RecordBatch::new(columns, batch.num_rows())))
     }
 }
 #[derive(Debug)]
@@ -138,27 +1137,491 @@ impl ExecutionNode for FilterExec {
         self.child.schema()
     }
 
-    fn next(&mut self) -> Result<Option<RecordBatch>> {
-        let evaluator = PredicateEvaluator;
+    fn name(&self) -> String {
+        "Filter".to_string()
+    }
 
-        while let Some(batch) = self.child.next()? {
-            let mut filtered_rows = Vec::with_capacity(batch.rows.len());
+    fn details(&self) -> Vec<String> {
+        vec![format!(
+            "Filter: {}",
+            self.expr.display(self.child.schema())
+        )]
+    }
 
-            for row in batch.rows {
-                if evaluator.evaluate(&self.expr, &row)? {
-                    filtered_rows.push(row);
-                }
-            }
+    fn children(&self) -> Vec<&dyn ExecutionNode> {
+        vec![self.child.as_ref()]
+    }
+
+    fn next(&mut self, context: &CatalogContext) -> Result<Option<RecordBatch>> {
+        let evaluator = VectorizedEvaluator;
+
+        while let Some(batch) = self.child.next(context)? {
+            let selection = evaluator.select(&self.expr, &batch)?;
 
             // If this batch had 0 rows after filtering
             // we loop again to grab the next batch
-            if !filtered_rows.is_empty() {
-                return Ok(Some(RecordBatch {
-                    rows: filtered_rows,
-                }));
+            if selection.len() == batch.num_rows() {
+                return Ok(Some(batch));
+            }
+            if !selection.is_empty() {
+                return Ok(Some(batch.take(&selection)));
             }
         }
 
         Ok(None)
     }
 }
+
+/// Hashable wrapper for the group by values of a row.
+///
+/// Floats are compared by their bit pattern so every value has a well defined hash.
+#[derive(Debug, Clone)]
+pub(crate) struct GroupKey(pub(crate) Vec<Value>);
+
+impl PartialEq for GroupKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len()
+            && self.0.iter().zip(&other.0).all(|(a, b)| match (a, b) {
+                (Value::Float64(a), Value::Float64(b)) => a.to_bits() == b.to_bits(),
+                (a, b) => a == b,
+            })
+    }
+}
+
+impl Eq for GroupKey {}
+
+impl Hash for GroupKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for value in &self.0 {
+            std::mem::discriminant(value).hash(state);
+            match value {
+                Value::Int16(n) => n.hash(state),
+                Value::Int32(n) => n.hash(state),
+                Value::Int64(n) => n.hash(state),
+                Value::Bytea(bytes) => bytes.hash(state),
+                Value::Uuid(uuid) => uuid.hash(state),
+                Value::Float64(n) => n.to_bits().hash(state),
+                Value::Text(s) => s.hash(state),
+                Value::Bool(b) => b.hash(state),
+                Value::Decimal(d) => d.hash(state),
+                Value::Timestamp(ts) => ts.hash(state),
+                Value::Date(days) => days.hash(state),
+                Value::Interval(interval) => interval.hash(state),
+                Value::Json(json) => json.to_string().hash(state),
+                Value::Null => {}
+            }
+        }
+    }
+}
+
+/// The groups of an aggregate with their accumulators, in order of first appearance.
+struct GroupTable {
+    indexes: HashMap<GroupKey, usize>,
+    groups: Vec<(GroupKey, Vec<Box<dyn Accumulator>>)>,
+}
+
+impl GroupTable {
+    fn with_capacity(capacity: usize) -> Self {
+        Self {
+            indexes: HashMap::with_capacity(capacity),
+            groups: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// The accumulators of a group, created for groups seen for the first time.
+    fn accumulators(
+        &mut self,
+        key: GroupKey,
+        aggregates: &[AggregateExpr],
+    ) -> &mut [Box<dyn Accumulator>] {
+        let index = match self.indexes.get(&key) {
+            Some(index) => *index,
+            None => {
+                self.groups
+                    .push((key.clone(), new_accumulators(aggregates)));
+                self.indexes.insert(key, self.groups.len() - 1);
+                self.groups.len() - 1
+            }
+        };
+        &mut self.groups[index].1
+    }
+
+    /// Folds the rows of an input batch into their groups.
+    fn update(
+        &mut self,
+        batch: &RecordBatch,
+        group_by: &[AnalyzedExpression],
+        aggregates: &[AggregateExpr],
+    ) -> Result<()> {
+        let evaluator = VectorizedEvaluator;
+        let group_columns = group_by
+            .iter()
+            .map(|expr| evaluator.evaluate(expr, batch))
+            .collect::<Result<Vec<_>>>()?;
+        let arg_columns = aggregates
+            .iter()
+            .map(|aggregate| {
+                aggregate
+                    .args
+                    .iter()
+                    .map(|expr| evaluator.evaluate(expr, batch))
+                    .collect::<Result<Vec<_>>>()
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        for row in 0..batch.num_rows() {
+            let key = GroupKey(
+                group_columns
+                    .iter()
+                    .map(|column| column.value(row))
+                    .collect(),
+            );
+
+            let accumulators = self.accumulators(key, aggregates);
+            for (args, accumulator) in arg_columns.iter().zip(accumulators) {
+                let args = args
+                    .iter()
+                    .map(|column| column.value(row))
+                    .collect::<Vec<_>>();
+                accumulator.update(&args)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Merges a batch of partial states, each row holding the `key_count` group keys
+    /// followed by the state of every aggregate.
+    fn merge(
+        &mut self,
+        batch: &RecordBatch,
+        key_count: usize,
+        aggregates: &[AggregateExpr],
+    ) -> Result<()> {
+        let widths = new_accumulators(aggregates)
+            .iter()
+            .map(|accumulator| accumulator.state().len())
+            .collect::<Vec<_>>();
+
+        for row in 0..batch.num_rows() {
+            let key = GroupKey(
+                batch.columns()[..key_count]
+                    .iter()
+                    .map(|column| column.value(row))
+                    .collect(),
+            );
+
+            let mut offset = key_count;
+            let accumulators = self.accumulators(key, aggregates);
+            for (accumulator, width) in accumulators.iter_mut().zip(&widths) {
+                let state = batch.columns()[offset..offset + width]
+                    .iter()
+                    .map(|column| column.value(row))
+                    .collect::<Vec<_>>();
+                accumulator.merge(&state)?;
+                offset += width;
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns a row per group, holding either the final values or the states.
+    fn into_rows(self, states: bool) -> Result<Vec<Row>> {
+        self.groups
+            .into_iter()
+            .map(|(key, accumulators)| {
+                let mut values = key.0;
+                for accumulator in accumulators {
+                    match states {
+                        true => values.extend(accumulator.state()),
+                        false => values.push(accumulator.finalize()?),
+                    }
+                }
+                Ok(Row::new(values))
+            })
+            .collect()
+    }
+}
+
+fn new_accumulators(aggregates: &[AggregateExpr]) -> Vec<Box<dyn Accumulator>> {
+    aggregates
+        .iter()
+        .map(|aggregate| aggregate.function.accumulator())
+        .collect()
+}
+
+/// What an [`AggregateExec`] reads and returns.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum AggregateMode {
+    /// Aggregates input rows into the final values.
+    Single,
+
+    /// Aggregates input rows into accumulator states, run by the workers of a
+    /// [`GatherExec`].
+    Partial,
+
+    /// Merges the states returned by partial aggregates into the final values.
+    Final,
+}
+
+/// Hash aggregation, consumes the whole input before producing a single batch.
+pub struct AggregateExec {
+    child: Box<dyn ExecutionNode>,
+    mode: AggregateMode,
+    group_by: Vec<AnalyzedExpression>,
+    aggregates: Vec<AggregateExpr>,
+
+    /// The rows `group_by` and the arguments refer to, the input of a final aggregate
+    /// holds partial states instead.
+    input_schema: OutputSchema,
+    schema: OutputSchema,
+    estimated_groups: usize,
+    done: bool,
+}
+
+impl std::fmt::Debug for AggregateExec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AggregateExec")
+            .field("child", &self.child)
+            .field("mode", &self.mode)
+            .field("group_by", &self.group_by)
+            .field("aggregates", &self.aggregates)
+            .field("schema", &self.schema)
+            .finish()
+    }
+}
+
+impl ExecutionNode for AggregateExec {
+    fn schema(&self) -> &OutputSchema {
+        &self.schema
+    }
+
+    fn name(&self) -> String {
+        match self.mode {
+            AggregateMode::Single => "Hash Aggregate".to_string(),
+            AggregateMode::Partial => "Partial Hash Aggregate".to_string(),
+            AggregateMode::Final => "Finalize Hash Aggregate".to_string(),
+        }
+    }
+
+    fn details(&self) -> Vec<String> {
+        let input_schema = &self.input_schema;
+        let aggregates = self
+            .aggregates
+            .iter()
+            .map(|aggregate| {
+                // Aggregates without arguments are written like count(*)
+                let args = match aggregate.args.as_slice() {
+                    [] => "*".to_string(),
+                    args => display_list(args, input_schema, ", "),
+                };
+                format!("{}({args})", aggregate.function.name)
+            })
+            .collect::<Vec<_>>();
+
+        let mut details = Vec::new();
+        if !self.group_by.is_empty() {
+            details.push(format!(
+                "Group Key: {}",
+                display_list(&self.group_by, input_schema, ", ")
+            ));
+        }
+        details.push(format!("Aggregates: {}", aggregates.join(", ")));
+        details
+    }
+
+    fn children(&self) -> Vec<&dyn ExecutionNode> {
+        vec![self.child.as_ref()]
+    }
+
+    fn next(&mut self, context: &CatalogContext) -> Result<Option<RecordBatch>> {
+        if self.done {
+            return Ok(None);
+        }
+        self.done = true;
+
+        let mut groups = GroupTable::with_capacity(self.estimated_groups);
+        while let Some(batch) = self.child.next(context)? {
+            match self.mode {
+                AggregateMode::Final => {
+                    groups.merge(&batch, self.group_by.len(), &self.aggregates)?
+                }
+                _ => groups.update(&batch, &self.group_by, &self.aggregates)?,
+            }
+        }
+
+        // Without GROUP BY an aggregate always produces exactly one row, partial
+        // aggregates leave that to the final one
+        if groups.groups.is_empty()
+            && self.group_by.is_empty()
+            && self.mode != AggregateMode::Partial
+        {
+            groups.accumulators(GroupKey(Vec::new()), &self.aggregates);
+        }
+
+        let rows = groups.into_rows(self.mode == AggregateMode::Partial)?;
+        if rows.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(RecordBatch::from_rows(rows, self.schema.fields.len())))
+    }
+}
+
+/// The aggregate run by the workers of a parallel aggregate over their rows.
+#[derive(Debug, Clone)]
+struct PartialAggregate {
+    group_by: Vec<AnalyzedExpression>,
+    aggregates: Vec<AggregateExpr>,
+
+    /// The group keys followed by the states of the aggregates.
+    schema: OutputSchema,
+}
+
+/// The nodes each worker of a [`GatherExec`] runs over its range of pages.
+#[derive(Debug)]
+struct WorkerPlan {
+    scan: ScanExec,
+    aggregate: Option<PartialAggregate>,
+}
+
+impl WorkerPlan {
+    /// Builds the nodes reading the given pages of the table.
+    fn build(&self, pages: Range<PageId>) -> Box<dyn ExecutionNode> {
+        let scan = Box::new(ScanExec {
+            table_name: self.scan.table_name.clone(),
+            schema: self.scan.schema.clone(),
+            projection: self.scan.projection.clone(),
+            filters: self.scan.filters.clone(),
+            next_page: pages.start,
+            page_count: pages.end,
+            parallel: true,
+        });
+
+        match &self.aggregate {
+            Some(aggregate) => Box::new(AggregateExec {
+                child: scan,
+                mode: AggregateMode::Partial,
+                group_by: aggregate.group_by.clone(),
+                aggregates: aggregate.aggregates.clone(),
+                input_schema: self.scan.schema.clone(),
+                schema: aggregate.schema.clone(),
+                estimated_groups: 0,
+                done: false,
+            }),
+            None => scan,
+        }
+    }
+}
+
+/// Exchange node running a plan over a table split into page ranges, one per worker
+/// thread, and returning the batches of the workers in page order.
+///
+/// Scans run in rounds of [`PAGES_PER_WORKER_ROUND`] pages per worker, so rows are
+/// returned while the table is read. Partial aggregates read their whole range at
+/// once, each worker then returns the states of its groups.
+#[derive(Debug)]
+pub struct GatherExec {
+    worker_plan: WorkerPlan,
+    workers: usize,
+
+    /// The plan of a worker, only shown by EXPLAIN.
+    worker_node: Box<dyn ExecutionNode>,
+
+    /// Batches of the last round not returned yet.
+    pending: VecDeque<RecordBatch>,
+    next_page: PageId,
+    page_count: PageId,
+}
+
+impl GatherExec {
+    fn new(mut worker_plan: WorkerPlan, workers: usize) -> Self {
+        worker_plan.scan.parallel = true;
+        let page_count = worker_plan.scan.page_count;
+
+        Self {
+            worker_node: worker_plan.build(0..0),
+            worker_plan,
+            workers,
+            pending: VecDeque::new(),
+            next_page: 0,
+            page_count,
+        }
+    }
+
+    /// Runs the workers over the next pages, splitting them in contiguous ranges.
+    fn run_round(&mut self, context: &CatalogContext) -> Result<()> {
+        let round_pages = match self.worker_plan.aggregate {
+            Some(_) => self.page_count - self.next_page,
+            None => PAGES_PER_WORKER_ROUND * self.workers as PageId,
+        };
+        let end = self.page_count.min(self.next_page + round_pages);
+        let worker_pages = (end - self.next_page).div_ceil(self.workers as PageId);
+
+        let ranges = (self.next_page..end)
+            .step_by(worker_pages.max(1) as usize)
+            .map(|start| start..end.min(start + worker_pages))
+            .collect::<Vec<_>>();
+        self.next_page = end;
+
+        let worker_plan = &self.worker_plan;
+        let results = std::thread::scope(|scope| {
+            let handles = ranges
+                .into_iter()
+                .map(|pages| {
+                    scope.spawn(move || {
+                        let mut node = worker_plan.build(pages);
+                        let mut batches = Vec::new();
+                        while let Some(batch) = node.next(context)? {
+                            batches.push(batch);
+                        }
+                        Ok(batches)
+                    })
+                })
+                .collect::<Vec<_>>();
+
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                })
+                .collect::<Vec<Result<Vec<RecordBatch>>>>()
+        });
+
+        for batches in results {
+            self.pending.extend(batches?);
+        }
+        Ok(())
+    }
+}
+
+impl ExecutionNode for GatherExec {
+    fn schema(&self) -> &OutputSchema {
+        self.worker_node.schema()
+    }
+
+    fn name(&self) -> String {
+        "Gather".to_string()
+    }
+
+    fn details(&self) -> Vec<String> {
+        vec![format!("Workers: {}", self.workers)]
+    }
+
+    fn children(&self) -> Vec<&dyn ExecutionNode> {
+        vec![self.worker_node.as_ref()]
+    }
+
+    fn next(&mut self, context: &CatalogContext) -> Result<Option<RecordBatch>> {
+        loop {
+            if let Some(batch) = self.pending.pop_front() {
+                return Ok(Some(batch));
+            }
+            if self.next_page >= self.page_count {
+                return Ok(None);
+            }
+            self.run_round(context)?;
+        }
+    }
+}
@@ -1,15 +1,20 @@
 use std::{
     collections::HashMap,
-    fs::File,
+    fs::{File, OpenOptions},
     io::{Read, Seek, SeekFrom, Write},
-    path::PathBuf,
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError, RwLock},
 };
 
-use miette::{IntoDiagnostic, Result, miette};
+use miette::{IntoDiagnostic, Result};
 
 use crate::{
+    DatabaseError,
     core::serialization::Serializable,
-    storage::page::{Page, PageId, PageType},
+    storage::{
+        free_space_map::{FreeSpaceMap, remove_file_if_exists},
+        page::{Page, PageId, PageType},
+    },
 };
 
 #[derive(Debug)]
@@ -20,46 +25,167 @@ pub struct TableFile {
     pub page_count: u32,
 }
 
-#[derive(Debug, Default)]
+/// Cached pages by relation and page id, every frame behind its own latch.
+type PageTable = HashMap<String, HashMap<PageId, RwLock<Page>>>;
+
+/// Caches pages of every relation in memory, shared by all sessions.
+///
+/// The page table is behind a lock only held while a page is looked up or cached.
+/// Every frame has its own read/write latch, held while a page is read through
+/// [`BufferPool::read_page`] or changed through [`BufferPool::write_page`], so
+/// different pages are used by several threads at once.
+#[derive(Debug)]
 pub struct BufferPool {
-    pool: HashMap<String, HashMap<PageId, Page>>,
+    pool: RwLock<PageTable>,
+
+    /// Directory holding one `<table>.table` file per relation.
+    data_directory: PathBuf,
+
+    /// Space left on every page, kept in a `<table>.fsm` file per relation.
+    free_space_map: Mutex<FreeSpaceMap>,
 }
 
 impl BufferPool {
-    pub fn new() -> Self {
+    pub fn new<P: AsRef<Path>>(data_directory: P) -> Self {
         Self {
-            pool: HashMap::new(),
+            pool: RwLock::new(HashMap::new()),
+            data_directory: data_directory.as_ref().to_path_buf(),
+            free_space_map: Mutex::new(FreeSpaceMap::new(&data_directory)),
         }
     }
 
-    pub fn get_page(&mut self, table_name: &str, page_id: PageId) -> Result<&mut Page> {
-        // Check if page exists in cache first
-        let page_exists = self
-            .pool
+    fn table_path(&self, table_name: &str) -> PathBuf {
+        self.data_directory.join(format!("{table_name}.table"))
+    }
+
+    /// Number of pages of a relation written to its file.
+    ///
+    /// A file ending in part of a page is corrupt, the last page was torn while written.
+    fn pages_on_disk(&self, table_name: &str) -> Result<PageId> {
+        let Ok(metadata) = std::fs::metadata(self.table_path(table_name)) else {
+            return Ok(0);
+        };
+
+        let pages = (metadata.len() / Page::SIZE as u64) as PageId;
+        if metadata.len() % Page::SIZE as u64 != 0 {
+            return Err(DatabaseError::Corruption {
+                table: table_name.to_string(),
+                page_id: pages,
+            }
+            .into());
+        }
+        Ok(pages)
+    }
+
+    /// Number of pages of a relation, counting pages that are only cached so far.
+    pub(crate) fn page_count(&self, table_name: &str) -> Result<PageId> {
+        let pool = self.pool.read().unwrap_or_else(PoisonError::into_inner);
+        let cached = pool
             .get(table_name)
-            .is_some_and(|pages| pages.contains_key(&page_id));
+            .and_then(|pages| pages.keys().max())
+            .map_or(0, |max_page_id| max_page_id + 1);
 
-        if !page_exists {
-            let page = self.load_page_from_file(table_name, page_id)?;
+        Ok(self.pages_on_disk(table_name)?.max(cached))
+    }
+
+    /// Removes a relation: its cached pages, its file and its free space map.
+    ///
+    /// Callers make sure no other thread uses the relation.
+    pub(crate) fn drop_relation(&self, table_name: &str) -> Result<()> {
+        self.pool
+            .write()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(table_name);
+        remove_file_if_exists(&self.table_path(table_name))?;
+
+        self.free_space_map
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .remove(table_name)
+    }
+
+    /// Appends a new empty page to a relation, returning its id.
+    pub(crate) fn allocate_page(&self, table_name: &str, page_type: PageType) -> Result<PageId> {
+        // Counted while holding the lock, so concurrent calls get different pages
+        let mut pool = self.pool.write().unwrap_or_else(PoisonError::into_inner);
+        let pages = pool.entry(table_name.to_string()).or_default();
+        let cached = pages.keys().max().map_or(0, |max_page_id| max_page_id + 1);
+        let page_id = self.pages_on_disk(table_name)?.max(cached);
+
+        pages.insert(page_id, RwLock::new(Page::new(page_id, page_type)));
+        Ok(page_id)
+    }
+
+    /// Returns a page for changing it in place, loading it into the cache if needed.
+    ///
+    /// Exclusive access to the pool rules out other threads, so no latch is taken.
+    pub fn get_page(&mut self, table_name: &str, page_id: PageId) -> Result<&mut Page> {
+        self.with_frame(table_name, page_id, |_| ())?;
+
+        let pool = self.pool.get_mut().unwrap_or_else(PoisonError::into_inner);
+        let frame = pool.get_mut(table_name).unwrap().get_mut(&page_id).unwrap();
+        Ok(frame.get_mut().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Calls `read` with a page, loading it into the cache first if needed.
+    ///
+    /// The page stays latched while `read` runs, which must not use the buffer pool.
+    pub(crate) fn read_page<R>(
+        &self,
+        table_name: &str,
+        page_id: PageId,
+        read: impl FnOnce(&Page) -> R,
+    ) -> Result<R> {
+        self.with_frame(table_name, page_id, |frame| {
+            read(&frame.read().unwrap_or_else(PoisonError::into_inner))
+        })
+    }
+
+    /// Calls `write` with a page to change it, loading it into the cache first if
+    /// needed. The change is only in memory until [`BufferPool::save_page`].
+    ///
+    /// The page stays latched while `write` runs, which must not use the buffer pool.
+    pub(crate) fn write_page<R>(
+        &self,
+        table_name: &str,
+        page_id: PageId,
+        write: impl FnOnce(&mut Page) -> R,
+    ) -> Result<R> {
+        self.with_frame(table_name, page_id, |frame| {
+            write(&mut frame.write().unwrap_or_else(PoisonError::into_inner))
+        })
+    }
+
+    /// Calls `f` with the frame of a page, caching the page first if needed.
+    fn with_frame<R>(
+        &self,
+        table_name: &str,
+        page_id: PageId,
+        f: impl FnOnce(&RwLock<Page>) -> R,
+    ) -> Result<R> {
+        let cached = |pool: &PageTable| {
+            pool.get(table_name)
+                .is_some_and(|pages| pages.contains_key(&page_id))
+        };
 
-            // Insert into cache
+        if !cached(&self.pool.read().unwrap_or_else(PoisonError::into_inner)) {
+            // Loaded without holding the lock, another thread may cache it meanwhile
+            let page = self.load_page_from_file(table_name, page_id)?;
             self.pool
+                .write()
+                .unwrap_or_else(PoisonError::into_inner)
                 .entry(table_name.to_string())
                 .or_default()
-                .insert(page_id, page);
+                .entry(page_id)
+                .or_insert(RwLock::new(page));
         }
 
-        // Return mutable reference to the cached page
-        Ok(self
-            .pool
-            .get_mut(table_name)
-            .unwrap()
-            .get_mut(&page_id)
-            .unwrap())
+        let pool = self.pool.read().unwrap_or_else(PoisonError::into_inner);
+        Ok(f(&pool[table_name][&page_id]))
     }
 
     fn load_page_from_file(&self, table_name: &str, page_id: PageId) -> Result<Page> {
-        let mut file = File::open(format!("./db/{table_name}.table")).into_diagnostic()?;
+        let mut file = File::open(self.table_path(table_name)).into_diagnostic()?;
 
         let mut buffer: [u8; Page::SIZE] = [0; Page::SIZE];
         let offset = (page_id as usize) * Page::SIZE;
@@ -67,84 +193,82 @@ impl BufferPool {
             .into_diagnostic()?;
         file.read_exact(&mut buffer).into_diagnostic()?;
 
-        Ok(Page::from_bytes(buffer))
+        if buffer.iter().all(|byte| *byte == 0) {
+            return Ok(Page::new(page_id, PageType::Table));
+        }
+
+        // Checked before parsing, a torn page may not even have a valid header
+        let page = Page::verify_checksum(&buffer)
+            .then(|| Page::from_bytes(buffer))
+            .filter(|page| page.header.page_id == page_id)
+            .ok_or_else(|| DatabaseError::Corruption {
+                table: table_name.to_string(),
+                page_id,
+            })?;
+
+        Ok(page)
     }
 
-    pub(crate) fn get_free_page(&mut self, table_name: &str, size: usize) -> Result<&mut Page> {
-        let max_pages = 1000;
-
-        // Find a page ID that has space or does not exist
-        let mut target_page_id = None;
-
-        for page_id in 0..max_pages {
-            let page_exists = self
-                .pool
-                .get(table_name)
-                .is_some_and(|pages| pages.contains_key(&page_id));
-
-            if page_exists {
-                // Check if existing page has space
-                let has_space = self
-                    .pool
-                    .get(table_name)
-                    .unwrap()
-                    .get(&page_id)
-                    .unwrap()
-                    .free_space()
-                    > size;
-
-                if has_space {
-                    target_page_id = Some(page_id);
-                    break;
-                }
-            } else {
-                // Page does not exist so we use the last page ID
-                target_page_id = Some(page_id);
-                break;
+    /// Returns the id of a page of the relation with room for `size` more bytes,
+    /// appending a new page of `page_type` if none has enough.
+    ///
+    /// The room is not reserved, callers hold a lock keeping other writers out of the
+    /// relation until the page is written.
+    pub(crate) fn free_page(
+        &self,
+        table_name: &str,
+        size: usize,
+        page_type: PageType,
+    ) -> Result<PageId> {
+        let mut free_space_map = self
+            .free_space_map
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        if !free_space_map.load(table_name)? {
+            // The relation predates its free space map, record its pages once
+            for page_id in 0..self.page_count(table_name)? {
+                let available = self.read_page(table_name, page_id, Page::available_space)?;
+                free_space_map.update(table_name, page_id, available)?;
             }
         }
 
-        let page_id = target_page_id.ok_or_else(|| miette!("No free page available."))?;
-
-        let page_exists = self
-            .pool
-            .get(table_name)
-            .is_some_and(|pages| pages.contains_key(&page_id));
-
-        if !page_exists {
-            if let Ok(loaded_page) = self.load_page_from_file(table_name, page_id) {
-                self.pool
-                    .entry(table_name.to_string())
-                    .or_default()
-                    .insert(page_id, loaded_page);
-            } else {
-                // Create new page
-                let new_page = Page::new(page_id, PageType::Table);
-                self.pool
-                    .entry(table_name.to_string())
-                    .or_default()
-                    .insert(page_id, new_page);
+        while let Some(page_id) = free_space_map.find(table_name, size) {
+            let available = self.read_page(table_name, page_id, Page::available_space)?;
+            if available >= size {
+                return Ok(page_id);
             }
+
+            // The map was stale, the page filled up since it was last saved
+            free_space_map.update(table_name, page_id, available)?;
         }
 
-        Ok(self
-            .pool
-            .get_mut(table_name)
-            .unwrap()
-            .get_mut(&page_id)
-            .unwrap())
+        self.allocate_page(table_name, page_type)
     }
 
-    pub(crate) fn save_page(&mut self, table_name: &str, page_id: PageId) -> Result<()> {
-        let page = self.get_page(table_name, page_id)?;
+    /// Writes a cached page to the file of its relation.
+    pub(crate) fn save_page(&self, table_name: &str, page_id: PageId) -> Result<()> {
+        let path = self.table_path(table_name);
+        let (bytes, available) = self.read_page(table_name, page_id, |page| {
+            (page.to_bytes(), page.available_space())
+        })?;
 
-        let mut file = File::create(format!("./db/{table_name}.table")).into_diagnostic()?;
+        // Pages are written in place, the rest of the file must be kept
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .into_diagnostic()?;
 
         let offset = (page_id as usize) * Page::SIZE;
         file.seek(SeekFrom::Start(offset as u64))
             .into_diagnostic()?;
-        file.write_all(&page.to_bytes()).into_diagnostic()?;
+        file.write_all(&bytes).into_diagnostic()?;
 
-        Ok(())
+        self.free_space_map
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .update(table_name, page_id, available)
     }
 }
@@ -0,0 +1,168 @@
+use std::{
+    collections::HashMap,
+    fs::OpenOptions,
+    io::{Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use miette::{IntoDiagnostic, Result};
+
+use crate::storage::page::PageId;
+
+/// Tracks how many bytes can still be added to each page of a relation.
+///
+/// Lets inserts find a page with room without reading every page of the relation. Every
+/// relation has a `<relation>.fsm` file next to its data, holding the available space of
+/// page `n` as a little-endian `u16` at offset `2 * n`.
+///
+/// The map is only a hint: it is refreshed whenever a page is saved, so callers must
+/// check the page itself before writing to it.
+#[derive(Debug)]
+pub(crate) struct FreeSpaceMap {
+    relations: HashMap<String, Vec<u16>>,
+    data_directory: PathBuf,
+}
+
+impl FreeSpaceMap {
+    const ENTRY_SIZE: usize = 2;
+
+    pub(crate) fn new<P: AsRef<Path>>(data_directory: P) -> Self {
+        Self {
+            relations: HashMap::new(),
+            data_directory: data_directory.as_ref().to_path_buf(),
+        }
+    }
+
+    fn fsm_path(&self, relation: &str) -> PathBuf {
+        self.data_directory.join(format!("{relation}.fsm"))
+    }
+
+    /// Makes sure the map of a relation is in memory.
+    ///
+    /// Returns `false` if the relation has no map on disk yet, in which case it starts
+    /// out empty and the caller has to record the space of the existing pages.
+    pub(crate) fn load(&mut self, relation: &str) -> Result<bool> {
+        if self.relations.contains_key(relation) {
+            return Ok(true);
+        }
+
+        let path = self.fsm_path(relation);
+        let exists = path.exists();
+        let pages = if exists {
+            std::fs::read(path)
+                .into_diagnostic()?
+                .chunks_exact(Self::ENTRY_SIZE)
+                .map(|entry| u16::from_le_bytes([entry[0], entry[1]]))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        self.relations.insert(relation.to_string(), pages);
+        Ok(exists)
+    }
+
+    /// Records the space available on a page, see [`Page::available_space`].
+    ///
+    /// [`Page::available_space`]: crate::storage::page::Page::available_space
+    pub(crate) fn update(
+        &mut self,
+        relation: &str,
+        page_id: PageId,
+        available: usize,
+    ) -> Result<()> {
+        self.load(relation)?;
+
+        let pages = self.relations.get_mut(relation).unwrap();
+        let index = page_id as usize;
+        let available = available.min(u16::MAX as usize) as u16;
+        if pages.get(index) == Some(&available) {
+            return Ok(());
+        }
+
+        if pages.len() <= index {
+            pages.resize(index + 1, 0);
+        }
+        pages[index] = available;
+
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(self.fsm_path(relation))
+            .into_diagnostic()?;
+        // Entries of pages never recorded read back as zero
+        file.seek(SeekFrom::Start((index * Self::ENTRY_SIZE) as u64))
+            .into_diagnostic()?;
+        file.write_all(&available.to_le_bytes()).into_diagnostic()?;
+
+        Ok(())
+    }
+
+    /// Forgets the map of a dropped relation and deletes its file.
+    pub(crate) fn remove(&mut self, relation: &str) -> Result<()> {
+        self.relations.remove(relation);
+        remove_file_if_exists(&self.fsm_path(relation))
+    }
+
+    /// The first page with at least `size` bytes available.
+    pub(crate) fn find(&self, relation: &str, size: usize) -> Option<PageId> {
+        self.relations
+            .get(relation)?
+            .iter()
+            .position(|available| *available as usize >= size)
+            .map(|page_id| page_id as PageId)
+    }
+}
+
+/// Deletes a file, a file that does not exist is already deleted.
+pub(crate) fn remove_file_if_exists(path: &Path) -> Result<()> {
+    match std::fs::remove_file(path) {
+        Err(error) if error.kind() != std::io::ErrorKind::NotFound => Err(error).into_diagnostic(),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_directory(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("scuttle_fsm_tests_{name}"));
+        std::fs::remove_dir_all(&directory).ok();
+        std::fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    #[test]
+    fn test_find_page_with_room() {
+        let mut map = FreeSpaceMap::new(test_directory("find"));
+        assert!(!map.load("users").unwrap());
+        assert_eq!(map.find("users", 10), None);
+
+        map.update("users", 0, 8).unwrap();
+        map.update("users", 2, 500).unwrap();
+        assert_eq!(map.find("users", 8), Some(0));
+        assert_eq!(map.find("users", 9), Some(2));
+        assert_eq!(map.find("users", 501), None);
+
+        map.update("users", 2, 0).unwrap();
+        assert_eq!(map.find("users", 9), None);
+        assert_eq!(map.find("orders", 1), None);
+    }
+
+    #[test]
+    fn test_map_is_persisted() {
+        let directory = test_directory("persisted");
+
+        let mut map = FreeSpaceMap::new(&directory);
+        map.update("users", 3, 1200).unwrap();
+        map.update("users", 1, 40).unwrap();
+
+        let mut map = FreeSpaceMap::new(&directory);
+        assert!(map.load("users").unwrap());
+        assert_eq!(map.find("users", 1), Some(1));
+        assert_eq!(map.find("users", 41), Some(3));
+        assert_eq!(map.find("users", 1201), None);
+    }
+}
@@ -1,3 +1,6 @@
 pub(crate) mod btree;
 pub(crate) mod buffer_pool;
+pub(crate) mod checksum;
+pub(crate) mod free_space_map;
 pub(crate) mod page;
+pub(crate) mod toast;
@@ -1,6 +1,6 @@
 use miette::{Result, miette};
 
-use crate::core::serialization::Serializable;
+use crate::{core::serialization::Serializable, storage::checksum::crc32c};
 
 pub type PageId = u32;
 pub type ItemId = u16;
@@ -17,6 +17,8 @@ pub enum PageType {
     },
     Table,
     Catalog,
+    /// Holds one chunk of an out-of-line value, see [`crate::storage::toast`].
+    Overflow,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -33,6 +35,9 @@ impl PageHeader {
     // Follow Postgres Size
     pub const SIZE: usize = 24;
 
+    /// Bytes holding the CRC32C of the page, only set by [`Page::to_bytes`].
+    const CHECKSUM: std::ops::Range<usize> = 13..17;
+
     pub fn new(page_id: PageId, page_type: PageType) -> Self {
         Self {
             page_id,
@@ -53,6 +58,7 @@ impl Serializable<{ PageHeader::SIZE }> for PageHeader {
         data[4] = match this.page_type {
             PageType::Table => 0,
             PageType::Catalog => 1,
+            PageType::Overflow => 4,
             _ => panic!("Unsupported page type"),
         };
         data[5..7].copy_from_slice(&this.lower.to_le_bytes());
@@ -68,6 +74,7 @@ impl Serializable<{ PageHeader::SIZE }> for PageHeader {
             0 => PageType::Table,
             1 => PageType::Catalog,
             2 | 3 => panic!("BTree is not supported yet"),
+            4 => PageType::Overflow,
             _ => panic!("Unknown page type"),
         };
         let lower = u16::from_le_bytes(data[5..7].try_into().unwrap());
@@ -94,9 +101,10 @@ pub struct ItemPointer {
 }
 
 impl ItemPointer {
-    const SIZE: usize = std::mem::size_of::<ItemPointer>();
+    pub(crate) const SIZE: usize = std::mem::size_of::<ItemPointer>();
 
     const DELETED_FLAG: u8 = 0b0000_0001; // Bit 0
+    const UNUSED_FLAG: u8 = 0b0000_0010; // Bit 1, slot reclaimed by compaction
     const LIVE_FLAG: u8 = 0b0000_0000; // Default state
 
     pub fn new(offset: u16, length: u16) -> Self {
@@ -114,6 +122,21 @@ impl ItemPointer {
     pub fn mark_deleted(&mut self) {
         self.flags |= Self::DELETED_FLAG;
     }
+
+    /// A slot whose tuple was removed by [`Page::compact`], free to be reused.
+    ///
+    /// Unused slots are also deleted, so scans skip them.
+    pub fn is_unused(self) -> bool {
+        self.flags & Self::UNUSED_FLAG != 0
+    }
+
+    fn unused() -> Self {
+        Self {
+            offset: 0,
+            length: 0,
+            flags: Self::DELETED_FLAG | Self::UNUSED_FLAG,
+        }
+    }
 }
 
 impl Serializable<{ ItemPointer::SIZE }> for ItemPointer {
@@ -156,9 +179,9 @@ impl Page {
         }
     }
 
+    /// Adds a tuple, reusing a slot freed by [`Page::compact`] if there is one.
     pub fn add_data(&mut self, data: &[u8]) -> Result<ItemId> {
-        let needed_space = data.len() + ItemPointer::SIZE;
-        if self.free_space() < needed_space {
+        if self.available_space() < data.len() {
             return Err(miette!("Not enough space in the page to add data."));
         }
 
@@ -168,15 +191,84 @@ impl Page {
 
         let item_pointer = ItemPointer::new(self.header.upper, data.len() as u16);
 
-        let offset = self.header.item_count as usize * ItemPointer::SIZE;
+        let unused_slot = self.item_pointers().position(ItemPointer::is_unused);
+        let item_id = match unused_slot {
+            Some(unused) => unused as ItemId,
+            None => {
+                self.header.lower += ItemPointer::SIZE as u16;
+                self.header.item_count += 1;
+                self.header.item_count - 1
+            }
+        };
+        self.write_item_pointer(item_id, item_pointer);
+
+        Ok(item_id)
+    }
+
+    fn write_item_pointer(&mut self, item_id: ItemId, item_pointer: ItemPointer) {
+        let offset = item_id as usize * ItemPointer::SIZE;
         self.data[offset..offset + ItemPointer::SIZE].copy_from_slice(&item_pointer.to_bytes());
+    }
+
+    /// Size of the largest tuple [`Page::add_data`] can currently add.
+    pub fn available_space(&self) -> usize {
+        if self.item_pointers().any(ItemPointer::is_unused) {
+            self.free_space()
+        } else {
+            self.free_space().saturating_sub(ItemPointer::SIZE)
+        }
+    }
 
-        let item_id = self.header.item_count;
+    /// Defragments the page, reclaiming the space of deleted tuples.
+    ///
+    /// Live tuples are moved together at the end of the page. Only the offsets in their
+    /// slots change, so their `ItemId`s stay valid. Slots of deleted tuples become
+    /// unused and are handed out again by [`Page::add_data`]; trailing ones are dropped.
+    /// Returns the number of bytes reclaimed.
+    pub fn compact(&mut self) -> usize {
+        let free_before = self.free_space();
+
+        let slots = self.item_pointers().collect::<Vec<_>>();
+        let live = slots
+            .iter()
+            .enumerate()
+            .filter(|(_, item_pointer)| !item_pointer.is_deleted())
+            .map(|(item_id, item_pointer)| {
+                let start = item_pointer.offset as usize - PageHeader::SIZE;
+                let end = start + item_pointer.length as usize;
+                (item_id as ItemId, self.data[start..end].to_vec())
+            })
+            .collect::<Vec<_>>();
+
+        let item_count = live.last().map_or(0, |(item_id, _)| item_id + 1);
+        for item_id in 0..item_count {
+            self.write_item_pointer(item_id, ItemPointer::unused());
+        }
+        self.header.item_count = item_count;
+        self.header.lower = (PageHeader::SIZE + item_count as usize * ItemPointer::SIZE) as u16;
+        self.header.upper = self.header.special;
 
-        self.header.lower += ItemPointer::SIZE as u16;
-        self.header.item_count += 1;
+        for (item_id, tuple) in live {
+            self.header.upper -= tuple.len() as u16;
+            let data_offset = self.header.upper as usize - PageHeader::SIZE;
+            self.data[data_offset..data_offset + tuple.len()].copy_from_slice(&tuple);
 
-        Ok(item_id)
+            let item_pointer = ItemPointer::new(self.header.upper, tuple.len() as u16);
+            self.write_item_pointer(item_id, item_pointer);
+        }
+
+        // Stale bytes of removed tuples and slots are zeroed
+        let lower = self.header.lower as usize - PageHeader::SIZE;
+        let upper = self.header.upper as usize - PageHeader::SIZE;
+        self.data[lower..upper].fill(0);
+
+        self.free_space() - free_before
+    }
+
+    /// Whether the page holds deleted tuples [`Page::compact`] would reclaim.
+    pub fn has_dead_items(&self) -> bool {
+        self.item_pointers()
+            .any(|item_pointer| item_pointer.is_deleted() && !item_pointer.is_unused())
     }
 
     pub fn get_item(&self, item_id: ItemId) -> Result<&[u8]> {
@@ -221,6 +313,24 @@ impl Page {
     }
 }
 
+impl Page {
+    /// CRC32C of a serialized page, computed as if its checksum bytes were zero.
+    fn checksum(bytes: &[u8; Page::SIZE]) -> u32 {
+        let mut bytes = *bytes;
+        bytes[PageHeader::CHECKSUM].fill(0);
+        crc32c(&bytes)
+    }
+
+    /// Checks the checksum of a page read from disk.
+    ///
+    /// An all-zero page was never written, e.g. a hole left by writing a later page
+    /// first, and is accepted as well.
+    pub(crate) fn verify_checksum(bytes: &[u8; Page::SIZE]) -> bool {
+        let stored = u32::from_le_bytes(bytes[PageHeader::CHECKSUM].try_into().unwrap());
+        stored == Self::checksum(bytes) || bytes.iter().all(|byte| *byte == 0)
+    }
+}
+
 impl Serializable<{ Page::SIZE }> for Page {
     fn to_bytes(&self) -> [u8; Self::SIZE] {
         let this = &self;
@@ -229,6 +339,9 @@ impl Serializable<{ Page::SIZE }> for Page {
 
         data[PageHeader::SIZE..].copy_from_slice(&this.data);
 
+        let checksum = Self::checksum(&data);
+        data[PageHeader::CHECKSUM].copy_from_slice(&checksum.to_le_bytes());
+
         data
     }
 
@@ -335,6 +448,79 @@ mod test {
         );
     }
 
+    #[test]
+    pub fn test_compact_keeps_item_ids() {
+        let mut page = Page::new(0, PageType::Table);
+        let items: [&[u8]; 4] = [b"first", b"second", b"third", b"fourth"];
+        for item in items {
+            page.add_data(item).unwrap();
+        }
+        let free_before = page.free_space();
+
+        page.delete_item(1).unwrap();
+        page.delete_item(3).unwrap();
+        assert!(page.has_dead_items());
+
+        // Slot 3 is trailing and dropped, slot 1 stays as an unused slot
+        let reclaimed = page.compact();
+        assert_eq!(
+            reclaimed,
+            b"second".len() + b"fourth".len() + ItemPointer::SIZE
+        );
+        assert_eq!(page.free_space(), free_before + reclaimed);
+        assert!(!page.has_dead_items());
+        assert_eq!(page.header.item_count, 3);
+        assert!(page.item_pointers().nth(1).unwrap().is_unused());
+
+        assert_eq!(page.get_item(0).unwrap(), b"first");
+        assert_eq!(page.get_item(2).unwrap(), b"third");
+
+        // The tuple data is contiguous again
+        let upper = page.header.upper as usize;
+        assert_eq!(upper, Page::SIZE - b"first".len() - b"third".len());
+
+        assert_eq!(page.compact(), 0);
+    }
+
+    #[test]
+    pub fn test_add_data_reuses_unused_slot() {
+        let mut page = Page::new(0, PageType::Table);
+        page.add_data(b"first").unwrap();
+        page.add_data(b"second").unwrap();
+        page.add_data(b"third").unwrap();
+
+        page.delete_item(0).unwrap();
+        page.compact();
+        assert_eq!(page.available_space(), page.free_space());
+
+        let lower = page.header.lower;
+        assert_eq!(page.add_data(b"replacement").unwrap(), 0);
+        assert_eq!(page.header.lower, lower);
+        assert_eq!(page.get_item(0).unwrap(), b"replacement");
+        assert!(!page.item_pointers().next().unwrap().is_deleted());
+
+        // Without unused slots a new item also needs room for its pointer
+        assert_eq!(page.add_data(b"fourth").unwrap(), 3);
+        assert_eq!(
+            page.available_space(),
+            page.free_space() - ItemPointer::SIZE
+        );
+    }
+
+    #[test]
+    pub fn test_compact_empty_page() {
+        let mut page = Page::new(0, PageType::Table);
+        page.add_data(b"only").unwrap();
+        page.delete_item(0).unwrap();
+
+        page.compact();
+        assert_eq!(page.header.item_count, 0);
+        assert_eq!(
+            page.free_space(),
+            Page::new(0, PageType::Table).free_space()
+        );
+    }
+
     #[test]
     pub fn test_header_to_bytes() {
         let page_id = 1;
@@ -406,6 +592,25 @@ mod test {
         assert_eq!(page.data[0..ItemPointer::SIZE], item_pointer_bytes);
     }
 
+    #[test]
+    pub fn test_page_checksum() {
+        let mut page = Page::new(3, PageType::Table);
+        page.add_data(b"checksummed").unwrap();
+
+        let mut bytes = page.to_bytes();
+        assert_ne!(&bytes[PageHeader::CHECKSUM], &[0; 4]);
+        assert!(Page::verify_checksum(&bytes));
+
+        // A single flipped bit anywhere in the page is detected
+        bytes[Page::SIZE - 2] ^= 0b0000_0100;
+        assert!(!Page::verify_checksum(&bytes));
+        bytes[Page::SIZE - 2] ^= 0b0000_0100;
+        bytes[5] ^= 1;
+        assert!(!Page::verify_checksum(&bytes));
+
+        assert!(Page::verify_checksum(&[0; Page::SIZE]));
+    }
+
     #[test]
     pub fn test_page_to_from_bytes() {
         let page_id = 1;
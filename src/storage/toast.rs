@@ -0,0 +1,670 @@
+//! Storage for values too large to keep inline in a row ("TOAST").
+//!
+//! Variable-length values are written as a 4-byte header followed by their bytes. The
+//! two high bits of the header mark values that are compressed or stored out of line:
+//!
+//! - plain: `length`, then the bytes
+//! - compressed: `COMPRESSED | length`, the raw length, then the compressed bytes
+//! - external: `EXTERNAL [| COMPRESSED]`, then a pointer to the value's overflow chain
+//!
+//! When an encoded row exceeds [`TOAST_THRESHOLD`], its largest values are compressed
+//! and, if that is not enough, moved to a chain of chunks in the table's TOAST
+//! relation. Reading a row follows the pointers transparently, and
+//! [`delete_varlena`] frees the chain once the row is gone.
+
+use std::cmp::Reverse;
+
+use miette::{Result, miette};
+
+use crate::storage::{
+    buffer_pool::BufferPool,
+    page::{ItemId, ItemPointer, Page, PageHeader, PageId, PageType},
+};
+
+/// Rows larger than this have their largest values compressed or moved out of line,
+/// so several rows fit in a page.
+pub(crate) const TOAST_THRESHOLD: usize = Page::SIZE / 4;
+
+/// Values shorter than this are never toasted, a pointer would not save much.
+pub(crate) const MIN_TOAST_LENGTH: usize = 128;
+
+const EXTERNAL_FLAG: u32 = 1 << 31;
+const COMPRESSED_FLAG: u32 = 1 << 30;
+const LENGTH_MASK: u32 = COMPRESSED_FLAG - 1;
+
+/// Bytes of a value stored in each full chunk, after the link to the next chunk.
+///
+/// A chunk item of this size takes a whole overflow page.
+const CHUNK_SIZE: usize = Page::SIZE - PageHeader::SIZE - ItemPointer::SIZE - ToastPointer::SIZE;
+
+/// Location of a chunk of an out-of-line value in the TOAST relation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ToastPointer {
+    pub(crate) page_id: PageId,
+    pub(crate) item_id: ItemId,
+}
+
+impl ToastPointer {
+    const SIZE: usize = 6;
+
+    /// Marks the last chunk of a chain.
+    const END: ToastPointer = ToastPointer {
+        page_id: PageId::MAX,
+        item_id: ItemId::MAX,
+    };
+
+    fn write(self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.page_id.to_le_bytes());
+        bytes.extend_from_slice(&self.item_id.to_le_bytes());
+    }
+
+    fn read(bytes: &[u8], offset: &mut usize) -> Result<Self> {
+        let raw = bytes
+            .get(*offset..*offset + Self::SIZE)
+            .ok_or_else(|| miette!("Not enough bytes for toast pointer"))?;
+        *offset += Self::SIZE;
+        Ok(Self {
+            page_id: PageId::from_le_bytes(raw[..4].try_into().unwrap()),
+            item_id: ItemId::from_le_bytes(raw[4..].try_into().unwrap()),
+        })
+    }
+}
+
+/// Where out-of-line values are kept and read back from.
+pub(crate) trait ToastStore {
+    /// Stores `data` out of line, returning the location of its first chunk.
+    fn store(&mut self, data: &[u8]) -> Result<ToastPointer>;
+
+    /// Reads back `length` bytes stored from `pointer` on.
+    fn fetch(&mut self, pointer: ToastPointer, length: usize) -> Result<Vec<u8>>;
+
+    /// Frees the chain of a value that is no longer referenced.
+    fn delete(&mut self, pointer: ToastPointer) -> Result<()>;
+}
+
+/// Name of the relation holding the out-of-line values of `table_name`.
+pub(crate) fn toast_relation(table_name: &str) -> String {
+    format!("{table_name}.toast")
+}
+
+/// The TOAST relation of a table, stored through the buffer pool.
+///
+/// A value is split into a chain of chunks, each item holding the location of the next
+/// chunk followed by the bytes. Full chunks fill a page of their own, the shorter last
+/// chunk goes to any page with room, so the tails of several values share a page.
+pub(crate) struct TableToast<'a> {
+    buffer_pool: &'a BufferPool,
+    relation: String,
+}
+
+impl<'a> TableToast<'a> {
+    pub(crate) fn new(buffer_pool: &'a BufferPool, table_name: &str) -> Self {
+        Self {
+            buffer_pool,
+            relation: toast_relation(table_name),
+        }
+    }
+}
+
+impl ToastStore for TableToast<'_> {
+    fn store(&mut self, data: &[u8]) -> Result<ToastPointer> {
+        // An empty value still gets a chunk to point to
+        let mut chunks = data.chunks(CHUNK_SIZE).collect::<Vec<_>>();
+        if chunks.is_empty() {
+            chunks.push(data);
+        }
+
+        // Written back to front, each chunk links to the one stored before it
+        let mut next = ToastPointer::END;
+        for chunk in chunks.into_iter().rev() {
+            let mut item = Vec::with_capacity(ToastPointer::SIZE + chunk.len());
+            next.write(&mut item);
+            item.extend_from_slice(chunk);
+
+            let page_id =
+                self.buffer_pool
+                    .free_page(&self.relation, item.len(), PageType::Overflow)?;
+            let item_id = self
+                .buffer_pool
+                .write_page(&self.relation, page_id, |page| page.add_data(&item))??;
+            self.buffer_pool.save_page(&self.relation, page_id)?;
+
+            next = ToastPointer { page_id, item_id };
+        }
+
+        Ok(next)
+    }
+
+    fn fetch(&mut self, pointer: ToastPointer, length: usize) -> Result<Vec<u8>> {
+        let mut data = Vec::with_capacity(length);
+        let mut next = pointer;
+
+        while data.len() < length {
+            if next == ToastPointer::END {
+                return Err(miette!("Overflow chain at {pointer:?} ended early"));
+            }
+
+            next = self
+                .buffer_pool
+                .read_page(&self.relation, next.page_id, |page| {
+                    let item = chunk(page, next.item_id)?;
+                    data.extend_from_slice(&item[ToastPointer::SIZE..]);
+                    ToastPointer::read(item, &mut 0)
+                })??;
+        }
+
+        if data.len() != length {
+            return Err(miette!("Overflow chain at {pointer:?} is too long"));
+        }
+        Ok(data)
+    }
+
+    fn delete(&mut self, pointer: ToastPointer) -> Result<()> {
+        let mut next = pointer;
+
+        while next != ToastPointer::END {
+            let page_id = next.page_id;
+            next = self
+                .buffer_pool
+                .write_page(&self.relation, page_id, |page| {
+                    let following = ToastPointer::read(chunk(page, next.item_id)?, &mut 0)?;
+                    page.delete_item(next.item_id)?;
+                    // Only this chain refers to the chunk, its space is reused at once
+                    page.compact();
+                    Ok::<_, miette::Report>(following)
+                })??;
+            self.buffer_pool.save_page(&self.relation, page_id)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The item of a live chunk in an overflow page.
+fn chunk(page: &Page, item_id: ItemId) -> Result<&[u8]> {
+    let page_id = page.header.page_id;
+    if page.header.page_type != PageType::Overflow {
+        return Err(miette!("Page {page_id} is not an overflow page"));
+    }
+
+    let live = page
+        .item_pointers()
+        .nth(item_id as usize)
+        .is_some_and(|item_pointer| !item_pointer.is_deleted());
+    if !live {
+        return Err(miette!("No chunk at item {item_id} of page {page_id}"));
+    }
+
+    let item = page.get_item(item_id)?;
+    if item.len() < ToastPointer::SIZE {
+        return Err(miette!(
+            "Chunk at item {item_id} of page {page_id} is truncated"
+        ));
+    }
+    Ok(item)
+}
+
+/// Appends a value stored inline and uncompressed.
+pub(crate) fn write_varlena(data: &[u8], bytes: &mut Vec<u8>) {
+    bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(data);
+}
+
+/// Shrinks a row larger than [`TOAST_THRESHOLD`] whose encoded values are `fields`,
+/// each flagged with whether it is a variable-length value written by
+/// [`write_varlena`].
+///
+/// Like PostgreSQL, the largest values are first compressed in place, and only if the
+/// row is still too large moved out of line, largest first.
+pub(crate) fn toast_row(
+    fields: &mut [(Vec<u8>, bool)],
+    mut row_length: usize,
+    store: &mut impl ToastStore,
+) -> Result<()> {
+    let mut candidates = (0..fields.len())
+        .filter(|&i| fields[i].1 && fields[i].0.len() >= MIN_TOAST_LENGTH)
+        .collect::<Vec<_>>();
+
+    candidates.sort_by_key(|&i| Reverse(fields[i].0.len()));
+    for &i in &candidates {
+        if row_length <= TOAST_THRESHOLD {
+            return Ok(());
+        }
+
+        let field = &mut fields[i].0;
+        if let Some(compressed) = compress_varlena(&field[4..]) {
+            row_length = row_length - field.len() + compressed.len();
+            *field = compressed;
+        }
+    }
+
+    candidates.sort_by_key(|&i| Reverse(fields[i].0.len()));
+    for &i in &candidates {
+        let field = &mut fields[i].0;
+        if row_length <= TOAST_THRESHOLD || field.len() < MIN_TOAST_LENGTH {
+            break;
+        }
+
+        let external = store_varlena(field, store)?;
+        row_length = row_length - field.len() + external.len();
+        *field = external;
+    }
+
+    Ok(())
+}
+
+/// The compressed inline form of a value, `None` if it does not compress.
+fn compress_varlena(data: &[u8]) -> Option<Vec<u8>> {
+    let compressed = compress(data)?;
+
+    let mut bytes = Vec::with_capacity(8 + compressed.len());
+    bytes.extend_from_slice(&(COMPRESSED_FLAG | compressed.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&compressed);
+    Some(bytes)
+}
+
+/// Moves an inline value, compressed or not, out of line and returns its pointer.
+fn store_varlena(field: &[u8], store: &mut impl ToastStore) -> Result<Vec<u8>> {
+    let header = u32::from_le_bytes(field[..4].try_into().unwrap());
+    let (stored, raw_length, header) = if header & COMPRESSED_FLAG != 0 {
+        let raw_length = u32::from_le_bytes(field[4..8].try_into().unwrap());
+        (&field[8..], raw_length, EXTERNAL_FLAG | COMPRESSED_FLAG)
+    } else {
+        (&field[4..], header, EXTERNAL_FLAG)
+    };
+    let pointer = store.store(stored)?;
+
+    let mut bytes = Vec::with_capacity(4 + ToastPointer::SIZE + 8);
+    bytes.extend_from_slice(&header.to_le_bytes());
+    pointer.write(&mut bytes);
+    bytes.extend_from_slice(&(stored.len() as u32).to_le_bytes());
+    bytes.extend_from_slice(&raw_length.to_le_bytes());
+    Ok(bytes)
+}
+
+/// Reads a value written by [`write_varlena`] or [`toast_row`] at `offset`,
+/// decompressing it and fetching it from its overflow pages as needed.
+pub(crate) fn read_varlena(
+    bytes: &[u8],
+    offset: &mut usize,
+    store: &mut impl ToastStore,
+) -> Result<Vec<u8>> {
+    let header = read_u32(bytes, offset, "value length")?;
+    let length = (header & LENGTH_MASK) as usize;
+    let compressed = header & COMPRESSED_FLAG != 0;
+
+    let (stored, raw_length) = if header & EXTERNAL_FLAG != 0 {
+        let pointer = ToastPointer::read(bytes, offset)?;
+        let stored_length = read_u32(bytes, offset, "toast pointer")? as usize;
+        let raw_length = read_u32(bytes, offset, "toast pointer")? as usize;
+        (store.fetch(pointer, stored_length)?, raw_length)
+    } else {
+        let raw_length = if compressed {
+            read_u32(bytes, offset, "raw length")? as usize
+        } else {
+            length
+        };
+        let data = bytes
+            .get(*offset..*offset + length)
+            .ok_or_else(|| miette!("Not enough bytes for value content"))?;
+        *offset += length;
+        (data.to_vec(), raw_length)
+    };
+
+    if compressed {
+        decompress(&stored, raw_length).ok_or_else(|| miette!("Compressed value is corrupted"))
+    } else {
+        Ok(stored)
+    }
+}
+
+/// Moves `offset` past a value written by [`write_varlena`] or [`toast_row`] without
+/// reading its content.
+pub(crate) fn skip_varlena(bytes: &[u8], offset: &mut usize) -> Result<()> {
+    let header = read_u32(bytes, offset, "value length")?;
+
+    let length = if header & EXTERNAL_FLAG != 0 {
+        // First chunk, stored length and raw length of the toast pointer
+        ToastPointer::SIZE + 2 * 4
+    } else if header & COMPRESSED_FLAG != 0 {
+        4 + (header & LENGTH_MASK) as usize
+    } else {
+        (header & LENGTH_MASK) as usize
+    };
+
+    if *offset + length > bytes.len() {
+        return Err(miette!("Not enough bytes for value content"));
+    }
+    *offset += length;
+    Ok(())
+}
+
+/// Moves `offset` past a value like [`skip_varlena`], freeing its overflow chain if it
+/// is stored out of line.
+pub(crate) fn delete_varlena(
+    bytes: &[u8],
+    offset: &mut usize,
+    store: &mut impl ToastStore,
+) -> Result<()> {
+    let start = *offset;
+    let header = read_u32(bytes, offset, "value length")?;
+    if header & EXTERNAL_FLAG != 0 {
+        store.delete(ToastPointer::read(bytes, offset)?)?;
+    }
+
+    *offset = start;
+    skip_varlena(bytes, offset)
+}
+
+fn read_u32(bytes: &[u8], offset: &mut usize, what: &str) -> Result<u32> {
+    let raw = bytes
+        .get(*offset..*offset + 4)
+        .ok_or_else(|| miette!("Not enough bytes for {what}"))?;
+    *offset += 4;
+    Ok(u32::from_le_bytes(raw.try_into().unwrap()))
+}
+
+/// Matches shorter than this are stored as literals.
+const MIN_MATCH: usize = 4;
+const MAX_MATCH: usize = MIN_MATCH + u8::MAX as usize;
+const MAX_OFFSET: usize = u16::MAX as usize;
+const HASH_BITS: u32 = 12;
+
+/// Compresses `data` with a simple LZ77 scheme.
+///
+/// The output is a sequence of groups: a control byte whose bits tell, for each of the
+/// following up to 8 items, whether it is a literal byte or a match of a 2-byte offset
+/// and a 1-byte length. `None` if this does not save at least a quarter of the size.
+pub(crate) fn compress(data: &[u8]) -> Option<Vec<u8>> {
+    let limit = data.len() - data.len() / 4;
+    let mut compressed = Vec::with_capacity(limit);
+    let mut last_seen = vec![usize::MAX; 1 << HASH_BITS];
+
+    let mut control_index = 0;
+    let mut control_bit = 8;
+    let mut position = 0;
+
+    while position < data.len() {
+        if control_bit == 8 {
+            control_index = compressed.len();
+            compressed.push(0);
+            control_bit = 0;
+        }
+
+        let mut match_length = 0;
+        let mut match_offset = 0;
+        if position + MIN_MATCH <= data.len() {
+            let hash = hash(&data[position..position + MIN_MATCH]);
+            let candidate = last_seen[hash];
+            last_seen[hash] = position;
+
+            if candidate != usize::MAX && position - candidate <= MAX_OFFSET {
+                let max_length = (data.len() - position).min(MAX_MATCH);
+                while match_length < max_length
+                    && data[candidate + match_length] == data[position + match_length]
+                {
+                    match_length += 1;
+                }
+                match_offset = position - candidate;
+            }
+        }
+
+        if match_length >= MIN_MATCH {
+            compressed[control_index] |= 1 << control_bit;
+            compressed.extend_from_slice(&(match_offset as u16).to_le_bytes());
+            compressed.push((match_length - MIN_MATCH) as u8);
+            position += match_length;
+        } else {
+            compressed.push(data[position]);
+            position += 1;
+        }
+        control_bit += 1;
+
+        if compressed.len() >= limit {
+            return None;
+        }
+    }
+
+    (compressed.len() < limit).then_some(compressed)
+}
+
+/// Reverses [`compress`], `None` if the data is corrupted.
+pub(crate) fn decompress(data: &[u8], raw_length: usize) -> Option<Vec<u8>> {
+    let mut output = Vec::with_capacity(raw_length);
+    let mut position = 0;
+
+    while output.len() < raw_length {
+        let control = *data.get(position)?;
+        position += 1;
+
+        for bit in 0..8 {
+            if output.len() >= raw_length {
+                break;
+            }
+
+            if control & (1 << bit) != 0 {
+                let item = data.get(position..position + 3)?;
+                position += 3;
+
+                let offset = u16::from_le_bytes([item[0], item[1]]) as usize;
+                let length = item[2] as usize + MIN_MATCH;
+                if offset == 0 || offset > output.len() {
+                    return None;
+                }
+
+                // Copied byte by byte, a match may overlap the bytes it produces
+                let start = output.len() - offset;
+                for i in 0..length {
+                    output.push(output[start + i]);
+                }
+            } else {
+                output.push(*data.get(position)?);
+                position += 1;
+            }
+        }
+    }
+
+    (output.len() == raw_length && position == data.len()).then_some(output)
+}
+
+fn hash(bytes: &[u8]) -> usize {
+    let value = u32::from_le_bytes(bytes[..4].try_into().unwrap());
+    (value.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+}
+
+/// Keeps out-of-line values in memory, for tests of the row format.
+///
+/// A value is addressed by its index, deleted values stay in place and are recorded in
+/// `deleted`.
+#[cfg(test)]
+#[derive(Debug, Default)]
+pub(crate) struct MemoryToast {
+    pub(crate) values: Vec<Vec<u8>>,
+    pub(crate) deleted: Vec<usize>,
+}
+
+#[cfg(test)]
+impl MemoryToast {
+    fn index(&self, pointer: ToastPointer) -> Result<usize> {
+        let index = pointer.page_id as usize;
+        if index >= self.values.len() || self.deleted.contains(&index) {
+            return Err(miette!("No value at {pointer:?}"));
+        }
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+impl ToastStore for MemoryToast {
+    fn store(&mut self, data: &[u8]) -> Result<ToastPointer> {
+        self.values.push(data.to_vec());
+        Ok(ToastPointer {
+            page_id: self.values.len() as PageId - 1,
+            item_id: 0,
+        })
+    }
+
+    fn fetch(&mut self, pointer: ToastPointer, length: usize) -> Result<Vec<u8>> {
+        let value = &self.values[self.index(pointer)?];
+        assert_eq!(value.len(), length);
+        Ok(value.clone())
+    }
+
+    fn delete(&mut self, pointer: ToastPointer) -> Result<()> {
+        let index = self.index(pointer)?;
+        self.deleted.push(index);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn incompressible(length: usize) -> Vec<u8> {
+        // A xorshift sequence has no repetitions for LZ77 to exploit
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        (0..length)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_compress_round_trip() {
+        let text = "the quick brown fox jumps over the lazy dog. ".repeat(200);
+        let compressed = compress(text.as_bytes()).expect("Repetitive text compresses");
+        assert!(compressed.len() < text.len() / 4);
+        assert_eq!(
+            decompress(&compressed, text.len()).unwrap(),
+            text.as_bytes()
+        );
+
+        // Long runs are matches overlapping their own output
+        let run = vec![7u8; 10_000];
+        let compressed = compress(&run).unwrap();
+        assert_eq!(decompress(&compressed, run.len()).unwrap(), run);
+
+        assert_eq!(compress(&incompressible(4096)), None);
+        assert_eq!(decompress(&compressed, run.len() + 1), None);
+    }
+
+    #[test]
+    fn test_varlena_formats() {
+        let mut store = MemoryToast::default();
+        let varlena = |data: &[u8]| {
+            let mut bytes = vec![];
+            write_varlena(data, &mut bytes);
+            bytes
+        };
+
+        let compressible = "abcdefgh".repeat(1000);
+        let random = incompressible(5000);
+
+        let plain = varlena(b"short");
+        let inline = compress_varlena(compressible.as_bytes()).unwrap();
+        let external = store_varlena(&inline, &mut store).unwrap();
+        let external_raw = store_varlena(&varlena(&random), &mut store).unwrap();
+        assert_eq!(store.values.len(), 2);
+        assert_eq!(store.values[1], random);
+
+        let mut bytes = [plain, inline, external, external_raw].concat();
+        bytes.push(0xFF);
+
+        let mut offset = 0;
+        assert_eq!(
+            read_varlena(&bytes, &mut offset, &mut store).unwrap(),
+            b"short"
+        );
+        for expected in [compressible.as_bytes(), compressible.as_bytes(), &random] {
+            assert_eq!(
+                read_varlena(&bytes, &mut offset, &mut store).unwrap(),
+                expected
+            );
+        }
+        assert_eq!(offset, bytes.len() - 1);
+        assert!(read_varlena(&bytes, &mut offset, &mut store).is_err());
+
+        // Only the values stored out of line have something to free
+        let mut offset = 0;
+        for _ in 0..4 {
+            delete_varlena(&bytes, &mut offset, &mut store).unwrap();
+        }
+        assert_eq!(offset, bytes.len() - 1);
+        assert_eq!(store.deleted, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_toast_row_prefers_compression() {
+        let mut store = MemoryToast::default();
+        let varlena = |data: &[u8]| {
+            let mut bytes = vec![];
+            write_varlena(data, &mut bytes);
+            (bytes, true)
+        };
+
+        let mut fields = vec![
+            (vec![0; 8], false),
+            varlena("compressible ".repeat(1000).as_bytes()),
+            varlena(&incompressible(3000)),
+            varlena(&incompressible(100)),
+        ];
+        let length = fields.iter().map(|(f, _)| f.len()).sum();
+        toast_row(&mut fields, length, &mut store).unwrap();
+
+        // Compression alone is not enough, the incompressible value moves out of line
+        assert_eq!(store.values, vec![incompressible(3000)]);
+        assert!(fields[1].0.len() < 1000);
+        assert_eq!(fields[2].0.len(), 18);
+        assert_eq!(fields[3].0.len(), 104);
+    }
+
+    #[test]
+    fn test_overflow_chain() {
+        let directory = std::env::temp_dir().join("scuttle_toast_tests");
+        std::fs::remove_dir_all(&directory).ok();
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let value = incompressible(CHUNK_SIZE * 2 + 100);
+        let (first, second) = {
+            let pool = BufferPool::new(&directory);
+            let mut toast = TableToast::new(&pool, "docs");
+            let first = toast.store(b"tiny").unwrap();
+            let second = toast.store(&value).unwrap();
+            (first, second)
+        };
+        // The tail of the second value shares the first page, its full chunks follow
+        let at = |page_id, item_id| ToastPointer { page_id, item_id };
+        assert_eq!((first, second), (at(0, 0), at(2, 0)));
+
+        // A fresh pool reads the chain back from disk
+        let pool = BufferPool::new(&directory);
+        assert_eq!(pool.page_count(&toast_relation("docs")).unwrap(), 3);
+        assert_eq!(
+            pool.read_page(&toast_relation("docs"), 0, |page| page.header.item_count)
+                .unwrap(),
+            2
+        );
+
+        let mut toast = TableToast::new(&pool, "docs");
+        assert_eq!(toast.fetch(first, 4).unwrap(), b"tiny");
+        assert_eq!(toast.fetch(second, value.len()).unwrap(), value);
+        assert!(toast.fetch(second, value.len() + 1).is_err());
+        assert!(toast.fetch(first, 5).is_err());
+
+        // Freed chunks are gone and their pages are filled again
+        toast.delete(second).unwrap();
+        assert!(toast.fetch(second, value.len()).is_err());
+        assert!(toast.delete(second).is_err());
+        assert_eq!(toast.fetch(first, 4).unwrap(), b"tiny");
+
+        let third = toast.store(&value).unwrap();
+        assert_eq!(toast.fetch(third, value.len()).unwrap(), value);
+        assert_eq!(pool.page_count(&toast_relation("docs")).unwrap(), 3);
+    }
+}